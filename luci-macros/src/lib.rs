@@ -0,0 +1,145 @@
+//! Macros for [luci](https://docs.rs/luci).
+//!
+//! Use them via the re-exports in `luci` itself.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprLit, Lit, MetaNameValue, Token};
+
+/// Generates a `#[tokio::test]` for every scenario file matching the glob.
+///
+/// ```ignore
+/// luci::scenario_tests!(
+///     glob = "tests/scenarios/*.luci.yaml",
+///     blueprint = my_blueprint,
+///     marshalling = registry(),
+/// );
+/// ```
+///
+/// - `glob` — a pattern relative to the crate root;
+/// - `blueprint` — a `Fn() -> elfo::Blueprint`;
+/// - `marshalling` — an expression producing a `MarshallingRegistry`, evaluated
+///   in each test.
+///
+/// Every test is named after its file, and runs the scenario via
+/// `luci::suite::Suite::run_scenario` with the file's directory as the root
+/// of the suite.
+///
+/// The glob is expanded at compile time: a newly added file becomes a test
+/// after the crate is rebuilt.
+#[proc_macro]
+pub fn scenario_tests(input: TokenStream) -> TokenStream {
+    match expand(input.into()) {
+        Ok(output) => output.into(),
+        Err(reason) => reason.to_compile_error().into(),
+    }
+}
+
+fn expand(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let args = Punctuated::<MetaNameValue, Token![,]>::parse_terminated.parse2(input)?;
+
+    let mut glob = None;
+    let mut blueprint = None;
+    let mut marshalling = None;
+    for arg in args {
+        let Some(name) = arg.path.get_ident() else {
+            return Err(syn::Error::new_spanned(arg.path, "unexpected argument"))
+        };
+        match name.to_string().as_str() {
+            "glob" => {
+                match &arg.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(s), ..
+                    }) => glob = Some(s.clone()),
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            arg.value,
+                            "expected a string literal",
+                        ))
+                    },
+                }
+            },
+            "blueprint" => blueprint = Some(arg.value),
+            "marshalling" => marshalling = Some(arg.value),
+            _ => return Err(syn::Error::new_spanned(name, "unexpected argument")),
+        }
+    }
+    let missing = |name| syn::Error::new(Span::call_site(), format!("missing `{}`", name));
+    let glob = glob.ok_or_else(|| missing("glob"))?;
+    let blueprint = blueprint.ok_or_else(|| missing("blueprint"))?;
+    let marshalling = marshalling.ok_or_else(|| missing("marshalling"))?;
+
+    let crate_root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    let pattern = crate_root.join(glob.value());
+    let files = glob::glob(&pattern.to_string_lossy())
+        .map_err(|e| syn::Error::new_spanned(&glob, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| syn::Error::new_spanned(&glob, e))?;
+    if files.is_empty() {
+        return Err(syn::Error::new_spanned(&glob, "no scenario files match"))
+    }
+
+    let mut names = HashSet::new();
+    let mut tests = vec![];
+    for file in files {
+        let relative = file.strip_prefix(&crate_root).unwrap_or(&file);
+        let root = relative
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_string_lossy()
+            .into_owned();
+        let file_name = relative
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+
+        let mut name = test_name(&file_name);
+        while !names.insert(name.clone()) {
+            name.push('_');
+        }
+        let name = format_ident!("{}", name);
+
+        tests.push(quote! {
+            #[::tokio::test]
+            async fn #name() {
+                ::tokio::time::pause();
+
+                let suite = ::luci::suite::Suite::new(#root, || #marshalling, #blueprint);
+                let outcome = suite.run_scenario(#file_name).await;
+                assert!(outcome.is_ok(), "{}", outcome);
+            }
+        });
+    }
+
+    Ok(quote! { #(#tests)* })
+}
+
+fn test_name(file_name: &str) -> String {
+    let stem = [".luci.yaml", ".luci.yml", ".yaml", ".yml"]
+        .into_iter()
+        .find_map(|suffix| file_name.strip_suffix(suffix))
+        .unwrap_or(file_name);
+    let name = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
@@ -1,17 +1,27 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs::{read_to_string, File};
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::Parser;
-use luci::scenario::Scenario;
-use luci::visualization::draw_scenario;
+use clap::{Parser, Subcommand};
+use luci::execution::{Executable, SourceCode, SourceCodeLoader};
+use luci::marshalling::{MarshallingRegistry, Mock};
+use luci::scenario::{DefEventKind, Scenario};
+use luci::visualization::{draw_scenario, draw_source_code, DrawOptions};
+use serde::Deserialize;
 
 #[derive(Parser, Debug)]
 #[command(
-    name = "luci-graph",
-    about = "Generate a Graphviz DOT graph from a scenario description."
+    name = "luci",
+    about = "Draw a scenario as a Graphviz DOT graph, or check and maintain scenarios.",
+    args_conflicts_with_subcommands = true
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[clap(long = "input", short = 'i', help = "Scenario file (default: stdin)")]
     scenario_file: Option<PathBuf>,
     #[clap(long = "output", short = 'o', help = "Graphviz file (default: stdout")]
@@ -23,11 +33,315 @@ struct Args {
         help = "Add additional information to the graph"
     )]
     verbose:       bool,
+    #[clap(
+        long = "expand",
+        default_value_t = false,
+        requires = "scenario_file",
+        help = "Draw the events of the called subroutines rather than a node per call"
+    )]
+    expand:        bool,
+    #[clap(
+        long = "toggle",
+        requires = "scenario_file",
+        help = "A call to draw the other way than the rest, by its path (e.g. `outer/inner`)"
+    )]
+    toggle:        Vec<String>,
+    #[clap(
+        long = "search-path",
+        short = 'I',
+        help = "Additional directories to look for subroutines in"
+    )]
+    search_path:   Vec<PathBuf>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Load and build every scenario found in a directory.
+    ///
+    /// The messages are marshalled by mocks, so this checks the scenarios
+    /// without running them. To run them against a blueprint, use
+    /// `luci::suite::main` in a binary of the crate defining the blueprint.
+    Suite(SuiteArgs),
+
+    /// Rewrite the deprecated fields of the scenarios in place.
+    ///
+    /// The scenarios migrated this way do not need the
+    /// `backward-compatibility` feature anymore. Note that the comments are
+    /// not preserved: the files with comments are not rewritten, unless
+    /// `--force` is given.
+    #[cfg(feature = "backward-compatibility")]
+    Migrate(MigrateArgs),
+
+    /// Print the scenarios in the canonical field order and style.
+    ///
+    /// With `--write`, the files are rewritten in place instead. Note that the
+    /// comments are not preserved: the files with comments are not rewritten,
+    /// unless `--force` is given.
+    Fmt(FmtArgs),
+
+    /// List the messages a scenario (along with its subroutines) needs to be
+    /// registered in the marshalling registry.
+    ///
+    /// The output has the same format as the `MessageInfo`s returned by
+    /// `MarshallingRegistry::messages`. A message is considered a request if
+    /// some event responds to it.
+    Messages(MessagesArgs),
+
+    /// Describe how an event is wired: the events it happens after, the ones
+    /// it unblocks, whether it is required, and the chain of the calls it is
+    /// found in.
+    Explain(ExplainArgs),
+
+    /// Create a skeleton scenario, and optionally a test running it.
+    ///
+    /// With `--repeat` or `--time-scale`, the test runs the scenario with
+    /// `Executable::run_repeated_with_options` instead.
+    New(NewArgs),
+
+    /// Print the JSON Schema of the scenario files.
+    ///
+    /// Point the editor's YAML support to it to have the scenarios validated
+    /// and autocompleted.
+    Schema,
+}
+
+#[derive(clap::Args, Debug)]
+struct MessagesArgs {
+    #[clap(help = "Scenario file")]
+    scenario_file: PathBuf,
+    #[clap(
+        long = "search-path",
+        short = 'I',
+        help = "Additional directories to look for subroutines in"
+    )]
+    search_path:   Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct SuiteArgs {
+    #[clap(help = "Directory to look for *.luci.yaml files in")]
+    dir:         PathBuf,
+    #[clap(
+        long = "search-path",
+        short = 'I',
+        help = "Additional directories to look for subroutines in"
+    )]
+    search_path: Vec<PathBuf>,
+    #[clap(
+        long = "verbose",
+        short = 'v',
+        default_value_t = false,
+        help = "Report how long loading and building took"
+    )]
+    verbose:     bool,
+}
+
+#[cfg(feature = "backward-compatibility")]
+#[derive(clap::Args, Debug)]
+struct MigrateArgs {
+    #[clap(
+        required = true,
+        help = "Scenario files, or directories to look for *.luci.yaml files in"
+    )]
+    paths:   Vec<PathBuf>,
+    #[clap(
+        long = "dry-run",
+        short = 'n',
+        default_value_t = false,
+        help = "Report the changes without writing them"
+    )]
+    dry_run: bool,
+    #[clap(
+        long = "force",
+        default_value_t = false,
+        conflicts_with = "dry_run",
+        help = "Rewrite the files even if their comments are lost this way"
+    )]
+    force:   bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct FmtArgs {
+    #[clap(
+        required = true,
+        help = "Scenario files, or directories to look for *.luci.yaml files in"
+    )]
+    paths: Vec<PathBuf>,
+    #[clap(
+        long = "check",
+        default_value_t = false,
+        conflicts_with = "write",
+        help = "Report the files that are not formatted, rather than print them"
+    )]
+    check: bool,
+    #[clap(
+        long = "write",
+        short = 'w',
+        default_value_t = false,
+        help = "Rewrite the files that are not formatted, rather than print them"
+    )]
+    write: bool,
+    #[clap(
+        long = "force",
+        default_value_t = false,
+        requires = "write",
+        help = "Rewrite the files even if their comments are lost this way"
+    )]
+    force: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct ExplainArgs {
+    #[clap(help = "Scenario file")]
+    scenario_file: PathBuf,
+    #[clap(help = "Event id, possibly qualified as `call/inner`")]
+    event:         String,
+    #[clap(
+        long = "search-path",
+        short = 'I',
+        help = "Additional directories to look for subroutines in"
+    )]
+    search_path:   Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct NewArgs {
+    #[clap(help = "Name of the scenario: the file is named `<name>.luci.yaml`")]
+    name:       String,
+    #[clap(
+        long = "dir",
+        short = 'd',
+        default_value = ".",
+        help = "Directory to create the files in"
+    )]
+    dir:        PathBuf,
+    #[clap(
+        long = "with-test",
+        default_value_t = false,
+        help = "Also create `<name>.rs` with a test running the scenario"
+    )]
+    with_test:  bool,
+    #[clap(
+        long = "repeat",
+        requires = "with_test",
+        help = "Make the test run the scenario the given number of times (see `run_repeated`)"
+    )]
+    repeat:     Option<NonZeroUsize>,
+    #[clap(
+        long = "time-scale",
+        requires = "with_test",
+        value_parser = parse_time_scale,
+        help = "Make the test scale the durations of the scenario (see `RunOptions`)"
+    )]
+    time_scale: Option<f64>,
+}
+
+fn parse_time_scale(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(time_scale) if time_scale.is_finite() && time_scale > 0.0 => Ok(time_scale),
+        Ok(_) => Err("should be a finite positive number".into()),
+        Err(reason) => Err(reason.to_string()),
+    }
+}
+
+const NEW_SCENARIO: &str = r#"# {name}: replace the types, the actors and the events with the real ones.
+
+types:
+  - use: my_crate::proto::Request
+    as: Request
+  - use: my_crate::proto::Reply
+    as: Reply
+
+actors:
+  - subject
+
+dummies:
+  - client
+
+events:
+  - id: client-sends-request
+    send:
+      from: client
+      type: Request
+      data:
+        literal: ~
+
+  - id: subject-replies
+    require: reached
+    happens_after:
+      - client-sends-request
+    recv:
+      from: subject
+      to: client
+      type: Reply
+      data: $REPLY
+"#;
+
+const NEW_TEST: &str = r#"use luci::execution::{Executable, SourceCodeLoader};
+use luci::marshalling::{MarshallingRegistry, Regular};
+use serde_json::json;
+
+#[tokio::test]
+async fn {test_name}() {
+    let marshalling = MarshallingRegistry::new()
+        .with(Regular::<my_crate::proto::Request>)
+        .with(Regular::<my_crate::proto::Reply>);
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("{scenario_file}")
+        .expect("SourceCodeLoader::load");
+    let executable = Executable::build(marshalling, &sources, key_main).expect("Executable::build");
+    let report = executable
+        .start(my_crate::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("Runner::run");
+
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+}
+"#;
+
+const NEW_SOAK_TEST: &str = r#"use luci::execution::{Executable, RunOptions, SourceCodeLoader};
+use luci::marshalling::{MarshallingRegistry, Regular};
+use serde_json::json;
+
+#[tokio::test]
+async fn {test_name}() {
+    let marshalling = MarshallingRegistry::new()
+        .with(Regular::<my_crate::proto::Request>)
+        .with(Regular::<my_crate::proto::Reply>);
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("{scenario_file}")
+        .expect("SourceCodeLoader::load");
+    let executable = Executable::build(marshalling, &sources, key_main).expect("Executable::build");
+    let options = RunOptions::default().with_time_scale({time_scale});
+    let soak = executable
+        .run_repeated_with_options({repeat}, my_crate::blueprint, json!(null), [], options)
+        .await;
+
+    assert!(soak.is_ok(), "{}", soak.message(&executable));
+}
+"#;
+
 fn main() {
     let args = Args::parse();
 
+    if let Some(command) = &args.command {
+        let exit_code = match command {
+            Command::Suite(suite_args) => suite(suite_args),
+            #[cfg(feature = "backward-compatibility")]
+            Command::Migrate(migrate_args) => migrate(migrate_args),
+            Command::Fmt(fmt_args) => fmt(fmt_args),
+            Command::Messages(messages_args) => messages(messages_args),
+            Command::Explain(explain_args) => explain(explain_args),
+            Command::New(new_args) => new(new_args),
+            Command::Schema => schema(),
+        };
+        std::process::exit(exit_code);
+    }
+
     let result = run(&args);
 
     match args.output_file {
@@ -48,35 +362,477 @@ fn run(args: &Args) -> String {
         .with_max_level(tracing::Level::DEBUG)
         .try_init();
 
-    let scenario = if let Some(path) = &args.scenario_file {
-        read_to_string(path).expect("Failed to read scenario file")
-    } else {
+    // the subroutines of a scenario read from stdin cannot be looked up
+    let Some(path) = &args.scenario_file else {
         let mut input = String::new();
         std::io::stdin()
             .read_to_string(&mut input)
             .expect("Failed to read from stdin");
-        input.trim().to_string()
+        let scenario: Scenario =
+            serde_yaml::from_str(input.trim()).expect("Failed to parse YAML scenario file");
+
+        return draw_scenario(&scenario, args.verbose)
+    };
+
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file_name = path.file_name().expect("Failed to read scenario file");
+    let (key_main, sources) = SourceCodeLoader::new()
+        .reset_search_path()
+        .with_search_path([dir])
+        .with_search_path(&args.search_path)
+        .load(file_name)
+        .expect("Failed to load scenario file");
+
+    let options = args.toggle.iter().fold(
+        DrawOptions::default()
+            .with_verbose(args.verbose)
+            .with_expand(args.expand),
+        |options, path| options.with_toggled(path),
+    );
+    draw_source_code(&sources, key_main, &options)
+}
+
+fn suite(args: &SuiteArgs) -> i32 {
+    let scenarios = luci::suite::discover(&args.dir).expect("Failed to discover scenarios");
+
+    let mut failed = 0;
+    for scenario in &scenarios {
+        match check_scenario(args, scenario) {
+            Ok((loaded_in, built_in)) if args.verbose => {
+                println!(
+                    "ok   {} (load: {:?}, build: {:?})",
+                    scenario.display(),
+                    loaded_in,
+                    built_in
+                )
+            },
+            Ok(_) => println!("ok   {}", scenario.display()),
+            Err(reason) => {
+                failed += 1;
+                println!("FAIL {}: {}", scenario.display(), reason);
+            },
+        }
+    }
+    println!("{} scenarios, {} failed", scenarios.len(), failed);
+
+    if failed == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+fn check_scenario(args: &SuiteArgs, scenario: &Path) -> Result<(Duration, Duration), String> {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .reset_search_path()
+        .with_search_path([&args.dir])
+        .with_search_path(&args.search_path)
+        .load(scenario)
+        .map_err(|e| e.to_string())?;
+    Executable::build(mock_marshalling(&sources), &sources, key_main)
+        .map(|executable| (sources.loaded_in(), executable.built_in()))
+        .map_err(|e| e.to_string())
+}
+
+fn mock_marshalling(sources: &SourceCode) -> MarshallingRegistry {
+    sources
+        .scenarios()
+        .flat_map(|(_, source)| source.scenario.types.iter())
+        .map(|t| t.type_name.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .fold(MarshallingRegistry::new(), |registry, fqn| {
+            registry.with(Mock::request(fqn))
+        })
+}
+
+fn scenario_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for path in paths {
+        if path.is_dir() {
+            let found = luci::suite::discover(path).expect("Failed to discover scenarios");
+            files.extend(found.into_iter().map(|f| path.join(f)));
+        } else {
+            files.push(path.to_owned());
+        }
+    }
+    files
+}
+
+#[cfg(feature = "backward-compatibility")]
+fn migrate(args: &MigrateArgs) -> i32 {
+    let files = scenario_files(&args.paths);
+
+    let mut failed = 0;
+    for file in &files {
+        if let Err(reason) = migrate_file(file, args) {
+            failed += 1;
+            println!("FAIL {}: {}", file.display(), reason);
+        }
+    }
+
+    if failed == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(feature = "backward-compatibility")]
+fn migrate_file(file: &Path, args: &MigrateArgs) -> Result<(), String> {
+    let yaml = read_to_string(file).map_err(|e| e.to_string())?;
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(&yaml).map_err(|e| e.to_string())?;
+
+    let changes = luci::migration::migrate(&mut doc);
+    for change in &changes {
+        println!("{}: {}", file.display(), change);
+    }
+
+    serde_yaml::from_value::<Scenario>(doc.clone()).map_err(|e| e.to_string())?;
+
+    if !changes.is_empty() && !args.dry_run {
+        if has_comments(&yaml) && !args.force {
+            return Err("the comments would be lost (use --force to rewrite it anyway)".into())
+        }
+        let yaml = serde_yaml::to_string(&doc).map_err(|e| e.to_string())?;
+        std::fs::write(file, yaml).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn fmt(args: &FmtArgs) -> i32 {
+    let files = scenario_files(&args.paths);
+
+    let mut failed = 0;
+    for file in &files {
+        match fmt_file(file, args) {
+            Ok(true) => (),
+            Ok(false) => {
+                failed += 1;
+                println!("UNFORMATTED {}", file.display());
+            },
+            Err(reason) => {
+                failed += 1;
+                println!("FAIL {}: {}", file.display(), reason);
+            },
+        }
+    }
+
+    if failed == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+/// Returns whether the file is formatted, unless it is only printed; the file
+/// is only rewritten with `--write`.
+fn fmt_file(file: &Path, args: &FmtArgs) -> Result<bool, String> {
+    let yaml = read_to_string(file).map_err(|e| e.to_string())?;
+    let formatted = format_scenarios(&yaml)?;
+
+    if args.check {
+        return Ok(formatted == yaml)
+    }
+    if !args.write {
+        if has_comments(&yaml) {
+            eprintln!("WARN {}: the comments are not preserved", file.display());
+        }
+        print!("{}", formatted);
+        return Ok(true)
+    }
+    if formatted == yaml {
+        return Ok(true)
+    }
+    if has_comments(&yaml) && !args.force {
+        return Err("the comments would be lost (use --force to rewrite it anyway)".into())
+    }
+    std::fs::write(file, formatted).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Whether there seem to be comments in the YAML: a `#` starting a line, or
+/// following a blank, outside of the quotes. A `#` in a block scalar is taken
+/// for a comment too.
+fn has_comments(yaml: &str) -> bool {
+    yaml.lines().any(|line| {
+        let mut quote = None;
+        let mut prev = ' ';
+        for c in line.chars() {
+            match (quote, c) {
+                (None, '#') if prev.is_whitespace() => return true,
+                (None, '\'' | '"') => quote = Some(c),
+                (Some(q), c) if q == c => quote = None,
+                _ => (),
+            }
+            prev = c;
+        }
+        false
+    })
+}
+
+/// Re-emits the scenarios (one per YAML document) in the order the fields are
+/// declared in [Scenario].
+fn format_scenarios(yaml: &str) -> Result<String, String> {
+    let documents = serde_yaml::Deserializer::from_str(yaml)
+        .map(|document| {
+            let scenario = Scenario::deserialize(document).map_err(|e| e.to_string())?;
+            serde_yaml::to_string(&scenario).map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(documents.join("---\n"))
+}
+
+fn explain(args: &ExplainArgs) -> i32 {
+    let built = SourceCodeLoader::new()
+        .with_search_path(&args.search_path)
+        .load(&args.scenario_file)
+        .map_err(|e| e.to_string())
+        .and_then(|(key_main, sources)| {
+            let built = Executable::build(mock_marshalling(&sources), &sources, key_main)
+                .map_err(|e| e.to_string());
+            built.map(|executable| (executable, sources))
+        });
+    let (executable, sources) = match built {
+        Ok(built) => built,
+        Err(reason) => {
+            println!("FAIL {}: {}", args.scenario_file.display(), reason);
+            return 1
+        },
     };
 
-    let scenario: Scenario =
-        serde_yaml::from_str(&scenario).expect("Failed to parse YAML scenario file");
+    let found = executable.events_named(&args.event);
+    if found.is_empty() {
+        println!("FAIL {}: no such event", args.event);
+        return 1
+    }
+    for event_key in found {
+        if let Some(explanation) = executable.explain(event_key) {
+            print!("{}", explanation.message(&executable, &sources));
+        }
+    }
+    0
+}
 
-    draw_scenario(&scenario, args.verbose)
+fn new(args: &NewArgs) -> i32 {
+    let is_valid_name = !args.name.is_empty()
+        && args
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_valid_name {
+        println!(
+            "FAIL {:?}: a name consists of letters, digits, '-' and '_'",
+            args.name
+        );
+        return 1
+    }
+
+    let scenario_file = args.dir.join(format!("{}.luci.yaml", args.name));
+    let mut files = vec![(scenario_file.clone(), new_scenario(&args.name))];
+    if args.with_test {
+        let test_file = args.dir.join(format!("{}.rs", args.name));
+        files.push((test_file, new_test(args, &scenario_file)));
+    }
+
+    for (file, _) in &files {
+        if file.exists() {
+            println!("FAIL {}: already exists", file.display());
+            return 1
+        }
+    }
+    for (file, contents) in &files {
+        if let Err(reason) = std::fs::write(file, contents) {
+            println!("FAIL {}: {}", file.display(), reason);
+            return 1
+        }
+        println!("created {}", file.display());
+    }
+    0
+}
+
+fn new_scenario(name: &str) -> String {
+    NEW_SCENARIO.replace("{name}", name)
+}
+
+fn new_test(args: &NewArgs, scenario_file: &Path) -> String {
+    let test = if args.repeat.is_none() && args.time_scale.is_none() {
+        NEW_TEST.to_owned()
+    } else {
+        let repeat = args.repeat.map_or(1, NonZeroUsize::get);
+        let time_scale = args.time_scale.unwrap_or(1.0);
+        NEW_SOAK_TEST
+            .replace("{repeat}", &repeat.to_string())
+            .replace("{time_scale}", &format!("{:?}", time_scale))
+    };
+    test.replace("{test_name}", &args.name.replace('-', "_"))
+        .replace("{scenario_file}", &scenario_file.display().to_string())
+}
+
+fn schema() -> i32 {
+    let schema = serde_json::to_string_pretty(&Scenario::json_schema())
+        .expect("Failed to serialize the schema");
+    println!("{}", schema);
+    0
+}
+
+fn messages(args: &MessagesArgs) -> i32 {
+    let (_, sources) = match SourceCodeLoader::new()
+        .with_search_path(&args.search_path)
+        .load(&args.scenario_file)
+    {
+        Ok(loaded) => loaded,
+        Err(reason) => {
+            println!("FAIL {}: {}", args.scenario_file.display(), reason);
+            return 1
+        },
+    };
+
+    let mut requests = BTreeSet::new();
+    let mut regulars = BTreeSet::new();
+    for (_, source) in sources.scenarios() {
+        let scenario = &source.scenario;
+        let fqn_by_alias = scenario
+            .types
+            .iter()
+            .map(|t| (&t.type_alias, t.type_name.as_str()))
+            .collect::<HashMap<_, _>>();
+        let responded_to = scenario
+            .all_events()
+            .filter_map(|e| {
+                match &e.kind {
+                    DefEventKind::Respond(respond) => Some(respond.to_request.clone()),
+                    _ => None,
+                }
+            })
+            .collect::<HashSet<_>>();
+        for event in scenario.all_events() {
+            let request_type = match &event.kind {
+                DefEventKind::Recv(recv) if responded_to.contains(&event.id) => {
+                    Some(&recv.message_type)
+                },
+                DefEventKind::Request(request) => Some(&request.message_type),
+                _ => None,
+            };
+            if let Some(fqn) = request_type.and_then(|t| fqn_by_alias.get(t)) {
+                requests.insert(*fqn);
+            }
+        }
+        regulars.extend(fqn_by_alias.into_values());
+    }
+
+    let registry = regulars
+        .into_iter()
+        .map(|fqn| Mock::new(fqn, requests.contains(fqn)))
+        .fold(MarshallingRegistry::new(), MarshallingRegistry::with);
+    for message in registry.messages() {
+        println!("{}", message);
+    }
+
+    0
 }
 
 #[cfg(test)]
 mod test {
-    use super::run;
+    use luci::execution::{Executable, SourceCodeLoader};
+    use luci::scenario::Scenario;
+
+    use super::{format_scenarios, has_comments, mock_marshalling, new_scenario, new_test, run};
 
     #[test]
     fn output_snapshot() {
         let args = super::Args {
+            command:       None,
             scenario_file: Some("tests/luci_graph/sample.luci.yml".into()),
-            output_file: None,
-            verbose: true,
+            output_file:   None,
+            verbose:       true,
+            expand:        false,
+            toggle:        vec![],
+            search_path:   vec![],
         };
         let result = run(&args);
 
         insta::assert_snapshot!(result);
     }
+
+    #[test]
+    fn expanding_requires_a_file() {
+        use clap::Parser;
+
+        assert!(super::Args::try_parse_from(["luci", "--expand"]).is_err());
+        assert!(super::Args::try_parse_from(["luci", "--toggle", "call"]).is_err());
+        assert!(super::Args::try_parse_from(["luci", "-i", "x.luci.yaml", "--expand"]).is_ok());
+    }
+
+    #[test]
+    fn format_is_idempotent() {
+        let yaml = std::fs::read_to_string("tests/source_loading/05-multi-document.luci.yaml")
+            .expect("fs::read_to_string");
+        let formatted = format_scenarios(&yaml).expect("format_scenarios");
+
+        assert_eq!(formatted.matches("---").count(), 1);
+        assert_eq!(
+            format_scenarios(&formatted).as_deref(),
+            Ok(formatted.as_str())
+        );
+    }
+
+    #[test]
+    fn comments_are_detected() {
+        assert!(has_comments("# a comment\nevents: []\n"));
+        assert!(has_comments("events: [] # a comment\n"));
+        assert!(!has_comments("values:\n  $N: \"#1\"\n  $M: a#b\n"));
+    }
+
+    #[test]
+    fn new_scenario_is_valid() {
+        let yaml = new_scenario("the-one-with-the-skeleton");
+        let scenario: Scenario = serde_yaml::from_str(&yaml).expect("yaml::from_str<Scenario>");
+
+        assert_eq!(scenario.events.len(), 2);
+
+        let dir = std::env::temp_dir().join(format!("luci-new-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("fs::create_dir_all");
+        let scenario_file = dir.join("the-one-with-the-skeleton.luci.yaml");
+        std::fs::write(&scenario_file, &yaml).expect("fs::write");
+        let loaded = SourceCodeLoader::new()
+            .with_search_path([&dir])
+            .load("the-one-with-the-skeleton.luci.yaml");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (key_main, sources) = loaded.expect("SourceLoader::load");
+        Executable::build(mock_marshalling(&sources), &sources, key_main).expect("building graph");
+    }
+
+    #[test]
+    fn new_soak_test() {
+        use clap::Parser;
+
+        let parse =
+            |args: &[&str]| super::Args::try_parse_from(["luci", "new", "soak"].iter().chain(args));
+        assert!(parse(&["--repeat", "3"]).is_err());
+        assert!(parse(&["--with-test", "--repeat", "0"]).is_err());
+        assert!(parse(&["--with-test", "--time-scale", "0"]).is_err());
+
+        let Ok(super::Args {
+            command: Some(super::Command::New(args)),
+            ..
+        }) = parse(&["--with-test", "--repeat", "3", "--time-scale", "0.5"])
+        else {
+            panic!("expected the `new` command")
+        };
+        let test = new_test(&args, "soak.luci.yaml".as_ref());
+
+        assert!(test.contains("async fn soak()"), "{}", test);
+        assert!(
+            test.contains("RunOptions::default().with_time_scale(0.5)"),
+            "{}",
+            test
+        );
+        assert!(test.contains(".run_repeated_with_options(3, "), "{}", test);
+        assert!(!test.contains("{repeat}") && !test.contains("{time_scale}"));
+    }
 }
@@ -1,29 +1,145 @@
 use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
 
 use bimap::BiHashMap;
 use elfo::Addr;
-use serde_json::Value;
+use parking_lot::Mutex;
+use serde_json::{Map, Value};
 use tracing::info;
 
 use crate::bindings;
-use crate::names::ActorName;
+use crate::execution::EventKey;
+use crate::names::{ActorName, DummyName};
 use crate::recorder::{records, Recorder};
-use crate::scenario::DstPattern;
+use crate::scenario::{BindingType, DstPattern};
+
+mod path;
 
 #[derive(Debug, thiserror::Error)]
 pub enum BindError {
     #[error("unbound value: {}", _0)]
     UnboundValue(String),
+
+    #[error("cannot apply {} to {}", _0, _1)]
+    CannotTransform(&'static str, Value),
+
+    #[error("invalid path (the root should be a bound variable): {}", _0)]
+    InvalidPath(String),
+
+    #[error("nothing at path: {}", _0)]
+    NothingAtPath(String),
+
+    #[error("unknown address: {}", _0)]
+    UnknownAddress(String),
+}
+
+/// The name the payload of a request is bound to when responding to it.
+pub(crate) const REQUEST_BINDING: &str = "$request";
+
+/// The operators transforming a rendered value, written as `{"$op": template}`:
+/// the template is rendered first, then the operator is applied to the result.
+/// The operators nest: `{"$len": {"$to_string": "$x"}}`.
+const TRANSFORMS: [(&str, Transform); 5] = [
+    ("$to_string", Transform::ToString),
+    ("$parse_int", Transform::ParseInt),
+    ("$lowercase", Transform::Lowercase),
+    ("$uppercase", Transform::Uppercase),
+    ("$len", Transform::Len),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Transform {
+    /// A string as it is, anything else as its JSON.
+    ToString,
+    /// A string holding an integer, or an integer as it is.
+    ParseInt,
+    Lowercase,
+    Uppercase,
+    /// The number of the characters of a string, of the items of an array, or
+    /// of the fields of an object.
+    Len,
+}
+
+/// An attempt to bind a variable that fails the run rather than the match.
+///
+/// The [TypeMismatch]es are not taken by [Txn::take_violation]: see
+/// [Txn::take_mismatch].
+#[derive(Debug, Clone)]
+pub(crate) enum Violation {
+    Invariant(InvariantViolation),
+    Type(TypeMismatch),
+}
+
+/// An attempt to unify a variable declared as [`Invariant::Same`] with a value
+/// different from the one it is bound to.
+///
+/// [`Invariant::Same`]: crate::scenario::Invariant::Same
+#[derive(Debug, Clone)]
+pub(crate) struct InvariantViolation {
+    pub(crate) name:      String,
+    pub(crate) bound:     Value,
+    pub(crate) bound_by:  Option<EventKey>,
+    pub(crate) attempted: Value,
+}
+
+/// An attempt to bind a variable to a value not of its declared type.
+#[derive(Debug, Clone)]
+pub(crate) struct TypeMismatch {
+    pub(crate) name:      String,
+    pub(crate) declared:  BindingType,
+    pub(crate) attempted: Value,
+}
+
+impl From<TypeMismatch> for Violation {
+    fn from(mismatch: TypeMismatch) -> Self {
+        Self::Type(mismatch)
+    }
 }
 
 /// Stores bindings:
 /// - luci variables bound to [values](Value);
-/// - actor names bound to [addresses](Addr).
+/// - actor names bound to [addresses](Addr);
+/// - the named counters.
+///
+/// The addresses of the actors and the dummies known in the scope are
+/// referred to as `{"$addr_of": "name"}`: in a template, it renders as the
+/// address the way elfo serializes it; in a pattern, it matches that address.
+/// The address of an actor is known once a recv has bound the actor.
+///
+/// The routing keys are known the same way: once a recv `from_key` has
+/// matched the actor serving the key, the sends with that `routing_key` go to
+/// its address directly. These are the aliases of the addresses kept by luci,
+/// rather than elfo's routing, and each scope has its own.
+///
+/// In a template, `$counter(name)` renders as the next number of the counter
+/// (starting with 1). In a pattern, it matches only the number following the
+/// one it has matched last: a gap or a reordering in the sequence fails the
+/// match.
+///
+/// A scope [with placeholders](Scope::with_placeholders) renders what it does
+/// not know as `"<$NAME>"` instead of failing the render.
 #[derive(Debug, Default)]
 pub(crate) struct Scope {
-    values: HashMap<String, Value>,
-    actors: BiHashMap<ActorName, Addr>,
+    values:  HashMap<String, Value>,
+    actors:  BiHashMap<ActorName, Addr>,
+    dummies: HashMap<DummyName, Addr>,
+
+    /// The addresses of the actors serving the routing keys, by the keys'
+    /// JSON.
+    routing_keys: HashMap<String, Addr>,
+
+    invariants: HashSet<String>,
+    types:      HashMap<String, BindingType>,
+    bound_by:   HashMap<String, EventKey>,
+
+    /// The last numbers rendered, shared with the forks of the scope.
+    issued:  Arc<Mutex<HashMap<String, u64>>>,
+    /// The last numbers matched.
+    matched: HashMap<String, u64>,
+
+    placeholders: bool,
 }
 
 /// A transaction on a [Scope].
@@ -38,6 +154,20 @@ pub(crate) struct Txn<'a> {
 
     actors_committed: &'a mut BiHashMap<ActorName, Addr>,
     actors_added:     BiHashMap<ActorName, Addr>,
+
+    dummies: &'a HashMap<DummyName, Addr>,
+
+    invariants: &'a HashSet<String>,
+    types:      &'a HashMap<String, BindingType>,
+    bound_by:   &'a mut HashMap<String, EventKey>,
+    violation:  Option<Violation>,
+    mismatch:   Option<TypeMismatch>,
+
+    matched_committed: &'a mut HashMap<String, u64>,
+    matched_added:     HashMap<String, u64>,
+
+    /// The values to append to the array bindings, bound as `$name[]`.
+    appended: Vec<(String, Value)>,
 }
 
 impl Scope {
@@ -45,33 +175,179 @@ impl Scope {
     pub(crate) fn from_values(values: HashMap<String, Value>) -> Self {
         Self {
             values,
-            actors: Default::default(),
+            ..Default::default()
+        }
+    }
+
+    /// Declares the variables that may not be re-bound to a different value.
+    pub(crate) fn with_invariants(self, invariants: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            invariants: invariants.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Declares the types of the variables.
+    pub(crate) fn with_types(self, types: impl IntoIterator<Item = (String, BindingType)>) -> Self {
+        Self {
+            types: types.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Renders the unbound variables, the unknown addresses and the paths from
+    /// the unbound variables as the placeholders: `"<$NAME>"`,
+    /// `"<{"$addr_of":"name"}>"`, and so on.
+    pub(crate) fn with_placeholders(self) -> Self {
+        Self {
+            placeholders: true,
+            ..self
+        }
+    }
+
+    /// Creates a [Scope] with the values of this one: the bindings made in it
+    /// do not leak back, the numbers rendered by its counters do.
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            actors: self.actors.clone(),
+            dummies: self.dummies.clone(),
+            routing_keys: self.routing_keys.clone(),
+            issued: self.issued.clone(),
+            matched: self.matched.clone(),
+            ..Self::from_values(self.values.clone())
         }
     }
 
     /// Creates a [Txn] on the current state of the [Scope].
-    pub(crate) fn txn(&mut self) -> Txn {
+    pub(crate) fn txn(&mut self) -> Txn<'_> {
         Txn {
             values_committed: &mut self.values,
             values_added:     Default::default(),
 
             actors_committed: &mut self.actors,
             actors_added:     Default::default(),
+
+            dummies: &self.dummies,
+
+            invariants: &self.invariants,
+            types:      &self.types,
+            bound_by:   &mut self.bound_by,
+            violation:  None,
+            mismatch:   None,
+
+            matched_committed: &mut self.matched,
+            matched_added:     Default::default(),
+
+            appended: Default::default(),
         }
     }
 
+    /// Returns the event that has bound the variable `key`, if any.
+    ///
+    /// The values the scope has been created with have no such event.
+    pub(crate) fn bound_by(&self, key: &str) -> Option<EventKey> {
+        self.bound_by.get(key).copied()
+    }
+
+    /// Iterates over the bound variables along with the events that have bound
+    /// them.
+    pub(crate) fn provenance(&self) -> impl Iterator<Item = (&str, EventKey)> + '_ {
+        self.bound_by.iter().map(|(k, e)| (k.as_str(), *e))
+    }
+
+    /// Iterates over the bound variables and their values.
+    pub(crate) fn values(&self) -> impl Iterator<Item = (&str, &Value)> + '_ {
+        self.values.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Makes the address of the actor known by `name` in the scope.
+    pub(crate) fn bind_actor(&mut self, name: ActorName, addr: Addr) {
+        self.actors.insert(name, addr);
+    }
+
+    /// Makes the address of the dummy known by `name` in the scope.
+    pub(crate) fn bind_dummy(&mut self, name: DummyName, addr: Addr) {
+        self.dummies.insert(name, addr);
+    }
+
+    /// Makes the address of the actor serving `routing_key` known in the
+    /// scope.
+    pub(crate) fn bind_routing_key(&mut self, routing_key: &Value, addr: Addr) {
+        self.routing_keys.insert(routing_key.to_string(), addr);
+    }
+
+    /// Returns the address of the actor serving `routing_key`, if known in
+    /// the scope.
+    pub(crate) fn routing_key_addr(&self, routing_key: &Value) -> Option<Addr> {
+        self.routing_keys.get(&routing_key.to_string()).copied()
+    }
+
+    /// Advances the counter `name`, returning its new number.
+    pub(crate) fn next_number(&self, name: &str) -> u64 {
+        let mut issued = self.issued.lock();
+        let number = issued.entry(name.to_owned()).or_default();
+        *number += 1;
+        *number
+    }
+
     /// Returns bound [Value] for the specified `key` if there is one.
     /// Otherwise returns `None`.
-    fn value_of(&self, key: &str) -> Option<&Value> {
-        self.values.get(key)
+    ///
+    /// Unless bound itself, `$request.field` refers to a field (or an item) of
+    /// the request being responded to. The other names are looked up as they
+    /// are, dots included: a path into their values is a `$path`.
+    pub(crate) fn value_of(&self, key: &str) -> Option<&Value> {
+        if let Some(value) = self.values.get(key) {
+            return Some(value)
+        }
+        let (name, path) = key
+            .split_once('.')
+            .filter(|(name, _)| *name == REQUEST_BINDING)?;
+        path.split('.')
+            .try_fold(self.values.get(name)?, |value, field| {
+                match value {
+                    Value::Object(kv) => kv.get(field),
+                    Value::Array(items) => items.get(field.parse::<usize>().ok()?),
+                    _ => None,
+                }
+            })
     }
 }
 
 impl Txn<'_> {
     /// Binds `key` to `value` and stores in the transaction.
+    ///
+    /// A `key` ending with `[]` never conflicts: the value is appended to the
+    /// array bound to the `key` without the suffix once the transaction is
+    /// committed. Should that variable be bound to anything but an array, the
+    /// binding becomes an array with that value as its first item.
     pub(crate) fn bind_value(&mut self, key: &str, value: &Value) -> bool {
+        if let Some(name) = key.strip_suffix("[]") {
+            self.appended.push((name.to_owned(), value.clone()));
+            return true
+        }
+        if let Some(declared) = self.types.get(key).filter(|ty| !ty.admits(value)) {
+            // the rest of the pattern is matched still: see `take_mismatch`
+            if self.mismatch.is_none() {
+                self.mismatch = Some(TypeMismatch {
+                    name:      key.to_owned(),
+                    declared:  *declared,
+                    attempted: value.clone(),
+                });
+            }
+            return true
+        }
         if let Some(defined_in_state) = self.values_committed.get(key) {
-            defined_in_state == value
+            let same = defined_in_state == value;
+            if !same && self.violation.is_none() && self.invariants.contains(key) {
+                self.violation = Some(Violation::Invariant(InvariantViolation {
+                    name:      key.to_owned(),
+                    bound:     defined_in_state.clone(),
+                    bound_by:  self.bound_by.get(key).copied(),
+                    attempted: value.clone(),
+                }));
+            }
+            same
         } else {
             match self.values_added.entry(key.to_owned()) {
                 Occupied(o) => o.get() == value,
@@ -83,11 +359,95 @@ impl Txn<'_> {
         }
     }
 
-    /// Commits transaction to the [Scope].
-    pub(crate) fn commit(self, recorder: &mut Recorder<'_>) {
+    /// Matches `value` against the number following the one the counter `name`
+    /// has matched last, and stores the match in the transaction.
+    pub(crate) fn match_number(&mut self, name: &str, value: &Value) -> bool {
+        let last = self
+            .matched_added
+            .get(name)
+            .or_else(|| self.matched_committed.get(name))
+            .copied()
+            .unwrap_or_default();
+        if value.as_u64() != Some(last + 1) {
+            return false
+        }
+        self.matched_added.insert(name.to_owned(), last + 1);
+        true
+    }
+
+    /// The values bound by this transaction.
+    pub(crate) fn added(&self) -> impl Iterator<Item = (&str, &Value)> + '_ {
+        self.values_added.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Returns the [Value] bound to `key`, by this transaction or before it.
+    pub(crate) fn bound_value(&self, key: &str) -> Option<&Value> {
+        self.values_added
+            .get(key)
+            .or_else(|| self.values_committed.get(key))
+    }
+
+    /// Takes the first [Violation] encountered by this transaction, if any.
+    pub(crate) fn take_violation(&mut self) -> Option<Violation> {
+        self.violation.take()
+    }
+
+    /// Takes the first [TypeMismatch] encountered by this transaction, if any.
+    ///
+    /// A mismatching value is not bound, but does not fail the match either:
+    /// the pattern is a match but for the types only if it is bound in full.
+    pub(crate) fn take_mismatch(&mut self) -> Option<TypeMismatch> {
+        self.mismatch.take()
+    }
+
+    /// Takes the values to be appended to the array bindings, so that they
+    /// survive a transaction that is not committed.
+    pub(crate) fn take_appended(&mut self) -> Vec<(String, Value)> {
+        std::mem::take(&mut self.appended)
+    }
+
+    /// Puts the values taken by [Txn::take_appended] ahead of the ones
+    /// appended by this transaction.
+    pub(crate) fn prepend_appended(&mut self, earlier: Vec<(String, Value)>) {
+        self.appended.splice(0..0, earlier);
+    }
+
+    /// Turns the appended values into the array bindings: a variable that is
+    /// already bound to a non-array value gets that value as the first item.
+    fn fold_appended(&mut self) {
+        for (name, value) in std::mem::take(&mut self.appended) {
+            let mut items = match self
+                .values_added
+                .remove(&name)
+                .or_else(|| self.values_committed.get(&name).cloned())
+            {
+                Some(Value::Array(items)) => items,
+                Some(other) => vec![other],
+                None => vec![],
+            };
+            items.push(value);
+            self.values_added.insert(name, Value::Array(items));
+        }
+    }
+
+    /// Applies the transaction to the [Scope] without attributing the new
+    /// bindings to an event.
+    pub(crate) fn apply(mut self) {
+        self.fold_appended();
+        self.values_committed.extend(self.values_added);
+        self.actors_committed.extend(self.actors_added);
+        self.matched_committed.extend(self.matched_added);
+    }
+
+    /// Commits transaction to the [Scope], remembering `bound_by` as the event
+    /// that introduced the new bindings.
+    pub(crate) fn commit(mut self, bound_by: EventKey, recorder: &mut Recorder<'_>) {
+        self.fold_appended();
+        let provenance = &mut *self.bound_by;
         self.values_committed
             .extend(self.values_added.into_iter().inspect(|(k, v)| {
                 recorder.write(records::NewBinding(k.clone(), v.clone()));
+                provenance.insert(k.clone(), bound_by);
                 info!("SET VALUE {:?} <- {:?}", k, v);
             }));
         self.actors_committed.extend(
@@ -95,6 +455,7 @@ impl Txn<'_> {
                 .into_iter()
                 .inspect(|(k, v)| info!("SET ACTOR {:?} <- {:?}", k, v)),
         );
+        self.matched_committed.extend(self.matched_added);
     }
 }
 
@@ -106,7 +467,10 @@ pub(crate) fn bind_to_pattern(value: Value, pattern: &DstPattern, bindings: &mut
             (_, Value::String(wildcard)) if wildcard == "$_" => true,
 
             (value, Value::String(var_name)) if var_name.starts_with('$') => {
-                bindings.bind_value(var_name, &value)
+                match counter_name(var_name) {
+                    Some(counter) => bindings.match_number(counter, &value),
+                    None => bindings.bind_value(var_name, &value),
+                }
             },
 
             (Value::Null, Value::Null) => true,
@@ -121,6 +485,23 @@ pub(crate) fn bind_to_pattern(value: Value, pattern: &DstPattern, bindings: &mut
                         .all(|(v, p)| inner(v, p, bindings))
             },
 
+            (value, Value::Object(p)) if addr_of(p).is_some() => {
+                let name = addr_of(p).expect("checked by the guard");
+                address_of(bindings.actors_committed, bindings.dummies, name)
+                    .is_some_and(|addr| value == addr_value(addr))
+            },
+
+            (value, Value::Object(p)) if p.contains_key(VARIANT) => {
+                let Some(Value::String(name)) = p.get(VARIANT) else {
+                    return false
+                };
+                let Some(content) = variant_content(value, name) else {
+                    return false
+                };
+                p.get(VARIANT_CONTENT)
+                    .is_none_or(|pattern| inner(content, pattern, bindings))
+            },
+
             (Value::Object(mut v), Value::Object(p)) => {
                 p.iter()
                     .all(|(pk, pv)| v.remove(pk).is_some_and(|vv| inner(vv, pv, bindings)))
@@ -132,32 +513,103 @@ pub(crate) fn bind_to_pattern(value: Value, pattern: &DstPattern, bindings: &mut
     inner(value, &pattern.0, bindings)
 }
 
+/// The key of an object standing for the address of an actor or a dummy.
+const ADDR_OF: &str = "$addr_of";
+
+/// The key of a pattern matching a variant of an enum, whichever way serde
+/// represents it: `{"$variant": "Name"}` matches `"Name"`, `{"Name": ...}`, and
+/// `{"type": "Name", ...}` alike (`tag` and `kind` are recognized as the tags
+/// besides `type`).
+const VARIANT: &str = "$variant";
+/// The key of the pattern to match the content of the variant with: the value
+/// inside `{"Name": ...}`, the fields besides the tag of `{"type": "Name",
+/// ...}`, or `null` for `"Name"`.
+const VARIANT_CONTENT: &str = "$with";
+const VARIANT_TAGS: [&str; 3] = ["type", "tag", "kind"];
+
+/// The content of the variant `name`, unless `value` is a different one.
+fn variant_content(value: Value, name: &str) -> Option<Value> {
+    match value {
+        Value::String(unit) => (unit == name).then_some(Value::Null),
+        Value::Object(mut kv) => {
+            if kv.len() == 1 && kv.contains_key(name) {
+                return kv.remove(name)
+            }
+            let tag = VARIANT_TAGS
+                .into_iter()
+                .find(|tag| kv.get(*tag).and_then(Value::as_str) == Some(name))?;
+            kv.remove(tag);
+            Some(Value::Object(kv))
+        },
+        _ => None,
+    }
+}
+
 /// Renders luci variables in `template` with values from `bindings`.
 ///
 /// Returns:
 /// - The resulting [Value] after template render on success;
 /// - [BindError] on error.
 pub(crate) fn render(template: Value, bindings: &bindings::Scope) -> Result<Value, BindError> {
+    render_with(template, bindings, bindings.placeholders)
+}
+
+fn render_with(
+    template: Value,
+    bindings: &bindings::Scope,
+    placeholders: bool,
+) -> Result<Value, BindError> {
     match template {
         Value::String(wildcard) if wildcard == "$_" => Err(BindError::UnboundValue(wildcard)),
         Value::String(var_name) if var_name.starts_with('$') => {
-            bindings
-                .value_of(&var_name)
-                .cloned()
-                .ok_or(BindError::UnboundValue(var_name))
+            if let Some(counter) = counter_name(&var_name) {
+                return Ok(bindings.next_number(counter).into())
+            }
+            match bindings.value_of(&var_name) {
+                Some(value) => Ok(value.clone()),
+                None if placeholders => Ok(placeholder(&var_name)),
+                None => Err(BindError::UnboundValue(var_name)),
+            }
         },
         Value::Array(items) => {
             Ok(Value::Array(
                 items
                     .into_iter()
-                    .map(|item| render(item, bindings))
+                    .map(|item| render_with(item, bindings, placeholders))
                     .collect::<Result<_, _>>()?,
             ))
         },
         Value::Object(kv) => {
+            if let Some(selector) = path::of(&kv) {
+                return match path::resolve(selector, bindings) {
+                    Err(BindError::UnboundValue(_)) if placeholders => Ok(placeholder(selector)),
+                    resolved => resolved,
+                }
+            }
+            if let Some(name) = addr_of(&kv) {
+                return match address_of(&bindings.actors, &bindings.dummies, name) {
+                    Some(addr) => Ok(addr_value(addr)),
+                    None if placeholders => Ok(placeholder(Value::Object(kv))),
+                    None => Err(BindError::UnknownAddress(name.to_owned())),
+                }
+            }
+            if let Some((name, transform, arg)) = Transform::of(&kv) {
+                // a transform of a placeholder would be a made up value
+                let value = match render_with(arg.clone(), bindings, false) {
+                    Err(BindError::UnboundValue(_) | BindError::UnknownAddress(_))
+                        if placeholders =>
+                    {
+                        return Ok(placeholder(Value::Object(kv)))
+                    },
+                    rendered => rendered?,
+                };
+                return transform
+                    .apply(&value)
+                    .ok_or(BindError::CannotTransform(name, value))
+            }
             Ok(Value::Object(
                 kv.into_iter()
-                    .map(|(k, v)| render(v, bindings).map(move |v| (k, v)))
+                    .map(|(k, v)| render_with(v, bindings, placeholders).map(move |v| (k, v)))
                     .collect::<Result<_, _>>()?,
             ))
         },
@@ -165,11 +617,82 @@ pub(crate) fn render(template: Value, bindings: &bindings::Scope) -> Result<Valu
     }
 }
 
+/// Stands for what a [Scope] with placeholders cannot render.
+fn placeholder(unknown: impl fmt::Display) -> Value {
+    Value::String(format!("<{}>", unknown))
+}
+
+impl Transform {
+    /// Recognizes an object of the form `{"$op": template}`.
+    fn of(kv: &Map<String, Value>) -> Option<(&'static str, Self, &Value)> {
+        let (key, arg) = kv.iter().next().filter(|_| kv.len() == 1)?;
+        let (name, transform) = TRANSFORMS.iter().find(|(name, _)| *name == key.as_str())?;
+        Some((*name, *transform, arg))
+    }
+
+    fn apply(self, value: &Value) -> Option<Value> {
+        match (self, value) {
+            (Self::ToString, Value::String(s)) => Some(s.as_str().into()),
+            (Self::ToString, other) => Some(other.to_string().into()),
+            (Self::ParseInt, Value::String(s)) => {
+                let s = s.trim();
+                s.parse::<i64>()
+                    .map(Value::from)
+                    .or_else(|_| s.parse::<u64>().map(Value::from))
+                    .ok()
+            },
+            (Self::ParseInt, Value::Number(n)) if n.is_i64() || n.is_u64() => {
+                Some(Value::Number(n.clone()))
+            },
+            (Self::Lowercase, Value::String(s)) => Some(s.to_lowercase().into()),
+            (Self::Uppercase, Value::String(s)) => Some(s.to_uppercase().into()),
+            (Self::Len, Value::String(s)) => Some(s.chars().count().into()),
+            (Self::Len, Value::Array(items)) => Some(items.len().into()),
+            (Self::Len, Value::Object(kv)) => Some(kv.len().into()),
+            (..) => None,
+        }
+    }
+}
+
+/// The name of the actor or the dummy, if `kv` is of the form
+/// `{"$addr_of": "name"}`.
+fn addr_of(kv: &Map<String, Value>) -> Option<&str> {
+    match kv.get(ADDR_OF) {
+        Some(Value::String(name)) if kv.len() == 1 => Some(name),
+        _ => None,
+    }
+}
+
+/// The address of the actor or the dummy known by `name`.
+fn address_of(
+    actors: &BiHashMap<ActorName, Addr>,
+    dummies: &HashMap<DummyName, Addr>,
+    name: &str,
+) -> Option<Addr> {
+    let actor = actors.iter().find(|(n, _)| n.as_ref() == name);
+    let dummy = || dummies.iter().find(|(n, _)| n.as_ref() == name);
+    actor
+        .map(|(_, addr)| *addr)
+        .or_else(|| dummy().map(|(_, addr)| *addr))
+}
+
+/// The address as a value: [Addr] is not `Serialize`, so it is rendered as
+/// displayed.
+fn addr_value(addr: Addr) -> Value {
+    Value::String(addr.to_string())
+}
+
+/// The name of the counter, if `var_name` is of the form `$counter(name)`.
+pub(crate) fn counter_name(var_name: &str) -> Option<&str> {
+    var_name.strip_prefix("$counter(")?.strip_suffix(')')
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
 
     use super::*;
+    use crate::execution::KeyBind;
     use crate::recorder::RecordLog;
 
     impl Scope {
@@ -204,10 +727,247 @@ mod tests {
             assert!(txn.bind_value("a", &json!("a")));
             assert!(!txn.bind_value("a", &json!("b")));
 
-            txn.commit(&mut recorder);
+            txn.commit(EventKey::Bind(KeyBind::default()), &mut recorder);
         }
 
         assert_eq!(scope.value_of("a").cloned(), Some(json!("a")));
         assert!(scope.value_of("b").is_none());
     }
+
+    #[test]
+    fn invariant_violation() {
+        let mut record_log = RecordLog::create();
+        let mut recorder = record_log.recorder();
+        let mut scope = Scope::new().with_invariants(["a".to_owned()]);
+
+        {
+            let mut txn = scope.txn();
+            assert!(txn.bind_value("a", &json!("a")));
+            assert!(txn.take_violation().is_none());
+            txn.commit(EventKey::Bind(KeyBind::default()), &mut recorder);
+        }
+
+        {
+            let mut txn = scope.txn();
+            assert!(txn.bind_value("a", &json!("a")));
+            assert!(txn.take_violation().is_none());
+
+            assert!(!txn.bind_value("a", &json!("b")));
+            let Some(Violation::Invariant(violation)) = txn.take_violation() else {
+                panic!("should have been violated")
+            };
+            assert_eq!(violation.name, "a");
+            assert_eq!(violation.bound, json!("a"));
+            assert_eq!(violation.attempted, json!("b"));
+            assert_eq!(violation.bound_by, Some(EventKey::Bind(KeyBind::default())));
+        }
+    }
+
+    #[test]
+    fn type_mismatch() {
+        let mut scope = Scope::new().with_types([("$n".to_owned(), BindingType::Integer)]);
+        let mut txn = scope.txn();
+        assert!(txn.bind_value("$n", &json!(1)));
+        assert!(txn.take_violation().is_none());
+
+        assert!(txn.bind_value("$n", &json!("1")));
+        assert!(txn.take_violation().is_none());
+        let Some(mismatch) = txn.take_mismatch() else {
+            panic!("should have mismatched")
+        };
+        assert_eq!(mismatch.name, "$n");
+        assert_eq!(mismatch.declared, BindingType::Integer);
+        assert_eq!(mismatch.attempted, json!("1"));
+    }
+
+    #[test]
+    fn array_binding_accumulates() {
+        let mut record_log = RecordLog::create();
+        let mut recorder = record_log.recorder();
+        let mut scope = Scope::new();
+
+        for id in [json!(1), json!(2)] {
+            let mut txn = scope.txn();
+            assert!(txn.bind_value("a[]", &id));
+            txn.commit(EventKey::Bind(KeyBind::default()), &mut recorder);
+        }
+        assert_eq!(scope.value_of("a").cloned(), Some(json!([1, 2])));
+
+        {
+            let mut txn = scope.txn();
+            assert!(txn.bind_value("a[]", &json!(3)));
+            let earlier = txn.take_appended();
+            assert!(txn.bind_value("a[]", &json!(4)));
+            txn.prepend_appended(earlier);
+            txn.commit(EventKey::Bind(KeyBind::default()), &mut recorder);
+        }
+        assert_eq!(scope.value_of("a").cloned(), Some(json!([1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn array_binding_over_a_scalar() {
+        let mut record_log = RecordLog::create();
+        let mut recorder = record_log.recorder();
+        let mut scope = Scope::new();
+
+        {
+            let mut txn = scope.txn();
+            assert!(txn.bind_value("a", &json!(1)));
+            txn.commit(EventKey::Bind(KeyBind::default()), &mut recorder);
+        }
+        {
+            let mut txn = scope.txn();
+            assert!(txn.bind_value("a[]", &json!(2)));
+            txn.commit(EventKey::Bind(KeyBind::default()), &mut recorder);
+        }
+        assert_eq!(scope.value_of("a").cloned(), Some(json!([1, 2])));
+
+        // the same within a single transaction
+        {
+            let mut txn = scope.txn();
+            assert!(txn.bind_value("b", &json!("x")));
+            assert!(txn.bind_value("b[]", &json!("y")));
+            txn.commit(EventKey::Bind(KeyBind::default()), &mut recorder);
+        }
+        assert_eq!(scope.value_of("b").cloned(), Some(json!(["x", "y"])));
+    }
+
+    #[test]
+    fn counters_are_contiguous() {
+        let mut scope = Scope::new();
+        let template = json!({"seq": "$counter(seq)"});
+        assert_eq!(render(template.clone(), &scope).unwrap(), json!({"seq": 1}));
+        assert_eq!(render(template, &scope.fork()).unwrap(), json!({"seq": 2}));
+
+        let pattern = DstPattern(json!({"seq": "$counter(seq)"}));
+        for (seq, matches) in [(1, true), (3, false), (2, true), (2, false)] {
+            let mut txn = scope.txn();
+            assert_eq!(
+                bind_to_pattern(json!({"seq": seq}), &pattern, &mut txn),
+                matches,
+                "{}",
+                seq
+            );
+            if matches {
+                txn.apply();
+            }
+        }
+    }
+
+    #[test]
+    fn transforms() {
+        let scope = Scope::from_values(
+            [
+                ("$s".to_owned(), json!(" 42 ")),
+                ("$arr".to_owned(), json!(["a", "b"])),
+                ("$name".to_owned(), json!("Alice")),
+            ]
+            .into(),
+        );
+        let template = json!({
+            "int": {"$parse_int": "$s"},
+            "len": {"$to_string": {"$len": "$arr"}},
+            "lower": {"$lowercase": "$name"},
+            "upper": {"$uppercase": "$name"},
+            "not-an-operator": {"$length": "$arr"},
+        });
+        assert_eq!(
+            render(template, &scope).unwrap(),
+            json!({
+                "int": 42,
+                "len": "2",
+                "lower": "alice",
+                "upper": "ALICE",
+                "not-an-operator": {"$length": ["a", "b"]},
+            })
+        );
+        assert!(matches!(
+            render(json!({"$parse_int": "$name"}), &scope),
+            Err(BindError::CannotTransform("$parse_int", _))
+        ));
+    }
+
+    #[test]
+    fn placeholders() {
+        let scope = Scope::from_values([("$request".to_owned(), json!({"b": 1}))].into())
+            .with_placeholders();
+        let template = json!({
+            "known": "$request.b",
+            "unknown": "$x.y",
+            "path": {"$path": "$x[0]"},
+            "addr": {"$addr_of": "dummy"},
+            "len": {"$len": "$x"},
+        });
+        assert_eq!(
+            render(template, &scope).unwrap(),
+            json!({
+                "known": 1,
+                "unknown": "<$x.y>",
+                "path": "<$x[0]>",
+                "addr": r#"<{"$addr_of":"dummy"}>"#,
+                "len": r#"<{"$len":"$x"}>"#,
+            })
+        );
+        assert!(matches!(
+            render(json!({"$path": "$request.c"}), &scope),
+            Err(BindError::NothingAtPath(_))
+        ));
+    }
+
+    #[test]
+    fn variants() {
+        let mut scope = Scope::new();
+        let pattern = DstPattern(json!({"$variant": "Started", "$with": {"id": "$ID"}}));
+        for value in [
+            json!({"Started": {"id": 1}}),
+            json!({"type": "Started", "id": 1}),
+            json!({"kind": "Started", "id": 1, "extra": true}),
+        ] {
+            let mut txn = scope.txn();
+            assert!(
+                bind_to_pattern(value.clone(), &pattern, &mut txn),
+                "{}",
+                value
+            );
+            txn.apply();
+        }
+        assert_eq!(scope.value_of("$ID").cloned(), Some(json!(1)));
+
+        let unit = DstPattern(json!({"$variant": "Stopped"}));
+        for (value, matches) in [
+            (json!("Stopped"), true),
+            (json!({"Stopped": null}), true),
+            (json!("Started"), false),
+            (json!({"type": "Started"}), false),
+            (json!({"Stopped": 1, "Started": 2}), false),
+        ] {
+            let mut txn = scope.txn();
+            assert_eq!(
+                bind_to_pattern(value.clone(), &unit, &mut txn),
+                matches,
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn value_of_a_field() {
+        let scope = Scope::from_values(
+            [
+                ("$request".to_owned(), json!({"b": [{"c": 1}]})),
+                ("$request.d".to_owned(), json!(2)),
+                ("$a".to_owned(), json!({"b": 3})),
+                ("$a.c".to_owned(), json!(4)),
+            ]
+            .into(),
+        );
+        assert_eq!(scope.value_of("$request.b.0.c").cloned(), Some(json!(1)));
+        assert_eq!(scope.value_of("$request.d").cloned(), Some(json!(2)));
+        assert!(scope.value_of("$request.b.1").is_none());
+        assert!(scope.value_of("$request.b.c").is_none());
+        // only the request's fields are looked up: the other dots are part of the names
+        assert!(scope.value_of("$a.b").is_none());
+        assert_eq!(scope.value_of("$a.c").cloned(), Some(json!(4)));
+    }
 }
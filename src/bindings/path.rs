@@ -0,0 +1,185 @@
+//! The paths into the values bound so far, written as
+//! `{"$path": "$ORDER.items[0].id"}`.
+//!
+//! The syntax borrows from JSONPath, but there is no document for `$` to stand
+//! for: the root of the path is a bound variable (e.g. `$request` in a
+//! respond), so `$.items` is not a path. The root is followed by any of:
+//! - `.field` or `['field']` — a field of an object;
+//! - `[N]` — an item of an array, counting from the end if negative;
+//! - `.*` or `[*]` — every field of an object, or every item of an array.
+//!
+//! A path without wildcards selects a single value; a path with any selects
+//! the array of all the values it reaches, empty if there are none.
+
+use serde_json::{Map, Value};
+
+use crate::bindings::{BindError, Scope};
+
+/// The key of an object standing for the value selected by a path.
+const PATH: &str = "$path";
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Wildcard,
+}
+
+#[derive(Debug, PartialEq)]
+struct Path<'a> {
+    root:  &'a str,
+    steps: Vec<Step>,
+}
+
+/// Recognizes an object of the form `{"$path": "..."}`.
+pub(super) fn of(kv: &Map<String, Value>) -> Option<&str> {
+    match kv.get(PATH) {
+        Some(Value::String(path)) if kv.len() == 1 => Some(path),
+        _ => None,
+    }
+}
+
+/// Selects the value at `path` in the `bindings`.
+pub(super) fn resolve(path: &str, bindings: &Scope) -> Result<Value, BindError> {
+    let Path { root, steps } =
+        parse(path).ok_or_else(|| BindError::InvalidPath(path.to_owned()))?;
+    let value = bindings
+        .value_of(root)
+        .ok_or_else(|| BindError::UnboundValue(root.to_owned()))?;
+
+    let mut selected = vec![];
+    select(value, &steps, &mut selected);
+    if steps.contains(&Step::Wildcard) {
+        Ok(Value::Array(selected.into_iter().cloned().collect()))
+    } else {
+        selected
+            .first()
+            .map(|value| (*value).clone())
+            .ok_or_else(|| BindError::NothingAtPath(path.to_owned()))
+    }
+}
+
+fn parse(path: &str) -> Option<Path<'_>> {
+    let end = path.find(['.', '[']).unwrap_or(path.len());
+    let (root, mut rest) = path.split_at(end);
+    if root.len() < 2 || !root.starts_with('$') {
+        return None
+    }
+
+    let mut steps = vec![];
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            let (field, tail) = after_dot.split_at(end);
+            steps.push(match field {
+                "" => return None,
+                "*" => Step::Wildcard,
+                field => Step::Field(field.to_owned()),
+            });
+            rest = tail;
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let (inner, tail) = after_bracket.split_once(']')?;
+            let quoted = ['\'', '"']
+                .into_iter()
+                .find_map(|q| inner.strip_prefix(q)?.strip_suffix(q));
+            steps.push(match (inner, quoted) {
+                ("*", _) => Step::Wildcard,
+                (_, Some(field)) => Step::Field(field.to_owned()),
+                (index, None) => Step::Index(index.trim().parse().ok()?),
+            });
+            rest = tail;
+        } else {
+            return None
+        }
+    }
+
+    Some(Path { root, steps })
+}
+
+fn select<'v>(value: &'v Value, steps: &[Step], acc: &mut Vec<&'v Value>) {
+    let Some((step, rest)) = steps.split_first() else {
+        acc.push(value);
+        return
+    };
+    match (step, value) {
+        (Step::Field(name), Value::Object(kv)) => {
+            if let Some(value) = kv.get(name) {
+                select(value, rest, acc)
+            }
+        },
+        (Step::Index(idx), Value::Array(items)) => {
+            let idx = if *idx < 0 {
+                items.len().checked_sub(idx.unsigned_abs() as usize)
+            } else {
+                Some(*idx as usize)
+            };
+            if let Some(value) = idx.and_then(|idx| items.get(idx)) {
+                select(value, rest, acc)
+            }
+        },
+        (Step::Wildcard, Value::Array(items)) => {
+            items.iter().for_each(|value| select(value, rest, acc))
+        },
+        (Step::Wildcard, Value::Object(kv)) => {
+            kv.values().for_each(|value| select(value, rest, acc))
+        },
+        (..) => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parse_steps() {
+        assert_eq!(
+            parse("$A.items[-1]['the id'].*[*]"),
+            Some(Path {
+                root:  "$A",
+                steps: vec![
+                    Step::Field("items".to_owned()),
+                    Step::Index(-1),
+                    Step::Field("the id".to_owned()),
+                    Step::Wildcard,
+                    Step::Wildcard,
+                ],
+            })
+        );
+        // the root is a variable, not the JSONPath's document
+        assert_eq!(parse("$.items"), None);
+        assert_eq!(parse("$A..items"), None);
+        assert_eq!(parse("$A[one]"), None);
+    }
+
+    #[test]
+    fn resolve_paths() {
+        let bindings = Scope::from_values(
+            [(
+                "$ORDER".to_owned(),
+                json!({"items": [{"id": "a"}, {"id": "b"}, {"sku": "c"}]}),
+            )]
+            .into(),
+        );
+        let resolve = |path| resolve(path, &bindings);
+
+        assert_eq!(resolve("$ORDER.items[0].id").unwrap(), json!("a"));
+        assert_eq!(resolve("$ORDER.items[-2]").unwrap(), json!({"id": "b"}));
+        assert_eq!(resolve("$ORDER.items[*].id").unwrap(), json!(["a", "b"]));
+        assert_eq!(resolve("$ORDER.missing[*]").unwrap(), json!([]));
+        assert!(matches!(
+            resolve("$ORDER.items[2].id"),
+            Err(BindError::NothingAtPath(_))
+        ));
+        assert!(matches!(
+            resolve("$OTHER.items"),
+            Err(BindError::UnboundValue(_))
+        ));
+        assert!(matches!(
+            resolve("$.items[0].id"),
+            Err(BindError::InvalidPath(_))
+        ));
+    }
+}
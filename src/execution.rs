@@ -1,41 +1,94 @@
-use std::collections::{BTreeSet, HashMap};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use slotmap::{SecondaryMap, SlotMap};
 
 use crate::marshalling::MarshallingRegistry;
-use crate::names::{ActorName, DummyName, EventName, SubroutineName};
-use crate::scenario::{DstPattern, RequiredToBe, SrcMsg};
+use crate::names::{ActorName, DummyName, EventName, GroupName, SubroutineName};
+use crate::scenario::{
+    BindingType, DefEnvelopeKind, DefRecvCount, DefSystemTopic, DstPattern, RequiredToBe, SrcMsg,
+};
 
 mod keys;
 pub use keys::*;
 
+mod annotations;
 mod build;
+mod cache;
+mod cases;
+mod cast;
 mod display;
+mod explain;
+mod explore;
+mod matrix;
+mod minimize;
 mod names;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+mod outcome_table;
+mod plan;
+mod precompiled;
 mod receives_and_delays;
 mod report;
 pub(crate) mod runner;
+mod soak;
+mod timeline;
+mod topology;
+mod trace;
 
-pub use build::BuildError;
-pub use report::Report;
-pub use runner::{RunError, Runner};
+pub(crate) use annotations::file_annotation;
+pub use build::{BuildError, BuildOptions, Lint};
+pub use cache::BuildCache;
+pub use cases::{CaseOutcome, CasesReport};
+pub use cast::{Cast, CastMapping, CastMember, CastScope};
+pub use display::{DisplayReport, Style};
+pub use explain::Explanation;
+pub use explore::{Exploration, FailedInterleaving};
+pub use matrix::{ConfigOutcome, Matrix, MatrixReport};
+pub use minimize::{minimize, Minimized};
+pub use plan::{Plan, PlannedEvent};
+pub use precompiled::PrecompiledError;
+pub use report::{
+    Deadline, FiredAt, LogLine, Report, RunStats, Throughput, UnmatchedEnvelope, Unreached,
+};
+pub use runner::{Choice, EventContext, RunError, RunErrorReason, RunOptions, Runner};
+pub use soak::SoakReport;
+pub use timeline::{Timeline, TimelineBar, TimelineEnd};
+pub use topology::{EventKind, Topology, TopologyEvent};
 
 pub use crate::sources::{SourceCode, SourceCodeLoader};
 
 /// A key corresponding to some event during test execution.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::From)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::From,
+)]
 pub enum EventKey {
     Bind(KeyBind),
     Send(KeySend),
     Recv(KeyRecv),
     Respond(KeyRespond),
     Delay(KeyDelay),
+    StopDummy(KeyStopDummy),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Executable {
+    /// Not serialized: given back to [Executable::from_json].
+    #[serde(skip)]
     marshalling:        MarshallingRegistry,
     pub(crate) actors:  SlotMap<KeyActor, ActorInfo>,
     pub(crate) dummies: SlotMap<KeyDummy, DummyInfo>,
@@ -43,31 +96,115 @@ pub struct Executable {
 
     root_scope_key:    KeyScope,
     pub(crate) scopes: SlotMap<KeyScope, ScopeInfo>,
+
+    system_messages: SystemMessages,
+    /// The seed of the injected faults, taken from the entry point.
+    faults_seed:     u64,
+    /// Whether the unexpected envelopes fail the run, taken from the entry
+    /// point.
+    strict_mailbox:  bool,
+    /// The template of the blueprint's config, taken from the entry point.
+    config:          Option<Value>,
+    /// The table of the cases to run in, taken from the entry point.
+    cases:           Vec<BTreeMap<String, Value>>,
+    auto_responders: Vec<AutoResponder>,
+    ignored:         Vec<Ignored>,
+
+    built_in: Duration,
+}
+
+/// The FQN of the recvs matching the captured log lines: not a message's.
+const LOG_FQN: &str = "luci::log";
+/// The FQN of the recvs checking the metrics: not a message's.
+#[cfg(feature = "metrics")]
+const METRIC_FQN: &str = "luci::metric";
+/// The groups elfo's test proxy mounts, in the order it adds them to the
+/// topology: the groups are numbered in that order.
+const TOPOLOGY_GROUPS: [&str; 3] = ["subject", "system.testers", "system.configurers"];
+
+/// The policy regarding the elfo system messages, taken from the entry point.
+#[derive(Debug, Serialize, Deserialize)]
+struct SystemMessages {
+    ignore:         Vec<Arc<str>>,
+    skip_unmatched: bool,
+    /// The system topics the dummies are subscribed to.
+    subscriptions:  Vec<(DefSystemTopic, KeyDummy)>,
+}
+
+/// The probabilities of the faults injected into a message sent by a dummy.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Faults {
+    drop:      f64,
+    duplicate: f64,
+    /// The range of the artificial delay, if any.
+    latency:   Option<(Duration, Duration)>,
+    /// The seed of the event's own random choices: without it, the choices
+    /// are those of the whole run.
+    seed:      Option<u64>,
+}
+
+/// The envelopes consumed silently.
+#[derive(Debug, Serialize, Deserialize)]
+struct Ignored {
+    scope_key: KeyScope,
+
+    fqn:     Arc<str>,
+    pattern: Option<DstPattern>,
+}
+
+/// A rule a dummy answers the matching envelopes by.
+#[derive(Debug, Serialize, Deserialize)]
+struct AutoResponder {
+    scope_key: KeyScope,
+
+    dummy:   KeyDummy,
+    fqn:     Arc<str>,
+    pattern: DstPattern,
+    reply:   AutoReply,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum AutoReply {
+    Send { fqn: Arc<str>, payload: SrcMsg },
+    Respond { payload: SrcMsg },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 // the fields of this structure can be used to build a sort of stack-trace, which might be useful
 #[allow(dead_code)]
 pub(crate) struct ScopeInfo {
     pub(crate) source_key: KeyScenario,
     pub(crate) invoked_as: Option<(KeyScope, EventName, SubroutineName)>,
+    pub(crate) invariants: BTreeSet<String>,
+    pub(crate) types:      BTreeMap<String, BindingType>,
+    pub(crate) values:     BTreeMap<String, serde_json::Value>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct ActorInfo {
     pub(crate) known_as: SecondaryMap<KeyScope, ActorName>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct DummyInfo {
     pub(crate) known_as: SecondaryMap<KeyScope, DummyName>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct Events {
-    priority: HashMap<EventKey, usize>,
-    required: HashMap<EventKey, RequiredToBe>,
-    names:    HashMap<EventKey, (KeyScope, EventName)>,
+    /// The order the simultaneously ready events are tried in: the explicit
+    /// priority first (reversed), then the order of definition.
+    #[serde(with = "precompiled::as_pairs")]
+    priority:     HashMap<EventKey, (Reverse<i32>, usize)>,
+    #[serde(with = "precompiled::as_pairs")]
+    required:     HashMap<EventKey, RequiredToBe>,
+    #[serde(with = "precompiled::as_pairs")]
+    deadlines:    HashMap<EventKey, Deadline>,
+    #[serde(with = "precompiled::as_pairs")]
+    names:        HashMap<EventKey, (KeyScope, EventName)>,
+    /// The `describe` of the events.
+    #[serde(default, with = "precompiled::as_pairs")]
+    descriptions: HashMap<EventKey, String>,
 
     bind:    SlotMap<KeyBind, EventBind>,
     send:    SlotMap<KeySend, EventSend>,
@@ -75,50 +212,125 @@ struct Events {
     respond: SlotMap<KeyRespond, EventRespond>,
     delay:   SlotMap<KeyDelay, EventDelay>,
 
+    #[serde(default)]
+    stop_dummy: SlotMap<KeyStopDummy, EventStopDummy>,
+
     entry_points: BTreeSet<EventKey>,
 
+    #[serde(with = "precompiled::as_pairs")]
     key_unblocks_values: HashMap<EventKey, BTreeSet<EventKey>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct EventSend {
     scope_key: KeyScope,
 
-    from:    KeyDummy,
-    to:      Option<KeyActor>,
-    fqn:     Arc<str>,
-    payload: SrcMsg,
+    from:        KeyDummy,
+    to:          Option<KeyActor>,
+    to_dummy:    Option<KeyDummy>,
+    /// Send to each of these, those bound by the time of sending.
+    to_all:      Vec<KeyActor>,
+    /// Send to one of these, those bound by the time of sending, in turn.
+    to_any:      Vec<KeyActor>,
+    /// A template of the routing key to send the message by.
+    routing_key: Option<Value>,
+    fqn:         Arc<str>,
+    payload:     SrcMsg,
+    /// For a request: the recv matching its response.
+    response:    Option<KeyRecv>,
+    faults:      Option<Faults>,
+    /// For the sends of a `send_many`: the last of them, standing for the
+    /// whole event.
+    #[serde(default)]
+    batch:       Option<KeySend>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 struct EventRecv {
     scope_key: KeyScope,
 
     from:             Option<KeyActor>,
+    from_dummy:       Option<KeyDummy>,
+    /// Whether the sender is expected not to be bound to a name yet.
+    from_unseen:      bool,
+    /// The group the sender is expected to belong to, whichever actor it is.
+    from_group:       Option<GroupName>,
+    /// A template of the routing key the sender is expected to serve.
+    from_key:         Option<Value>,
     to:               Option<KeyDummy>,
+    envelope_kind:    Option<DefEnvelopeKind>,
+    /// The number of matches the recv stands for, if more than one.
+    count:            Option<DefRecvCount>,
     fqn:              Arc<str>,
     after_duration:   Duration,
     before_duration:  Option<Duration>,
     payload_matchers: Vec<DstPattern>,
+    /// For a response to a request: the send issuing the request.
+    response_to:      Option<KeySend>,
+    /// For an item of a stream: the items expected after this one.
+    stream_rest:      Vec<KeyRecv>,
+    /// What is matched: an envelope, a captured log line, or a metric.
+    source:           RecvSource,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum RecvSource {
+    Envelope,
+    Log,
+    #[cfg(feature = "metrics")]
+    Metric(MetricCheck),
 }
 
-#[derive(Debug)]
+/// The bounds a metric recorded during the run is checked against.
+#[cfg(feature = "metrics")]
+#[derive(Debug, Serialize, Deserialize)]
+struct MetricCheck {
+    kind:     MetricKind,
+    name:     String,
+    /// The labels of the series to check: the others are not taken into
+    /// account.
+    labels:   BTreeMap<String, String>,
+    at_least: Option<f64>,
+    at_most:  Option<f64>,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum MetricKind {
+    /// The increase since the check has become ready is checked, summed up
+    /// over the series.
+    Counter,
+    /// The current value is checked, of any of the series.
+    Gauge,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct EventRespond {
     scope_key: KeyScope,
 
     respond_to:   KeyRecv,
     request_type: Arc<str>,
     respond_from: Option<KeyDummy>,
-    payload:      SrcMsg,
+    /// `None` stands for failing the request.
+    payload:      Option<SrcMsg>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 struct EventDelay {
     delay_for:  Duration,
     delay_step: Duration,
+    /// Advance the clock by `delay_for` in one jump, ignoring `delay_step`.
+    advance:    bool,
+}
+
+/// Drops the proxy of the dummy.
+#[derive(Debug, Serialize, Deserialize)]
+struct EventStopDummy {
+    scope_key: KeyScope,
+    dummy:     KeyDummy,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 struct EventBind {
     dst: DstPattern,
     src: SrcMsg,
@@ -126,8 +338,13 @@ struct EventBind {
     scope: BindScope,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 enum BindScope {
     Same(KeyScope),
-    Two { src: KeyScope, dst: KeyScope },
+    Two {
+        src:       KeyScope,
+        dst:       KeyScope,
+        /// Also bind all the values of `src` in `dst`.
+        share_all: bool,
+    },
 }
@@ -0,0 +1,232 @@
+//! Rendering the unmet requirements as the GitHub Actions workflow commands,
+//! so that the failures show up inline on the pull requests.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::execution::{EventKey, Executable, Report, SourceCode};
+use crate::scenario::RequiredToBe;
+use crate::sources;
+
+impl Report {
+    /// Renders an `::error` annotation per unmet requirement, pointing at the
+    /// line the event is defined at.
+    ///
+    /// The scenarios are not kept in memory as text: the files are read again
+    /// to find the lines. If a file can no longer be read, the annotation
+    /// points at the file only.
+    pub fn github_annotations<'a>(
+        &'a self,
+        executable: &'a Executable,
+        source_code: &'a SourceCode,
+    ) -> impl fmt::Display + 'a {
+        DisplayAnnotations {
+            report: self,
+            executable,
+            source_code,
+        }
+    }
+}
+
+/// Renders an `::error` annotation of the whole file `path`.
+pub(crate) fn file_annotation(path: &Path, message: &str) -> String {
+    let (file, _) = sources::split_document(path);
+    format!(
+        "::error file={}::{}",
+        escape_property(&display_path(&file)),
+        escape_data(message)
+    )
+}
+
+/// The path as the annotations refer to it: relative to the workspace, so
+/// without the leading `./` the search path `.` resolves the files with.
+fn display_path(path: &Path) -> String {
+    path.strip_prefix(".").unwrap_or(path).display().to_string()
+}
+
+struct DisplayAnnotations<'a> {
+    report:      &'a Report,
+    executable:  &'a Executable,
+    source_code: &'a SourceCode,
+}
+
+impl fmt::Display for DisplayAnnotations<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            report,
+            executable,
+            source_code,
+        } = self;
+
+        let mut unmet = report.unmet_requirements().collect::<Vec<_>>();
+        unmet.sort();
+
+        for event_key in unmet {
+            let Some((scope_key, event_name)) = executable.event_name(event_key) else {
+                continue
+            };
+            let source_key = executable.scopes[scope_key].source_key;
+            let (file, document) =
+                sources::split_document(&source_code.sources[source_key].source_file);
+            let line = std::fs::read_to_string(&file)
+                .ok()
+                .and_then(|text| line_of(&text, document.as_deref(), event_name.as_ref()));
+
+            write!(f, "::error file={}", escape_property(&display_path(&file)))?;
+            if let Some(line) = line {
+                write!(f, ",line={}", line)?;
+            }
+            writeln!(
+                f,
+                ",title={}::{}",
+                escape_property(event_name.as_ref()),
+                escape_data(&unmet_requirement(report, event_key))
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn unmet_requirement(report: &Report, event_key: EventKey) -> String {
+    let reached = report.reached_events.contains(&event_key);
+    match (report.required_events.get(&event_key), reached) {
+        (Some(RequiredToBe::Unreached), _) => {
+            "the event is required to be unreached, but has been reached".to_owned()
+        },
+        (_, true) => {
+            let within = report.deadlines[&event_key].within;
+            let took = report
+                .missed_deadline(event_key)
+                .expect("the event is reached, yet its requirement is unmet");
+            format!("the event has been reached in {took:?}, past the deadline of {within:?}")
+        },
+        (_, false) => "the event is required to be reached, but has not been".to_owned(),
+    }
+}
+
+/// Finds the 1-based number of the line defining the event `event_name`: in
+/// the document named `document`, if the file has several.
+///
+/// Only the `id` of an item of the top-level `events` or `script` counts, not
+/// a field named `id` in the data of the events.
+fn line_of(text: &str, document: Option<&str>, event_name: &str) -> Option<usize> {
+    let mut lines = text.lines().enumerate();
+    if let Some(document) = document {
+        lines.by_ref().find(|(_, line)| {
+            !line.starts_with(' ') && yaml_value_of(line, "name") == Some(document)
+        })?;
+    }
+
+    let mut in_events = false;
+    // the indentation of the events' dashes, and that of their fields
+    let mut item_indent = None;
+    let mut field_indent = None;
+    for (idx, line) in lines {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue
+        }
+        if line.starts_with("---") {
+            if document.is_some() {
+                return None
+            }
+            in_events = false;
+            continue
+        }
+        if indent == 0 && !trimmed.starts_with('-') {
+            in_events = matches!(trimmed.trim_end(), "events:" | "script:");
+            item_indent = None;
+            continue
+        }
+        if !in_events {
+            continue
+        }
+
+        let field = if let Some(after_dash) = trimmed.strip_prefix('-') {
+            if *item_indent.get_or_insert(indent) != indent {
+                continue
+            }
+            let field = after_dash.trim_start();
+            field_indent = Some(line.len() - field.len());
+            field
+        } else if Some(indent) == field_indent {
+            trimmed
+        } else {
+            continue
+        };
+        if yaml_value_of(field, "id") == Some(event_name) {
+            return Some(idx + 1)
+        }
+    }
+    None
+}
+
+/// Parses a line such as `  - id: "some-event"` as the value of `key`.
+fn yaml_value_of<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let line = line.trim_start();
+    let line = line.strip_prefix('-').unwrap_or(line).trim_start();
+    let value = line
+        .strip_prefix(key)?
+        .trim_start()
+        .strip_prefix(':')?
+        .trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value);
+    Some(value)
+}
+
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_of_finds_the_event_in_the_document() {
+        let text = "\
+name: first
+events:
+  - id: hello
+    delay: { for: 1s }
+---
+name: second
+events:
+  - id: 'hello'
+    delay: { for: 1s }
+";
+        assert_eq!(line_of(text, None, "hello"), Some(3));
+        assert_eq!(line_of(text, Some("second"), "hello"), Some(8));
+        assert_eq!(line_of(text, Some("third"), "hello"), None);
+    }
+
+    #[test]
+    fn line_of_skips_the_ids_in_the_data() {
+        let text = "\
+name: first
+values:
+  id: hello
+events:
+  - send:
+      data:
+        literal:
+          - id: hello
+    id: hello
+  - id: other
+";
+        assert_eq!(line_of(text, None, "hello"), Some(9));
+        assert_eq!(line_of(text, None, "other"), Some(10));
+    }
+}
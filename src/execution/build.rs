@@ -1,27 +1,48 @@
 //! This module is responsible for building an [`Executable`] from [`Sources`].
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::Hash;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bimap::BiHashMap;
-use serde_json::json;
+use elfo::messages::{ActorStatusReport, SubscribeToActorStatuses, Terminate, UpdateConfig};
+use serde_json::{json, Value};
 use slotmap::{SecondaryMap, SlotMap};
 use tracing::{debug, error, trace, warn};
 
 use crate::execution::{
-    ActorInfo, BindScope, DummyInfo, EventBind, EventDelay, EventKey, EventRecv, EventRespond,
-    EventSend, Events, Executable, KeyActor, KeyBind, KeyDelay, KeyDummy, KeyRecv, KeyRespond,
-    KeyScenario, KeyScope, KeySend, ScopeInfo, SourceCode,
+    ActorInfo, AutoReply, AutoResponder, BindScope, Deadline, DummyInfo, EventBind, EventDelay,
+    EventKey, EventRecv, EventRespond, EventSend, EventStopDummy, Events, Executable, Faults,
+    Ignored, KeyActor, KeyBind, KeyDelay, KeyDummy, KeyRecv, KeyRespond, KeyScenario, KeyScope,
+    KeySend, KeyStopDummy, RecvSource, ScopeInfo, SourceCode, SystemMessages, LOG_FQN,
+    TOPOLOGY_GROUPS,
 };
-use crate::marshalling::MarshallingRegistry;
-use crate::names::{ActorName, DummyName, EventName, MessageName, SubroutineName};
+#[cfg(feature = "metrics")]
+use crate::execution::{MetricCheck, MetricKind, METRIC_FQN};
+use crate::marshalling::{MarshallingRegistry, Regular};
+use crate::names::{ActorName, DummyName, EventName, GroupName, MessageName, SubroutineName};
 use crate::scenario::{
-    DefEvent, DefEventBind, DefEventDelay, DefEventKind, DefEventRecv, DefEventRespond,
-    DefEventSend, DefTypeAlias, DstPattern, RequiredToBe, SrcMsg,
+    defaults, BindingType, DefAutoReply, DefAutoRespond, DefAutoResponder, DefAutoSend,
+    DefDeadline, DefEnvelopeKind, DefEvent, DefEventAdvanceTime, DefEventBind, DefEventDelay,
+    DefEventKind, DefEventLog, DefEventRecv, DefEventRequest, DefEventRespond, DefEventSend,
+    DefEventSendMany, DefEventStatus, DefEventStream, DefEventTerminate, DefEventUpdateConfig,
+    DefFaults, DefIgnore, DefLatency, DefRecvCount, DefRequirement, DefSystemMessages,
+    DefTypeAlias, DstPattern, RequiredToBe, SrcMsg, UnmatchedSystemMessages,
 };
+#[cfg(feature = "metrics")]
+use crate::scenario::{DefEventMetric, DefMetric};
 use crate::sources::SingleScenarioSource;
 
+mod dataflow;
+mod types;
+
+/// The key of an object standing for a named fragment of a pattern.
+const PATTERN_REF: &str = "$ref";
+/// The key of an object standing for a named template of a message.
+const MESSAGE_REF: &str = "$message";
+
 #[derive(thiserror::Error)]
 pub struct BuildError<'a> {
     pub(super) reason:  BuildErrorReason,
@@ -40,12 +61,18 @@ pub(super) enum BuildErrorReason {
     #[error("not a request: {}", _0)]
     NotARequest(EventName, KeyScope),
 
+    #[error("not a request type: {}", _0)]
+    NotARequestType(MessageName, KeyScope),
+
     #[error("unknown actor: {}", _0)]
     UnknownActor(ActorName, KeyScope),
 
     #[error("unknown dummy: {}", _0)]
     UnknownDummy(DummyName, KeyScope),
 
+    #[error("unknown group: {} (known are: {})", _0, TOPOLOGY_GROUPS.join(", "))]
+    UnknownGroup(GroupName, KeyScope),
+
     #[error("unknown subroutine: {}", _0)]
     UnknownSubroutine(SubroutineName, KeyScope),
 
@@ -63,6 +90,116 @@ pub(super) enum BuildErrorReason {
 
     #[error("duplicate dummy name: {}", _0)]
     DuplicateDummyName(DummyName, KeyScope),
+
+    #[error("too many dummies: {} (at most {} allowed)", _0, _1)]
+    TooManyDummies(usize, usize, KeyScope),
+
+    #[error("invalid pool of dummies (expected `name x N`, N > 0): {}", _0)]
+    InvalidDummyPool(DummyName, KeyScope),
+
+    #[error("conflicting fields in {}: `{}` and `{}`", _0, _1, _2)]
+    ConflictingFields(EventName, &'static str, &'static str, KeyScope),
+
+    #[error("invalid probability of `{}`: {} (must be within 0..=1)", _0, _1)]
+    InvalidProbability(&'static str, f64, KeyScope),
+
+    #[error("invalid latency range: {:?}..={:?}", _0, _1)]
+    InvalidLatency(Duration, Duration, KeyScope),
+
+    #[error("invalid count in {}: at least one match is expected", _0)]
+    InvalidCount(EventName, KeyScope),
+
+    #[error("missing field in {}: `{}`", _0, _1)]
+    MissingField(EventName, &'static str, KeyScope),
+
+    #[error("unknown pattern: {}", _0)]
+    UnknownPattern(String, KeyScope),
+
+    #[error("recursive pattern: {}", _0)]
+    RecursivePattern(String, KeyScope),
+
+    #[error("unknown message template: {}", _0)]
+    UnknownMessageTemplate(String, KeyScope),
+
+    #[error("message template can not be overridden: {} (it is injected)", _0)]
+    InjectedMessageTemplate(String, KeyScope),
+
+    #[error(
+        "message template mixes literal and bind with the data overriding it: {}",
+        _0
+    )]
+    MixedMessageTemplate(String, KeyScope),
+
+    #[cfg(feature = "json-schema")]
+    #[error("invalid payload in {}: {}", _0, _1)]
+    InvalidPayload(EventName, String, KeyScope),
+
+    #[error("actor is not mapped: {} (this might not be what you meant)", _0)]
+    UnmappedActor(ActorName, KeyScope),
+
+    #[error("dummy is not mapped: {} (this might not be what you meant)", _0)]
+    UnmappedDummy(DummyName, KeyScope),
+
+    #[cfg(feature = "json-schema")]
+    #[error("{}: the pattern uses an unknown key: {}", _0, _1)]
+    UnknownKey(EventName, String, KeyScope),
+
+    #[error("{} reads {}, which none of the events it happens after binds", _0, _1)]
+    UnboundVariable(EventName, String, KeyScope),
+
+    #[error("{} is declared {}, but its value is not", _0, _1)]
+    ValueTypeMismatch(String, BindingType, KeyScope),
+
+    #[error("{} binds {}, declared {}, to a value of another type", _0, _1, _2)]
+    BindTypeMismatch(EventName, String, BindingType, KeyScope),
+
+    #[cfg(not(feature = "metrics"))]
+    #[error("{} checks a metric, which requires the `metrics` feature", _0)]
+    MetricsDisabled(EventName, KeyScope),
+}
+
+/// The options of a build, see [Executable::build_with_options].
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Whether the warnings fail the build, save for the allowed ones.
+    pub deny_warnings: bool,
+    pub allowed_lints: HashSet<Lint>,
+}
+
+/// The kinds of the warnings the builder may emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// An actor of a subroutine is not mapped onto an actor of the caller.
+    UnmappedActor,
+    /// A dummy of a subroutine is not mapped onto a dummy of the caller.
+    UnmappedDummy,
+    /// A recv pattern uses a key the message's schema does not have.
+    #[cfg(feature = "json-schema")]
+    UnknownKey,
+    /// An event may read a variable before any of the events binding it has
+    /// fired: fine, if the value is passed to the run.
+    UnboundVariable,
+}
+
+impl BuildOptions {
+    /// Turns the warnings into the build errors.
+    pub fn with_deny_warnings(self, deny_warnings: bool) -> Self {
+        Self {
+            deny_warnings,
+            ..self
+        }
+    }
+
+    /// Keeps the warnings of the kind `lint` warnings, even if the warnings
+    /// are denied.
+    pub fn with_allowed_lint(mut self, lint: Lint) -> Self {
+        self.allowed_lints.insert(lint);
+        self
+    }
+
+    fn denies(&self, lint: Lint) -> bool {
+        self.deny_warnings && !self.allowed_lints.contains(&lint)
+    }
 }
 
 impl Executable {
@@ -75,10 +212,32 @@ impl Executable {
         marshalling: MarshallingRegistry,
         source_code: &SourceCode,
         entry_point_key: KeyScenario,
-    ) -> Result<Self, BuildError> {
+    ) -> Result<Self, BuildError<'_>> {
+        Self::build_with_options(
+            marshalling,
+            source_code,
+            entry_point_key,
+            BuildOptions::default(),
+        )
+    }
+
+    /// Same as [Executable::build], but with the [BuildOptions] other than the
+    /// default ones.
+    pub fn build_with_options(
+        marshalling: MarshallingRegistry,
+        source_code: &SourceCode,
+        entry_point_key: KeyScenario,
+        options: BuildOptions,
+    ) -> Result<Self, BuildError<'_>> {
         debug!("building...");
+        let t_start = Instant::now();
+
+        let marshalling = with_builtins(marshalling);
 
-        let mut builder: Builder = Default::default();
+        let mut builder = Builder {
+            options,
+            ..Default::default()
+        };
 
         let result = builder.add_subgraph(
             &marshalling,
@@ -93,19 +252,27 @@ impl Executable {
             actors,
             dummies,
             event_names,
+            descriptions,
             definition_order,
+            explicit_priority,
+            deadlines,
             events_delay,
             events_bind,
             events_recv,
             events_send,
             events_respond,
+            events_stop_dummy,
             key_unblocks_values,
+            auto_responders,
+            ignored,
+            options,
         } = builder;
 
         let SubgraphAdded {
             scope_key,
             entry_points,
             require: required,
+            exports: _,
         } = match result {
             Ok(added) => added,
             Err(reason) => {
@@ -117,25 +284,78 @@ impl Executable {
             },
         };
 
+        if let Some(max_dummies) = source_code[entry_point_key].scenario.max_dummies {
+            if dummies.len() > max_dummies {
+                return Err(BuildError {
+                    reason: BuildErrorReason::TooManyDummies(dummies.len(), max_dummies, scope_key),
+                    scopes,
+                    sources: &source_code.sources,
+                });
+            }
+        }
+
+        let system_messages = match system_messages(
+            &marshalling,
+            &dummies,
+            scope_key,
+            source_code[entry_point_key]
+                .scenario
+                .system_messages
+                .as_ref(),
+        ) {
+            Ok(system_messages) => system_messages,
+            Err(reason) => {
+                return Err(BuildError {
+                    reason,
+                    scopes,
+                    sources: &source_code.sources,
+                })
+            },
+        };
+
         let priority = definition_order
             .into_iter()
             .enumerate()
-            .map(|(p, k)| (k, p))
+            .map(|(p, k)| {
+                let explicit = explicit_priority.get(&k).copied().unwrap_or_default();
+                (k, (Reverse(explicit), p))
+            })
             .collect();
 
         let events = Events {
             priority,
             required,
+            deadlines,
             names: event_names,
+            descriptions,
             bind: events_bind,
             send: events_send,
             recv: events_recv,
             respond: events_respond,
             delay: events_delay,
+            stop_dummy: events_stop_dummy,
             entry_points,
             key_unblocks_values,
         };
 
+        for reason in dataflow::check(&events, &scopes) {
+            if options.denies(Lint::UnboundVariable) {
+                return Err(BuildError {
+                    reason,
+                    scopes,
+                    sources: &source_code.sources,
+                })
+            }
+            warn!("{}", reason);
+        }
+        if let Err(reason) = types::check(&events, &scopes) {
+            return Err(BuildError {
+                reason,
+                scopes,
+                sources: &source_code.sources,
+            })
+        }
+
         Ok(Executable {
             marshalling,
             events,
@@ -143,8 +363,82 @@ impl Executable {
             dummies,
             root_scope_key: scope_key,
             scopes,
+            system_messages,
+            faults_seed: source_code[entry_point_key]
+                .scenario
+                .faults
+                .as_ref()
+                .and_then(|def| def.seed)
+                .unwrap_or_default(),
+            strict_mailbox: source_code[entry_point_key].scenario.strict_mailbox,
+            config: source_code[entry_point_key].scenario.config.clone(),
+            cases: source_code[entry_point_key].scenario.cases.clone(),
+            auto_responders,
+            ignored,
+            built_in: t_start.elapsed(),
+        })
+    }
+
+    /// Wall-clock time it took to build the executable.
+    pub fn built_in(&self) -> Duration {
+        self.built_in
+    }
+}
+
+fn system_messages(
+    marshalling: &MarshallingRegistry,
+    dummies: &SlotMap<KeyDummy, DummyInfo>,
+    scope_key: KeyScope,
+    def: Option<&DefSystemMessages>,
+) -> Result<SystemMessages, BuildErrorReason> {
+    let Some(def) = def else {
+        return Ok(SystemMessages {
+            ignore:         vec![],
+            skip_unmatched: false,
+            subscriptions:  vec![],
         })
+    };
+    let ignore = def
+        .ignore
+        .iter()
+        .map(|fqn| {
+            marshalling
+                .resolve(fqn)
+                .map(|_| fqn.as_str().into())
+                .ok_or_else(|| BuildErrorReason::UnknownFqn(fqn.to_owned(), scope_key))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut subscriptions = vec![];
+    for (dummy_name, topics) in def.subscribe.iter() {
+        let dummy_key = dummies
+            .iter()
+            .find(|(_, info)| info.known_as.get(scope_key) == Some(dummy_name))
+            .map(|(key, _)| key)
+            .ok_or_else(|| BuildErrorReason::UnknownDummy(dummy_name.clone(), scope_key))?;
+        subscriptions.extend(topics.iter().map(|topic| (*topic, dummy_key)));
     }
+
+    Ok(SystemMessages {
+        ignore,
+        skip_unmatched: def.unmatched == UnmatchedSystemMessages::Skip,
+        subscriptions,
+    })
+}
+
+/// Validates the literal payload against the message schema, if there is one.
+#[cfg(feature = "json-schema")]
+fn check_literal(
+    schema: Option<&serde_json::Value>,
+    payload: &SrcMsg,
+    event_name: &EventName,
+    scope_key: KeyScope,
+) -> Result<(), BuildErrorReason> {
+    let (Some(schema), SrcMsg::Literal(payload)) = (schema, payload) else {
+        return Ok(())
+    };
+    crate::marshalling::schema::validate(schema, payload)
+        .map_err(|reason| BuildErrorReason::InvalidPayload(event_name.clone(), reason, scope_key))
 }
 
 fn type_aliases<'a>(
@@ -195,28 +489,18 @@ where
     Ok(out)
 }
 
-fn resolve_event_ids<'a>(
-    idx_keys: &'a HashMap<&'a EventName, EventKey>,
-    scope_key: KeyScope,
-    names: &'a [EventName],
-) -> impl Iterator<Item = Result<EventKey, BuildErrorReason>> + 'a {
-    names.iter().map(move |name: &EventName| {
-        idx_keys
-            .get(name)
-            .copied()
-            .ok_or(BuildErrorReason::UnknownEvent(name.clone(), scope_key))
-    })
-}
-
 #[derive(Debug, Default)]
 struct Builder {
     scopes:  SlotMap<KeyScope, ScopeInfo>,
     actors:  SlotMap<KeyActor, ActorInfo>,
     dummies: SlotMap<KeyDummy, DummyInfo>,
 
-    event_names: HashMap<EventKey, (KeyScope, EventName)>,
+    event_names:  HashMap<EventKey, (KeyScope, EventName)>,
+    descriptions: HashMap<EventKey, String>,
 
-    definition_order: Vec<EventKey>,
+    definition_order:  Vec<EventKey>,
+    explicit_priority: HashMap<EventKey, i32>,
+    deadlines:         HashMap<EventKey, Deadline>,
 
     events_delay:   SlotMap<KeyDelay, EventDelay>,
     events_bind:    SlotMap<KeyBind, EventBind>,
@@ -224,7 +508,14 @@ struct Builder {
     events_send:    SlotMap<KeySend, EventSend>,
     events_respond: SlotMap<KeyRespond, EventRespond>,
 
+    events_stop_dummy: SlotMap<KeyStopDummy, EventStopDummy>,
+
     key_unblocks_values: HashMap<EventKey, BTreeSet<EventKey>>,
+
+    auto_responders: Vec<AutoResponder>,
+    ignored:         Vec<Ignored>,
+
+    options: BuildOptions,
 }
 
 #[derive(Debug)]
@@ -232,9 +523,65 @@ struct SubgraphAdded {
     scope_key:    KeyScope,
     entry_points: BTreeSet<EventKey>,
     require:      HashMap<EventKey, RequiredToBe>,
+    exports:      BTreeSet<EventKey>,
 }
 
 impl Builder {
+    /// Warns of `reason`, unless the lint is denied: then it is an error.
+    fn lint(&self, lint: Lint, reason: BuildErrorReason) -> Result<(), BuildErrorReason> {
+        if self.options.denies(lint) {
+            return Err(reason)
+        }
+        warn!("{}", reason);
+        Ok(())
+    }
+
+    /// Resolves the name of an event defined in the scope being added, or
+    /// `call/inner` — of an event defined in the scope of the subroutine
+    /// invoked by the event `call` (the qualified names may nest).
+    fn resolve_event_id(
+        &self,
+        this_scope_name_to_key: &HashMap<&EventName, EventKey>,
+        this_scope_key: KeyScope,
+        name: &EventName,
+    ) -> Result<EventKey, BuildErrorReason> {
+        if let Some(key) = this_scope_name_to_key.get(name) {
+            return Ok(*key)
+        }
+        name.as_ref()
+            .split_once('/')
+            .and_then(|(call_name, inner_name)| {
+                let sub_scope_key = self.sub_scope(this_scope_key, call_name)?;
+                self.find_event(sub_scope_key, inner_name)
+            })
+            .ok_or(BuildErrorReason::UnknownEvent(name.clone(), this_scope_key))
+    }
+
+    /// Finds an event of a scope that has been added completely.
+    fn find_event(&self, scope_key: KeyScope, name: &str) -> Option<EventKey> {
+        self.event_names
+            .iter()
+            .find(|(_, (s, n))| *s == scope_key && n.as_ref() == name)
+            .map(|(key, _)| *key)
+            .or_else(|| {
+                let (call_name, inner_name) = name.split_once('/')?;
+                let sub_scope_key = self.sub_scope(scope_key, call_name)?;
+                self.find_event(sub_scope_key, inner_name)
+            })
+    }
+
+    /// Finds the scope of the subroutine invoked by the event `call_name`.
+    fn sub_scope(&self, caller_scope_key: KeyScope, call_name: &str) -> Option<KeyScope> {
+        self.scopes
+            .iter()
+            .find(|(_, scope)| {
+                scope.invoked_as.as_ref().is_some_and(|(caller, call, _)| {
+                    *caller == caller_scope_key && call.as_ref() == call_name
+                })
+            })
+            .map(|(key, _)| key)
+    }
+
     fn add_subgraph(
         &mut self,
         marshalling: &MarshallingRegistry,
@@ -249,6 +596,9 @@ impl Builder {
         let this_scope_key = self.scopes.insert(ScopeInfo {
             source_key,
             invoked_as,
+            invariants: this_source.scenario.invariants.keys().cloned().collect(),
+            types: this_source.scenario.bindings.clone(),
+            values: this_source.scenario.values.clone(),
         });
 
         debug!("storing type-aliases...");
@@ -257,13 +607,33 @@ impl Builder {
             trace!("- {:?} -> {:?}", a, fqn);
         }
 
-        let actor_names = ensure_uniqueness(
+        let mut actor_names = ensure_uniqueness(
             &this_source.scenario.actors,
             this_scope_key,
             BuildErrorReason::DuplicateActorName,
         )?;
+        // the actors captured by `recv.from: $name` need not be declared.
+        actor_names.extend(this_source.scenario.all_events().filter_map(|e| {
+            match &e.kind {
+                DefEventKind::Recv(DefEventRecv {
+                    from: Some(from), ..
+                })
+                | DefEventKind::Stream(DefEventStream {
+                    from: Some(from), ..
+                }) if is_captured(from) => Some(from.clone()),
+                _ => None,
+            }
+        }));
+
+        let mut declared_dummies = vec![];
+        for dummy_name in &this_source.scenario.dummies {
+            let members = dummy_name.members().ok_or_else(|| {
+                BuildErrorReason::InvalidDummyPool(dummy_name.clone(), this_scope_key)
+            })?;
+            declared_dummies.extend(members);
+        }
         let dummy_names = ensure_uniqueness(
-            &this_source.scenario.dummies,
+            &declared_dummies,
             this_scope_key,
             BuildErrorReason::DuplicateDummyName,
         )?;
@@ -279,10 +649,10 @@ impl Builder {
                 actors.insert(actor_name.clone(), key);
             } else {
                 if self.scopes[this_scope_key].invoked_as.is_some() {
-                    warn!(
-                        "actor {} is not mapped. This might not be what you meant.",
-                        actor_name
-                    );
+                    self.lint(
+                        Lint::UnmappedActor,
+                        BuildErrorReason::UnmappedActor(actor_name.clone(), this_scope_key),
+                    )?;
                 }
 
                 let mut known_as = SecondaryMap::default();
@@ -304,10 +674,10 @@ impl Builder {
                 dummies.insert(dummy_name.clone(), key);
             } else {
                 if self.scopes[this_scope_key].invoked_as.is_some() {
-                    warn!(
-                        "dummy {} is not mapped. This might not be what you meant.",
-                        dummy_name
-                    );
+                    self.lint(
+                        Lint::UnmappedDummy,
+                        BuildErrorReason::UnmappedDummy(dummy_name.clone(), this_scope_key),
+                    )?;
                 }
 
                 let mut known_as = SecondaryMap::default();
@@ -321,21 +691,162 @@ impl Builder {
             return Err(BuildErrorReason::UnknownDummy(dummy_name, this_scope_key))
         }
 
+        for DefIgnore {
+            message_type,
+            message_data,
+            no_extra: _,
+        } in this_source.scenario.ignore.iter()
+        {
+            let fqn =
+                type_aliases
+                    .get(message_type)
+                    .cloned()
+                    .ok_or(BuildErrorReason::UnknownAlias(
+                        message_type.clone(),
+                        this_scope_key,
+                    ))?;
+            self.ignored.push(Ignored {
+                scope_key: this_scope_key,
+                fqn,
+                pattern: message_data.clone(),
+            });
+        }
+
+        for DefAutoResponder {
+            dummy,
+            when,
+            reply,
+            no_extra: _,
+        } in this_source.scenario.auto_responders.iter()
+        {
+            let dummy = resolve_name_opt(
+                &dummies,
+                this_scope_key,
+                Some(dummy),
+                BuildErrorReason::UnknownDummy,
+            )?
+            .expect("the name is specified");
+            let fqn = type_aliases.get(&when.message_type).cloned().ok_or(
+                BuildErrorReason::UnknownAlias(when.message_type.clone(), this_scope_key),
+            )?;
+            let reply = match reply {
+                DefAutoReply::Send(DefAutoSend {
+                    message_type,
+                    message_data,
+                    no_extra: _,
+                }) => {
+                    let fqn = type_aliases.get(message_type).cloned().ok_or(
+                        BuildErrorReason::UnknownAlias(message_type.clone(), this_scope_key),
+                    )?;
+                    AutoReply::Send {
+                        fqn,
+                        payload: message_data.clone(),
+                    }
+                },
+                DefAutoReply::Respond(DefAutoRespond { data, no_extra: _ }) => {
+                    if marshalling
+                        .resolve(&fqn)
+                        .and_then(|m| m.response())
+                        .is_none()
+                    {
+                        return Err(BuildErrorReason::NotARequestType(
+                            when.message_type.clone(),
+                            this_scope_key,
+                        ));
+                    }
+                    AutoReply::Respond {
+                        payload: data.clone(),
+                    }
+                },
+            };
+            self.auto_responders.push(AutoResponder {
+                scope_key: this_scope_key,
+                dummy,
+                fqn,
+                pattern: when.message_data.clone(),
+                reply,
+            });
+        }
+
+        let scenario_defaults = this_source.scenario.defaults.clone().unwrap_or_default();
+        let patterns = &this_source.scenario.patterns;
+        let messages = &this_source.scenario.messages;
         let mut this_scope_name_to_key = HashMap::new();
         let mut this_scope_entry_points = BTreeSet::new();
         let mut this_scope_requires = HashMap::new();
 
-        for DefEvent {
-            id: this_name,
-            require: this_event_required_to_be,
-            prerequisites,
-            kind,
-            ..
-        } in this_source.scenario.events.iter()
-        {
-            let prerequisites =
-                resolve_event_ids(&this_scope_name_to_key, this_scope_key, prerequisites)
-                    .collect::<Result<Vec<_>, _>>()?;
+        // a message sent by a dummy, but for its recipients: those are up to the event.
+        let dummy_send =
+            |this_name: &EventName,
+             from: Option<&DummyName>,
+             message_type: &MessageName,
+             message_data: &SrcMsg,
+             faults: Option<&DefFaults>|
+             -> Result<EventSend, BuildErrorReason> {
+                let mut fault_profile = faults
+                    .or(this_source.scenario.faults.as_ref())
+                    .map(|def| check_faults(def, this_scope_key))
+                    .transpose()?;
+                // the scenario's seed is that of the whole run, rather than of each send
+                if let Some(fault_profile) = fault_profile.as_mut() {
+                    fault_profile.seed = faults.and_then(|def| def.seed);
+                }
+                let from = from.or(scenario_defaults.from.as_ref()).ok_or_else(|| {
+                    BuildErrorReason::MissingField(this_name.clone(), "from", this_scope_key)
+                })?;
+                if !dummy_names.contains(from) {
+                    return Err(BuildErrorReason::UnknownDummy(from.clone(), this_scope_key));
+                }
+                let type_fqn = type_aliases.get(message_type).cloned().ok_or(
+                    BuildErrorReason::UnknownAlias(message_type.clone(), this_scope_key),
+                )?;
+                let message_data = expand_message(messages, this_scope_key, message_data)?;
+
+                #[cfg(feature = "json-schema")]
+                check_literal(
+                    marshalling.schema(&type_fqn),
+                    &message_data,
+                    this_name,
+                    this_scope_key,
+                )?;
+
+                Ok(EventSend {
+                    from:        dummies[from],
+                    to:          None,
+                    to_dummy:    None,
+                    to_all:      vec![],
+                    to_any:      vec![],
+                    routing_key: None,
+                    fqn:         type_fqn,
+                    payload:     message_data,
+                    response:    None,
+                    faults:      fault_profile,
+                    batch:       None,
+                    scope_key:   this_scope_key,
+                })
+            };
+
+        // the names of the events are borrowed by the scope's map past the loop.
+        let all_events = this_source.scenario.all_events().collect::<Vec<_>>();
+        for def_event in &all_events {
+            let DefEvent {
+                id: this_name,
+                require: this_event_required_to_be,
+                priority: this_event_priority,
+                describe: this_event_description,
+                kind,
+                ..
+            } = &**def_event;
+            // a call pushes the events of its subroutine along with its own ones.
+            let defined_before = self.definition_order.len();
+
+            let mut prerequisites = def_event
+                .all_prerequisites()
+                .map(|name| self.resolve_event_id(&this_scope_name_to_key, this_scope_key, name))
+                .collect::<Result<Vec<_>, _>>()?;
+            // an event may be both listed in `happens_after` and joined by a barrier.
+            prerequisites.sort();
+            prerequisites.dedup();
 
             let (head_key, tail_key) = match kind {
                 DefEventKind::Call(def_call) => {
@@ -374,6 +885,7 @@ impl Builder {
                         scope_key: sub_scope_key,
                         entry_points: sub_entry_points,
                         require: sub_required_to_be,
+                        exports: sub_exports,
                     } = self.add_subgraph(
                         marshalling,
                         sources,
@@ -401,7 +913,7 @@ impl Builder {
                     let event_bind_in = {
                         let (dst, src) = if let Some(def_bind_in) = def_call.input.as_ref() {
                             (
-                                def_bind_in.dst.clone(),
+                                expand_pattern(patterns, this_scope_key, &def_bind_in.dst)?,
                                 SrcMsg::Bind(def_bind_in.src.clone()),
                             )
                         } else {
@@ -411,8 +923,9 @@ impl Builder {
                             dst,
                             src,
                             scope: BindScope::Two {
-                                src: this_scope_key,
-                                dst: sub_scope_key,
+                                src:       this_scope_key,
+                                dst:       sub_scope_key,
+                                share_all: def_call.share_bindings,
                             },
                         }
                     };
@@ -435,7 +948,7 @@ impl Builder {
                     let event_bind_out = {
                         let (dst, src) = if let Some(def_bind_out) = def_call.output.as_ref() {
                             (
-                                def_bind_out.dst.clone(),
+                                expand_pattern(patterns, this_scope_key, &def_bind_out.dst)?,
                                 SrcMsg::Bind(def_bind_out.src.clone()),
                             )
                         } else {
@@ -445,8 +958,9 @@ impl Builder {
                             dst,
                             src,
                             scope: BindScope::Two {
-                                src: sub_scope_key,
-                                dst: this_scope_key,
+                                src:       sub_scope_key,
+                                dst:       this_scope_key,
+                                share_all: def_call.share_bindings,
                             },
                         }
                     };
@@ -454,6 +968,9 @@ impl Builder {
                     let ek_bind_out = EventKey::Bind(bind_out);
 
                     for (sub_key, requirement) in sub_required_to_be {
+                        if sub_exports.contains(&sub_key) {
+                            this_scope_requires.insert(sub_key, requirement);
+                        }
                         if matches!(requirement, RequiredToBe::Reached) {
                             let hasnt_been_added_before = self
                                 .key_unblocks_values
@@ -466,6 +983,16 @@ impl Builder {
 
                     (ek_bind_in, ek_bind_out)
                 },
+                DefEventKind::Barrier(_) => {
+                    // the joined events are among the prerequisites already.
+                    let key = self.events_bind.insert(EventBind {
+                        dst:   DstPattern(json!(null)),
+                        src:   SrcMsg::Literal(json!(null)),
+                        scope: BindScope::Same(this_scope_key),
+                    });
+                    let ek_barrier = EventKey::Bind(key);
+                    (ek_barrier, ek_barrier)
+                },
                 DefEventKind::Delay(def_delay) => {
                     let DefEventDelay {
                         delay_for,
@@ -473,22 +1000,39 @@ impl Builder {
                         no_extra: _,
                     } = def_delay;
                     let delay_for = *delay_for;
-                    let delay_step = *delay_step;
+                    let delay_step = delay_step
+                        .or(scenario_defaults.step)
+                        .unwrap_or_else(defaults::default_delay_step);
 
                     let key = self.events_delay.insert(EventDelay {
                         delay_for,
                         delay_step,
+                        advance: false,
                     });
                     let ek_delay = EventKey::Delay(key);
                     (ek_delay, ek_delay)
                 },
+                DefEventKind::AdvanceTime(def_advance) => {
+                    let DefEventAdvanceTime {
+                        advance_by,
+                        no_extra: _,
+                    } = def_advance;
+
+                    let key = self.events_delay.insert(EventDelay {
+                        delay_for:  *advance_by,
+                        delay_step: *advance_by,
+                        advance:    true,
+                    });
+                    let ek_advance = EventKey::Delay(key);
+                    (ek_advance, ek_advance)
+                },
                 DefEventKind::Bind(def_bind) => {
                     let DefEventBind {
                         dst,
                         src,
                         no_extra: _,
                     } = def_bind;
-                    let dst = dst.clone();
+                    let dst = expand_pattern(patterns, this_scope_key, dst)?;
                     let src = src.clone();
                     let key = self.events_bind.insert(EventBind {
                         dst,
@@ -505,72 +1049,305 @@ impl Builder {
                         message_data,
                         also_match_data,
                         from,
+                        from_dummy,
+                        from_group,
+                        from_key,
                         to,
+                        kind,
+                        count,
                         before_duration,
                         after_duration,
                         no_extra: _,
                     } = def_recv;
+                    let recv_before = before_duration.or(scenario_defaults.timeout);
+                    let recv_after = after_duration
+                        .or(scenario_defaults.after_duration)
+                        .unwrap_or_default();
+
+                    match count {
+                        Some(DefRecvCount::AtLeast(0)) => {
+                            return Err(BuildErrorReason::InvalidCount(
+                                this_name.clone(),
+                                this_scope_key,
+                            ))
+                        },
+                        Some(DefRecvCount::Exactly { .. }) if recv_before.is_none() => {
+                            return Err(BuildErrorReason::MissingField(
+                                this_name.clone(),
+                                "timeout",
+                                this_scope_key,
+                            ))
+                        },
+                        _ => (),
+                    }
+
+                    check_exclusive(
+                        this_name,
+                        this_scope_key,
+                        [
+                            ("from", from.is_some()),
+                            ("from_dummy", from_dummy.is_some()),
+                            ("from_group", from_group.is_some()),
+                            ("from_key", from_key.is_some()),
+                        ],
+                    )?;
+                    if let Some(group) = from_group
+                        .as_ref()
+                        .filter(|g| !TOPOLOGY_GROUPS.contains(&g.as_ref()))
+                    {
+                        return Err(BuildErrorReason::UnknownGroup(
+                            group.clone(),
+                            this_scope_key,
+                        ));
+                    }
+
+                    let from = if from_dummy.is_none() && from_group.is_none() && from_key.is_none()
+                    {
+                        from.as_ref().or(scenario_defaults.to.as_ref())
+                    } else {
+                        from.as_ref()
+                    };
+                    let to = to.as_ref().or(scenario_defaults.from.as_ref());
 
                     let type_fqn = type_aliases.get(message_type).cloned().ok_or(
                         BuildErrorReason::UnknownAlias(message_type.clone(), this_scope_key),
                     )?;
 
+                    if *kind == Some(DefEnvelopeKind::Request)
+                        && marshalling
+                            .resolve(&type_fqn)
+                            .is_none_or(|m| m.response().is_none())
+                    {
+                        return Err(BuildErrorReason::NotARequest(
+                            this_name.clone(),
+                            this_scope_key,
+                        ));
+                    }
+
+                    let recv_matchers = [message_data]
+                        .into_iter()
+                        .chain(also_match_data)
+                        .map(|pattern| expand_pattern(patterns, this_scope_key, pattern))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    #[cfg(feature = "json-schema")]
+                    if let Some(schema) = marshalling.schema(&type_fqn) {
+                        for pattern in recv_matchers.iter() {
+                            for key in crate::marshalling::schema::unknown_keys(schema, &pattern.0)
+                            {
+                                self.lint(
+                                    Lint::UnknownKey,
+                                    BuildErrorReason::UnknownKey(
+                                        this_name.clone(),
+                                        key,
+                                        this_scope_key,
+                                    ),
+                                )?;
+                            }
+                        }
+                    }
+
                     let key = self.events_recv.insert(EventRecv {
                         from:             resolve_name_opt(
                             &actors,
                             this_scope_key,
-                            from.as_ref(),
+                            from,
                             BuildErrorReason::UnknownActor,
                         )?,
+                        from_dummy:       resolve_name_opt(
+                            &dummies,
+                            this_scope_key,
+                            from_dummy.as_ref(),
+                            BuildErrorReason::UnknownDummy,
+                        )?,
+                        from_unseen:      from.is_some_and(is_captured),
+                        from_group:       from_group.clone(),
+                        from_key:         from_key.clone(),
                         to:               resolve_name_opt(
                             &dummies,
                             this_scope_key,
-                            to.as_ref(),
+                            to,
                             BuildErrorReason::UnknownDummy,
                         )?,
+                        envelope_kind:    *kind,
+                        count:            *count,
                         fqn:              type_fqn,
-                        payload_matchers: [message_data.clone()]
-                            .into_iter()
-                            .chain(also_match_data.iter().cloned())
-                            .collect(),
-                        after_duration:   *after_duration,
-                        before_duration:  *before_duration,
+                        payload_matchers: recv_matchers,
+                        after_duration:   recv_after,
+                        before_duration:  recv_before,
+                        response_to:      None,
+                        stream_rest:      vec![],
+                        source:           RecvSource::Envelope,
                         scope_key:        this_scope_key,
                     });
                     let ek_recv = EventKey::Recv(key);
                     (ek_recv, ek_recv)
                 },
+                DefEventKind::Stream(def_stream) => {
+                    let DefEventStream {
+                        message_type,
+                        items,
+                        from,
+                        from_dummy,
+                        to,
+                        timeout,
+                        no_extra: _,
+                    } = def_stream;
+                    let timeout = timeout.or(scenario_defaults.timeout);
+
+                    if items.is_empty() {
+                        return Err(BuildErrorReason::MissingField(
+                            this_name.clone(),
+                            "items",
+                            this_scope_key,
+                        ))
+                    }
+                    check_exclusive(
+                        this_name,
+                        this_scope_key,
+                        [
+                            ("from", from.is_some()),
+                            ("from_dummy", from_dummy.is_some()),
+                        ],
+                    )?;
+
+                    let type_fqn = type_aliases.get(message_type).cloned().ok_or(
+                        BuildErrorReason::UnknownAlias(message_type.clone(), this_scope_key),
+                    )?;
+                    let from_actor = resolve_name_opt(
+                        &actors,
+                        this_scope_key,
+                        from.as_ref(),
+                        BuildErrorReason::UnknownActor,
+                    )?;
+                    let from_dummy = resolve_name_opt(
+                        &dummies,
+                        this_scope_key,
+                        from_dummy.as_ref(),
+                        BuildErrorReason::UnknownDummy,
+                    )?;
+                    let to = resolve_name_opt(
+                        &dummies,
+                        this_scope_key,
+                        to.as_ref(),
+                        BuildErrorReason::UnknownDummy,
+                    )?;
+
+                    // the stream is a chain of recvs, each unblocking the next one; the last one
+                    // stands for the whole stream.
+                    let items = items
+                        .iter()
+                        .map(|item| expand_pattern(patterns, this_scope_key, item))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let keys = items
+                        .into_iter()
+                        .map(|item| {
+                            self.events_recv.insert(EventRecv {
+                                from: from_actor,
+                                from_dummy,
+                                from_unseen: false,
+                                from_group: None,
+                                from_key: None,
+                                to,
+                                envelope_kind: None,
+                                count: None,
+                                fqn: type_fqn.clone(),
+                                payload_matchers: vec![item],
+                                after_duration: Duration::ZERO,
+                                before_duration: timeout,
+                                response_to: None,
+                                stream_rest: vec![],
+                                source: RecvSource::Envelope,
+                                scope_key: this_scope_key,
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    // only the first item may capture the sender.
+                    self.events_recv[keys[0]].from_unseen = from.as_ref().is_some_and(is_captured);
+
+                    for (idx, pair) in keys.windows(2).enumerate() {
+                        self.events_recv[pair[0]].stream_rest = keys[idx + 1..].to_vec();
+                        self.definition_order.push(EventKey::Recv(pair[0]));
+                        self.event_names.insert(
+                            EventKey::Recv(pair[0]),
+                            (this_scope_key, this_name.with_suffix(&format!("[{}]", idx))),
+                        );
+                        self.key_unblocks_values
+                            .entry(EventKey::Recv(pair[0]))
+                            .or_default()
+                            .insert(EventKey::Recv(pair[1]));
+                    }
+
+                    let head = EventKey::Recv(keys[0]);
+                    let tail = EventKey::Recv(keys[keys.len() - 1]);
+                    (head, tail)
+                },
                 DefEventKind::Respond(def_respond) => {
                     let DefEventRespond {
                         from,
                         to_request: to,
                         data,
+                        fail,
+                        after,
                         no_extra: _,
                     } = def_respond;
 
+                    match (data, fail) {
+                        (Some(_), true) => {
+                            return Err(BuildErrorReason::ConflictingFields(
+                                this_name.clone(),
+                                "data",
+                                "fail",
+                                this_scope_key,
+                            ))
+                        },
+                        (None, false) => {
+                            return Err(BuildErrorReason::MissingField(
+                                this_name.clone(),
+                                "data",
+                                this_scope_key,
+                            ))
+                        },
+                        (..) => (),
+                    }
+
                     let causing_event_key = this_scope_name_to_key
                         .get(&to)
                         .ok_or(BuildErrorReason::UnknownEvent(to.clone(), this_scope_key))?;
                     let EventKey::Recv(recv_key) = causing_event_key else {
                         return Err(BuildErrorReason::NotARequest(to.clone(), this_scope_key));
                     };
-                    let request_fqn = self
-                        .events_recv
-                        .get(*recv_key)
-                        .expect(
-                            "we do not delete items from `recv`; neither we store keys that are \
-                             unrelated to our collections",
-                        )
-                        .fqn
-                        .clone();
-
-                    if marshalling
-                        .resolve(&request_fqn)
-                        .is_none_or(|m| m.response().is_none())
+                    let request_recv = self.events_recv.get(*recv_key).expect(
+                        "we do not delete items from `recv`; neither we store keys that are \
+                         unrelated to our collections",
+                    );
+                    let request_fqn = request_recv.fqn.clone();
+
+                    if request_recv
+                        .envelope_kind
+                        .is_some_and(|k| k != DefEnvelopeKind::Request)
+                        || marshalling
+                            .resolve(&request_fqn)
+                            .is_none_or(|m| m.response().is_none())
                     {
                         return Err(BuildErrorReason::NotARequest(to.clone(), this_scope_key));
                     }
 
+                    let respond_payload = data
+                        .as_ref()
+                        .map(|data| expand_message(messages, this_scope_key, data))
+                        .transpose()?;
+
+                    #[cfg(feature = "json-schema")]
+                    if let Some(data) = respond_payload.as_ref() {
+                        check_literal(
+                            marshalling.response_schema(&request_fqn),
+                            data,
+                            this_name,
+                            this_scope_key,
+                        )?;
+                    }
+
                     let key = self.events_respond.insert(EventRespond {
                         respond_to:   *recv_key,
                         request_type: request_fqn,
@@ -580,26 +1357,77 @@ impl Builder {
                             from.as_ref(),
                             BuildErrorReason::UnknownDummy,
                         )?,
-                        payload:      data.clone(),
+                        payload:      respond_payload,
                         scope_key:    this_scope_key,
                     });
                     let ek_respond = EventKey::Respond(key);
-                    (ek_respond, ek_respond)
+
+                    if after.is_zero() {
+                        (ek_respond, ek_respond)
+                    } else {
+                        // the response is held by the head, and sent by the tail.
+                        let delay_key = self.events_delay.insert(EventDelay {
+                            delay_for:  *after,
+                            delay_step: scenario_defaults
+                                .step
+                                .unwrap_or_else(defaults::default_delay_step),
+                            advance:    false,
+                        });
+                        let ek_delay = EventKey::Delay(delay_key);
+                        self.event_names
+                            .insert(ek_delay, (this_scope_key, this_name.with_suffix("[DELAY]")));
+                        self.key_unblocks_values
+                            .entry(ek_delay)
+                            .or_default()
+                            .insert(ek_respond);
+
+                        (ek_delay, ek_respond)
+                    }
                 },
                 DefEventKind::Send(def_send) => {
                     let DefEventSend {
                         from,
                         to,
+                        to_dummy,
+                        to_all,
+                        to_any,
+                        routing_key,
                         message_type,
                         message_data,
+                        faults,
                         no_extra: _,
                     } = def_send;
 
-                    let type_fqn = type_aliases.get(message_type).cloned().ok_or(
-                        BuildErrorReason::UnknownAlias(message_type.clone(), this_scope_key),
+                    check_exclusive(
+                        this_name,
+                        this_scope_key,
+                        [
+                            ("to", to.is_some()),
+                            ("to_dummy", to_dummy.is_some()),
+                            ("to_all", !to_all.is_empty()),
+                            ("to_any", !to_any.is_empty()),
+                            ("routing_key", routing_key.is_some()),
+                        ],
                     )?;
 
-                    if let Some(to_actor) = to.as_ref() {
+                    let send = dummy_send(
+                        this_name,
+                        from.as_ref(),
+                        message_type,
+                        message_data,
+                        faults.as_ref(),
+                    )?;
+                    let to = if to_dummy.is_none()
+                        && to_all.is_empty()
+                        && to_any.is_empty()
+                        && routing_key.is_none()
+                    {
+                        to.as_ref().or(scenario_defaults.to.as_ref())
+                    } else {
+                        to.as_ref()
+                    };
+
+                    for to_actor in to.into_iter().chain(to_all).chain(to_any) {
                         if !actor_names.contains(to_actor) {
                             return Err(BuildErrorReason::UnknownActor(
                                 to_actor.clone(),
@@ -607,35 +1435,470 @@ impl Builder {
                             ));
                         }
                     }
-                    if !dummy_names.contains(from) {
-                        return Err(BuildErrorReason::UnknownDummy(from.clone(), this_scope_key));
-                    }
 
                     let key = self.events_send.insert(EventSend {
-                        from:      resolve_name_opt(
+                        to: resolve_name_opt(
+                            &actors,
+                            this_scope_key,
+                            to,
+                            BuildErrorReason::UnknownActor,
+                        )?,
+                        to_dummy: resolve_name_opt(
+                            &dummies,
+                            this_scope_key,
+                            to_dummy.as_ref(),
+                            BuildErrorReason::UnknownDummy,
+                        )?,
+                        to_all: to_all.iter().map(|name| actors[name]).collect(),
+                        to_any: to_any.iter().map(|name| actors[name]).collect(),
+                        routing_key: routing_key.clone(),
+                        ..send
+                    });
+                    let ek_send = EventKey::Send(key);
+                    (ek_send, ek_send)
+                },
+                DefEventKind::SendMany(def_send_many) => {
+                    let DefEventSendMany {
+                        from,
+                        to,
+                        message_type,
+                        message_data,
+                        count,
+                        interval,
+                        no_extra: _,
+                    } = def_send_many;
+
+                    if *count == 0 {
+                        return Err(BuildErrorReason::InvalidCount(
+                            this_name.clone(),
+                            this_scope_key,
+                        ))
+                    }
+                    let send =
+                        dummy_send(this_name, from.as_ref(), message_type, message_data, None)?;
+                    let to = resolve_name_opt(
+                        &actors,
+                        this_scope_key,
+                        to.as_ref().or(scenario_defaults.to.as_ref()),
+                        BuildErrorReason::UnknownActor,
+                    )?;
+
+                    // the messages are a chain of sends, paced by the delays; the last send stands
+                    // for the whole event.
+                    let mut keys = vec![];
+                    for index in 0..*count {
+                        if let Some(interval) = interval.filter(|_| index > 0) {
+                            let delay_key = self.events_delay.insert(EventDelay {
+                                delay_for:  interval,
+                                delay_step: scenario_defaults
+                                    .step
+                                    .unwrap_or_else(defaults::default_delay_step),
+                                advance:    false,
+                            });
+                            keys.push((EventKey::Delay(delay_key), format!("[{}][DELAY]", index)));
+                        }
+                        let key = self.events_send.insert(EventSend {
+                            to,
+                            payload: with_index(&send.payload, index),
+                            ..send.clone()
+                        });
+                        keys.push((EventKey::Send(key), format!("[{}]", index)));
+                    }
+                    for pair in keys.windows(2) {
+                        let (this_key, suffix) = &pair[0];
+                        self.definition_order.push(*this_key);
+                        self.event_names
+                            .insert(*this_key, (this_scope_key, this_name.with_suffix(suffix)));
+                        self.key_unblocks_values
+                            .entry(*this_key)
+                            .or_default()
+                            .insert(pair[1].0);
+                    }
+
+                    let head = keys[0].0;
+                    let tail = keys[keys.len() - 1].0;
+                    if let EventKey::Send(tail_key) = tail {
+                        for (key, _) in keys.iter() {
+                            if let EventKey::Send(send_key) = key {
+                                self.events_send[*send_key].batch = Some(tail_key);
+                            }
+                        }
+                    }
+                    (head, tail)
+                },
+                DefEventKind::UpdateConfig(def_update) => {
+                    let DefEventUpdateConfig {
+                        from,
+                        to,
+                        config,
+                        no_extra: _,
+                    } = def_update;
+
+                    let payload = match config {
+                        SrcMsg::Literal(config) => SrcMsg::Literal(json!({ "config": config })),
+                        SrcMsg::Bind(config) => SrcMsg::Bind(json!({ "config": config })),
+                        SrcMsg::Inject(key) => SrcMsg::Inject(key.clone()),
+                    };
+
+                    let key = self.events_send.insert(builtin_send(
+                        &dummies,
+                        &actors,
+                        this_scope_key,
+                        from,
+                        to.as_ref(),
+                        std::any::type_name::<UpdateConfig>(),
+                        payload,
+                    )?);
+                    let ek_send = EventKey::Send(key);
+                    (ek_send, ek_send)
+                },
+                DefEventKind::Terminate(def_terminate) => {
+                    let DefEventTerminate {
+                        from,
+                        to,
+                        closing,
+                        no_extra: _,
+                    } = def_terminate;
+
+                    let key = self.events_send.insert(builtin_send(
+                        &dummies,
+                        &actors,
+                        this_scope_key,
+                        from,
+                        to.as_ref(),
+                        std::any::type_name::<Terminate>(),
+                        SrcMsg::Literal(json!({ "closing": closing })),
+                    )?);
+                    let ek_send = EventKey::Send(key);
+                    (ek_send, ek_send)
+                },
+                DefEventKind::StopDummy(dummy) => {
+                    let dummy = resolve_name_opt(
+                        &dummies,
+                        this_scope_key,
+                        Some(dummy),
+                        BuildErrorReason::UnknownDummy,
+                    )?
+                    .expect("the name is specified");
+                    let key = self.events_stop_dummy.insert(EventStopDummy {
+                        scope_key: this_scope_key,
+                        dummy,
+                    });
+                    let ek_stop = EventKey::StopDummy(key);
+                    (ek_stop, ek_stop)
+                },
+                DefEventKind::Status(def_status) => {
+                    let DefEventStatus {
+                        to,
+                        key,
+                        kind,
+                        details,
+                        timeout,
+                        no_extra: _,
+                    } = def_status;
+                    let timeout = timeout.or(scenario_defaults.timeout);
+
+                    let dummy_key = resolve_name_opt(
+                        &dummies,
+                        this_scope_key,
+                        Some(to),
+                        BuildErrorReason::UnknownDummy,
+                    )?
+                    .expect("the name is specified");
+
+                    let mut status = json!({ "kind": kind });
+                    if let Some(DstPattern(details)) = details {
+                        status["details"] = details.clone();
+                    }
+                    let mut report = json!({ "status": status });
+                    if let Some(key) = key {
+                        report["meta"] = json!({ "key": key });
+                    }
+
+                    // the subscription is sent by the head, and the report is matched by the
+                    // tail.
+                    let send_key = self.events_send.insert(builtin_send(
+                        &dummies,
+                        &actors,
+                        this_scope_key,
+                        to,
+                        None,
+                        std::any::type_name::<SubscribeToActorStatuses>(),
+                        SrcMsg::Literal(json!({ "forcing": false })),
+                    )?);
+                    let recv_key = self.events_recv.insert(EventRecv {
+                        from:             None,
+                        from_dummy:       None,
+                        from_unseen:      false,
+                        from_group:       None,
+                        from_key:         None,
+                        to:               Some(dummy_key),
+                        envelope_kind:    None,
+                        count:            None,
+                        fqn:              std::any::type_name::<ActorStatusReport>().into(),
+                        payload_matchers: vec![DstPattern(report)],
+                        after_duration:   Duration::ZERO,
+                        before_duration:  timeout,
+                        response_to:      None,
+                        stream_rest:      vec![],
+                        source:           RecvSource::Envelope,
+                        scope_key:        this_scope_key,
+                    });
+
+                    let ek_send = EventKey::Send(send_key);
+                    let ek_recv = EventKey::Recv(recv_key);
+                    self.event_names.insert(
+                        ek_send,
+                        (this_scope_key, this_name.with_suffix("[SUBSCRIBE]")),
+                    );
+                    self.key_unblocks_values
+                        .entry(ek_send)
+                        .or_default()
+                        .insert(ek_recv);
+
+                    (ek_send, ek_recv)
+                },
+                DefEventKind::Log(def_log) => {
+                    let DefEventLog {
+                        level,
+                        target,
+                        message,
+                        fields,
+                        timeout,
+                        no_extra: _,
+                    } = def_log;
+                    let timeout = timeout.or(scenario_defaults.timeout);
+
+                    let mut line = json!({});
+                    if let Some(level) = level {
+                        line["level"] = json!(level);
+                    }
+                    if let Some(target) = target {
+                        line["target"] = json!(target);
+                    }
+                    if let Some(DstPattern(message)) = message {
+                        line["message"] = message.clone();
+                    }
+                    if let Some(fields) = fields {
+                        line["fields"] = expand_pattern(patterns, this_scope_key, fields)?.0;
+                    }
+
+                    let key = self.events_recv.insert(EventRecv {
+                        from:             None,
+                        from_dummy:       None,
+                        from_unseen:      false,
+                        from_group:       None,
+                        from_key:         None,
+                        to:               None,
+                        envelope_kind:    None,
+                        count:            None,
+                        fqn:              LOG_FQN.into(),
+                        payload_matchers: vec![DstPattern(line)],
+                        after_duration:   Duration::ZERO,
+                        before_duration:  timeout,
+                        response_to:      None,
+                        stream_rest:      vec![],
+                        source:           RecvSource::Log,
+                        scope_key:        this_scope_key,
+                    });
+                    let ek_recv = EventKey::Recv(key);
+                    (ek_recv, ek_recv)
+                },
+                #[cfg(not(feature = "metrics"))]
+                DefEventKind::Metric(_) => {
+                    return Err(BuildErrorReason::MetricsDisabled(
+                        this_name.clone(),
+                        this_scope_key,
+                    ))
+                },
+                #[cfg(feature = "metrics")]
+                DefEventKind::Metric(def_metric) => {
+                    let DefEventMetric {
+                        metric,
+                        labels,
+                        at_least,
+                        at_most,
+                        timeout,
+                        no_extra: _,
+                    } = def_metric;
+                    let timeout = timeout.or(scenario_defaults.timeout);
+
+                    let (kind, name) = match metric {
+                        DefMetric::Counter(name) => (MetricKind::Counter, name),
+                        DefMetric::Gauge(name) => (MetricKind::Gauge, name),
+                    };
+                    // a counter with no bounds is checked for any increase.
+                    let at_least = match (kind, at_least, at_most) {
+                        (MetricKind::Counter, None, None) => Some(1.0),
+                        (_, at_least, _) => *at_least,
+                    };
+
+                    let key = self.events_recv.insert(EventRecv {
+                        from:             None,
+                        from_dummy:       None,
+                        from_unseen:      false,
+                        from_group:       None,
+                        from_key:         None,
+                        to:               None,
+                        envelope_kind:    None,
+                        count:            None,
+                        fqn:              METRIC_FQN.into(),
+                        payload_matchers: vec![],
+                        after_duration:   Duration::ZERO,
+                        before_duration:  timeout,
+                        response_to:      None,
+                        stream_rest:      vec![],
+                        source:           RecvSource::Metric(MetricCheck {
+                            kind,
+                            name: name.clone(),
+                            labels: labels.clone(),
+                            at_least,
+                            at_most: *at_most,
+                        }),
+                        scope_key:        this_scope_key,
+                    });
+                    let ek_recv = EventKey::Recv(key);
+                    (ek_recv, ek_recv)
+                },
+                DefEventKind::Request(def_request) => {
+                    let DefEventRequest {
+                        from,
+                        to,
+                        to_all,
+                        message_type,
+                        message_data,
+                        response,
+                        timeout,
+                        no_extra: _,
+                    } = def_request;
+                    let timeout = timeout.or(scenario_defaults.timeout);
+
+                    check_exclusive(
+                        this_name,
+                        this_scope_key,
+                        [("to", to.is_some()), ("to_all", !to_all.is_empty())],
+                    )?;
+                    for to_actor in to_all {
+                        if !actor_names.contains(to_actor) {
+                            return Err(BuildErrorReason::UnknownActor(
+                                to_actor.clone(),
+                                this_scope_key,
+                            ));
+                        }
+                    }
+
+                    let type_fqn = type_aliases.get(message_type).cloned().ok_or(
+                        BuildErrorReason::UnknownAlias(message_type.clone(), this_scope_key),
+                    )?;
+                    if marshalling
+                        .resolve(&type_fqn)
+                        .is_none_or(|m| m.response().is_none())
+                    {
+                        return Err(BuildErrorReason::NotARequest(
+                            this_name.clone(),
+                            this_scope_key,
+                        ));
+                    }
+                    let message_data = &expand_message(messages, this_scope_key, message_data)?;
+
+                    #[cfg(feature = "json-schema")]
+                    check_literal(
+                        marshalling.schema(&type_fqn),
+                        message_data,
+                        this_name,
+                        this_scope_key,
+                    )?;
+
+                    // the request is sent by the head, and its response is matched by the tail.
+                    let recv_key = self.events_recv.insert(EventRecv {
+                        from:             None,
+                        from_dummy:       None,
+                        from_unseen:      false,
+                        from_group:       None,
+                        from_key:         None,
+                        to:               None,
+                        envelope_kind:    None,
+                        count:            None,
+                        fqn:              type_fqn.clone(),
+                        payload_matchers: vec![expand_pattern(patterns, this_scope_key, response)?],
+                        after_duration:   Duration::ZERO,
+                        before_duration:  timeout,
+                        response_to:      None,
+                        stream_rest:      vec![],
+                        source:           RecvSource::Envelope,
+                        scope_key:        this_scope_key,
+                    });
+                    let send_key = self.events_send.insert(EventSend {
+                        from:        resolve_name_opt(
                             &dummies,
                             this_scope_key,
                             Some(from),
                             BuildErrorReason::UnknownDummy,
                         )?
                         .unwrap(),
-                        to:        resolve_name_opt(
+                        to:          resolve_name_opt(
                             &actors,
                             this_scope_key,
                             to.as_ref(),
                             BuildErrorReason::UnknownActor,
                         )?,
-                        fqn:       type_fqn,
-                        payload:   message_data.clone(),
-                        scope_key: this_scope_key,
+                        to_dummy:    None,
+                        to_all:      to_all.iter().map(|name| actors[name]).collect(),
+                        to_any:      vec![],
+                        routing_key: None,
+                        fqn:         type_fqn,
+                        payload:     message_data.clone(),
+                        response:    Some(recv_key),
+                        faults:      None,
+                        batch:       None,
+                        scope_key:   this_scope_key,
                     });
-                    let ek_send = EventKey::Send(key);
-                    (ek_send, ek_send)
+                    self.events_recv[recv_key].response_to = Some(send_key);
+
+                    let ek_send = EventKey::Send(send_key);
+                    let ek_recv = EventKey::Recv(recv_key);
+                    self.event_names.insert(
+                        ek_send,
+                        (this_scope_key, this_name.with_suffix("[REQUEST]")),
+                    );
+                    self.key_unblocks_values
+                        .entry(ek_send)
+                        .or_default()
+                        .insert(ek_recv);
+
+                    (ek_send, ek_recv)
                 },
             };
 
-            if let Some(r) = this_event_required_to_be {
-                this_scope_requires.insert(tail_key, *r);
+            if let Some(description) = this_event_description {
+                self.descriptions.insert(tail_key, description.clone());
+            }
+
+            if let Some(requirement) = this_event_required_to_be {
+                let r = match requirement {
+                    DefRequirement::Plain(r) => *r,
+                    DefRequirement::Deadline(DefDeadline {
+                        reached_within,
+                        since,
+                        no_extra: _,
+                    }) => {
+                        let since = since
+                            .as_ref()
+                            .map(|name| {
+                                self.resolve_event_id(&this_scope_name_to_key, this_scope_key, name)
+                            })
+                            .transpose()?;
+                        self.deadlines.insert(
+                            tail_key,
+                            Deadline {
+                                within: *reached_within,
+                                since,
+                            },
+                        );
+                        RequiredToBe::Reached
+                    },
+                };
+                this_scope_requires.insert(tail_key, r);
             }
 
             if prerequisites.is_empty() {
@@ -670,8 +1933,26 @@ impl Builder {
             }
             self.definition_order.push(head_key);
             self.definition_order.push(tail_key);
+
+            if let Some(priority) = this_event_priority {
+                for key in &self.definition_order[defined_before..] {
+                    self.explicit_priority.entry(*key).or_insert(*priority);
+                }
+            }
         }
 
+        let this_scope_exports = this_source
+            .scenario
+            .exports
+            .iter()
+            .map(|name| {
+                this_scope_name_to_key
+                    .get(name)
+                    .copied()
+                    .ok_or(BuildErrorReason::UnknownEvent(name.clone(), this_scope_key))
+            })
+            .collect::<Result<_, _>>()?;
+
         for (name, key) in this_scope_name_to_key {
             let should_be_none = self.event_names.insert(key, (this_scope_key, name.clone()));
             assert!(should_be_none.is_none());
@@ -681,7 +1962,250 @@ impl Builder {
             scope_key:    this_scope_key,
             entry_points: this_scope_entry_points,
             require:      this_scope_requires,
+            exports:      this_scope_exports,
+        })
+    }
+}
+
+/// Registers the elfo messages that the built-in events send, unless the user
+/// has already registered them.
+pub(super) fn with_builtins(marshalling: MarshallingRegistry) -> MarshallingRegistry {
+    fn with<M: elfo::Message>(marshalling: MarshallingRegistry) -> MarshallingRegistry {
+        if marshalling.resolve(std::any::type_name::<M>()).is_some() {
+            marshalling
+        } else {
+            marshalling.with(Regular::<M>)
+        }
+    }
+
+    let marshalling = with::<UpdateConfig>(marshalling);
+    let marshalling = with::<Terminate>(marshalling);
+    let marshalling = with::<SubscribeToActorStatuses>(marshalling);
+    with::<ActorStatusReport>(marshalling)
+}
+
+/// Makes an [EventSend] for a built-in event sending a message registered by
+/// [with_builtins].
+fn builtin_send(
+    dummies: &HashMap<DummyName, KeyDummy>,
+    actors: &HashMap<ActorName, KeyActor>,
+    scope_key: KeyScope,
+    from: &DummyName,
+    to: Option<&ActorName>,
+    fqn: &'static str,
+    payload: SrcMsg,
+) -> Result<EventSend, BuildErrorReason> {
+    Ok(EventSend {
+        from: resolve_name_opt(
+            dummies,
+            scope_key,
+            Some(from),
+            BuildErrorReason::UnknownDummy,
+        )?
+        .expect("the name is specified"),
+        to: resolve_name_opt(actors, scope_key, to, BuildErrorReason::UnknownActor)?,
+        to_dummy: None,
+        to_all: vec![],
+        to_any: vec![],
+        routing_key: None,
+        fqn: fqn.into(),
+        payload,
+        response: None,
+        faults: None,
+        batch: None,
+        scope_key,
+    })
+}
+
+/// Checks that the probabilities of the faults are within `0..=1`, and that
+/// the latency range is not empty.
+fn check_faults(def: &DefFaults, scope_key: KeyScope) -> Result<Faults, BuildErrorReason> {
+    for (field, p) in [("drop", def.drop), ("duplicate", def.duplicate)] {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(BuildErrorReason::InvalidProbability(field, p, scope_key))
+        }
+    }
+    let latency = def
+        .latency
+        .as_ref()
+        .map(|DefLatency { min, max, .. }| {
+            if min > max {
+                Err(BuildErrorReason::InvalidLatency(*min, *max, scope_key))
+            } else {
+                Ok((*min, *max))
+            }
         })
+        .transpose()?;
+    Ok(Faults {
+        drop: def.drop,
+        duplicate: def.duplicate,
+        latency,
+        seed: def.seed,
+    })
+}
+
+/// Whether the actor is captured by the first unseen sender matching a recv,
+/// rather than declared.
+fn is_captured(actor_name: &ActorName) -> bool {
+    actor_name.as_ref().starts_with('$')
+}
+
+/// Replaces every `{$ref: name}` in the `pattern` with the fragment `name` of
+/// the scenario's `patterns`, the fragments referred to by that one included.
+fn expand_pattern(
+    patterns: &BTreeMap<String, DstPattern>,
+    scope_key: KeyScope,
+    pattern: &DstPattern,
+) -> Result<DstPattern, BuildErrorReason> {
+    fn inner(
+        patterns: &BTreeMap<String, DstPattern>,
+        scope_key: KeyScope,
+        expanding: &mut Vec<String>,
+        value: &Value,
+    ) -> Result<Value, BuildErrorReason> {
+        match value {
+            Value::Object(kv) if kv.len() == 1 && kv.contains_key(PATTERN_REF) => {
+                let Some(Value::String(name)) = kv.get(PATTERN_REF) else {
+                    return Ok(value.clone())
+                };
+                if expanding.contains(name) {
+                    return Err(BuildErrorReason::RecursivePattern(name.clone(), scope_key))
+                }
+                let fragment = patterns
+                    .get(name)
+                    .ok_or_else(|| BuildErrorReason::UnknownPattern(name.clone(), scope_key))?;
+                expanding.push(name.clone());
+                let expanded = inner(patterns, scope_key, expanding, &fragment.0)?;
+                expanding.pop();
+                Ok(expanded)
+            },
+            Value::Object(kv) => {
+                kv.iter()
+                    .map(|(k, v)| Ok((k.clone(), inner(patterns, scope_key, expanding, v)?)))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Object)
+            },
+            Value::Array(items) => {
+                items
+                    .iter()
+                    .map(|item| inner(patterns, scope_key, expanding, item))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Array)
+            },
+            _ => Ok(value.clone()),
+        }
+    }
+    inner(patterns, scope_key, &mut vec![], &pattern.0).map(DstPattern)
+}
+
+/// Replaces the `data` of the form `{$message: name, ...}` with the template
+/// `name` of the scenario's `messages`, the other keys of the object merged on
+/// top of it. The template and the `data` are either both literal, or both
+/// bound: otherwise the literal values could be taken for variables.
+fn expand_message(
+    messages: &BTreeMap<String, SrcMsg>,
+    scope_key: KeyScope,
+    data: &SrcMsg,
+) -> Result<SrcMsg, BuildErrorReason> {
+    let (SrcMsg::Literal(Value::Object(kv)) | SrcMsg::Bind(Value::Object(kv))) = data else {
+        return Ok(data.clone())
+    };
+    let Some(Value::String(name)) = kv.get(MESSAGE_REF) else {
+        return Ok(data.clone())
+    };
+    let mut overrides = kv.clone();
+    overrides.remove(MESSAGE_REF);
+
+    let template = messages
+        .get(name)
+        .ok_or_else(|| BuildErrorReason::UnknownMessageTemplate(name.clone(), scope_key))?;
+    match (template, data) {
+        (SrcMsg::Literal(template), SrcMsg::Literal(_)) => {
+            Ok(SrcMsg::Literal(merge_values(
+                template.clone(),
+                Value::Object(overrides),
+            )))
+        },
+        (SrcMsg::Bind(template), SrcMsg::Bind(_)) => {
+            Ok(SrcMsg::Bind(merge_values(
+                template.clone(),
+                Value::Object(overrides),
+            )))
+        },
+        (SrcMsg::Inject(_), _) => {
+            Err(BuildErrorReason::InjectedMessageTemplate(
+                name.clone(),
+                scope_key,
+            ))
+        },
+        (..) => {
+            Err(BuildErrorReason::MixedMessageTemplate(
+                name.clone(),
+                scope_key,
+            ))
+        },
+    }
+}
+
+/// Substitutes the number of the message of a `send_many` for `$index` in
+/// its bound template.
+fn with_index(data: &SrcMsg, index: usize) -> SrcMsg {
+    fn substitute(value: &Value, index: usize) -> Value {
+        match value {
+            Value::String(var_name) if var_name == "$index" => json!(index),
+            Value::Array(items) => items.iter().map(|item| substitute(item, index)).collect(),
+            Value::Object(kv) => {
+                Value::Object(
+                    kv.iter()
+                        .map(|(k, v)| (k.clone(), substitute(v, index)))
+                        .collect(),
+                )
+            },
+            as_is => as_is.clone(),
+        }
+    }
+    match data {
+        SrcMsg::Bind(template) => SrcMsg::Bind(substitute(template, index)),
+        as_is => as_is.clone(),
+    }
+}
+
+/// Merges the objects of `over` into the ones of `base`, key by key; anything
+/// else in `over` replaces what is in `base`.
+fn merge_values(base: Value, over: Value) -> Value {
+    match (base, over) {
+        (Value::Object(mut base), Value::Object(over)) => {
+            for (key, over) in over {
+                let merged = match base.remove(&key) {
+                    Some(base) => merge_values(base, over),
+                    None => over,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        },
+        (_, over) => over,
+    }
+}
+
+/// Fails with [BuildErrorReason::ConflictingFields] if more than one of the
+/// `fields` is set.
+fn check_exclusive<const N: usize>(
+    event_name: &EventName,
+    scope_key: KeyScope,
+    fields: [(&'static str, bool); N],
+) -> Result<(), BuildErrorReason> {
+    let mut set = fields.into_iter().filter(|(_, is_set)| *is_set);
+    match (set.next(), set.next()) {
+        (Some((this, _)), Some((that, _))) => {
+            Err(BuildErrorReason::ConflictingFields(
+                event_name.clone(),
+                this,
+                that,
+                scope_key,
+            ))
+        },
+        _ => Ok(()),
     }
 }
 
@@ -0,0 +1,190 @@
+//! Checking that the variables are bound before they are read.
+//!
+//! An event reads the variables of the templates it renders, and binds those
+//! of the patterns it matches. A variable bound by some events of a scope may
+//! only be read by an event happening after at least one of them: otherwise,
+//! whether it is bound by then depends on luck, and the run fails with an
+//! `UnboundValue` as soon as luck runs out. Unless the value is passed to the
+//! run, that is: hence a [Lint](crate::execution::Lint), rather than an error.
+//!
+//! The variables not bound by any event of the scope are left alone: those
+//! come from the outside, such as the values passed to the run.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+use slotmap::SlotMap;
+
+use crate::bindings;
+use crate::execution::build::BuildErrorReason;
+use crate::execution::{BindScope, EventKey, Events, KeyScope, ScopeInfo};
+use crate::scenario::SrcMsg;
+
+/// Returns the reads that may happen before the variable is bound, the
+/// earliest defined events first.
+pub(super) fn check(
+    events: &Events,
+    scopes: &SlotMap<KeyScope, ScopeInfo>,
+) -> Vec<BuildErrorReason> {
+    let mut bound_by: HashMap<(KeyScope, &str), Vec<EventKey>> = HashMap::new();
+    // the binds sharing all the values of one scope with another
+    let mut shared_by: HashMap<KeyScope, Vec<EventKey>> = HashMap::new();
+    let mut reads: Vec<(EventKey, KeyScope, &str)> = vec![];
+
+    for (k, bind) in events.bind.iter() {
+        let event_key = EventKey::Bind(k);
+        let (src_scope, dst_scope) = match bind.scope {
+            BindScope::Same(scope_key) => (scope_key, scope_key),
+            BindScope::Two {
+                src,
+                dst,
+                share_all,
+            } => {
+                if share_all {
+                    shared_by.entry(dst).or_default().push(event_key);
+                }
+                (src, dst)
+            },
+        };
+        for name in variables(&bind.dst.0) {
+            bound_by
+                .entry((dst_scope, name))
+                .or_default()
+                .push(event_key);
+        }
+        for name in src_variables(&bind.src) {
+            reads.push((event_key, src_scope, name));
+        }
+    }
+    for (k, recv) in events.recv.iter() {
+        let event_key = EventKey::Recv(k);
+        for pattern in recv.payload_matchers.iter() {
+            for name in variables(&pattern.0) {
+                bound_by
+                    .entry((recv.scope_key, name))
+                    .or_default()
+                    .push(event_key);
+            }
+        }
+        for name in recv.from_key.iter().flat_map(variables) {
+            reads.push((event_key, recv.scope_key, name));
+        }
+    }
+    for (k, send) in events.send.iter() {
+        let event_key = EventKey::Send(k);
+        let names = src_variables(&send.payload)
+            .into_iter()
+            .chain(send.routing_key.iter().flat_map(variables));
+        for name in names {
+            reads.push((event_key, send.scope_key, name));
+        }
+    }
+    for (k, respond) in events.respond.iter() {
+        let event_key = EventKey::Respond(k);
+        for name in respond.payload.iter().flat_map(src_variables) {
+            reads.push((event_key, respond.scope_key, name));
+        }
+    }
+
+    let mut prerequisites: HashMap<EventKey, Vec<EventKey>> = HashMap::new();
+    for (prereq, dependants) in events.key_unblocks_values.iter() {
+        for dependant in dependants {
+            prerequisites.entry(*dependant).or_default().push(*prereq);
+        }
+    }
+
+    let mut reads = reads
+        .into_iter()
+        .filter(|(_, scope_key, name)| {
+            !scopes[*scope_key].values.contains_key(*name)
+                && bound_by.contains_key(&(*scope_key, *name))
+        })
+        .collect::<Vec<_>>();
+    // report the earliest defined event first
+    reads.sort_by_key(|(event_key, ..)| events.priority.get(event_key).map(|(_, p)| *p));
+
+    let mut unbound = vec![];
+    for (event_key, scope_key, name) in reads {
+        let happens_after = ancestors(&prerequisites, event_key);
+        let mut binders = bound_by[&(scope_key, name)]
+            .iter()
+            .chain(shared_by.get(&scope_key).into_iter().flatten());
+        if binders.any(|k| happens_after.contains(k)) {
+            continue;
+        }
+        let Some((_, event_name)) = events.names.get(&event_key) else {
+            continue;
+        };
+        unbound.push(BuildErrorReason::UnboundVariable(
+            event_name.clone(),
+            name.to_owned(),
+            scope_key,
+        ));
+    }
+
+    unbound
+}
+
+fn ancestors(
+    prerequisites: &HashMap<EventKey, Vec<EventKey>>,
+    event_key: EventKey,
+) -> HashSet<EventKey> {
+    let mut seen = HashSet::new();
+    let mut queue = vec![event_key];
+    while let Some(this) = queue.pop() {
+        for prereq in prerequisites.get(&this).into_iter().flatten() {
+            if seen.insert(*prereq) {
+                queue.push(*prereq);
+            }
+        }
+    }
+    seen
+}
+
+fn src_variables(src: &SrcMsg) -> Vec<&str> {
+    match src {
+        SrcMsg::Bind(template) => variables(template),
+        SrcMsg::Literal(_) | SrcMsg::Inject(_) => vec![],
+    }
+}
+
+/// The variables of a template or a pattern: `$name.field`, `$name[0]` and
+/// `$name[]` stand for `$name`; the counters are not variables.
+fn variables(value: &Value) -> Vec<&str> {
+    fn inner<'a>(value: &'a Value, acc: &mut Vec<&'a str>) {
+        match value {
+            Value::String(wildcard) if wildcard == "$_" => (),
+            Value::String(counter) if bindings::counter_name(counter).is_some() => (),
+            Value::String(var_name) if var_name.starts_with('$') => {
+                let var_name = var_name.strip_suffix("[]").unwrap_or(var_name);
+                let var_name = var_name.split(['.', '[']).next().unwrap_or(var_name);
+                acc.push(var_name)
+            },
+            Value::Array(items) => items.iter().for_each(|item| inner(item, acc)),
+            Value::Object(kv) => kv.values().for_each(|v| inner(v, acc)),
+            _ => (),
+        }
+    }
+    let mut acc = vec![];
+    inner(value, &mut acc);
+    acc
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn variables_stand_for_the_whole_binding() {
+        let template = json!({
+            "a": "$ONE.field",
+            "b": ["$TWO[]", "$ONE[0].field", "$_", "$counter(c)", "literal"],
+        });
+        let mut names = variables(&template);
+        names.sort();
+        names.dedup();
+        assert_eq!(names, ["$ONE", "$TWO"]);
+    }
+}
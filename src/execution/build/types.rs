@@ -0,0 +1,132 @@
+//! Checking the values of the declared variables against their types, as far
+//! as it can be done without running anything: the values of the scopes, and
+//! the binds of the literals and of the other declared variables.
+//!
+//! Whatever is only known once the run is under way (e.g., the values the
+//! recvs bind) is checked by the runner.
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+use slotmap::SlotMap;
+
+use crate::bindings;
+use crate::execution::build::BuildErrorReason;
+use crate::execution::{BindScope, EventKey, Events, KeyScope, ScopeInfo};
+use crate::scenario::{BindingType, SrcMsg};
+
+type Types = BTreeMap<String, BindingType>;
+
+/// What is known about the value a template renders as.
+enum Rendered<'a> {
+    Value(&'a Value),
+    Typed(BindingType),
+    Unknown,
+}
+
+pub(super) fn check(
+    events: &Events,
+    scopes: &SlotMap<KeyScope, ScopeInfo>,
+) -> Result<(), BuildErrorReason> {
+    for (scope_key, info) in scopes.iter() {
+        for (name, value) in info.values.iter() {
+            if let Some(declared) = info.types.get(name).filter(|ty| !ty.admits(value)) {
+                return Err(BuildErrorReason::ValueTypeMismatch(
+                    name.clone(),
+                    *declared,
+                    scope_key,
+                ))
+            }
+        }
+    }
+
+    for (k, bind) in events.bind.iter() {
+        let (src, dst) = match bind.scope {
+            BindScope::Same(scope_key) => (scope_key, scope_key),
+            BindScope::Two { src, dst, .. } => (src, dst),
+        };
+        let (template, literal) = match &bind.src {
+            SrcMsg::Literal(value) => (value, true),
+            SrcMsg::Bind(template) => (template, false),
+            SrcMsg::Inject(_) => continue,
+        };
+        let Some((name, declared)) = mismatch(
+            &bind.dst.0,
+            template,
+            literal,
+            &scopes[src].types,
+            &scopes[dst].types,
+        ) else {
+            continue
+        };
+        let Some((_, event_name)) = events.names.get(&EventKey::Bind(k)) else {
+            continue
+        };
+        return Err(BuildErrorReason::BindTypeMismatch(
+            event_name.clone(),
+            name.to_owned(),
+            declared,
+            dst,
+        ))
+    }
+
+    Ok(())
+}
+
+/// Finds a declared variable of the `pattern` the `template` would bind to a
+/// value of another type.
+fn mismatch<'a>(
+    pattern: &'a Value,
+    template: &Value,
+    literal: bool,
+    src_types: &Types,
+    dst_types: &Types,
+) -> Option<(&'a str, BindingType)> {
+    match (pattern, template) {
+        (Value::String(var_name), template) if var_name.starts_with('$') => {
+            let declared = *dst_types.get(var_name)?;
+            let fits = match rendered(template, literal, src_types) {
+                Rendered::Value(value) => declared.admits(value),
+                Rendered::Typed(ty) => declared.includes(ty),
+                Rendered::Unknown => true,
+            };
+            (!fits).then_some((var_name.as_str(), declared))
+        },
+        (Value::Object(patterns), Value::Object(templates)) => {
+            patterns.iter().find_map(|(key, pattern)| {
+                mismatch(pattern, templates.get(key)?, literal, src_types, dst_types)
+            })
+        },
+        (Value::Array(patterns), Value::Array(templates)) => {
+            patterns
+                .iter()
+                .zip(templates)
+                .find_map(|(p, t)| mismatch(p, t, literal, src_types, dst_types))
+        },
+        _ => None,
+    }
+}
+
+fn rendered<'a>(template: &'a Value, literal: bool, src_types: &Types) -> Rendered<'a> {
+    if literal {
+        return Rendered::Value(template)
+    }
+    match template {
+        Value::String(var_name) if bindings::counter_name(var_name).is_some() => {
+            Rendered::Typed(BindingType::Integer)
+        },
+        Value::String(var_name) if var_name.starts_with('$') => {
+            src_types
+                .get(var_name)
+                .copied()
+                .map_or(Rendered::Unknown, Rendered::Typed)
+        },
+        // an operator, such as `{"$len": ...}`
+        Value::Object(kv) if kv.len() == 1 && kv.keys().all(|k| k.starts_with('$')) => {
+            Rendered::Unknown
+        },
+        Value::Object(_) => Rendered::Typed(BindingType::Object),
+        Value::Array(_) => Rendered::Typed(BindingType::Array),
+        scalar => Rendered::Value(scalar),
+    }
+}
@@ -0,0 +1,90 @@
+//! Reusing the executables built from the unchanged sources.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+
+use crate::execution::build::with_builtins;
+use crate::execution::{BuildError, Executable, KeyScenario, SourceCode};
+use crate::marshalling::MarshallingRegistry;
+
+/// The content hash of the sources, and the entry point's file.
+type BuiltKey = (u64, Arc<Path>);
+
+/// The executables built so far, keyed by the [content
+/// hash](SourceCode::content_hash) of the sources they have been built from.
+///
+/// The executables are stored in their serialized form (see
+/// [Executable::to_json]): unlike the marshalling registries, those can be
+/// shared between the threads, and each hit gets the registry of its own.
+#[derive(Debug, Default)]
+pub struct BuildCache {
+    built:  Mutex<HashMap<BuiltKey, Arc<str>>>,
+    hits:   AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl BuildCache {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Same as [Executable::build], unless an executable has been built from
+    /// the same sources before.
+    pub fn build<'a>(
+        &self,
+        marshalling: MarshallingRegistry,
+        sources: &'a SourceCode,
+        entry_point: KeyScenario,
+    ) -> Result<Executable, BuildError<'a>> {
+        let t_start = Instant::now();
+        let marshalling = with_builtins(marshalling);
+        let key = (
+            sources.content_hash(),
+            sources[entry_point].source_file.clone(),
+        );
+
+        let cached = self.built.lock().get(&key).cloned();
+        if let Some(mut executable) =
+            cached.and_then(|json| serde_json::from_str::<Executable>(&json).ok())
+        {
+            // a registry lacking some messages gets the build error it deserves
+            if executable.unregistered_message(&marshalling).is_none() {
+                executable.marshalling = marshalling;
+                executable.built_in = t_start.elapsed();
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(executable)
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let executable = Executable::build(marshalling, sources, entry_point)?;
+        if let Ok(json) = serde_json::to_string(&executable) {
+            self.built.lock().insert(key, json.into());
+        }
+        Ok(executable)
+    }
+
+    /// The number of executables stored.
+    pub fn len(&self) -> usize {
+        self.built.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of builds the stored executables have been reused for.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of builds there has been no stored executable for.
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,152 @@
+//! Running the same scenario once per row of a table of values.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use elfo::Blueprint;
+use serde_json::Value;
+
+use crate::execution::outcome_table::OutcomeTable;
+use crate::execution::{Executable, Report, RunError, RunOptions, SourceCode};
+
+/// The per-case outcomes of [Executable::run_cases].
+#[derive(Debug, Default)]
+pub struct CasesReport {
+    pub cases: Vec<CaseOutcome>,
+}
+
+/// The outcome of the run in a single case.
+#[derive(Debug)]
+pub struct CaseOutcome {
+    /// The values of the case's row.
+    pub values:  BTreeMap<String, Value>,
+    pub outcome: Result<Report, RunError>,
+}
+
+impl Executable {
+    /// The table of the cases taken from the entry point scenario.
+    pub fn cases(&self) -> &[BTreeMap<String, Value>] {
+        &self.cases
+    }
+
+    /// Runs the scenario once per row of its `cases`, each time with a fresh
+    /// runner and a fresh `blueprint`.
+    ///
+    /// A scenario without the `cases` is run once, as if it had a single
+    /// empty row.
+    pub async fn run_cases<B, C>(
+        &self,
+        blueprint: B,
+        config: C,
+        root_scope_values: impl IntoIterator<Item = (String, Value)>,
+    ) -> CasesReport
+    where
+        B: Fn() -> Blueprint,
+        C: for<'de> serde::de::Deserializer<'de> + Clone,
+    {
+        self.run_cases_with_options(
+            self.cases.clone(),
+            blueprint,
+            config,
+            root_scope_values,
+            RunOptions::default(),
+        )
+        .await
+    }
+
+    /// Same as [Executable::run_cases], but with the table of `cases` given
+    /// by the caller rather than taken from the scenario, and with the
+    /// [RunOptions] other than the default ones.
+    ///
+    /// The values of a row take precedence over `root_scope_values`.
+    pub async fn run_cases_with_options<B, C>(
+        &self,
+        cases: impl IntoIterator<Item = BTreeMap<String, Value>>,
+        blueprint: B,
+        config: C,
+        root_scope_values: impl IntoIterator<Item = (String, Value)>,
+        options: RunOptions,
+    ) -> CasesReport
+    where
+        B: Fn() -> Blueprint,
+        C: for<'de> serde::de::Deserializer<'de> + Clone,
+    {
+        let root_scope_values = root_scope_values.into_iter().collect::<Vec<_>>();
+        let mut cases = cases.into_iter().collect::<Vec<_>>();
+        if cases.is_empty() {
+            cases.push(Default::default());
+        }
+
+        let mut report = CasesReport::default();
+        for values in cases {
+            let case_values = root_scope_values
+                .iter()
+                .cloned()
+                .chain(values.iter().map(|(k, v)| (k.clone(), v.clone())));
+            let outcome = self
+                .start_with_options(blueprint(), config.clone(), case_values, options)
+                .await
+                .run()
+                .await;
+            report.cases.push(CaseOutcome { values, outcome });
+        }
+
+        report
+    }
+}
+
+impl CasesReport {
+    pub fn is_ok(&self) -> bool {
+        self.cases.iter().all(CaseOutcome::is_ok)
+    }
+
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|c| c.is_ok()).count()
+    }
+
+    pub fn message<'a>(
+        &'a self,
+        executable: &'a Executable,
+        source_code: &'a SourceCode,
+    ) -> impl fmt::Display + 'a {
+        DisplayCasesReport {
+            report: self,
+            executable,
+            source_code,
+        }
+    }
+}
+
+impl CaseOutcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(&self.outcome, Ok(report) if report.is_ok())
+    }
+}
+
+struct DisplayCasesReport<'a> {
+    report:      &'a CasesReport,
+    executable:  &'a Executable,
+    source_code: &'a SourceCode,
+}
+
+impl fmt::Display for DisplayCasesReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            report,
+            executable,
+            source_code,
+        } = self;
+
+        let mut table = OutcomeTable::new(f, "CASES", report.passed(), report.cases.len())?;
+        for (idx, case) in report.cases.iter().enumerate() {
+            if case.is_ok() {
+                continue
+            }
+            let values = serde_json::to_string(&case.values).expect("values are serializable");
+            table.failed(format_args!("case #{}: {}", idx + 1, values))?;
+            table.outcome(&case.outcome, executable, source_code)?;
+        }
+
+        Ok(())
+    }
+}
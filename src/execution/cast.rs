@@ -0,0 +1,112 @@
+//! The static cast of an [`Executable`]: every actor and dummy, the scopes
+//! they are known in, and how their names are mapped across subroutine calls.
+
+use slotmap::{SecondaryMap, SlotMap};
+
+use crate::execution::{Executable, KeyScope, ScopeInfo};
+use crate::names::{ActorName, DummyName, EventName};
+
+#[derive(Debug, Clone)]
+pub struct Cast {
+    pub scopes:  Vec<CastScope>,
+    pub actors:  Vec<CastMember<ActorName>>,
+    pub dummies: Vec<CastMember<DummyName>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CastScope {
+    pub key:    KeyScope,
+    pub parent: Option<KeyScope>,
+
+    /// Names of the call-events leading to this scope from the root, joined
+    /// with `/`. The root scope is `.`.
+    pub path: String,
+}
+
+/// A single actor or dummy.
+#[derive(Debug, Clone)]
+pub struct CastMember<N> {
+    /// The names this member is known as, per scope.
+    pub known_as: Vec<(KeyScope, N)>,
+
+    /// The name mappings established by the subroutine calls.
+    pub mappings: Vec<CastMapping<N>>,
+}
+
+/// `caller` is passed into a subroutine call `via` as `callee`.
+#[derive(Debug, Clone)]
+pub struct CastMapping<N> {
+    pub caller: (KeyScope, N),
+    pub callee: (KeyScope, N),
+    pub via:    EventName,
+}
+
+impl Executable {
+    /// Describes the actors and dummies of this executable.
+    pub fn cast(&self) -> Cast {
+        let scopes = self
+            .scopes
+            .iter()
+            .map(|(key, info)| {
+                CastScope {
+                    key,
+                    parent: info.invoked_as.as_ref().map(|(parent, ..)| *parent),
+                    path: scope_path(&self.scopes, key),
+                }
+            })
+            .collect();
+        let actors = self
+            .actors
+            .values()
+            .map(|a| cast_member(&self.scopes, &a.known_as))
+            .collect();
+        let dummies = self
+            .dummies
+            .values()
+            .map(|d| cast_member(&self.scopes, &d.known_as))
+            .collect();
+
+        Cast {
+            scopes,
+            actors,
+            dummies,
+        }
+    }
+}
+
+fn cast_member<N: Clone>(
+    scopes: &SlotMap<KeyScope, ScopeInfo>,
+    known_as: &SecondaryMap<KeyScope, N>,
+) -> CastMember<N> {
+    let mappings = known_as
+        .iter()
+        .filter_map(|(callee_scope, callee_name)| {
+            let (caller_scope, via, _) = scopes[callee_scope].invoked_as.as_ref()?;
+            let caller_name = known_as.get(*caller_scope)?;
+            Some(CastMapping {
+                caller: (*caller_scope, caller_name.clone()),
+                callee: (callee_scope, callee_name.clone()),
+                via:    via.clone(),
+            })
+        })
+        .collect();
+    let known_as = known_as.iter().map(|(s, n)| (s, n.clone())).collect();
+
+    CastMember { known_as, mappings }
+}
+
+pub(super) fn scope_path(scopes: &SlotMap<KeyScope, ScopeInfo>, scope_key: KeyScope) -> String {
+    let mut call_events: Vec<&str> = vec![];
+    let mut invoked_as = scopes[scope_key].invoked_as.as_ref();
+    while let Some((parent, event_name, _)) = invoked_as.take() {
+        call_events.push(event_name.as_ref());
+        invoked_as = scopes[*parent].invoked_as.as_ref();
+    }
+
+    if call_events.is_empty() {
+        ".".into()
+    } else {
+        call_events.reverse();
+        call_events.join("/")
+    }
+}
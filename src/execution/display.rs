@@ -1,28 +1,114 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::fmt;
+use std::io::IsTerminal;
 
 use slotmap::SlotMap;
 
 use crate::execution::build::{BuildError, BuildErrorReason};
-use crate::execution::runner::ReadyEventKey;
+use crate::execution::runner::{EventContext, ReadyEventKey, RunError};
 use crate::execution::{
-    EventKey, Executable, KeyScenario, KeyScope, Report, ScopeInfo, SourceCode,
+    EventKey, Executable, KeyScenario, KeyScope, Report, ScopeInfo, SourceCode, Throughput,
+    UnmatchedEnvelope, Unreached,
 };
 use crate::recorder::{records as r, Record, RecordKind, RecordLog};
 use crate::scenario::{RequiredToBe, SrcMsg};
 use crate::sources::SingleScenarioSource;
 
+/// Whether the messages are coloured with the ANSI escape codes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Style {
+    /// Coloured, unless `NO_COLOR` is set, or the output is not a terminal:
+    /// see [Style::for_sink].
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+const GREY: &str = "90";
+const DIM: &str = "2";
+const RED: &str = "31";
+const GREEN: &str = "32";
+const BOLD_GREEN: &str = "1;32";
+const LIGHT_GREEN: &str = "92";
+const YELLOW: &str = "33";
+const BOLD_YELLOW: &str = "1;33";
+const BLUE: &str = "34";
+const MAGENTA: &str = "35";
+const BOLD_MAGENTA: &str = "1;35";
+const CYAN: &str = "36";
+
+/// `content` in the colour of the ANSI SGR code `sgr`, unless the colours are
+/// off.
+struct Paint<T> {
+    colours: bool,
+    sgr:     &'static str,
+    content: T,
+}
+
+#[derive(Clone, Copy)]
 pub(super) struct DisplayRecord<'a> {
     pub(super) record:      &'a Record,
     pub(super) log:         &'a RecordLog,
     pub(super) executable:  &'a Executable,
     pub(super) source_code: &'a SourceCode,
+    pub(super) style:       Style,
 }
 
-pub(super) struct DisplayReport<'a> {
+/// The message of a [Report], see [Report::message].
+#[derive(Clone, Copy)]
+pub struct DisplayReport<'a> {
     pub(super) report:      &'a Report,
     pub(super) executable:  &'a Executable,
     pub(super) source_code: &'a SourceCode,
+    pub(super) timings:     bool,
+    pub(super) style:       Style,
+}
+
+impl Style {
+    /// Resolves [Style::Auto] for the output written to `sink`: coloured,
+    /// unless `NO_COLOR` is set, or `sink` is not a terminal.
+    pub fn for_sink(self, sink: &impl IsTerminal) -> Self {
+        match self {
+            Self::Auto if no_color() || !sink.is_terminal() => Self::Never,
+            Self::Auto => Self::Always,
+            resolved => resolved,
+        }
+    }
+
+    /// Whether the output is coloured: [Style::Auto], unless resolved with
+    /// [Style::for_sink], is resolved for the standard output.
+    pub fn colours(self) -> bool {
+        self.for_sink(&std::io::stdout()) == Self::Always
+    }
+}
+
+fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+fn paint<T: fmt::Display>(colours: bool, sgr: &'static str, content: T) -> Paint<T> {
+    Paint {
+        colours,
+        sgr,
+        content,
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Paint<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.colours {
+            write!(f, "\x1b[{}m{}\x1b[0m", self.sgr, self.content)
+        } else {
+            write!(f, "{}", self.content)
+        }
+    }
+}
+
+impl DisplayReport<'_> {
+    pub fn with_style(self, style: Style) -> Self {
+        Self { style, ..self }
+    }
 }
 
 impl fmt::Display for DisplayReport<'_> {
@@ -31,67 +117,61 @@ impl fmt::Display for DisplayReport<'_> {
             report,
             executable,
             source_code,
+            timings,
+            style,
         } = self;
+        let colours = style.colours();
 
         let mut visited = HashSet::new();
-        let mut key_requires_value = HashMap::new();
-        for (&k, dependants) in executable.events.key_unblocks_values.iter() {
-            for d in dependants.iter().copied() {
-                key_requires_value
-                    .entry(d)
-                    .or_insert(HashSet::new())
-                    .insert(k);
-            }
-        }
+        let key_requires_value = executable.key_requires_value();
 
-        #[allow(clippy::too_many_arguments)]
         fn failed_to_reach(
             io: &mut impl fmt::Write,
-            visited: &mut HashSet<EventKey>,
             depth: usize,
-            event_key: EventKey,
-            key_requires_value: &HashMap<EventKey, HashSet<EventKey>>,
-            report: &Report,
+            unreached: &Unreached,
             executable: &Executable,
             source_code: &SourceCode,
+            colours: bool,
         ) -> fmt::Result {
-            let event_name = event_full_name(event_key, executable, source_code);
+            let event_name = event_full_name(unreached.event_key, executable, source_code);
+            let description = description(unreached.event_key, executable, colours);
             write!(io, "{:1$}", "", depth)?;
-            writeln!(io, "- \x1b[31m{event_name}\x1b[0m")?;
+            writeln!(io, "- {}{description}", paint(colours, RED, event_name))?;
 
-            if !visited.insert(event_key) {
+            if unreached.elided {
                 write!(io, "{:1$}", "", depth + 1)?;
                 writeln!(io, "...")?;
                 return Ok(())
             }
 
-            for prerequisite in key_requires_value
-                .get(&event_key)
-                .into_iter()
-                .flatten()
-                .copied()
-            {
-                if report.reached_events.contains(&prerequisite) {
-                    let prerequisite_name = event_full_name(prerequisite, executable, source_code);
-                    write!(io, "{:1$}", "", depth + 1)?;
-                    writeln!(io, "+ \x1b[32m{prerequisite_name}\x1b[0m")?;
-                } else {
-                    failed_to_reach(
-                        io,
-                        visited,
-                        depth + 1,
-                        prerequisite,
-                        key_requires_value,
-                        report,
-                        executable,
-                        source_code,
-                    )?;
-                }
+            for prerequisite in unreached.reached.iter().copied() {
+                let prerequisite_name = event_full_name(prerequisite, executable, source_code);
+                write!(io, "{:1$}", "", depth + 1)?;
+                writeln!(io, "+ {}", paint(colours, GREEN, prerequisite_name))?;
+            }
+            for prerequisite in unreached.unreached.iter() {
+                failed_to_reach(
+                    io,
+                    depth + 1,
+                    prerequisite,
+                    executable,
+                    source_code,
+                    colours,
+                )?;
             }
 
             Ok(())
         }
 
+        fn description(ek: EventKey, executable: &Executable, colours: bool) -> String {
+            executable
+                .events
+                .descriptions
+                .get(&ek)
+                .map(|d| format!(" {}", paint(colours, DIM, format_args!("— {d}"))))
+                .unwrap_or_default()
+        }
+
         fn event_full_name(
             ek: EventKey,
             executable: &Executable,
@@ -113,39 +193,107 @@ impl fmt::Display for DisplayReport<'_> {
 
         writeln!(f, "REPORT")?;
 
-        // let colour = if failure { "\x1b[31m" } else { "\x1b[32m" };
-        let colour_red = "\x1b[31m";
-        let colour_green = "\x1b[32m";
-        let colour_reset = "\x1b[0m";
+        if let Some(seed) = report.seed {
+            writeln!(f, " seed: {seed}")?;
+        }
+        if let Some(interleaving) = report.interleaving.as_ref() {
+            write!(f, " interleaving: [")?;
+            for (idx, choice) in interleaving.iter().enumerate() {
+                let sep = if idx == 0 { "" } else { ", " };
+                write!(f, "{sep}{}/{}", choice.taken, choice.of)?;
+            }
+            writeln!(f, "]")?;
+        }
+
+        if report.cancelled {
+            writeln!(
+                f,
+                " {} — the run was interrupted",
+                paint(colours, BOLD_YELLOW, "CANCELLED")
+            )?;
+        }
 
         for (&ek, &r) in report.required_events.iter() {
             let en = event_full_name(ek, executable, source_code);
+            let description = description(ek, executable, colours);
             match (r, report.reached_events.contains(&ek)) {
                 (RequiredToBe::Reached, false) => {
-                    failed_to_reach(
-                        f,
-                        &mut visited,
-                        1,
-                        ek,
-                        &key_requires_value,
-                        report,
-                        executable,
-                        source_code,
-                    )?
+                    let unreached = report.unreached(&key_requires_value, &mut visited, ek);
+                    failed_to_reach(f, 1, &unreached, executable, source_code, colours)?
                 },
                 (RequiredToBe::Unreached, true) => {
-                    writeln!(f, " + {colour_red}{en}{colour_reset}")?
+                    writeln!(f, " + {}{description}", paint(colours, RED, en))?
                 },
 
                 (RequiredToBe::Reached, true) => {
-                    writeln!(f, " + {colour_green}{en}{colour_reset}")?
+                    if let Some(took) = report.missed_deadline(ek) {
+                        let within = report.deadlines[&ek].within;
+                        writeln!(
+                            f,
+                            " + {} (took {took:?}, the deadline is {within:?}){description}",
+                            paint(colours, RED, en)
+                        )?
+                    } else {
+                        writeln!(f, " + {}", paint(colours, GREEN, en))?
+                    }
                 },
                 (RequiredToBe::Unreached, false) => {
-                    writeln!(f, " - {colour_green}{en}{colour_reset}")?
+                    writeln!(f, " - {}", paint(colours, GREEN, en))?
                 },
             }
         }
 
+        if !report.unmatched.is_empty() {
+            writeln!(f, "UNMATCHED ENVELOPES")?;
+            for UnmatchedEnvelope {
+                message_name,
+                from,
+                to,
+                payload,
+            } in report.unmatched.iter()
+            {
+                let to = to.map_or_else(|| "routed".to_owned(), |addr| addr.to_string());
+                writeln!(f, " {message_name} [from: {from}; to: {to}]: {payload}")?;
+            }
+        }
+
+        if !report.stats.throughput.is_empty() {
+            writeln!(f, "THROUGHPUT")?;
+            for Throughput {
+                event_key,
+                messages,
+                duration,
+                min_interval,
+                mean_interval,
+                max_interval,
+            } in report.stats.throughput.iter()
+            {
+                let en = event_full_name(*event_key, executable, source_code);
+                writeln!(
+                    f,
+                    " {en}: {messages} messages in {duration:?} (intervals: min {min_interval:?}, \
+                     mean {mean_interval:?}, max {max_interval:?})"
+                )?;
+            }
+        }
+
+        if *timings {
+            let mut fired_at = report.fired_at.iter().collect::<Vec<_>>();
+            fired_at.sort_by_key(|(ek, at)| (at.runtime, at.wall, **ek));
+
+            writeln!(f, "TIMINGS")?;
+            writeln!(f, " {:>12} {:>12}  event", "wall", "runtime")?;
+            for (&ek, at) in fired_at {
+                let en = event_full_name(ek, executable, source_code);
+                writeln!(
+                    f,
+                    " {:>12} {:>12}  {en}",
+                    format!("{:?}", at.wall),
+                    format!("{:?}", at.runtime)
+                )?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -157,6 +305,7 @@ impl fmt::Display for DisplayRecord<'_> {
             log,
             executable,
             source_code,
+            style,
         } = self;
         let (t0_wall, t0_rt) = log.t_zero;
         let (t_wall, t_rt) = record.at;
@@ -173,6 +322,7 @@ impl fmt::Display for DisplayRecord<'_> {
                 kind,
                 executable,
                 source_code,
+                colours: style.colours(),
             }
         )
     }
@@ -193,13 +343,38 @@ impl fmt::Display for BuildError<'_> {
             NotARequest(_, k) => k,
             UnknownActor(_, k) => k,
             UnknownDummy(_, k) => k,
+            UnknownGroup(_, k) => k,
             UnknownSubroutine(_, k) => k,
             UnknownFqn(_, k) => k,
             UnknownAlias(_, k) => k,
+            UnknownPattern(_, k) => k,
+            RecursivePattern(_, k) => k,
+            UnknownMessageTemplate(_, k) => k,
+            InjectedMessageTemplate(_, k) => k,
+            MixedMessageTemplate(_, k) => k,
             DuplicateAlias(_, k) => k,
             DuplicateEventName(_, k) => k,
             DuplicateActorName(_, k) => k,
             DuplicateDummyName(_, k) => k,
+            TooManyDummies(_, _, k) => k,
+            InvalidDummyPool(_, k) => k,
+            InvalidProbability(_, _, k) => k,
+            InvalidLatency(_, _, k) => k,
+            NotARequestType(_, k) => k,
+            InvalidCount(_, k) => k,
+            ConflictingFields(_, _, _, k) => k,
+            MissingField(_, _, k) => k,
+            #[cfg(feature = "json-schema")]
+            InvalidPayload(_, _, k) => k,
+            UnmappedActor(_, k) => k,
+            UnmappedDummy(_, k) => k,
+            #[cfg(feature = "json-schema")]
+            UnknownKey(_, _, k) => k,
+            UnboundVariable(_, _, k) => k,
+            ValueTypeMismatch(_, _, k) => k,
+            BindTypeMismatch(_, _, _, k) => k,
+            #[cfg(not(feature = "metrics"))]
+            MetricsDisabled(_, k) => k,
         };
 
         write!(f, "{} (", reason)?;
@@ -208,6 +383,29 @@ impl fmt::Display for BuildError<'_> {
     }
 }
 
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)?;
+        if let Some(EventContext {
+            name, called_by, ..
+        }) = &self.event
+        {
+            write!(f, " (at {}", name)?;
+            for call in called_by {
+                write!(f, " < {}", call)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RunError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.reason)
+    }
+}
+
 impl fmt::Debug for BuildError<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self, f)
@@ -215,15 +413,16 @@ impl fmt::Debug for BuildError<'_> {
 }
 
 pub(super) struct DisplayRecordKind<'a> {
-    kind:        &'a RecordKind,
-    executable:  &'a Executable,
-    source_code: &'a SourceCode,
+    pub(super) kind:        &'a RecordKind,
+    pub(super) executable:  &'a Executable,
+    pub(super) source_code: &'a SourceCode,
+    pub(super) colours:     bool,
 }
 
-struct DisplayScope<'a> {
-    scope:       KeyScope,
-    executable:  &'a Executable,
-    source_code: &'a SourceCode,
+pub(super) struct DisplayScope<'a> {
+    pub(super) scope:       KeyScope,
+    pub(super) executable:  &'a Executable,
+    pub(super) source_code: &'a SourceCode,
 }
 
 impl<'a> DisplayRecordKind<'a> {
@@ -234,6 +433,10 @@ impl<'a> DisplayRecordKind<'a> {
             source_code: self.source_code,
         }
     }
+
+    fn paint<T: fmt::Display>(&self, sgr: &'static str, content: T) -> Paint<T> {
+        paint(self.colours, sgr, content)
+    }
 }
 
 impl fmt::Display for DisplayScope<'_> {
@@ -253,55 +456,83 @@ impl fmt::Display for DisplayRecordKind<'_> {
 
         match self.kind {
             ProcessEventClass(r::ProcessEventClass(ReadyEventKey::Bind)) => {
-                write!(f, "\x1b[90mrequested BIND\x1b[0m")
+                write!(f, "{}", self.paint(GREY, "requested BIND"))
             },
             ProcessEventClass(r::ProcessEventClass(ReadyEventKey::RecvOrDelay)) => {
-                write!(f, "\x1b[90mrequested RECV or DELAY\x1b[0m")
+                write!(f, "{}", self.paint(GREY, "requested RECV or DELAY"))
             },
             ProcessEventClass(r::ProcessEventClass(ReadyEventKey::Send(k))) => {
                 let (scope, event) = self.executable.event_name((*k).into()).unwrap();
                 write!(
                     f,
-                    "\x1b[90mrequested SEND: {} ({})\x1b[0m",
-                    event,
-                    self.scope(scope)
+                    "{}",
+                    self.paint(
+                        GREY,
+                        format_args!("requested SEND: {} ({})", event, self.scope(scope))
+                    )
                 )
             },
             ProcessEventClass(r::ProcessEventClass(ReadyEventKey::Respond(k))) => {
                 let (scope, event) = self.executable.event_name((*k).into()).unwrap();
                 write!(
                     f,
-                    "\x1b[90mrequested RESP: {} ({})\x1b[0m",
-                    event,
-                    self.scope(scope)
+                    "{}",
+                    self.paint(
+                        GREY,
+                        format_args!("requested RESP: {} ({})", event, self.scope(scope))
+                    )
+                )
+            },
+            ProcessEventClass(r::ProcessEventClass(ReadyEventKey::StopDummy(k))) => {
+                let (scope, event) = self.executable.event_name((*k).into()).unwrap();
+                write!(
+                    f,
+                    "{}",
+                    self.paint(
+                        GREY,
+                        format_args!("requested STOP DUMMY: {} ({})", event, self.scope(scope))
+                    )
                 )
             },
 
             ReadyBindKeys(r::ReadyBindKeys(ks)) => {
-                write!(f, "\x1b[90mready binds: [")?;
-                for k in ks {
-                    let (scope, event) = self.executable.event_name((*k).into()).unwrap();
-                    write!(f, " {}({}) ", event, self.scope(scope))?;
-                }
-                write!(f, "]\x1b[0m")
+                let ready = DisplayEvents {
+                    keys: ks.iter().map(|k| EventKey::from(*k)),
+                    kind: self,
+                };
+                write!(
+                    f,
+                    "{}",
+                    self.paint(GREY, format_args!("ready binds: [{}]", ready))
+                )
             },
             ReadyRecvKeys(r::ReadyRecvKeys(ks)) => {
-                write!(f, "\x1b[90mready recvs: [")?;
-                for k in ks {
-                    let (scope, event) = self.executable.event_name((*k).into()).unwrap();
-                    write!(f, " {}({}) ", event, self.scope(scope))?;
-                }
-                write!(f, "]\x1b[0m")
+                let ready = DisplayEvents {
+                    keys: ks.iter().map(|k| EventKey::from(*k)),
+                    kind: self,
+                };
+                write!(
+                    f,
+                    "{}",
+                    self.paint(GREY, format_args!("ready recvs: [{}]", ready))
+                )
             },
             TimedOutRecvKey(r::TimedOutRecvKey(k)) => {
                 let (scope, event) = self.executable.event_name((*k).into()).unwrap();
                 write!(
                     f,
-                    "\x1b[31mtimed out RECV: {} \x1b[0m({})",
-                    event,
+                    "{}({})",
+                    self.paint(RED, format_args!("timed out RECV: {} ", event)),
                     self.scope(scope)
                 )
             },
+            TimeAdvanced(r::TimeAdvanced(d)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(MAGENTA, format_args!("time advanced by {:?}", d))
+                )
+            },
 
             ProcessBindKey(r::ProcessBindKey(k)) => {
                 let (scope, event) = self.executable.event_name((*k).into()).unwrap();
@@ -309,21 +540,31 @@ impl fmt::Display for DisplayRecordKind<'_> {
             },
             ProcessSend(r::ProcessSend(k)) => write!(f, "process send {:?}", k),
             ProcessRespond(r::ProcessRespond(k)) => write!(f, "process resp {:?}", k),
+            FailRequest(r::FailRequest) => write!(f, "failing the request"),
 
             BindSrcScope(r::BindSrcScope(k)) => {
-                write!(f, "\x1b[92msrc scope\x1b[0m {}", self.scope(*k))
+                write!(
+                    f,
+                    "{} {}",
+                    self.paint(LIGHT_GREEN, "src scope"),
+                    self.scope(*k)
+                )
             },
             BindDstScope(r::BindDstScope(k)) => {
-                write!(f, "\x1b[92mdst scope\x1b[0m {}", self.scope(*k))
+                write!(
+                    f,
+                    "{} {}",
+                    self.paint(LIGHT_GREEN, "dst scope"),
+                    self.scope(*k)
+                )
             },
 
             MatchActorAddress(r::MatchActorAddress(ka, ks, exp, act)) if exp == act => {
                 let actor_name = &self.executable.actors[*ka].known_as[*ks];
                 write!(
                     f,
-                    "\x1b[32mMATCH ACTOR {} = {}\x1b[0m {}",
-                    exp,
-                    actor_name,
+                    "{} {}",
+                    self.paint(GREEN, format_args!("MATCH ACTOR {} = {}", exp, actor_name)),
                     self.scope(*ks)
                 )
             },
@@ -331,10 +572,11 @@ impl fmt::Display for DisplayRecordKind<'_> {
                 let actor_name = &self.executable.actors[*ka].known_as[*ks];
                 write!(
                     f,
-                    "\x1b[33mMISMATCH ACTOR exp={}, act={}; {}\x1b[0m {}",
-                    exp,
-                    act,
-                    actor_name,
+                    "{} {}",
+                    self.paint(
+                        YELLOW,
+                        format_args!("MISMATCH ACTOR exp={}, act={}; {}", exp, act, actor_name)
+                    ),
                     self.scope(*ks)
                 )
             },
@@ -342,9 +584,11 @@ impl fmt::Display for DisplayRecordKind<'_> {
                 let actor_name = &self.executable.actors[*ka].known_as[*ks];
                 write!(
                     f,
-                    "\x1b[32mSET actor name {} = {} \x1b[0m {}",
-                    addr,
-                    actor_name,
+                    "{} {}",
+                    self.paint(
+                        GREEN,
+                        format_args!("SET actor name {} = {} ", addr, actor_name)
+                    ),
                     self.scope(*ks)
                 )
             },
@@ -359,13 +603,85 @@ impl fmt::Display for DisplayRecordKind<'_> {
                 )
             },
 
+            EnvelopeKindMismatch(r::EnvelopeKindMismatch(exp, act)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(
+                        YELLOW,
+                        format_args!("MISMATCH KIND exp={:?}, act={:?}", exp, act)
+                    )
+                )
+            },
+            SenderAlreadySeen(r::SenderAlreadySeen(addr)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(
+                        YELLOW,
+                        format_args!("MISMATCH ACTOR {} is already known", addr)
+                    )
+                )
+            },
+            MatchGroup(r::MatchGroup(addr, exp, Some(act))) if exp.as_ref() == *act => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(GREEN, format_args!("MATCH GROUP {} = {}", addr, act))
+                )
+            },
+            MatchGroup(r::MatchGroup(addr, exp, act)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(
+                        YELLOW,
+                        format_args!(
+                            "MISMATCH GROUP {}: exp={}, act={}",
+                            addr,
+                            exp.as_ref(),
+                            act.unwrap_or("?")
+                        )
+                    )
+                )
+            },
+            MatchRoutingKey(r::MatchRoutingKey(key, None, act)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(GREEN, format_args!("SET routing key {} = {}", act, key))
+                )
+            },
+            MatchRoutingKey(r::MatchRoutingKey(key, Some(exp), act)) if exp == act => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(GREEN, format_args!("MATCH ROUTING KEY {} = {}", exp, key))
+                )
+            },
+            MatchRoutingKey(r::MatchRoutingKey(key, Some(exp), act)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(
+                        YELLOW,
+                        format_args!("MISMATCH ROUTING KEY exp={}, act={}; {}", exp, act, key)
+                    )
+                )
+            },
+            ResolveRoutingKey(r::ResolveRoutingKey(key, Some(addr))) => {
+                write!(f, "resolve routing key {} = {}", addr, key)
+            },
+            ResolveRoutingKey(r::ResolveRoutingKey(key, None)) => {
+                write!(f, "resolve routing key {} = <via routing>", key)
+            },
+
             MatchDummyAddress(r::MatchDummyAddress(kd, ks, exp, act)) if exp == act => {
                 let dummy_name = &self.executable.dummies[*kd].known_as[*ks];
                 write!(
                     f,
-                    "\x1b[32mMATCH DUMMY {} = {}\x1b[0m {}",
-                    exp,
-                    dummy_name,
+                    "{} {}",
+                    self.paint(GREEN, format_args!("MATCH DUMMY {} = {}", exp, dummy_name)),
                     self.scope(*ks)
                 )
             },
@@ -373,10 +689,11 @@ impl fmt::Display for DisplayRecordKind<'_> {
                 let dummy_name = &self.executable.dummies[*kd].known_as[*ks];
                 write!(
                     f,
-                    "\x1b[33mMISMATCH DUMMY exp={}, act={}; {}\x1b[0m {}",
-                    exp,
-                    act,
-                    dummy_name,
+                    "{} {}",
+                    self.paint(
+                        YELLOW,
+                        format_args!("MISMATCH DUMMY exp={}, act={}; {}", exp, act, dummy_name)
+                    ),
                     self.scope(*ks)
                 )
             },
@@ -395,16 +712,21 @@ impl fmt::Display for DisplayRecordKind<'_> {
             UsingValue(r::UsingValue(json)) => {
                 write!(
                     f,
-                    "\x1b[34mvalue: {}\x1b[0m",
-                    serde_json::to_string(json).unwrap()
+                    "{}",
+                    self.paint(
+                        BLUE,
+                        format_args!("value: {}", serde_json::to_string(json).unwrap())
+                    )
                 )
             },
             NewBinding(r::NewBinding(key, value)) => {
                 write!(
                     f,
-                    "\x1b[32mSET {} = {}\x1b[0m",
-                    key,
-                    serde_json::to_string(value).unwrap()
+                    "{}",
+                    self.paint(
+                        GREEN,
+                        format_args!("SET {} = {}", key, serde_json::to_string(value).unwrap())
+                    )
                 )
             },
 
@@ -412,37 +734,57 @@ impl fmt::Display for DisplayRecordKind<'_> {
                 let (scope, event) = self.executable.event_name(*k).unwrap();
                 write!(
                     f,
-                    "\x1b[1;32mcompleted {} \x1b[0m({})",
-                    event,
+                    "{}({})",
+                    self.paint(BOLD_GREEN, format_args!("completed {} ", event)),
                     self.scope(scope)
                 )
             },
 
             SendMessageType(r::SendMessageType(fqn)) => {
-                write!(f, "\x1b[36msend {}\x1b[0m", fqn)
+                write!(f, "{}", self.paint(CYAN, format_args!("send {}", fqn)))
+            },
+            SendTo(r::SendTo(None)) => write!(f, "{}", self.paint(CYAN, "routed")),
+            SendTo(r::SendTo(Some(addr))) => {
+                write!(f, "{}", self.paint(CYAN, format_args!("to:{}", addr)))
+            },
+            FaultDropped(r::FaultDropped) => {
+                write!(f, "{}", self.paint(BOLD_YELLOW, "FAULT: DROPPED"))
+            },
+            FaultDelayed(r::FaultDelayed(d)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(BOLD_YELLOW, format_args!("FAULT: DELAYED for {:?}", d))
+                )
+            },
+            FaultDuplicated(r::FaultDuplicated) => {
+                write!(f, "{}", self.paint(BOLD_YELLOW, "FAULT: DUPLICATED"))
             },
-            SendTo(r::SendTo(None)) => write!(f, "\x1b[36mrouted\x1b[0m"),
-            SendTo(r::SendTo(Some(addr))) => write!(f, "\x1b[36mto:{}\x1b[0m", addr),
 
-            BindOutcome(r::BindOutcome(true)) => write!(f, "\x1b[1;32mBOUND\x1b[0m"),
-            BindOutcome(r::BindOutcome(false)) => write!(f, "\x1b[33mNOT BOUND\x1b[0m"),
+            BindOutcome(r::BindOutcome(true)) => write!(f, "{}", self.paint(BOLD_GREEN, "BOUND")),
+            BindOutcome(r::BindOutcome(false)) => write!(f, "{}", self.paint(YELLOW, "NOT BOUND")),
 
             EnvelopeReceived(r::EnvelopeReceived {
                 message_name,
                 from,
                 to_opt,
             }) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(MAGENTA, format_args!("received {} ", message_name))
+                )?;
                 if let Some(to) = to_opt {
                     write!(
                         f,
-                        "\x1b[35mreceived {} \x1b[1mfrom {} to {}\x1b[0m",
-                        message_name, from, to
+                        "{}",
+                        self.paint(BOLD_MAGENTA, format_args!("from {} to {}", from, to))
                     )
                 } else {
                     write!(
                         f,
-                        "\x1b[35mreceived {} \x1b[1mfrom {} routed\x1b[0m",
-                        message_name, from
+                        "{}",
+                        self.paint(BOLD_MAGENTA, format_args!("from {} routed", from))
                     )
                 }
             },
@@ -452,13 +794,86 @@ impl fmt::Display for DisplayRecordKind<'_> {
                 write!(f, "matching RECV: {} ({})", event, self.scope(scope))
             },
 
+            ResponseReceived(r::ResponseReceived(k)) => {
+                let (scope, event) = self.executable.event_name((*k).into()).unwrap();
+                write!(
+                    f,
+                    "{} for REQUEST: {} ({})",
+                    self.paint(MAGENTA, "received the response"),
+                    event,
+                    self.scope(scope)
+                )
+            },
+
             ExpectedDirectedGotRouted(r::ExpectedDirectedGotRouted(name)) => {
                 write!(f, "expected directed to {:?}, got routed", name)
             },
 
             ValidFrom(r::ValidFrom(i)) => write!(f, "valid from {:?}", i),
 
-            TooEarly(r::TooEarly(d)) => write!(f, "\x1b[31mtoo early\x1b[0m ({:?} till okay)", d),
+            TooEarly(r::TooEarly(d)) => {
+                write!(f, "{} ({:?} till okay)", self.paint(RED, "too early"), d)
+            },
+
+            CountedMatch(r::CountedMatch(n)) => {
+                write!(f, "{}", self.paint(GREEN, format_args!("COUNTED #{}", n)))
+            },
+            CountExceeded(r::CountExceeded(n)) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(YELLOW, format_args!("COUNT EXCEEDED (exactly {})", n))
+                )
+            },
+            Cancelled(r::Cancelled) => write!(f, "{}", self.paint(BOLD_YELLOW, "CANCELLED")),
+
+            SystemMessageSkipped(r::SystemMessageSkipped {
+                message_name,
+                ignored_as: Some(fqn),
+            }) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(
+                        DIM,
+                        format_args!("skipped {} (ignored as {})", message_name, fqn)
+                    )
+                )
+            },
+            SystemMessageSkipped(r::SystemMessageSkipped {
+                message_name,
+                ignored_as: None,
+            }) => {
+                write!(
+                    f,
+                    "{}",
+                    self.paint(
+                        DIM,
+                        format_args!("skipped unmatched system message {}", message_name)
+                    )
+                )
+            },
+            EnvelopeIgnored(r::EnvelopeIgnored(ks)) => {
+                write!(f, "{} {}", self.paint(DIM, "ignored"), self.scope(*ks))
+            },
+            AutoResponded(r::AutoResponded(kd, ks)) => {
+                let dummy_name = &self.executable.dummies[*kd].known_as[*ks];
+                write!(
+                    f,
+                    "{} {}",
+                    self.paint(GREEN, format_args!("AUTO-RESPONDED by {}", dummy_name)),
+                    self.scope(*ks)
+                )
+            },
+            DummyStopped(r::DummyStopped(kd, ks)) => {
+                let dummy_name = &self.executable.dummies[*kd].known_as[*ks];
+                write!(
+                    f,
+                    "{} {}",
+                    self.paint(BOLD_YELLOW, format_args!("STOPPED {}", dummy_name)),
+                    self.scope(*ks)
+                )
+            },
 
             Root => write!(f, "ROOT"),
             Error(r::Error { reason }) => write!(f, "{}", reason),
@@ -467,6 +882,25 @@ impl fmt::Display for DisplayRecordKind<'_> {
     }
 }
 
+/// The names of the events, as in the lists of the ready ones.
+struct DisplayEvents<'a, I> {
+    keys: I,
+    kind: &'a DisplayRecordKind<'a>,
+}
+
+impl<I> fmt::Display for DisplayEvents<'_, I>
+where
+    I: Iterator<Item = EventKey> + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for k in self.keys.clone() {
+            let (scope, event) = self.kind.executable.event_name(k).unwrap();
+            write!(f, " {}({}) ", event, self.kind.scope(scope))?;
+        }
+        Ok(())
+    }
+}
+
 pub(super) fn fmt_scope_recursively(
     f: &mut fmt::Formatter<'_>,
     this_scope_key: KeyScope,
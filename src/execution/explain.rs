@@ -0,0 +1,166 @@
+//! Describing how an event is wired into the graph: what it waits for, and
+//! what waits for it.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::execution::cast::scope_path;
+use crate::execution::display::DisplayScope;
+use crate::execution::{Deadline, EventKey, Executable, KeyScope, SourceCode};
+use crate::names::EventName;
+use crate::scenario::RequiredToBe;
+
+/// The outcome of [Executable::explain].
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub event_key:      EventKey,
+    pub scope:          KeyScope,
+    pub name:           EventName,
+    pub required:       Option<RequiredToBe>,
+    pub deadline:       Option<Deadline>,
+    /// Whether the event is ready as soon as the run starts.
+    pub is_entry_point: bool,
+    /// The events this one waits for.
+    pub prerequisites:  BTreeSet<EventKey>,
+    /// The events waiting for this one.
+    pub unblocks:       BTreeSet<EventKey>,
+}
+
+impl Executable {
+    /// Returns the events named `name` in any scope: an event of a subroutine
+    /// is found once per call of that subroutine.
+    ///
+    /// The name may be qualified as `call/inner`, same as in `happens_after`:
+    /// then only the events of the subroutines invoked by `call` are found.
+    pub fn events_named(&self, name: &str) -> Vec<EventKey> {
+        let mut found = self
+            .events
+            .names
+            .iter()
+            .filter(|(_, (scope_key, event_name))| {
+                let event_name: &str = event_name.as_ref();
+                if event_name == name {
+                    return true
+                }
+                let Some(expected) = name
+                    .strip_suffix(event_name)
+                    .and_then(|calls| calls.strip_suffix('/'))
+                else {
+                    return false
+                };
+                let actual = scope_path(&self.scopes, *scope_key);
+                actual == expected || actual.ends_with(&format!("/{expected}"))
+            })
+            .map(|(ek, _)| *ek)
+            .collect::<Vec<_>>();
+        found.sort();
+        found
+    }
+
+    /// Describes the event's dependencies, as they are resolved by
+    /// [Executable::build].
+    pub fn explain(&self, event_key: EventKey) -> Option<Explanation> {
+        let (scope, name) = self.event_name(event_key)?;
+        let prerequisites = self
+            .events
+            .key_unblocks_values
+            .iter()
+            .filter(|(_, dependants)| dependants.contains(&event_key))
+            .map(|(ek, _)| *ek)
+            .collect();
+        let unblocks = self
+            .events
+            .key_unblocks_values
+            .get(&event_key)
+            .cloned()
+            .unwrap_or_default();
+
+        Some(Explanation {
+            event_key,
+            scope,
+            name,
+            required: self.events.required.get(&event_key).copied(),
+            deadline: self.events.deadlines.get(&event_key).copied(),
+            is_entry_point: self.events.entry_points.contains(&event_key),
+            prerequisites,
+            unblocks,
+        })
+    }
+}
+
+impl Explanation {
+    pub fn message<'a>(
+        &'a self,
+        executable: &'a Executable,
+        source_code: &'a SourceCode,
+    ) -> impl fmt::Display + 'a {
+        DisplayExplanation {
+            explanation: self,
+            executable,
+            source_code,
+        }
+    }
+}
+
+struct DisplayExplanation<'a> {
+    explanation: &'a Explanation,
+    executable:  &'a Executable,
+    source_code: &'a SourceCode,
+}
+
+impl fmt::Display for DisplayExplanation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            explanation,
+            executable,
+            source_code,
+        } = self;
+        let scope = |scope| {
+            DisplayScope {
+                scope,
+                executable,
+                source_code,
+            }
+        };
+
+        writeln!(
+            f,
+            "EVENT: {} @ {}",
+            explanation.name,
+            scope(explanation.scope)
+        )?;
+        writeln!(f, " kind: {}", explanation.event_key.kind())?;
+        match explanation.required {
+            Some(RequiredToBe::Reached) => writeln!(f, " required to be reached")?,
+            Some(RequiredToBe::Unreached) => writeln!(f, " required to be unreached")?,
+            None => writeln!(f, " not required")?,
+        }
+        if let Some(Deadline { within, since }) = explanation.deadline {
+            match since.and_then(|ek| executable.event_name(ek)) {
+                Some((_, since)) => writeln!(f, " deadline: {within:?} since {since}")?,
+                None => writeln!(f, " deadline: {within:?} since the start")?,
+            }
+        }
+        if explanation.is_entry_point {
+            writeln!(f, " ready at the start")?;
+        }
+
+        for (title, events) in [
+            ("happens after", &explanation.prerequisites),
+            ("unblocks", &explanation.unblocks),
+        ] {
+            if events.is_empty() {
+                continue
+            }
+            writeln!(f, " {title}:")?;
+            for &ek in events {
+                match executable.event_name(ek) {
+                    Some((s, event_name)) => writeln!(f, "  - {event_name} @ {}", scope(s))?,
+                    None => writeln!(f, "  - {ek:?}")?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
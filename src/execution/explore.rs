@@ -0,0 +1,167 @@
+//! Running a scenario under the different orders of its concurrently-ready
+//! events, one order after another, depth first.
+
+use std::fmt;
+
+use elfo::Blueprint;
+
+use crate::execution::{Choice, Executable, Report, RunError, SourceCode};
+
+/// The outcome of [Executable::explore].
+#[derive(Debug)]
+pub struct Exploration {
+    /// The number of runs performed.
+    pub runs:      usize,
+    /// Whether all the orders have been tried within the budget.
+    pub exhausted: bool,
+    /// The first failing run, if any.
+    pub failure:   Option<FailedInterleaving>,
+}
+
+/// A run that has not met the requirements, or has failed with an error.
+#[derive(Debug)]
+pub struct FailedInterleaving {
+    /// The script reproducing the run with
+    /// [Runner::with_interleaving](crate::execution::Runner::with_interleaving).
+    pub script:  Vec<usize>,
+    pub outcome: Result<Report, RunError>,
+}
+
+impl Executable {
+    /// Runs the scenario under every order of the concurrently-ready sends and
+    /// responds, stopping at the first failing run or after `budget` runs.
+    pub async fn explore<B, C>(
+        &self,
+        budget: usize,
+        blueprint: B,
+        config: C,
+        root_scope_values: impl IntoIterator<Item = (String, serde_json::Value)>,
+    ) -> Exploration
+    where
+        B: Fn() -> Blueprint,
+        C: for<'de> serde::de::Deserializer<'de> + Clone,
+    {
+        let root_scope_values = root_scope_values.into_iter().collect::<Vec<_>>();
+
+        let mut runs = 0;
+        let mut next = Some(vec![]);
+        while let Some(script) = next.take() {
+            if runs == budget {
+                return Exploration {
+                    runs,
+                    exhausted: false,
+                    failure: None,
+                };
+            }
+            runs += 1;
+
+            let outcome = self
+                .start(blueprint(), config.clone(), root_scope_values.clone())
+                .await
+                .with_interleaving(script.clone())
+                .run()
+                .await;
+            match outcome {
+                Ok(report) if report.is_ok() => {
+                    next = report.interleaving.as_deref().and_then(next_script);
+                },
+                outcome => {
+                    return Exploration {
+                        runs,
+                        exhausted: false,
+                        failure: Some(FailedInterleaving { script, outcome }),
+                    }
+                },
+            }
+        }
+
+        Exploration {
+            runs,
+            exhausted: true,
+            failure: None,
+        }
+    }
+}
+
+impl Exploration {
+    pub fn is_ok(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    pub fn message<'a>(
+        &'a self,
+        executable: &'a Executable,
+        source_code: &'a SourceCode,
+    ) -> impl fmt::Display + 'a {
+        DisplayExploration {
+            exploration: self,
+            executable,
+            source_code,
+        }
+    }
+}
+
+/// Returns the script of the next run, depth first: the last choice that has
+/// an alternative is advanced, and the ones after it are left to default.
+fn next_script(taken: &[Choice]) -> Option<Vec<usize>> {
+    let idx = taken.iter().rposition(|c| c.taken + 1 < c.of)?;
+    let mut script = taken[..idx].iter().map(|c| c.taken).collect::<Vec<_>>();
+    script.push(taken[idx].taken + 1);
+    Some(script)
+}
+
+struct DisplayExploration<'a> {
+    exploration: &'a Exploration,
+    executable:  &'a Executable,
+    source_code: &'a SourceCode,
+}
+
+impl fmt::Display for DisplayExploration<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            exploration,
+            executable,
+            source_code,
+        } = self;
+
+        let completeness = if exploration.exhausted {
+            "all the orders tried"
+        } else {
+            "the budget is spent"
+        };
+        writeln!(
+            f,
+            "EXPLORATION: {} run(s), {}",
+            exploration.runs, completeness
+        )?;
+
+        let Some(failure) = exploration.failure.as_ref() else {
+            return Ok(());
+        };
+        writeln!(f, " failed with the script {:?}", failure.script)?;
+        match &failure.outcome {
+            Ok(report) => write!(f, "{}", report.message(executable, source_code)),
+            Err(reason) => writeln!(f, " {}", reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choices(taken_of: &[(usize, usize)]) -> Vec<Choice> {
+        taken_of
+            .iter()
+            .map(|&(taken, of)| Choice { taken, of })
+            .collect()
+    }
+
+    #[test]
+    fn next_script_advances_the_last_open_choice() {
+        assert_eq!(next_script(&choices(&[(0, 2), (0, 3)])), Some(vec![0, 1]));
+        assert_eq!(next_script(&choices(&[(0, 2), (2, 3)])), Some(vec![1]));
+        assert_eq!(next_script(&choices(&[(1, 2), (2, 3)])), None);
+        assert_eq!(next_script(&[]), None);
+    }
+}
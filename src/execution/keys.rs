@@ -6,6 +6,7 @@ new_key_type! {
     pub struct KeyRecv;
     pub struct KeyRespond;
     pub struct KeyDelay;
+    pub struct KeyStopDummy;
 }
 
 new_key_type! {
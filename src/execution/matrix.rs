@@ -0,0 +1,229 @@
+//! Running the same scenario against several configs of the blueprint.
+
+use std::fmt;
+
+use elfo::Blueprint;
+use serde_json::Value;
+
+use crate::execution::outcome_table::OutcomeTable;
+use crate::execution::{Executable, Report, RunError, RunOptions, SourceCode};
+
+/// The runs of a scenario against several configs, made by
+/// [Executable::start_matrix].
+pub struct Matrix<'a, B> {
+    executable:        &'a Executable,
+    blueprint:         B,
+    base_config:       Option<Value>,
+    configs:           Vec<(String, Value)>,
+    root_scope_values: Vec<(String, Value)>,
+    options:           RunOptions,
+}
+
+/// The per-config outcomes of a [Matrix].
+#[derive(Debug, Default)]
+pub struct MatrixReport {
+    pub configs: Vec<ConfigOutcome>,
+}
+
+/// The outcome of the run against a single config.
+#[derive(Debug)]
+pub struct ConfigOutcome {
+    pub label:   String,
+    /// The config the blueprint has been started with: the patch applied to
+    /// the base config, if there is one.
+    pub config:  Value,
+    pub outcome: Result<Report, RunError>,
+}
+
+impl Executable {
+    /// Prepares the runs of the scenario against each of the labeled
+    /// `configs`, each time with a fresh runner and a fresh `blueprint`.
+    pub fn start_matrix<B>(
+        &self,
+        blueprint: B,
+        configs: impl IntoIterator<Item = (impl Into<String>, Value)>,
+        root_scope_values: impl IntoIterator<Item = (String, Value)>,
+    ) -> Matrix<'_, B>
+    where
+        B: Fn() -> Blueprint,
+    {
+        Matrix {
+            executable: self,
+            blueprint,
+            base_config: None,
+            configs: configs
+                .into_iter()
+                .map(|(label, config)| (label.into(), config))
+                .collect(),
+            root_scope_values: root_scope_values.into_iter().collect(),
+            options: Default::default(),
+        }
+    }
+}
+
+impl<B> Matrix<'_, B>
+where
+    B: Fn() -> Blueprint,
+{
+    /// Treats the configs as the [JSON merge patches][rfc] to apply to
+    /// `base_config`, rather than as the complete configs.
+    ///
+    /// [rfc]: https://www.rfc-editor.org/rfc/rfc7386
+    pub fn with_base_config(self, base_config: Value) -> Self {
+        Self {
+            base_config: Some(base_config),
+            ..self
+        }
+    }
+
+    pub fn with_options(self, options: RunOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    /// Runs the scenario against each config, one after another.
+    pub async fn run(self) -> MatrixReport {
+        let Self {
+            executable,
+            blueprint,
+            base_config,
+            configs,
+            root_scope_values,
+            options,
+        } = self;
+
+        let mut report = MatrixReport::default();
+        for (label, patch) in configs {
+            let config = match base_config.as_ref() {
+                None => patch,
+                Some(base_config) => {
+                    let mut config = base_config.clone();
+                    merge_patch(&mut config, patch);
+                    config
+                },
+            };
+            let outcome = executable
+                .start_with_options(
+                    blueprint(),
+                    config.clone(),
+                    root_scope_values.clone(),
+                    options,
+                )
+                .await
+                .run()
+                .await;
+            report.configs.push(ConfigOutcome {
+                label,
+                config,
+                outcome,
+            });
+        }
+
+        report
+    }
+}
+
+impl MatrixReport {
+    pub fn is_ok(&self) -> bool {
+        self.configs.iter().all(ConfigOutcome::is_ok)
+    }
+
+    pub fn passed(&self) -> usize {
+        self.configs.iter().filter(|c| c.is_ok()).count()
+    }
+
+    /// The outcome of the run against the config labeled `label`.
+    pub fn get(&self, label: &str) -> Option<&ConfigOutcome> {
+        self.configs.iter().find(|c| c.label == label)
+    }
+
+    pub fn message<'a>(
+        &'a self,
+        executable: &'a Executable,
+        source_code: &'a SourceCode,
+    ) -> impl fmt::Display + 'a {
+        DisplayMatrixReport {
+            report: self,
+            executable,
+            source_code,
+        }
+    }
+}
+
+impl ConfigOutcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(&self.outcome, Ok(report) if report.is_ok())
+    }
+}
+
+/// Applies a JSON merge patch: the objects are merged key by key, a `null`
+/// removes the key, anything else replaces the target.
+fn merge_patch(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let target = target
+        .as_object_mut()
+        .expect("has just been made an object");
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(&key);
+        } else {
+            merge_patch(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+struct DisplayMatrixReport<'a> {
+    report:      &'a MatrixReport,
+    executable:  &'a Executable,
+    source_code: &'a SourceCode,
+}
+
+impl fmt::Display for DisplayMatrixReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            report,
+            executable,
+            source_code,
+        } = self;
+
+        let mut table = OutcomeTable::new(f, "MATRIX", report.passed(), report.configs.len())?;
+        for config in report.configs.iter() {
+            if config.is_ok() {
+                table.passed(&config.label)?;
+                continue
+            }
+            table.failed(format_args!("{}: {}", config.label, config.config))?;
+            table.outcome(&config.outcome, executable, source_code)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merge_patch_merges_objects_and_removes_nulls() {
+        let mut config = json!({"a": 1, "b": {"c": 2, "d": 3}, "e": [1]});
+        merge_patch(
+            &mut config,
+            json!({"b": {"c": null, "f": 4}, "e": [2], "g": 5}),
+        );
+        assert_eq!(
+            config,
+            json!({"a": 1, "b": {"d": 3, "f": 4}, "e": [2], "g": 5})
+        );
+
+        merge_patch(&mut config, json!(7));
+        assert_eq!(config, json!(7));
+    }
+}
@@ -0,0 +1,197 @@
+//! Shrinking a failing scenario down to the events that matter for the
+//! failure.
+
+use std::collections::BTreeSet;
+use std::mem;
+
+use elfo::Blueprint;
+use tracing::debug;
+
+use crate::execution::{
+    BuildError, Executable, KeyScenario, Report, RunError, RunErrorReason, SourceCode,
+};
+use crate::marshalling::MarshallingRegistry;
+use crate::names::EventName;
+use crate::scenario::Scenario;
+
+/// The outcome of [minimize].
+#[derive(Debug, Clone)]
+pub struct Minimized {
+    /// The smallest entry point scenario found that still fails the same way.
+    pub scenario:  Scenario,
+    /// The events removed from the entry point scenario, in the order of
+    /// removal.
+    pub removed:   Vec<EventName>,
+    /// The number of runs performed, the one of the original scenario
+    /// included.
+    pub runs:      usize,
+    /// Whether every removal has been tried within the budget.
+    pub exhausted: bool,
+}
+
+/// How a run has failed: two runs reproduce the same failure if these are
+/// equal.
+#[derive(Debug, PartialEq, Eq)]
+enum Failure {
+    Unmet(BTreeSet<EventName>),
+    Error(mem::Discriminant<RunErrorReason>),
+}
+
+/// Shrinks the failing entry point scenario by removing its events one at a
+/// time, keeping a removal if the scenario still builds and fails the same
+/// way: with the same requirements unmet, or with the same kind of
+/// [RunErrorReason].
+///
+/// Only the events without a `require` are removed, and only those of the
+/// entry point: the subroutines are left intact. The events that happened
+/// after a removed one are made to happen after its prerequisites instead.
+///
+/// `marshalling` and `blueprint` are invoked once per run. No more than
+/// `budget` runs are performed.
+///
+/// Returns `Ok(None)` if the scenario does not fail in the first place.
+pub async fn minimize<M, B, C>(
+    marshalling: M,
+    source_code: &SourceCode,
+    entry_point_key: KeyScenario,
+    budget: usize,
+    blueprint: B,
+    config: C,
+    root_scope_values: impl IntoIterator<Item = (String, serde_json::Value)>,
+) -> Result<Option<Minimized>, BuildError<'_>>
+where
+    M: Fn() -> MarshallingRegistry,
+    B: Fn() -> Blueprint,
+    C: for<'de> serde::de::Deserializer<'de> + Clone,
+{
+    let root_scope_values = root_scope_values.into_iter().collect::<Vec<_>>();
+
+    let run = |executable: Executable| {
+        let blueprint = blueprint();
+        let config = config.clone();
+        let root_scope_values = root_scope_values.clone();
+        async move {
+            let outcome = executable
+                .start(blueprint, config, root_scope_values)
+                .await
+                .run()
+                .await;
+            failure(&executable, outcome)
+        }
+    };
+
+    let executable = Executable::build(marshalling(), source_code, entry_point_key)?;
+    let Some(original) = run(executable).await else {
+        return Ok(None)
+    };
+    let mut source_code = source_code.clone();
+    let mut runs = 1;
+    let mut removed = vec![];
+    let mut exhausted = true;
+
+    'passes: loop {
+        let mut shrunk = false;
+        let scenario = source_code.sources[entry_point_key].scenario.clone();
+        let candidates = scenario
+            .all_events()
+            .filter(|e| e.require.is_none())
+            .map(|e| e.id.clone())
+            .collect::<Vec<_>>();
+
+        for event_name in candidates.into_iter().rev() {
+            if runs == budget {
+                exhausted = false;
+                break 'passes
+            }
+
+            let current = &source_code.sources[entry_point_key].scenario;
+            let Some(candidate) = without_event(current, &event_name) else {
+                continue
+            };
+            let kept = mem::replace(
+                &mut source_code.sources[entry_point_key].scenario,
+                candidate,
+            );
+
+            runs += 1;
+            // a candidate that does not build does not fail the same way.
+            let failed = match Executable::build(marshalling(), &source_code, entry_point_key) {
+                Ok(executable) => run(executable).await,
+                Err(_) => None,
+            };
+            match failed {
+                Some(failure) if failure == original => {
+                    debug!("removed {}", event_name);
+                    removed.push(event_name);
+                    shrunk = true;
+                },
+                _ => source_code.sources[entry_point_key].scenario = kept,
+            }
+        }
+
+        if !shrunk {
+            break
+        }
+    }
+
+    Ok(Some(Minimized {
+        scenario: source_code.sources[entry_point_key].scenario.clone(),
+        removed,
+        runs,
+        exhausted,
+    }))
+}
+
+fn failure(executable: &Executable, outcome: Result<Report, RunError>) -> Option<Failure> {
+    match outcome {
+        Err(error) => Some(Failure::Error(mem::discriminant(&error.reason))),
+        Ok(report) if report.is_ok() => None,
+        Ok(report) => {
+            let unmet = report
+                .unmet_requirements()
+                .filter_map(|ek| executable.event_name(ek))
+                .map(|(_, event_name)| event_name)
+                .collect();
+            Some(Failure::Unmet(unmet))
+        },
+    }
+}
+
+/// Returns `scenario` without the event `event_name`, the references to it
+/// (and to the events of the subroutine it calls) replaced with its own
+/// prerequisites.
+fn without_event(scenario: &Scenario, event_name: &EventName) -> Option<Scenario> {
+    let mut scenario = scenario.clone();
+    let removed = if let Some(idx) = scenario.events.iter().position(|e| e.id == *event_name) {
+        scenario.events.remove(idx)
+    } else {
+        let idx = scenario.script.iter().position(|e| e.id == *event_name)?;
+        scenario.script.remove(idx)
+    };
+
+    let call_prefix = format!("{}/", event_name.as_ref());
+    let refers_to_removed =
+        |name: &EventName| name == event_name || name.as_ref().starts_with(&call_prefix);
+
+    for event in scenario.events.iter_mut().chain(scenario.script.iter_mut()) {
+        if !event.prerequisites.iter().any(refers_to_removed) {
+            continue
+        }
+        let mut prerequisites = vec![];
+        for name in event.prerequisites.drain(..) {
+            let substitutes = if refers_to_removed(&name) {
+                removed.prerequisites.clone()
+            } else {
+                vec![name]
+            };
+            for name in substitutes {
+                if !prerequisites.contains(&name) {
+                    prerequisites.push(name);
+                }
+            }
+        }
+        event.prerequisites = prerequisites;
+    }
+
+    Some(scenario)
+}
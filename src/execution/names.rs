@@ -1,3 +1,4 @@
+use crate::execution::cast::scope_path;
 use crate::execution::{EventKey, Executable, KeyScope};
 use crate::names::EventName;
 
@@ -5,4 +6,14 @@ impl Executable {
     pub fn event_name(&self, key: EventKey) -> Option<(KeyScope, EventName)> {
         self.events.names.get(&key).cloned()
     }
+
+    /// The name of the event, qualified by the call events its scope has been
+    /// invoked by: `call/inner`, as it is referred to in `happens_after`.
+    pub fn event_path(&self, key: EventKey) -> Option<String> {
+        let (scope_key, name) = self.events.names.get(&key)?;
+        match scope_path(&self.scopes, *scope_key).as_str() {
+            "." => Some(name.as_ref().to_owned()),
+            path => Some(format!("{}/{}", path, name.as_ref())),
+        }
+    }
 }
@@ -0,0 +1,241 @@
+//! Exporting the record log as the OpenTelemetry spans.
+
+use std::time::{Instant, SystemTime};
+
+use opentelemetry::trace::{TraceContextExt, TraceError, Tracer, TracerProvider as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::Resource;
+
+use crate::execution::cast::scope_path;
+use crate::execution::display::DisplayRecordKind;
+use crate::execution::{EventKey, Executable, KeyScope, Report, SourceCode};
+use crate::recorder::{records as r, KeyRecord, RecordKind, RecordLog};
+
+impl Report {
+    /// Exports the spans of [Report::export_spans] via OTLP over HTTP to the
+    /// collector's `endpoint`, such as `http://localhost:4318/v1/traces` of a
+    /// Jaeger instance, on behalf of the service `luci`.
+    ///
+    /// Blocks until all the spans are exported: call it from a thread of its
+    /// own rather than from within the async runtime.
+    pub fn export_otlp(
+        &self,
+        endpoint: &str,
+        executable: &Executable,
+        source_code: &SourceCode,
+    ) -> Result<(), TraceError> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()?;
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter)
+            .with_resource(Resource::new([KeyValue::new("service.name", "luci")]))
+            .build();
+        self.export_spans(&provider.tracer("luci"), executable, source_code);
+        provider.shutdown()
+    }
+
+    /// Emits a span per record of the run, nested the way the records are,
+    /// all under a single span of the run itself, the parent of which is the
+    /// current context.
+    ///
+    /// The spans go wherever the provider of `tracer` exports them: sharing
+    /// the provider with the actors under test, the run can be inspected in
+    /// Jaeger alongside their traces.
+    ///
+    /// A record's span lasts until the latest of its descendants has been
+    /// written. The simulated time of a record is attached as `luci.rt`, in
+    /// seconds since the start of the run.
+    pub fn export_spans<T>(&self, tracer: &T, executable: &Executable, source_code: &SourceCode)
+    where
+        T: Tracer,
+        T::Span: Send + Sync + 'static,
+    {
+        let log = &self.record_log;
+        let clock = Clock::new(log);
+        let root_scope = &executable.scopes[executable.root_scope_key];
+        let scenario = source_code.sources[root_scope.source_key]
+            .source_file
+            .display()
+            .to_string();
+
+        let run_end = log
+            .roots
+            .iter()
+            .map(|root_key| subtree_end(log, *root_key))
+            .max()
+            .unwrap_or(log.t_zero.0);
+        let run_span = tracer
+            .span_builder("luci run")
+            .with_start_time(clock.system_time(log.t_zero.0))
+            .with_attributes(vec![KeyValue::new("luci.scenario", scenario)])
+            .start_with_context(tracer, &Context::current());
+        let run_cx = Context::current().with_span(run_span);
+
+        for root_key in log.roots.iter().copied() {
+            export(
+                tracer,
+                &run_cx,
+                &clock,
+                log,
+                root_key,
+                executable,
+                source_code,
+            );
+        }
+
+        run_cx.span().end_with_timestamp(clock.system_time(run_end));
+    }
+}
+
+/// Maps the wall-clock instants of the records onto the system time.
+struct Clock {
+    t_zero:      Instant,
+    system_zero: SystemTime,
+}
+
+impl Clock {
+    fn new(log: &RecordLog) -> Self {
+        let t_zero = log.t_zero.0;
+        Self {
+            t_zero,
+            system_zero: SystemTime::now() - t_zero.elapsed(),
+        }
+    }
+
+    fn system_time(&self, at: Instant) -> SystemTime {
+        self.system_zero + at.duration_since(self.t_zero)
+    }
+}
+
+fn export<T>(
+    tracer: &T,
+    parent_cx: &Context,
+    clock: &Clock,
+    log: &RecordLog,
+    this_key: KeyRecord,
+    executable: &Executable,
+    source_code: &SourceCode,
+) where
+    T: Tracer,
+    T::Span: Send + Sync + 'static,
+{
+    let record = &log.records[this_key];
+    let name = DisplayRecordKind {
+        kind: &record.kind,
+        executable,
+        source_code,
+        colours: false,
+    }
+    .to_string()
+    .trim()
+    .to_owned();
+    let mut attributes = vec![
+        KeyValue::new("luci.record", kind_name(&record.kind)),
+        KeyValue::new(
+            "luci.rt",
+            record.at.1.duration_since(log.t_zero.1).as_secs_f64(),
+        ),
+    ];
+    attributes.extend(attributes_of(&record.kind, executable));
+
+    let span = tracer
+        .span_builder(name)
+        .with_start_time(clock.system_time(record.at.0))
+        .with_attributes(attributes)
+        .start_with_context(tracer, parent_cx);
+    let cx = parent_cx.with_span(span);
+
+    for child_key in record.children.iter().copied() {
+        export(tracer, &cx, clock, log, child_key, executable, source_code);
+    }
+
+    cx.span()
+        .end_with_timestamp(clock.system_time(subtree_end(log, this_key)));
+}
+
+/// The attributes naming the event, the scope, the actor or the dummy the
+/// record is about.
+fn attributes_of(kind: &RecordKind, executable: &Executable) -> Vec<KeyValue> {
+    use RecordKind::*;
+
+    use crate::execution::runner::ReadyEventKey;
+
+    let event = |event_key: EventKey| {
+        let Some((scope, event_name)) = executable.event_name(event_key) else {
+            return vec![]
+        };
+        vec![
+            KeyValue::new("luci.event", event_name.as_ref().to_owned()),
+            KeyValue::new("luci.scope", scope_path(&executable.scopes, scope)),
+        ]
+    };
+    let scope = |scope: KeyScope| {
+        vec![KeyValue::new(
+            "luci.scope",
+            scope_path(&executable.scopes, scope),
+        )]
+    };
+
+    match kind {
+        ProcessEventClass(r::ProcessEventClass(ReadyEventKey::Send(k))) => event((*k).into()),
+        ProcessEventClass(r::ProcessEventClass(ReadyEventKey::Respond(k))) => event((*k).into()),
+        ProcessEventClass(r::ProcessEventClass(ReadyEventKey::StopDummy(k))) => event((*k).into()),
+        TimedOutRecvKey(r::TimedOutRecvKey(k)) | MatchingRecv(r::MatchingRecv(k)) => {
+            event((*k).into())
+        },
+        ProcessBindKey(r::ProcessBindKey(k)) => event((*k).into()),
+        ResponseReceived(r::ResponseReceived(k)) => event((*k).into()),
+        EventFired(r::EventFired(k)) => event(*k),
+
+        BindSrcScope(r::BindSrcScope(ks))
+        | BindDstScope(r::BindDstScope(ks))
+        | EnvelopeIgnored(r::EnvelopeIgnored(ks)) => scope(*ks),
+
+        MatchActorAddress(r::MatchActorAddress(ka, ks, ..))
+        | StoreActorAddress(r::StoreActorAddress(ka, ks, _))
+        | ResolveActorName(r::ResolveActorName(ka, ks, _)) => {
+            let mut attributes = scope(*ks);
+            attributes.push(KeyValue::new(
+                "luci.actor",
+                executable.actors[*ka].known_as[*ks].as_ref().to_owned(),
+            ));
+            attributes
+        },
+        MatchDummyAddress(r::MatchDummyAddress(kd, ks, ..))
+        | AutoResponded(r::AutoResponded(kd, ks))
+        | DummyStopped(r::DummyStopped(kd, ks)) => {
+            let mut attributes = scope(*ks);
+            attributes.push(KeyValue::new(
+                "luci.dummy",
+                executable.dummies[*kd].known_as[*ks].as_ref().to_owned(),
+            ));
+            attributes
+        },
+
+        _ => vec![],
+    }
+}
+
+/// The name of the record's variant, such as `EventFired`.
+fn kind_name(kind: &RecordKind) -> String {
+    let debug = format!("{:?}", kind);
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// The latest instant at which a record of the subtree has been written.
+fn subtree_end(log: &RecordLog, this_key: KeyRecord) -> Instant {
+    let record = &log.records[this_key];
+    record
+        .children
+        .iter()
+        .map(|child_key| subtree_end(log, *child_key))
+        .fold(record.at.0, Instant::max)
+}
@@ -0,0 +1,72 @@
+//! The layout shared by the reports of several runs of the same scenario:
+//! [CasesReport](crate::execution::CasesReport),
+//! [MatrixReport](crate::execution::MatrixReport) and
+//! [SoakReport](crate::execution::SoakReport).
+
+use std::fmt;
+
+use crate::execution::{Executable, Report, RunError, SourceCode};
+
+/// Writes the header with the share of the runs that have passed, followed by
+/// a row per run (or per requirement).
+pub(crate) struct OutcomeTable<'a, 'f> {
+    f: &'a mut fmt::Formatter<'f>,
+}
+
+impl<'a, 'f> OutcomeTable<'a, 'f> {
+    pub(crate) fn new(
+        f: &'a mut fmt::Formatter<'f>,
+        title: &str,
+        passed: usize,
+        total: usize,
+    ) -> Result<Self, fmt::Error> {
+        let pass_rate = if total == 0 {
+            1.0
+        } else {
+            passed as f64 / total as f64
+        };
+        writeln!(
+            f,
+            "{}: {}/{} passed ({:.1}%)",
+            title,
+            passed,
+            total,
+            pass_rate * 100.0
+        )?;
+        Ok(Self { f })
+    }
+
+    pub(crate) fn passed(&mut self, label: impl fmt::Display) -> fmt::Result {
+        writeln!(self.f, " + {}", label)
+    }
+
+    /// To be followed by the [outcome](Self::outcome) of the run, if there is
+    /// one.
+    pub(crate) fn failed(&mut self, label: impl fmt::Display) -> fmt::Result {
+        writeln!(self.f, " - {}", label)
+    }
+
+    /// An error that none of the rows is to blame for.
+    pub(crate) fn error(&mut self, reason: impl fmt::Display) -> fmt::Result {
+        writeln!(self.f, " ! {}", reason)
+    }
+
+    /// The outcome of a failed run, indented under its row.
+    pub(crate) fn outcome(
+        &mut self,
+        outcome: &Result<Report, RunError>,
+        executable: &Executable,
+        source_code: &SourceCode,
+    ) -> fmt::Result {
+        match outcome {
+            Ok(report) => {
+                let message = report.message(executable, source_code).to_string();
+                for line in message.lines() {
+                    writeln!(self.f, "   {}", line)?;
+                }
+                Ok(())
+            },
+            Err(reason) => writeln!(self.f, "   ! {}", reason),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Walking the graph without running anything: the order the events would
+//! become ready in, were each of them reached as soon as it is ready.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::Value;
+use slotmap::SecondaryMap;
+
+use crate::bindings::{self, Scope};
+use crate::execution::display::DisplayScope;
+use crate::execution::{BindScope, EventKey, Executable, KeyScope, SourceCode};
+use crate::scenario::SrcMsg;
+
+/// The outcome of [Executable::plan].
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    /// The events in the order they would become ready: the events of a step
+    /// are unblocked by those of the previous steps.
+    pub steps:       Vec<Vec<PlannedEvent>>,
+    /// The events that would never become ready.
+    pub never_ready: Vec<EventKey>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedEvent {
+    pub key:   EventKey,
+    /// The value a bind, a send or a respond would render: the variables the
+    /// plan knows nothing about are rendered as `"<$NAME>"`. `None` if the
+    /// template cannot be rendered even so.
+    pub value: Option<Value>,
+}
+
+impl Executable {
+    /// Walks the graph as if every event was reached as soon as it is ready,
+    /// without starting any actors.
+    ///
+    /// The values the recvs would bind, as well as those passed to the run,
+    /// are not known: the binds are resolved with the placeholders instead.
+    pub fn plan(&self) -> Plan {
+        let events = &self.events;
+
+        let mut scopes: SecondaryMap<KeyScope, Scope> = self
+            .scopes
+            .iter()
+            .map(|(key, info)| {
+                let values = info.values.clone().into_iter().collect();
+                (key, Scope::from_values(values).with_placeholders())
+            })
+            .collect();
+        let mut requires: HashMap<EventKey, usize> = HashMap::new();
+        for dependants in events.key_unblocks_values.values() {
+            for dependant in dependants {
+                *requires.entry(*dependant).or_default() += 1;
+            }
+        }
+
+        let by_priority = |keys: &mut Vec<EventKey>| {
+            keys.sort_by_key(|k| (events.priority.get(k).map(|(_, idx)| *idx), *k));
+        };
+
+        let mut plan = Plan::default();
+        let mut ready = events.entry_points.iter().copied().collect::<Vec<_>>();
+        by_priority(&mut ready);
+
+        while !ready.is_empty() {
+            let mut step = vec![];
+            let mut unblocked = vec![];
+            for key in ready {
+                let value = self.plan_event(key, &mut scopes);
+                step.push(PlannedEvent { key, value });
+
+                for dependant in events.key_unblocks_values.get(&key).into_iter().flatten() {
+                    let left = requires
+                        .get_mut(dependant)
+                        .expect("every dependant requires something");
+                    *left -= 1;
+                    if *left == 0 {
+                        unblocked.push(*dependant);
+                    }
+                }
+            }
+            plan.steps.push(step);
+            by_priority(&mut unblocked);
+            ready = unblocked;
+        }
+
+        let mut never_ready = requires
+            .into_iter()
+            .filter(|(_, left)| *left > 0)
+            .map(|(k, _)| k)
+            .filter(|k| events.names.contains_key(k))
+            .collect();
+        by_priority(&mut never_ready);
+        plan.never_ready = never_ready;
+
+        plan
+    }
+
+    /// Renders the value of the event and binds the variables it would bind.
+    fn plan_event(
+        &self,
+        event_key: EventKey,
+        scopes: &mut SecondaryMap<KeyScope, Scope>,
+    ) -> Option<Value> {
+        let events = &self.events;
+        match event_key {
+            EventKey::Bind(k) => {
+                let bind = &events.bind[k];
+                let (src, dst, share_all) = match bind.scope {
+                    BindScope::Same(scope_key) => (scope_key, scope_key, false),
+                    BindScope::Two {
+                        src,
+                        dst,
+                        share_all,
+                    } => (src, dst, share_all),
+                };
+                let value = render_src(&bind.src, &scopes[src])?;
+                let shared = if share_all {
+                    scopes[src]
+                        .values()
+                        .map(|(k, v)| (k.to_owned(), v.clone()))
+                        .collect::<Vec<_>>()
+                } else {
+                    vec![]
+                };
+
+                // the variables a bind fails to bind stay unknown
+                let mut txn = scopes[dst].txn();
+                if bindings::bind_to_pattern(value.clone(), &bind.dst, &mut txn)
+                    && shared.iter().all(|(k, v)| txn.bind_value(k, v))
+                {
+                    txn.apply();
+                }
+                Some(value)
+            },
+            EventKey::Send(k) => {
+                let send = &events.send[k];
+                render_src(&send.payload, &scopes[send.scope_key])
+            },
+            EventKey::Respond(k) => {
+                let respond = &events.respond[k];
+                respond
+                    .payload
+                    .as_ref()
+                    .and_then(|payload| render_src(payload, &scopes[respond.scope_key]))
+            },
+            EventKey::Recv(_) | EventKey::Delay(_) | EventKey::StopDummy(_) => None,
+        }
+    }
+}
+
+impl Plan {
+    /// The number of events that would become ready.
+    pub fn len(&self) -> usize {
+        self.steps.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn message<'a>(
+        &'a self,
+        executable: &'a Executable,
+        source_code: &'a SourceCode,
+    ) -> impl fmt::Display + 'a {
+        DisplayPlan {
+            plan: self,
+            executable,
+            source_code,
+        }
+    }
+}
+
+fn render_src(src: &SrcMsg, scope: &Scope) -> Option<Value> {
+    match src {
+        SrcMsg::Literal(value) => Some(value.clone()),
+        SrcMsg::Bind(template) => bindings::render(template.clone(), scope).ok(),
+        SrcMsg::Inject(key) => Some(Value::String(format!("<{}>", key))),
+    }
+}
+
+struct DisplayPlan<'a> {
+    plan:        &'a Plan,
+    executable:  &'a Executable,
+    source_code: &'a SourceCode,
+}
+
+impl fmt::Display for DisplayPlan<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            plan,
+            executable,
+            source_code,
+        } = self;
+        let scope = |scope| {
+            DisplayScope {
+                scope,
+                executable,
+                source_code,
+            }
+        };
+
+        writeln!(
+            f,
+            "PLAN: {} events in {} steps",
+            plan.len(),
+            plan.steps.len()
+        )?;
+        for (idx, step) in plan.steps.iter().enumerate() {
+            writeln!(f, " {}.", idx + 1)?;
+            for PlannedEvent { key, value } in step {
+                let Some((s, event_name)) = executable.event_name(*key) else {
+                    continue
+                };
+                write!(f, "  - {} {} @ {}", key.kind(), event_name, scope(s))?;
+                match value {
+                    Some(value) => writeln!(f, ": {}", value)?,
+                    None => writeln!(f)?,
+                }
+            }
+        }
+        if !plan.never_ready.is_empty() {
+            writeln!(f, " never ready:")?;
+            for key in plan.never_ready.iter().copied() {
+                if let Some((s, event_name)) = executable.event_name(key) {
+                    writeln!(f, "  - {} {} @ {}", key.kind(), event_name, scope(s))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
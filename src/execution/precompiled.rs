@@ -0,0 +1,131 @@
+//! Storing a built [Executable] along with the [SourceCode] it has been built
+//! from, so that loading it back takes neither parsing the scenarios nor
+//! building the graph.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::execution::build::with_builtins;
+use crate::execution::{AutoReply, Executable, RecvSource, SourceCode};
+use crate::marshalling::MarshallingRegistry;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PrecompiledError {
+    #[error("json: {}", _0)]
+    Json(#[source] serde_json::Error),
+
+    #[error("message is not registered: {}", _0)]
+    UnknownMessage(Arc<str>),
+}
+
+#[derive(Serialize)]
+struct PrecompiledRef<'a> {
+    executable: &'a Executable,
+    sources:    &'a SourceCode,
+}
+
+#[derive(Deserialize)]
+struct Precompiled {
+    executable: Executable,
+    sources:    SourceCode,
+}
+
+impl Executable {
+    /// Serializes the executable into JSON, along with the sources it has been
+    /// built from.
+    ///
+    /// The marshalling registry is not serialized: the messages are
+    /// re-attached by their FQNs in [Executable::from_json].
+    pub fn to_json(&self, sources: &SourceCode) -> Result<String, PrecompiledError> {
+        serde_json::to_string(&PrecompiledRef {
+            executable: self,
+            sources,
+        })
+        .map_err(PrecompiledError::Json)
+    }
+
+    /// Loads the executable stored by [Executable::to_json].
+    ///
+    /// The `marshalling` should have all the messages used by the executable
+    /// registered, as it would for [Executable::build]: the messages of the
+    /// built-in events are registered the same way, too.
+    pub fn from_json(
+        marshalling: MarshallingRegistry,
+        json: &str,
+    ) -> Result<(Self, SourceCode), PrecompiledError> {
+        let Precompiled {
+            mut executable,
+            sources,
+        } = serde_json::from_str(json).map_err(PrecompiledError::Json)?;
+
+        let marshalling = with_builtins(marshalling);
+        if let Some(fqn) = executable.unregistered_message(&marshalling) {
+            return Err(PrecompiledError::UnknownMessage(fqn.clone()))
+        }
+        executable.marshalling = marshalling;
+
+        Ok((executable, sources))
+    }
+
+    /// Returns a message the events refer to, but `marshalling` lacks, if any.
+    pub(super) fn unregistered_message(
+        &self,
+        marshalling: &MarshallingRegistry,
+    ) -> Option<&Arc<str>> {
+        self.fqns().find(|fqn| marshalling.resolve(fqn).is_none())
+    }
+
+    /// The FQNs of all the messages the events refer to.
+    fn fqns(&self) -> impl Iterator<Item = &Arc<str>> + '_ {
+        let events = &self.events;
+        let auto_replies = self.auto_responders.iter().flat_map(|a| {
+            let reply = match &a.reply {
+                AutoReply::Send { fqn, .. } => Some(fqn),
+                AutoReply::Respond { .. } => None,
+            };
+            std::iter::once(&a.fqn).chain(reply)
+        });
+
+        events
+            .send
+            .values()
+            .map(|s| &s.fqn)
+            .chain(
+                events
+                    .recv
+                    .values()
+                    .filter(|r| matches!(r.source, RecvSource::Envelope))
+                    .map(|r| &r.fqn),
+            )
+            .chain(events.respond.values().map(|r| &r.request_type))
+            .chain(self.ignored.iter().map(|i| &i.fqn))
+            .chain(auto_replies)
+    }
+}
+
+/// Serializes a map as a sequence of pairs: JSON only allows strings as the
+/// keys of an object.
+pub(super) mod as_pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<'a, M, K, V, S>(map: &'a M, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        K: Serialize + 'a,
+        V: Serialize + 'a,
+        S: Serializer,
+    {
+        serializer.collect_seq(map)
+    }
+
+    pub(crate) fn deserialize<'de, M, K, V, D>(deserializer: D) -> Result<M, D::Error>
+    where
+        M: FromIterator<(K, V)>,
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Vec::<(K, V)>::deserialize(deserializer).map(|pairs| pairs.into_iter().collect())
+    }
+}
@@ -3,21 +3,26 @@ use std::time::Duration;
 
 use tokio::time::Instant;
 
-use crate::execution::{EventDelay, EventRecv, KeyDelay, KeyRecv};
+use crate::execution::{EventDelay, EventRecv, KeyDelay, KeyRecv, KeySend};
 
 const RECV_RESOLUTION_DIVISOR: u32 = 1000;
 
-#[derive(Default)]
 pub(crate) struct ReceivesAndDelays {
     schedule:   BTreeSet<ScheduleEntry>,
     resolution: BTreeSet<ResolutionEntry>,
     valid_from: HashMap<KeyRecv, Instant>,
+    /// The instant the clock is to be advanced to, by the ready
+    /// `advance_time` events.
+    advance_to: Option<Instant>,
+    time_scale: f64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) enum KeyDelayOrRecv {
     Delay(KeyDelay),
     Recv(KeyRecv),
+    /// A send held back by the injected latency.
+    Send(KeySend),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -40,6 +45,18 @@ struct ResolutionEntry {
 }
 
 impl ReceivesAndDelays {
+    /// All the durations of the delays and the recvs inserted are multiplied
+    /// by `time_scale`.
+    pub(crate) fn new(time_scale: f64) -> Self {
+        Self {
+            schedule: Default::default(),
+            resolution: Default::default(),
+            valid_from: Default::default(),
+            advance_to: None,
+            time_scale,
+        }
+    }
+
     pub(crate) fn next_sleep_until(&self, now: Instant) -> Option<Instant> {
         let ResolutionEntry { resolution, .. } = self.resolution.first().copied()?;
         let one_step_after_now = now.checked_add(resolution).unwrap_or(now);
@@ -74,6 +91,19 @@ impl ReceivesAndDelays {
         out
     }
 
+    /// Returns the duration the clock is to be advanced by right now, if any.
+    pub(crate) fn take_advance(&mut self, now: Instant) -> Option<Duration> {
+        let advance_to = self.advance_to.take()?;
+        Some(advance_to.saturating_duration_since(now)).filter(|d| !d.is_zero())
+    }
+
+    pub(crate) fn valid_from(&self, key: KeyRecv) -> Instant {
+        *self
+            .valid_from
+            .get(&key)
+            .expect("recv-key should have existed")
+    }
+
     pub(crate) fn remove_recv_by_key(&mut self, key: KeyRecv) -> Instant {
         let valid_from = self
             .valid_from
@@ -93,11 +123,21 @@ impl ReceivesAndDelays {
     }
 
     pub(crate) fn insert_delay(&mut self, now: Instant, key: KeyDelay, event: &EventDelay) {
-        let delay_for = event.delay_for;
-        let resolution = event.delay_step;
+        let delay_for = event.delay_for.mul_f64(self.time_scale);
+        let resolution = event.delay_step.mul_f64(self.time_scale);
         let at = now.checked_add(delay_for).expect("please pretty please");
         let key = KeyDelayOrRecv::Delay(key);
 
+        if event.advance {
+            self.advance_to = self.advance_to.max(Some(at));
+            let new_s_entry = self.schedule.insert(ScheduleEntry {
+                at,
+                event: ScheduledEvent::Ripe(key),
+            });
+            assert!(new_s_entry);
+            return
+        }
+
         let r_entry = ResolutionEntry { resolution, key };
         let new_r_entry = self.resolution.insert(r_entry);
         let new_s_entry_1 = self.schedule.insert(ScheduleEntry {
@@ -112,9 +152,30 @@ impl ReceivesAndDelays {
         assert!(new_r_entry && new_s_entry_1 && new_s_entry_2);
     }
 
+    /// Schedules the send held back until `at`: unlike the delays, it is not
+    /// scaled, as the latency is scaled by the caller already.
+    pub(crate) fn insert_send(&mut self, now: Instant, key: KeySend, at: Instant) {
+        let key = KeyDelayOrRecv::Send(key);
+        let r_entry = ResolutionEntry {
+            resolution: at.saturating_duration_since(now),
+            key,
+        };
+        let new_r_entry = self.resolution.insert(r_entry);
+        let new_s_entry_1 = self.schedule.insert(ScheduleEntry {
+            at,
+            event: ScheduledEvent::UnsetResolution(r_entry),
+        });
+        let new_s_entry_2 = self.schedule.insert(ScheduleEntry {
+            at,
+            event: ScheduledEvent::Ripe(key),
+        });
+
+        assert!(new_r_entry && new_s_entry_1 && new_s_entry_2);
+    }
+
     pub(crate) fn insert_recv(&mut self, now: Instant, key: KeyRecv, event: &EventRecv) {
         let valid_from = now
-            .checked_add(event.after_duration)
+            .checked_add(event.after_duration.mul_f64(self.time_scale))
             .expect("exceeded the range of the Instant");
         self.valid_from.insert(key, valid_from);
 
@@ -134,6 +195,7 @@ impl ReceivesAndDelays {
         }
 
         if let Some(timeout) = event.before_duration {
+            let timeout = timeout.mul_f64(self.time_scale);
             let valid_thru = now.checked_add(timeout).expect("oh don't be ridiculous!");
 
             let resolution =
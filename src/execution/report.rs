@@ -1,7 +1,13 @@
-use std::collections::{HashMap, HashSet};
-use std::{fmt, io};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{self, IsTerminal};
+use std::time::Duration;
 
-use crate::execution::{display, EventKey, Executable, SourceCode};
+use elfo::Addr;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::execution::display::{self, DisplayReport, Style};
+use crate::execution::{Choice, EventKey, Executable, SourceCode};
 use crate::recorder::{KeyRecord, RecordKind, RecordLog};
 use crate::scenario::RequiredToBe;
 
@@ -10,52 +16,295 @@ pub struct Report {
     pub reached_events:  HashSet<EventKey>,
     pub required_events: HashMap<EventKey, RequiredToBe>,
     pub record_log:      RecordLog,
+    pub cancelled:       bool,
+    pub stats:           RunStats,
+    pub fired_at:        HashMap<EventKey, FiredAt>,
+    /// The seed of the randomized scheduling, if it was used.
+    pub seed:            Option<u64>,
+    /// The choices made by the scripted scheduling, if it was used.
+    pub interleaving:    Option<Vec<Choice>>,
+    /// The envelopes consumed by neither a recv, nor an ignore rule, nor an
+    /// auto-responder, in the order of their arrival.
+    pub unmatched:       Vec<UnmatchedEnvelope>,
+    /// The deadlines of the required events, if set.
+    pub deadlines:       HashMap<EventKey, Deadline>,
+    /// The lines logged by the actors under test, in the order they were
+    /// logged in.
+    pub logs:            Vec<LogLine>,
+}
+
+/// The time an event is required to be reached within.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deadline {
+    pub within: Duration,
+    /// The event the time is counted from, rather than from the start of the
+    /// run.
+    pub since:  Option<EventKey>,
+}
+
+/// An envelope received by a proxy, but never matched.
+#[derive(Debug, Clone)]
+pub struct UnmatchedEnvelope {
+    pub message_name: &'static str,
+    pub from:         Addr,
+    /// The dummy's address, unless the envelope was routed.
+    pub to:           Option<Addr>,
+    pub payload:      serde_json::Value,
+}
+
+/// A line logged during a run, at `DEBUG` or above.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    pub level:   tracing::Level,
+    pub target:  String,
+    pub message: String,
+    /// The structured fields of the line, but the message.
+    pub fields:  serde_json::Map<String, serde_json::Value>,
+}
+
+/// Why an event has not been reached: the prerequisites it has been waiting
+/// for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unreached {
+    pub event_key: EventKey,
+    /// The prerequisites that have been reached.
+    pub reached:   Vec<EventKey>,
+    /// The prerequisites that have not been reached, each with its own reasons.
+    pub unreached: Vec<Unreached>,
+    /// Whether the reasons are given elsewhere in the tree.
+    pub elided:    bool,
+}
+
+/// When an event has fired, counting from the start of the run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FiredAt {
+    /// By the wall clock.
+    pub wall:    Duration,
+    /// By the runtime's clock, which does not advance while it is paused.
+    pub runtime: Duration,
+}
+
+/// Quantitative facts about a run.
+#[derive(Debug, Clone, Default)]
+pub struct RunStats {
+    /// The number of dummies the run has started with, the members of the
+    /// pools included. None are added later, while `stop_dummy` drops some:
+    /// this is also the most of them alive at the same time.
+    pub initial_dummies: usize,
+
+    /// Wall-clock time it took to start the blueprint and the dummies.
+    pub start_time: Duration,
+    /// Wall-clock time spent in [`Runner::run`](crate::execution::Runner::run).
+    pub run_time:   Duration,
+
+    /// The pace of the counted recvs and of the `send_many` events, in the
+    /// order of their definition.
+    pub throughput: Vec<Throughput>,
+}
+
+/// The pace of the messages matched by a counted recv, or sent by a
+/// `send_many`, by the runtime's clock, which is the simulated one, if the
+/// time is paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Throughput {
+    pub event_key:     EventKey,
+    pub messages:      usize,
+    /// From the first of the messages to the last one.
+    pub duration:      Duration,
+    /// The shortest of the intervals between the consecutive messages.
+    pub min_interval:  Duration,
+    pub mean_interval: Duration,
+    pub max_interval:  Duration,
+}
+
+impl Throughput {
+    /// The statistics of the messages that came at the given instants, in
+    /// the order they came in.
+    pub(crate) fn of(event_key: EventKey, instants: &[Instant]) -> Self {
+        let intervals = instants
+            .windows(2)
+            .map(|pair| pair[1].duration_since(pair[0]))
+            .collect::<Vec<_>>();
+        let duration = intervals.iter().sum::<Duration>();
+        Self {
+            event_key,
+            messages: instants.len(),
+            duration,
+            min_interval: intervals.iter().copied().min().unwrap_or_default(),
+            mean_interval: duration
+                .checked_div(intervals.len() as u32)
+                .unwrap_or_default(),
+            max_interval: intervals.iter().copied().max().unwrap_or_default(),
+        }
+    }
+}
+
+impl LogLine {
+    /// The line as matched by the `log` events: the level is in lowercase.
+    pub(crate) fn to_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "level": self.level.as_str().to_lowercase(),
+            "target": self.target,
+            "message": self.message,
+            "fields": self.fields,
+        })
+    }
 }
 
 impl Report {
     pub fn is_ok(&self) -> bool {
-        let reached_necessary = self
-            .required_events
-            .iter()
-            .filter(|(_, r)| matches!(r, RequiredToBe::Reached))
-            .all(|(e, _)| self.reached_events.contains(e));
-        let avoided_restricted = self
-            .required_events
+        !self.cancelled && self.unmet_requirements().next().is_none()
+    }
+
+    /// Returns the events that were required to be reached but were not, and
+    /// the other way round.
+    pub fn unmet_requirements(&self) -> impl Iterator<Item = EventKey> + '_ {
+        self.required_events
             .iter()
-            .filter(|(_, r)| matches!(r, RequiredToBe::Unreached))
-            .all(|(e, _)| !self.reached_events.contains(e));
+            .filter(|(e, r)| {
+                match r {
+                    RequiredToBe::Reached => {
+                        !self.reached_events.contains(e) || self.missed_deadline(**e).is_some()
+                    },
+                    RequiredToBe::Unreached => self.reached_events.contains(e),
+                }
+            })
+            .map(|(e, _)| *e)
+    }
+
+    /// Returns the time it took to reach the event, if it was reached past its
+    /// deadline.
+    ///
+    /// The deadline counted from an event that has not been reached is not
+    /// missed.
+    pub fn missed_deadline(&self, event_key: EventKey) -> Option<Duration> {
+        let Deadline { within, since } = self.deadlines.get(&event_key)?;
+        let reached_at = self.fired_at.get(&event_key)?.runtime;
+        let since_at = match since {
+            Some(anchor) => self.fired_at.get(anchor)?.runtime,
+            None => Duration::ZERO,
+        };
+        let took = reached_at.saturating_sub(since_at);
+        (took > *within).then_some(took)
+    }
+
+    /// Returns the tree of the prerequisites that have kept the event from
+    /// being reached, or `None` if it has been reached.
+    pub fn why_unreached(&self, executable: &Executable, event_key: EventKey) -> Option<Unreached> {
+        if self.reached_events.contains(&event_key) {
+            return None
+        }
+        let key_requires_value = executable.key_requires_value();
+        Some(self.unreached(&key_requires_value, &mut HashSet::new(), event_key))
+    }
+
+    /// The events `visited` before are elided.
+    pub(super) fn unreached(
+        &self,
+        key_requires_value: &HashMap<EventKey, BTreeSet<EventKey>>,
+        visited: &mut HashSet<EventKey>,
+        event_key: EventKey,
+    ) -> Unreached {
+        let mut unreached = Unreached {
+            event_key,
+            reached: vec![],
+            unreached: vec![],
+            elided: false,
+        };
+        if !visited.insert(event_key) {
+            unreached.elided = true;
+            return unreached
+        }
+
+        for prerequisite in key_requires_value
+            .get(&event_key)
+            .into_iter()
+            .flatten()
+            .copied()
+        {
+            if self.reached_events.contains(&prerequisite) {
+                unreached.reached.push(prerequisite);
+            } else {
+                let reasons = self.unreached(key_requires_value, visited, prerequisite);
+                unreached.unreached.push(reasons);
+            }
+        }
+
+        unreached
+    }
 
-        reached_necessary && avoided_restricted
+    /// Returns the time at which the event has fired, if it has.
+    pub fn fired_at(&self, event_key: EventKey) -> Option<FiredAt> {
+        self.fired_at.get(&event_key).copied()
     }
 
+    /// The report as a text, coloured as [Style::Auto], unless
+    /// [DisplayReport::with_style] says otherwise.
     pub fn message<'a>(
         &'a self,
         executable: &'a Executable,
         source_code: &'a SourceCode,
-    ) -> impl fmt::Display + 'a {
-        display::DisplayReport {
+    ) -> DisplayReport<'a> {
+        DisplayReport {
             report: self,
             executable,
             source_code,
+            timings: false,
+            style: Style::Auto,
         }
     }
 
+    /// Same as [Report::message], followed by the table of the times at which
+    /// the events have fired.
+    pub fn message_with_timings<'a>(
+        &'a self,
+        executable: &'a Executable,
+        source_code: &'a SourceCode,
+    ) -> DisplayReport<'a> {
+        DisplayReport {
+            timings: true,
+            ..self.message(executable, source_code)
+        }
+    }
+
+    /// Writes the records of the run to `io`, coloured if `io` is a terminal.
     pub fn dump_record_log(
+        &self,
+        io: impl std::io::Write + IsTerminal,
+        source_code: &SourceCode,
+        executable: &Executable,
+    ) -> Result<(), io::Error> {
+        let style = Style::Auto.for_sink(&io);
+        self.dump_record_log_with_style(io, source_code, executable, style)
+    }
+
+    /// Same as [Report::dump_record_log], coloured as `style` says:
+    /// [Style::Auto] is resolved for the standard output, unless resolved for
+    /// `io` with [Style::for_sink].
+    pub fn dump_record_log_with_style(
         &self,
         mut io: impl std::io::Write,
         source_code: &SourceCode,
         executable: &Executable,
+        style: Style,
     ) -> Result<(), io::Error> {
         use std::io::Write;
 
+        /// What each of the records is displayed with.
+        #[derive(Clone, Copy)]
+        struct Env<'e> {
+            executable:  &'e Executable,
+            source_code: &'e SourceCode,
+            style:       Style,
+        }
+
         fn dump<'a>(
             io: &mut impl Write,
             depth: usize,
             last_kind: &mut Option<&'a RecordKind>,
             log: &'a RecordLog,
             this_key: KeyRecord,
-            executable: &Executable,
-            source_code: &SourceCode,
+            env: Env<'_>,
         ) -> Result<(), io::Error> {
             let record = &log.records[this_key];
 
@@ -72,40 +321,71 @@ impl Report {
                 display::DisplayRecord {
                     record,
                     log,
-                    executable,
-                    source_code,
+                    executable: env.executable,
+                    source_code: env.source_code,
+                    style: env.style,
                 }
             )?;
 
             for child_key in record.children.iter().copied() {
-                dump(
-                    io,
-                    depth + 1,
-                    last_kind,
-                    log,
-                    child_key,
-                    executable,
-                    source_code,
-                )?;
+                dump(io, depth + 1, last_kind, log, child_key, env)?;
             }
 
             Ok(())
         }
 
+        // resolved once rather than per record
+        let style = if style.colours() {
+            Style::Always
+        } else {
+            Style::Never
+        };
+        let env = Env {
+            executable,
+            source_code,
+            style,
+        };
         let mut last_kind = None;
         for root_key in self.record_log.roots.iter().copied() {
             writeln!(io, "ROOT: {:?}", root_key)?;
-            dump(
-                &mut io,
-                0,
-                &mut last_kind,
-                &self.record_log,
-                root_key,
-                executable,
-                source_code,
-            )?;
+            dump(&mut io, 0, &mut last_kind, &self.record_log, root_key, env)?;
         }
 
         Ok(())
     }
 }
+
+impl Unreached {
+    /// The unreached events the tree grows from: the ones all the
+    /// prerequisites of which have been reached.
+    pub fn earliest_blockers(&self) -> BTreeSet<EventKey> {
+        fn collect(unreached: &Unreached, out: &mut BTreeSet<EventKey>) {
+            if unreached.elided {
+                return
+            }
+            if unreached.unreached.is_empty() {
+                out.insert(unreached.event_key);
+            }
+            for prerequisite in unreached.unreached.iter() {
+                collect(prerequisite, out);
+            }
+        }
+
+        let mut out = BTreeSet::new();
+        collect(self, &mut out);
+        out
+    }
+}
+
+impl Executable {
+    /// The inverse of `key_unblocks_values`: the prerequisites of each event.
+    pub(super) fn key_requires_value(&self) -> HashMap<EventKey, BTreeSet<EventKey>> {
+        let mut key_requires_value = HashMap::<_, BTreeSet<_>>::new();
+        for (&k, dependants) in self.events.key_unblocks_values.iter() {
+            for d in dependants.iter().copied() {
+                key_requires_value.entry(d).or_default().insert(k);
+            }
+        }
+        key_requires_value
+    }
+}
@@ -1,25 +1,67 @@
+use std::any::Any;
 use std::collections::{BTreeSet, HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
 
 use elfo::_priv::MessageKind;
+use elfo::addr::GroupNo;
+use elfo::messages::SubscribeToActorStatuses;
 use elfo::test::Proxy;
-use elfo::{Addr, Blueprint, Envelope, Message};
+use elfo::{Addr, AnyMessage, Blueprint, Envelope, Message};
+use futures::future::LocalBoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
 use slotmap::{new_key_type, SecondaryMap, SlotMap};
 use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, trace, warn};
 
 use crate::bindings::Scope;
 use crate::execution::receives_and_delays::{KeyDelayOrRecv, ReceivesAndDelays};
 use crate::execution::{
-    BindScope, EventBind, EventKey, EventRecv, EventRespond, EventSend, Executable, KeyActor,
-    KeyDummy, KeyRecv, KeyRespond, KeyScope, KeySend, Report,
+    AutoReply, AutoResponder, BindScope, Deadline, EventBind, EventKey, EventRecv, EventRespond,
+    EventSend, EventStopDummy, Events, Executable, FiredAt, Ignored, KeyActor, KeyDummy, KeyRecv,
+    KeyRespond, KeyScope, KeySend, KeyStopDummy, RecvSource, Report, RunStats, Throughput,
+    UnmatchedEnvelope, TOPOLOGY_GROUPS,
 };
-use crate::names::{ActorName, EventName};
-use crate::recorder::{records, RecordLog, Recorder};
-use crate::scenario::SrcMsg;
+use crate::marshalling::{extract_message_payload, AnError};
+use crate::names::{ActorName, DummyName, EventName};
+use crate::recorder::{records, KeyRecord, RecordLog, Recorder};
+use crate::scenario::{BindingType, DefEnvelopeKind, DefRecvCount, DefSystemTopic, SrcMsg};
 use crate::{bindings, marshalling};
 
+mod audit;
+mod logs;
+#[cfg(feature = "metrics")]
+mod telemetry;
+
+/// The error a run has stopped with, along with where in the scenario it has
+/// happened.
+#[derive(Debug)]
+pub struct RunError {
+    pub reason:     RunErrorReason,
+    /// The event being processed when the error has occurred, if any: e.g.,
+    /// an unexpected envelope is not related to any event.
+    pub event:      Option<EventContext>,
+    /// The record of the step the error has occurred at.
+    pub record:     Option<KeyRecord>,
+    pub record_log: Box<RecordLog>,
+}
+
+/// The event a [RunError] has occurred at.
+#[derive(Debug, Clone)]
+pub struct EventContext {
+    pub key:       EventKey,
+    pub name:      EventName,
+    /// The call events the event's scope has been invoked by, the innermost
+    /// first; empty for an event of the root scope.
+    pub called_by: Vec<EventName>,
+}
+
 #[derive(Debug, thiserror::Error)]
-pub enum RunError {
+pub enum RunErrorReason {
     #[error("event is not ready: {:?}", _0)]
     EventIsNotReady(ReadyEventKey),
 
@@ -29,17 +71,97 @@ pub enum RunError {
     #[error("name already taken by an actor: {}", _0)]
     ActorName(ActorName),
 
+    #[error("the dummy has been stopped: {}", _0)]
+    DummyStopped(DummyName),
+
     #[error("name has not yet been bound to an address: {:?}", _0)]
     UnboundName(KeyActor),
 
+    #[error("none of the names has yet been bound to an address: {:?}", _0)]
+    NoneBound(Vec<KeyActor>),
+
     #[error("no request envelope found")]
     NoRequest,
 
+    #[error("the request has failed: {}", _0)]
+    RequestFailed(elfo::errors::RequestError),
+
     #[error("bind: {}", _0)]
     BindError(bindings::BindError),
 
     #[error("marshalling error: {}", _0)]
     Marshalling(marshalling::AnError),
+
+    #[error(
+        "invariant violated: {name} is bound to {bound} (by {bound_by:?}), attempted to bind \
+         {attempted} (by {attempted_by})"
+    )]
+    InvariantViolated {
+        name:         String,
+        bound:        serde_json::Value,
+        bound_by:     Option<EventName>,
+        attempted:    serde_json::Value,
+        attempted_by: EventName,
+    },
+
+    #[error(
+        "type mismatch: {name} is declared {declared}, attempted to bind {attempted} (by \
+         {attempted_by})"
+    )]
+    TypeMismatch {
+        name:         String,
+        declared:     BindingType,
+        attempted:    serde_json::Value,
+        attempted_by: EventName,
+    },
+
+    #[error(
+        "shared binding conflict: {name} is bound to {bound}, attempted to share {shared} (by \
+         {shared_by})"
+    )]
+    SharedBindingConflict {
+        name:      String,
+        bound:     serde_json::Value,
+        shared:    serde_json::Value,
+        shared_by: EventName,
+    },
+
+    #[error("unexpected envelope {message_name} [from: {from}; to: {to:?}]: {payload}")]
+    UnexpectedEnvelope {
+        message_name: &'static str,
+        from:         Addr,
+        to:           Option<Addr>,
+        payload:      serde_json::Value,
+    },
+
+    #[error("out-of-order message: {got} has arrived while {expected} was expected: {payload}")]
+    OutOfOrder {
+        expected: EventName,
+        got:      EventName,
+        payload:  serde_json::Value,
+    },
+
+    #[error("panicked: {reason}")]
+    Panicked { reason: String },
+
+    #[error(
+        "luci bug: the run audit has failed ({}); please report it along with the record log",
+        violations.join("; ")
+    )]
+    AuditFailed { violations: Vec<String> },
+
+    #[error("the run has failed already: {}", _0)]
+    AlreadyFailed(String),
+
+    #[error("the scenario's config: {}", _0)]
+    Config(String),
+
+    #[error("type mismatch: {name} is declared {declared}, supplied {supplied}")]
+    SuppliedTypeMismatch {
+        name:     String,
+        declared: BindingType,
+        supplied: serde_json::Value,
+    },
 }
 
 /// A key for an event that is ready to be processed by [Runner].
@@ -51,6 +173,7 @@ pub enum ReadyEventKey {
     RecvOrDelay,
     Send(KeySend),
     Respond(KeyRespond),
+    StopDummy(KeyStopDummy),
 }
 
 impl From<EventKey> for ReadyEventKey {
@@ -59,6 +182,7 @@ impl From<EventKey> for ReadyEventKey {
             EventKey::Bind(_) => Self::Bind,
             EventKey::Send(k) => Self::Send(k),
             EventKey::Respond(k) => Self::Respond(k),
+            EventKey::StopDummy(k) => Self::StopDummy(k),
             EventKey::Delay(_) | EventKey::Recv(_) => Self::RecvOrDelay,
         }
     }
@@ -71,6 +195,7 @@ impl TryFrom<ReadyEventKey> for EventKey {
             ReadyEventKey::Bind => Err(()),
             ReadyEventKey::Send(k) => Ok(Self::Send(k)),
             ReadyEventKey::Respond(k) => Ok(Self::Respond(k)),
+            ReadyEventKey::StopDummy(k) => Ok(Self::StopDummy(k)),
             ReadyEventKey::RecvOrDelay => Err(()),
         }
     }
@@ -85,27 +210,231 @@ pub struct Runner<'a> {
 
     main_proxy_key: ProxyKey,
     proxies:        SlotMap<ProxyKey, Proxy>,
+    /// The proxies of the dummies, save for the stopped ones.
     dummies:        SecondaryMap<KeyDummy, ProxyKey>,
+    /// The addresses of the dummies, the stopped ones included.
+    dummy_addrs:    SecondaryMap<KeyDummy, Addr>,
     actors:         SecondaryMap<KeyActor, Addr>,
-
-    envelopes: HashMap<KeyRecv, Envelope>,
+    /// The names of the groups of the topology, by their numbers.
+    groups:         HashMap<GroupNo, &'static str>,
+    /// Whose turn it is among the actors of the `to_any` sends.
+    turns:          HashMap<Vec<KeyActor>, usize>,
+
+    envelopes:        HashMap<KeyRecv, Envelope>,
+    /// The requests issued by the dummies, yet to be responded to.
+    requests:         FuturesUnordered<PendingRequest>,
+    /// The responses yet to be matched.
+    responses:        HashMap<KeyRecv, Response>,
+    /// The number of respond events yet to fire for each request.
+    responds_pending: HashMap<KeyRecv, usize>,
+    /// The sends held back by the injected latency, until they are due.
+    delayed_sends:    HashMap<KeySend, DelayedSend>,
 
     receives_and_delays: ReceivesAndDelays,
+
+    cancellation: CancellationToken,
+    stats:        RunStats,
+    options:      RunOptions,
+
+    audit: bool,
+    trail: audit::Trail,
+
+    /// Picks among the equally-ready events, if set.
+    scheduling:        Option<Scheduling>,
+    /// Decides which messages the injected faults hit.
+    faults_rng:        SplitMix64,
+    /// The same, for the sends whose faults have a seed of their own.
+    send_rngs:         HashMap<KeySend, SplitMix64>,
+    unmatched:         Vec<UnmatchedEnvelope>,
+    /// The number of matches so far of the recvs expecting several.
+    recv_counts:       HashMap<KeyRecv, usize>,
+    /// The instants of the matches of those recvs, and of the sends of the
+    /// `send_many` events, by the event standing for them.
+    arrivals:          HashMap<EventKey, Vec<Instant>>,
+    /// The values the earlier matches of those recvs append to the array
+    /// bindings.
+    recv_appended:     HashMap<KeyRecv, Vec<(String, Value)>>,
+    /// The last matches of the recvs expecting exactly N, to bind from once
+    /// their timeouts expire.
+    recv_last_matches: HashMap<KeyRecv, LastMatch>,
+
+    logs:         logs::LogCapture,
+    /// The indices of the captured lines matched by the `log` events.
+    logs_matched: HashSet<usize>,
+
+    #[cfg(feature = "metrics")]
+    metrics:          telemetry::MetricsCapture,
+    /// The values of the counters at the moment the checks became ready.
+    #[cfg(feature = "metrics")]
+    metric_baselines: HashMap<KeyRecv, f64>,
+
+    /// The event being processed, for the errors to point at.
+    current_event: Option<EventKey>,
+    /// The run so far, if it has been started by [Runner::run_until].
+    progress:      Option<Progress>,
+    /// The run has failed to start (e.g. the scenario's config has failed to
+    /// render): it fails right away.
+    start_error:   Option<RunErrorReason>,
+}
+
+type PendingRequest = LocalBoxFuture<'static, (KeyRecv, Result<Response, AnError>)>;
+
+/// A response to a request issued by a dummy.
+struct Response {
+    message_name: &'static str,
+    /// The addressee of the request, unless it has been routed.
+    from:         Option<Addr>,
+    /// The dummy that has issued the request.
+    to:           Addr,
+    payload:      Value,
+}
+
+/// What the last match of a recv expecting exactly N binds, once reached.
+#[derive(Default)]
+struct LastMatch {
+    values:        Vec<(String, Value)>,
+    actor_address: Option<(KeyActor, Addr)>,
+    routing_key:   Option<(Value, Addr)>,
+}
+
+/// A message ready to be handed to the proxy, once the latency has elapsed.
+struct DelayedSend {
+    message: AnyMessage,
+    addrs:   Vec<Option<Addr>>,
+    copies:  usize,
+}
+
+struct Progress {
+    t_start:        std::time::Instant,
+    t_start_rt:     Instant,
+    record_log:     RecordLog,
+    reached_events: HashSet<EventKey>,
+    fired_at:       HashMap<EventKey, FiredAt>,
+    cancelled:      bool,
+    /// Whether there is no more progress to make.
+    done:           bool,
+    /// The error the run has stopped with, if any.
+    failed:         Option<String>,
+}
+
+impl Progress {
+    fn start() -> Self {
+        Self {
+            t_start:        std::time::Instant::now(),
+            t_start_rt:     Instant::now(),
+            record_log:     RecordLog::create(),
+            reached_events: Default::default(),
+            fired_at:       Default::default(),
+            cancelled:      false,
+            done:           false,
+            failed:         None,
+        }
+    }
+
+    /// Stops the run for good: the record log is kept, should the run be
+    /// carried on.
+    fn fail(&mut self, reason: &RunErrorReason) -> Box<RecordLog> {
+        self.done = true;
+        self.failed = Some(reason.to_string());
+        Box::new(self.record_log.clone())
+    }
+}
+
+enum Scheduling {
+    Seeded {
+        seed: u64,
+        rng:  SplitMix64,
+    },
+    Scripted {
+        script: Vec<usize>,
+        taken:  Vec<Choice>,
+    },
+}
+
+/// A choice made by the runner among several equally-ready events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Choice {
+    /// The index of the event picked.
+    pub taken: usize,
+    /// The number of events there were to pick from.
+    pub of:    usize,
 }
 
 new_key_type! {
     struct ProxyKey;
 }
 
+/// The options of a run, see [Executable::start_with_options].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunOptions {
+    /// The factor every delay, timeout, injected latency and deadline of the
+    /// scenario is multiplied by.
+    pub time_scale: f64,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self { time_scale: 1.0 }
+    }
+}
+
+impl RunOptions {
+    /// Scales the durations of the scenario by `time_scale`: e.g., `0.01` to
+    /// run a scenario written for a live system against the paused clock, or
+    /// `10.0` to give a slow live system more room.
+    ///
+    /// # Panics
+    /// Panics if `time_scale` is not a finite positive number.
+    pub fn with_time_scale(self, time_scale: f64) -> Self {
+        assert!(
+            time_scale.is_finite() && time_scale > 0.0,
+            "time scale should be a finite positive number: {}",
+            time_scale
+        );
+        Self { time_scale }
+    }
+
+    pub(crate) fn scale(&self, duration: Duration) -> Duration {
+        duration.mul_f64(self.time_scale)
+    }
+}
+
+/// The protocol of the elfo's own messages (pings, configs, statuses, etc).
+const SYSTEM_PROTOCOL: &str = "elfo-core";
+
 impl Executable {
     /// Returns a [Runner] to run the test corresponding to this [Executable]
     /// and specified `blueprint` and `config`.
+    ///
+    /// If the entry point scenario has a `config` of its own, that one is
+    /// rendered with the values of the root scope, and merged over `config`:
+    /// the keys the scenario sets take precedence, the rest are kept.
+    ///
+    /// Should the scenario's `config` fail to render (e.g. refer to an unbound
+    /// value), the blueprint is started with `config` as is, and the run fails
+    /// with [RunErrorReason::Config].
     pub async fn start<C>(
         &self,
         blueprint: Blueprint,
         config: C,
         root_scope_values: impl IntoIterator<Item = (String, serde_json::Value)>,
     ) -> Runner<'_>
+    where
+        C: for<'de> serde::de::Deserializer<'de>,
+    {
+        self.start_with_options(blueprint, config, root_scope_values, RunOptions::default())
+            .await
+    }
+
+    /// Same as [Executable::start], but with the [RunOptions] other than the
+    /// default ones.
+    pub async fn start_with_options<C>(
+        &self,
+        blueprint: Blueprint,
+        config: C,
+        root_scope_values: impl IntoIterator<Item = (String, serde_json::Value)>,
+        options: RunOptions,
+    ) -> Runner<'_>
     where
         C: for<'de> serde::de::Deserializer<'de>,
     {
@@ -114,12 +443,68 @@ impl Executable {
             blueprint,
             config,
             root_scope_values.into_iter().collect(),
+            options,
         )
         .await
     }
 }
 
 impl Runner<'_> {
+    /// Makes the run interruptible with the `cancellation` token.
+    ///
+    /// Once the token is cancelled, [Runner::run] stops at the nearest await
+    /// point and returns a [Report] containing everything recorded so far.
+    pub fn with_cancellation(self, cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation,
+            ..self
+        }
+    }
+
+    /// Enables or disables the audit of the runner's own bookkeeping after the
+    /// run.
+    ///
+    /// The audit is disabled by default: it checks luci rather than the
+    /// scenario. Should it find a violation, [Runner::run] returns
+    /// [RunErrorReason::AuditFailed].
+    pub fn with_audit(self, audit: bool) -> Self {
+        Self { audit, ..self }
+    }
+
+    /// Makes the runner pick among the equally-ready events at random, rather
+    /// than in a fixed order: which of the ready sends or responds goes
+    /// first, and which of the ready recvs of the same priority gets to match
+    /// an envelope first.
+    ///
+    /// The choices are reproducible: the same `seed` yields the same choices.
+    /// The seed is included in the [Report].
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            scheduling: Some(Scheduling::Seeded {
+                seed,
+                rng: SplitMix64(seed),
+            }),
+            ..self
+        }
+    }
+
+    /// Makes the runner follow the `script` when picking among the ready sends
+    /// and responds: at the n-th point where there are several of them, the
+    /// `script[n]`-th one is picked (the first one, past the end of the
+    /// script).
+    ///
+    /// The choices made are included in the [Report], to be explored further.
+    /// See [Executable::explore](crate::execution::Executable::explore).
+    pub fn with_interleaving(self, script: Vec<usize>) -> Self {
+        Self {
+            scheduling: Some(Scheduling::Scripted {
+                script,
+                taken: vec![],
+            }),
+            ..self
+        }
+    }
+
     /// Runs the test for which the runner was set up.
     ///
     /// Returns;
@@ -127,18 +512,155 @@ impl Runner<'_> {
     ///   completed without errors, either successfully or not.
     /// - [RunError] in case of any errors during the test run.
     pub async fn run(mut self) -> Result<Report, RunError> {
-        let mut record_log = RecordLog::create();
-        let mut recorder = record_log.recorder();
+        let mut progress = self.progress.take().unwrap_or_else(Progress::start);
+        self.proceed(&mut progress, &HashSet::new()).await?;
 
+        let Progress {
+            t_start,
+            record_log,
+            reached_events,
+            fired_at,
+            cancelled,
+            ..
+        } = progress;
         let required_events = self.executable.events.required.clone();
-        let mut reached_events = HashSet::new();
-
-        while let Some(event_key) = {
-            // NOTE: if we do not introduce a variable `event_key_opt` here, the `self`
-            // would remain mutably borrowed.
-            let event_key_opt = self.ready_events().next();
-            event_key_opt
-        } {
+
+        // let reached = reached
+        //     .into_iter()
+        //     // XXX: are we expecting only the root-scope's names here?
+        //     // FIXME: no we are not, names can come from different places.
+        //     .map(|(k, v)| (self.event_name(k).expect("bad event-key").1.clone(), v))
+        //     .collect();
+        // let unreached = unreached
+        //     .into_iter()
+        //     // XXX: are we expecting only the root-scope's names here?
+        //     // FIXME: no we are not, names can come from different places.
+        //     .map(|(k, v)| (self.event_name(k).expect("bad event-key").1.clone(), v))
+        //     .collect();
+
+        let (seed, interleaving) = match self.scheduling {
+            None => (None, None),
+            Some(Scheduling::Seeded { seed, .. }) => (Some(seed), None),
+            Some(Scheduling::Scripted { taken, .. }) => (None, Some(taken)),
+        };
+
+        let mut throughput = self
+            .arrivals
+            .iter()
+            .map(|(event_key, instants)| Throughput::of(*event_key, instants))
+            .collect::<Vec<_>>();
+        throughput.sort_by_key(|t| {
+            (
+                self.executable
+                    .events
+                    .priority
+                    .get(&t.event_key)
+                    .map(|(_, p)| *p),
+                t.event_key,
+            )
+        });
+        let stats = RunStats {
+            run_time: t_start.elapsed(),
+            throughput,
+            ..self.stats
+        };
+
+        Ok(Report {
+            reached_events,
+            required_events,
+            record_log,
+            cancelled,
+            stats,
+            fired_at,
+            seed,
+            interleaving,
+            unmatched: self.unmatched,
+            logs: self.logs.take(),
+            deadlines: self
+                .executable
+                .events
+                .deadlines
+                .iter()
+                .map(|(&ek, deadline)| {
+                    let deadline = Deadline {
+                        within: self.options.scale(deadline.within),
+                        ..*deadline
+                    };
+                    (ek, deadline)
+                })
+                .collect(),
+        })
+    }
+
+    /// Runs the test until any of the events named `event_name` fires, so that
+    /// the test can check whatever it needs to in the middle of the scenario.
+    /// The run is then carried on by another call to [Runner::run_until], or
+    /// finished by [Runner::run].
+    ///
+    /// Returns whether the event has fired: `false` means the run is over
+    /// without it. Once it has returned an error, so do the further calls, as
+    /// well as [Runner::run].
+    ///
+    /// # Panics
+    /// Panics if there is no event named `event_name`.
+    pub async fn run_until(&mut self, event_name: &str) -> Result<bool, RunError> {
+        let stop_at = self
+            .executable
+            .events_named(event_name)
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert!(!stop_at.is_empty(), "no event named {}", event_name);
+
+        let mut progress = self.progress.take().unwrap_or_else(Progress::start);
+        let result = self.proceed(&mut progress, &stop_at).await;
+        self.progress = Some(progress);
+        result
+    }
+
+    /// Whether the event has fired so far, see [Runner::run_until].
+    pub fn has_fired(&self, event_key: EventKey) -> bool {
+        self.progress
+            .as_ref()
+            .is_some_and(|progress| progress.reached_events.contains(&event_key))
+    }
+
+    /// Fires the events until there is no more progress to make, or until any
+    /// of `stop_at` has fired.
+    ///
+    /// Returns whether any of `stop_at` has fired.
+    async fn proceed(
+        &mut self,
+        progress: &mut Progress,
+        stop_at: &HashSet<EventKey>,
+    ) -> Result<bool, RunError> {
+        if let Some(failure) = progress.failed.as_ref() {
+            return Err(RunError {
+                reason:     RunErrorReason::AlreadyFailed(failure.clone()),
+                event:      None,
+                record:     None,
+                record_log: Box::new(progress.record_log.clone()),
+            })
+        }
+        if let Some(reason) = self.start_error.take() {
+            let record_log = progress.fail(&reason);
+            return Err(RunError {
+                reason,
+                event: None,
+                record: None,
+                record_log,
+            })
+        }
+        if stop_at.iter().any(|k| progress.reached_events.contains(k)) {
+            return Ok(true)
+        }
+        if progress.done {
+            return Ok(false)
+        }
+
+        let mut recorder = progress.record_log.recorder();
+        let cancellation = self.cancellation.clone();
+
+        while let Some(event_key) = self.next_ready_event() {
             debug!("firing: {:?}", event_key);
             if std::env::var("LUCI_STEP_BY_STEP").is_ok_and(|one| one == "1") {
                 println!("=== ENTER TO CONTINUE ===");
@@ -146,7 +668,40 @@ impl Runner<'_> {
                 let _ = std::io::stdin().read_line(&mut line);
             }
 
-            let fired_events = self.fire_event(&mut recorder, event_key).await?;
+            let fired_events = tokio::select! {
+                biased;
+
+                () = cancellation.cancelled() => {
+                    info!("cancelled. Stopping here.");
+                    recorder.write(records::Cancelled);
+                    progress.cancelled = true;
+                    break;
+                },
+                fired_events = AssertUnwindSafe(
+                    self.fire_event(&mut recorder, event_key)
+                ).catch_unwind() => {
+                    fired_events.unwrap_or_else(|payload| {
+                        Err(RunErrorReason::Panicked {
+                            reason: panic_message(payload.as_ref()),
+                        })
+                    })
+                },
+            };
+            let fired_events = match fired_events {
+                Ok(fired_events) => fired_events,
+                Err(reason) => {
+                    let record = recorder.last_key();
+                    recorder.write(records::Error {
+                        reason: reason.to_string(),
+                    });
+                    return Err(RunError {
+                        event: self.current_event.map(|k| self.event_context(k)),
+                        record,
+                        record_log: progress.fail(&reason),
+                        reason,
+                    });
+                },
+            };
 
             for ek in fired_events.iter() {
                 // FIXME: show scope info too
@@ -157,34 +712,46 @@ impl Runner<'_> {
                 }
             }
 
-            if fired_events.is_empty() {
+            if fired_events.is_empty() && self.delayed_sends.is_empty() {
                 info!("no more progress. I think we're done here.");
                 break;
             }
 
+            self.trail.fired(fired_events.iter().copied());
+            let at = FiredAt {
+                wall:    progress.t_start.elapsed(),
+                runtime: progress.t_start_rt.elapsed(),
+            };
+            let stop = fired_events.iter().any(|k| stop_at.contains(k));
             for event_id in fired_events {
-                reached_events.insert(event_id);
+                progress.reached_events.insert(event_id);
+                progress.fired_at.insert(event_id, at);
+            }
+            if stop {
+                return Ok(true)
+            }
+        }
+        progress.done = true;
+
+        if self.audit {
+            let violations = self.trail.check(&self.executable.events, &self.scopes);
+            if !violations.is_empty() {
+                for violation in violations.iter() {
+                    recorder.write(records::Error {
+                        reason: format!("audit: {}", violation),
+                    });
+                }
+                let reason = RunErrorReason::AuditFailed { violations };
+                return Err(RunError {
+                    event: None,
+                    record: None,
+                    record_log: progress.fail(&reason),
+                    reason,
+                });
             }
         }
 
-        // let reached = reached
-        //     .into_iter()
-        //     // XXX: are we expecting only the root-scope's names here?
-        //     // FIXME: no we are not, names can come from different places.
-        //     .map(|(k, v)| (self.event_name(k).expect("bad event-key").1.clone(), v))
-        //     .collect();
-        // let unreached = unreached
-        //     .into_iter()
-        //     // XXX: are we expecting only the root-scope's names here?
-        //     // FIXME: no we are not, names can come from different places.
-        //     .map(|(k, v)| (self.event_name(k).expect("bad event-key").1.clone(), v))
-        //     .collect();
-
-        Ok(Report {
-            reached_events,
-            required_events,
-            record_log,
-        })
+        Ok(false)
     }
 
     // #[doc(hidden)]
@@ -201,15 +768,22 @@ impl Runner<'_> {
             .ready_events
             .iter()
             .copied()
-            .filter(|k| matches!(k, EventKey::Send(_) | EventKey::Respond(_)))
+            .filter(|k| {
+                matches!(
+                    k,
+                    EventKey::Send(_) | EventKey::Respond(_) | EventKey::StopDummy(_)
+                )
+            })
+            .filter(|k| !self.is_delayed(*k))
             .map(ReadyEventKey::from);
 
+        // the delayed sends are waited for along with the recvs and the delays
         let recv_or_delay = self
             .ready_events
             .iter()
             .copied()
-            .filter(|k| matches!(k, EventKey::Recv(_) | EventKey::Delay(_)))
-            .map(ReadyEventKey::from)
+            .filter(|k| matches!(k, EventKey::Recv(_) | EventKey::Delay(_)) || self.is_delayed(*k))
+            .map(|_| ReadyEventKey::RecvOrDelay)
             .take(1);
 
         // this is just a predictable order of events, no significant scientific basis
@@ -217,6 +791,49 @@ impl Runner<'_> {
         binds.chain(send_and_respond).chain(recv_or_delay)
     }
 
+    fn is_delayed(&self, event_key: EventKey) -> bool {
+        matches!(event_key, EventKey::Send(k) if self.delayed_sends.contains_key(&k))
+    }
+
+    fn next_ready_event(&mut self) -> Option<ReadyEventKey> {
+        let candidates = self.ready_events().collect::<Vec<_>>();
+
+        // only the sends and the responds are picked among: a bind always goes
+        // first, and the recvs wait until there is nothing else to do.
+        let sends_and_responds = candidates
+            .iter()
+            .take_while(|k| {
+                matches!(
+                    k,
+                    ReadyEventKey::Send(_)
+                        | ReadyEventKey::Respond(_)
+                        | ReadyEventKey::StopDummy(_)
+                )
+            })
+            .count();
+        if sends_and_responds < 2 {
+            return candidates.first().copied();
+        }
+
+        let idx = match self.scheduling.as_mut() {
+            None => 0,
+            Some(Scheduling::Seeded { rng, .. }) => rng.below(sends_and_responds),
+            Some(Scheduling::Scripted { script, taken }) => {
+                let idx = script
+                    .get(taken.len())
+                    .copied()
+                    .filter(|idx| *idx < sends_and_responds)
+                    .unwrap_or(0);
+                taken.push(Choice {
+                    taken: idx,
+                    of:    sends_and_responds,
+                });
+                idx
+            },
+        };
+        Some(candidates[idx])
+    }
+
     pub fn event_name(&self, event_key: EventKey) -> Option<(KeyScope, &EventName)> {
         self.executable
             .events
@@ -225,18 +842,39 @@ impl Runner<'_> {
             .map(|(s, e)| (*s, e))
     }
 
+    /// The name of the event, along with the call events its scope has been
+    /// invoked by.
+    fn event_context(&self, event_key: EventKey) -> EventContext {
+        let scopes = &self.executable.scopes;
+        let (scope_key, name) = self
+            .event_name(event_key)
+            .expect("every processed event is named");
+        let mut called_by = vec![];
+        let mut invoked_as = scopes[scope_key].invoked_as.as_ref();
+        while let Some((scope_key, event_name, _subroutine_name)) = invoked_as.take() {
+            called_by.push(event_name.clone());
+            invoked_as = scopes[*scope_key].invoked_as.as_ref();
+        }
+        EventContext {
+            key: event_key,
+            name: name.clone(),
+            called_by,
+        }
+    }
+
     // #[doc(hidden)]
     // pub
     async fn fire_event(
         &mut self,
         recorder: &mut Recorder<'_>,
         ready_event_key: ReadyEventKey,
-    ) -> Result<Vec<EventKey>, RunError> {
+    ) -> Result<Vec<EventKey>, RunErrorReason> {
         let mut recorder = recorder.write(records::ProcessEventClass(ready_event_key));
+        self.current_event = EventKey::try_from(ready_event_key).ok();
 
         if let Ok(event_key) = EventKey::try_from(ready_event_key) {
             if !self.ready_events.remove(&event_key) {
-                return Err(RunError::EventIsNotReady(ready_event_key));
+                return Err(RunErrorReason::EventIsNotReady(ready_event_key));
             }
 
             let event_name = self
@@ -253,9 +891,9 @@ impl Runner<'_> {
                 matches!(
                     e,
                     EventKey::Recv(_) | EventKey::Delay(_) | EventKey::Bind(_)
-                )
+                ) || self.is_delayed(*e)
             }) {
-                return Err(RunError::EventIsNotReady(ready_event_key));
+                return Err(RunErrorReason::EventIsNotReady(ready_event_key));
             }
 
             debug!("doing {:?}", ready_event_key);
@@ -265,6 +903,7 @@ impl Runner<'_> {
             ReadyEventKey::Bind => self.fire_event_bind(&mut recorder).await?,
             ReadyEventKey::Send(k) => self.fire_event_send(&mut recorder, k).await?,
             ReadyEventKey::Respond(k) => self.fire_event_respond(&mut recorder, k).await?,
+            ReadyEventKey::StopDummy(k) => self.fire_event_stop_dummy(&mut recorder, k)?,
             ReadyEventKey::RecvOrDelay => self.fire_event_recv_or_delay(&mut recorder).await?,
         };
 
@@ -298,6 +937,7 @@ impl Runner<'_> {
                         debug!("  unblocked {:?}", dependent_key);
                         remove_from.remove();
                         self.ready_events.insert(dependent_key);
+                        self.trail.became_ready(dependent_key);
 
                         match dependent_key {
                             EventKey::Delay(k) => {
@@ -313,6 +953,13 @@ impl Runner<'_> {
                                     k,
                                     &events.recv[k],
                                 );
+                                #[cfg(feature = "metrics")]
+                                if let RecvSource::Metric(check) = &events.recv[k].source {
+                                    if check.kind == crate::execution::MetricKind::Counter {
+                                        let baseline = self.metrics.observe(check)[0];
+                                        self.metric_baselines.insert(k, baseline);
+                                    }
+                                }
                             },
                             _ => (),
                         }
@@ -325,7 +972,7 @@ impl Runner<'_> {
     async fn fire_event_bind(
         &mut self,
         recorder: &mut Recorder<'_>,
-    ) -> Result<Vec<EventKey>, RunError> {
+    ) -> Result<Vec<EventKey>, RunErrorReason> {
         let Executable {
             marshalling,
             events,
@@ -355,6 +1002,7 @@ impl Runner<'_> {
         for bind_key in ready_bind_keys {
             let mut recorder = recorder.write(records::ProcessBindKey(bind_key));
             self.ready_events.remove(&EventKey::Bind(bind_key));
+            self.current_event = Some(bind_key.into());
 
             trace!(" binding {:?}", bind_key);
             let EventBind {
@@ -363,9 +1011,13 @@ impl Runner<'_> {
                 scope: bind_scope,
             } = &events.bind[bind_key];
 
-            let (src_scope_key, dst_scope_key) = match bind_scope {
-                BindScope::Same(scope_id) => (*scope_id, *scope_id),
-                BindScope::Two { src, dst, .. } => (*src, *dst),
+            let (src_scope_key, dst_scope_key, share_all) = match bind_scope {
+                BindScope::Same(scope_id) => (*scope_id, *scope_id, false),
+                BindScope::Two {
+                    src,
+                    dst,
+                    share_all,
+                } => (*src, *dst, *share_all),
             };
 
             let mut recorder_src = recorder.write(records::BindSrcScope(src_scope_key));
@@ -375,28 +1027,65 @@ impl Runner<'_> {
             let value = match src {
                 SrcMsg::Literal(value) => value.clone(),
                 SrcMsg::Bind(template) => {
-                    bindings::render(template.clone(), src_scope).map_err(RunError::BindError)?
+                    bindings::render(template.clone(), src_scope)
+                        .map_err(RunErrorReason::BindError)?
                 },
                 SrcMsg::Inject(key) => {
-                    let m = marshalling.value(key).ok_or(RunError::Marshalling(
-                        format!("no such key: {:?}", key).into(),
-                    ))?;
-                    serde_json::to_value(m).expect("can't serialize a message?")
+                    let m = marshalling
+                        .inject(key, src_scope)
+                        .map_err(RunErrorReason::Marshalling)?;
+                    serde_json::to_value(&m).expect("can't serialize a message?")
                 },
             };
             recorder_src.write(records::UsingValue(value.clone()));
 
+            let shared = if share_all {
+                src_scope
+                    .values()
+                    .map(|(k, v)| (k.to_owned(), v.clone()))
+                    .collect::<Vec<_>>()
+            } else {
+                vec![]
+            };
+
             let mut recorder_dst = recorder.write(records::BindDstScope(dst_scope_key));
             let mut dst_scope_txn = self.scopes[dst_scope_key].txn();
 
             recorder_dst.write(records::BindToPattern(dst.clone()));
             if !bindings::bind_to_pattern(value, dst, &mut dst_scope_txn) {
+                if let Some(violation) = dst_scope_txn.take_violation() {
+                    return Err(violated(events, violation, bind_key.into()));
+                }
                 recorder.write(records::BindOutcome(false));
                 trace!("could not bind {:?}", bind_key);
                 continue;
             }
+            // the shared values are not a pattern to match: a conflict is the scenario's
+            // fault
+            if let Some((name, shared)) = shared
+                .into_iter()
+                .find(|(k, v)| !dst_scope_txn.bind_value(k, v))
+            {
+                if let Some(violation) = dst_scope_txn.take_violation() {
+                    return Err(violated(events, violation, bind_key.into()));
+                }
+                let bound = dst_scope_txn
+                    .bound_value(&name)
+                    .cloned()
+                    .unwrap_or_default();
+                let (_, shared_by) = events.names[&EventKey::Bind(bind_key)].clone();
+                return Err(RunErrorReason::SharedBindingConflict {
+                    name,
+                    bound,
+                    shared,
+                    shared_by,
+                });
+            }
+            if let Some(mismatch) = dst_scope_txn.take_mismatch() {
+                return Err(violated(events, mismatch.into(), bind_key.into()));
+            }
 
-            dst_scope_txn.commit(&mut recorder_dst);
+            dst_scope_txn.commit(bind_key.into(), &mut recorder_dst);
             recorder_dst.write(records::BindOutcome(true));
 
             recorder.write(records::EventFired(bind_key.into()));
@@ -409,7 +1098,7 @@ impl Runner<'_> {
     async fn fire_event_recv_or_delay(
         &mut self,
         recorder: &mut Recorder<'_>,
-    ) -> Result<Vec<EventKey>, RunError> {
+    ) -> Result<Vec<EventKey>, RunErrorReason> {
         let Executable {
             marshalling,
             events,
@@ -421,18 +1110,47 @@ impl Runner<'_> {
         'recv_or_delay: loop {
             self.proxies[self.main_proxy_key].sync().await;
 
+            if let Some(advance_by) = self.receives_and_delays.take_advance(Instant::now()) {
+                recorder.write(records::TimeAdvanced(advance_by));
+                trace!("advancing the time by {:?}", advance_by);
+                tokio::time::advance(advance_by).await;
+                self.proxies[self.main_proxy_key].sync().await;
+            }
+
             for ripe_key in self.receives_and_delays.select_ripe_keys(Instant::now()) {
                 match ripe_key {
                     KeyDelayOrRecv::Recv(key) => {
                         recorder.write(records::TimedOutRecvKey(key));
                         trace!("recv timed out: {:?}", key);
                         self.ready_events.remove(&EventKey::Recv(key));
+
+                        let matches = self.recv_counts.get(&key).copied().unwrap_or_default();
+                        if events.recv[key].count
+                            == Some(DefRecvCount::Exactly { exactly: matches })
+                            && self.bind_last_match(recorder, key)?
+                        {
+                            recorder.write(records::EventFired(key.into()));
+                            actually_fired_events.push(EventKey::Recv(key));
+                        }
                     },
                     KeyDelayOrRecv::Delay(key) => {
                         trace!("delay done: {:?}", key);
                         self.ready_events.remove(&EventKey::Delay(key));
                         actually_fired_events.push(EventKey::Delay(key));
                     },
+                    KeyDelayOrRecv::Send(key) => {
+                        trace!("delayed send due: {:?}", key);
+                        self.current_event = Some(key.into());
+                        let delayed = self
+                            .delayed_sends
+                            .remove(&key)
+                            .expect("a scheduled send should have been held back");
+                        self.ready_events.remove(&EventKey::Send(key));
+                        self.hand_over(key, delayed).await?;
+
+                        recorder.write(records::EventFired(key.into()));
+                        actually_fired_events.push(EventKey::Send(key));
+                    },
                 }
             }
             if !actually_fired_events.is_empty() {
@@ -453,14 +1171,175 @@ impl Runner<'_> {
                         }
                     })
                     .collect::<Vec<_>>();
-                tmp.sort_by_key(|k| events.priority.get(&EventKey::Recv(*k)));
+                let priority = |k: &KeyRecv| events.priority.get(&EventKey::Recv(*k));
+                let explicit = |k: &KeyRecv| priority(k).map(|(explicit, _)| *explicit);
+                tmp.sort_by_key(priority);
+                // the definition order only breaks the ties of the explicit priorities
+                if let Some(Scheduling::Seeded { rng, .. }) = self.scheduling.as_mut() {
+                    for same_priority in tmp.chunk_by_mut(|a, b| explicit(a) == explicit(b)) {
+                        rng.shuffle(same_priority);
+                    }
+                }
                 tmp
             };
 
             trace!("ready_recv_keys: {:?}", ready_recv_keys);
             recorder.write(records::ReadyRecvKeys(ready_recv_keys.clone()));
 
+            while let Some(Some((recv_key, response))) = self.requests.next().now_or_never() {
+                self.current_event = Some(recv_key.into());
+                let response = response.map_err(request_failed)?;
+                self.responses.insert(recv_key, response);
+            }
+
+            for recv_key in ready_recv_keys.iter().copied() {
+                let Some(response) = self.responses.remove(&recv_key) else {
+                    continue;
+                };
+                let mut recorder = recorder.write(records::ResponseReceived(recv_key));
+                self.current_event = Some(recv_key.into());
+                recorder.write(records::UsingValue(response.payload.clone()));
+
+                let EventRecv {
+                    payload_matchers,
+                    scope_key,
+                    ..
+                } = &events.recv[recv_key];
+                let mut scope_txn = self.scopes[*scope_key].txn();
+
+                let bound = payload_matchers.iter().all(|m| {
+                    recorder.write(records::BindToPattern(m.clone()));
+                    bindings::bind_to_pattern(response.payload.clone(), m, &mut scope_txn)
+                });
+                if !bound {
+                    if let Some(violation) = scope_txn.take_violation() {
+                        return Err(violated(events, violation, recv_key.into()));
+                    }
+                    trace!("   the response didn't match");
+                    recorder.write(records::BindOutcome(false));
+
+                    // no longer awaited: left unmatched, as an envelope would be
+                    let Response {
+                        message_name,
+                        from,
+                        to,
+                        payload,
+                    } = response;
+                    let from = from.unwrap_or(Addr::NULL);
+                    if self.executable.strict_mailbox {
+                        return Err(RunErrorReason::UnexpectedEnvelope {
+                            message_name,
+                            from,
+                            to: Some(to),
+                            payload,
+                        })
+                    }
+                    warn!("unmatched response with message {}", message_name);
+                    self.unmatched.push(UnmatchedEnvelope {
+                        message_name,
+                        from,
+                        to: Some(to),
+                        payload,
+                    });
+                    continue;
+                }
+                if let Some(mismatch) = scope_txn.take_mismatch() {
+                    return Err(violated(events, mismatch.into(), recv_key.into()));
+                }
+
+                scope_txn.commit(recv_key.into(), &mut recorder);
+                recorder.write(records::BindOutcome(true));
+
+                self.receives_and_delays.remove_recv_by_key(recv_key);
+                self.ready_events.remove(&EventKey::Recv(recv_key));
+                actually_fired_events.push(EventKey::Recv(recv_key));
+
+                recorder.write(records::EventFired(recv_key.into()));
+            }
+
+            for recv_key in ready_recv_keys.iter().copied() {
+                let EventRecv {
+                    payload_matchers,
+                    scope_key,
+                    source: RecvSource::Log,
+                    ..
+                } = &events.recv[recv_key]
+                else {
+                    continue;
+                };
+                let mut recorder = recorder.write(records::MatchingRecv(recv_key));
+                self.current_event = Some(recv_key.into());
+
+                for idx in 0..self.logs.len() {
+                    if self.logs_matched.contains(&idx) {
+                        continue;
+                    }
+                    let Some(line) = self.logs.get(idx) else {
+                        break;
+                    };
+                    let line = line.to_value();
+                    recorder.write(records::UsingValue(line.clone()));
+
+                    let mut scope_txn = self.scopes[*scope_key].txn();
+                    let bound = payload_matchers.iter().all(|m| {
+                        recorder.write(records::BindToPattern(m.clone()));
+                        bindings::bind_to_pattern(line.clone(), m, &mut scope_txn)
+                    });
+                    if !bound {
+                        if let Some(violation) = scope_txn.take_violation() {
+                            return Err(violated(events, violation, recv_key.into()));
+                        }
+                        recorder.write(records::BindOutcome(false));
+                        continue;
+                    }
+                    if let Some(mismatch) = scope_txn.take_mismatch() {
+                        return Err(violated(events, mismatch.into(), recv_key.into()));
+                    }
+
+                    scope_txn.commit(recv_key.into(), &mut recorder);
+                    recorder.write(records::BindOutcome(true));
+
+                    self.logs_matched.insert(idx);
+                    self.receives_and_delays.remove_recv_by_key(recv_key);
+                    self.ready_events.remove(&EventKey::Recv(recv_key));
+                    actually_fired_events.push(EventKey::Recv(recv_key));
+
+                    recorder.write(records::EventFired(recv_key.into()));
+                    break;
+                }
+            }
+
+            #[cfg(feature = "metrics")]
+            for recv_key in ready_recv_keys.iter().copied() {
+                let RecvSource::Metric(check) = &events.recv[recv_key].source else {
+                    continue;
+                };
+                let mut recorder = recorder.write(records::MatchingRecv(recv_key));
+                self.current_event = Some(recv_key.into());
+
+                let observed = self.metrics.observe(check);
+                let baseline = self
+                    .metric_baselines
+                    .get(&recv_key)
+                    .copied()
+                    .unwrap_or_default();
+                recorder.write(records::UsingValue(observed.clone().into()));
+                if !check.is_met(&observed, baseline) {
+                    recorder.write(records::BindOutcome(false));
+                    continue;
+                }
+                recorder.write(records::BindOutcome(true));
+
+                self.receives_and_delays.remove_recv_by_key(recv_key);
+                self.ready_events.remove(&EventKey::Recv(recv_key));
+                actually_fired_events.push(EventKey::Recv(recv_key));
+
+                recorder.write(records::EventFired(recv_key.into()));
+            }
+
             let mut unmatched_envelopes = 0;
+            let mut counted_envelopes = 0;
+            let mut ignored_envelopes = 0;
 
             let proxy_keys = self.proxies.keys().collect::<Vec<_>>();
             for receiving_proxy_key in proxy_keys {
@@ -490,10 +1369,40 @@ impl Runner<'_> {
                     serde_json::to_value(envelope.message()).unwrap(),
                 ));
 
-                let mut envelope_unused = true;
-
-                for recv_key in ready_recv_keys.iter().copied() {
-                    let mut recorder = recorder.write(records::MatchingRecv(recv_key));
+                if let Some(fqn) = self
+                    .executable
+                    .system_messages
+                    .ignore
+                    .iter()
+                    .find(|fqn| marshalling.is_type_of(fqn, &envelope))
+                {
+                    trace!("  ignored as {}", fqn);
+                    recorder.write(records::SystemMessageSkipped {
+                        message_name: envelope_message_name,
+                        ignored_as:   Some(fqn.clone()),
+                    });
+                    ignored_envelopes += 1;
+                    continue;
+                }
+
+                if let Some(scope_key) = self.ignored_by(&envelope) {
+                    trace!("  ignored by {:?}", scope_key);
+                    recorder.write(records::EnvelopeIgnored(scope_key));
+                    ignored_envelopes += 1;
+                    continue;
+                }
+
+                let envelope_seq_no = self.trail.envelope_received();
+                let mut matched_by = None;
+                // whether the envelope has been counted by a recv yet to be reached
+                let mut counted = false;
+                // the recv expecting exactly N the envelope is the last match of, so far
+                let mut kept_by = None;
+                // the first recv the envelope has matched but for the types of the values
+                let mut mismatched = None;
+
+                for recv_key in ready_recv_keys.iter().copied() {
+                    let mut recorder = recorder.write(records::MatchingRecv(recv_key));
 
                     trace!(
                         "   matching against {:?} [{:?}]",
@@ -503,16 +1412,83 @@ impl Runner<'_> {
                     let EventRecv {
                         fqn: match_type,
                         from: match_from,
+                        from_unseen: match_from_unseen,
+                        from_dummy: match_from_dummy,
+                        from_group: match_from_group,
+                        from_key: match_from_key,
                         to: match_to,
+                        envelope_kind: match_envelope_kind,
+                        count: match_count,
                         payload_matchers,
                         after_duration: _,
                         before_duration: _,
+                        response_to,
+                        stream_rest: _,
+                        source,
                         scope_key,
                     } = &events.recv[recv_key];
 
-                    let mut scope_txn = self.scopes[*scope_key].txn();
+                    if response_to.is_some() || !matches!(source, RecvSource::Envelope) {
+                        // neither responses, nor log lines, nor metrics come as envelopes
+                        continue;
+                    }
+                    self.current_event = Some(recv_key.into());
 
                     let marshaller = marshalling.resolve(match_type).expect("bad FQN");
+                    if !marshalling.is_type_of(match_type, &envelope) {
+                        continue;
+                    }
+
+                    if let Some(expected_kind) = match_envelope_kind {
+                        let actual_kind = match envelope.message_kind() {
+                            MessageKind::Regular { .. } => DefEnvelopeKind::Message,
+                            MessageKind::RequestAny(_) | MessageKind::RequestAll(_) => {
+                                DefEnvelopeKind::Request
+                            },
+                            MessageKind::Response { .. } => DefEnvelopeKind::Response,
+                        };
+                        if actual_kind != *expected_kind {
+                            recorder
+                                .write(records::EnvelopeKindMismatch(*expected_kind, actual_kind));
+                            continue;
+                        }
+                    }
+
+                    if let Some(expected_group) = match_from_group {
+                        let actual_group = sent_from
+                            .group_no()
+                            .and_then(|no| self.groups.get(&no))
+                            .copied();
+                        recorder.write(records::MatchGroup(
+                            sent_from,
+                            expected_group.clone(),
+                            actual_group,
+                        ));
+                        if actual_group != Some(expected_group.as_ref()) {
+                            continue;
+                        }
+                    }
+
+                    let routing_key_to_store = if let Some(template) = match_from_key {
+                        let routing_key =
+                            bindings::render(template.clone(), &self.scopes[*scope_key])
+                                .map_err(RunErrorReason::BindError)?;
+                        let expected_addr = self.scopes[*scope_key].routing_key_addr(&routing_key);
+                        recorder.write(records::MatchRoutingKey(
+                            routing_key.clone(),
+                            expected_addr,
+                            sent_from,
+                        ));
+                        match expected_addr {
+                            Some(expected_addr) if expected_addr != sent_from => continue,
+                            Some(_) => None,
+                            None => Some((routing_key, sent_from)),
+                        }
+                    } else {
+                        None
+                    };
+
+                    let mut scope_txn = self.scopes[*scope_key].txn();
 
                     let actor_address_to_store = if let Some(from_key) = match_from {
                         if let Some(expected_addr) = self.actors.get(*from_key).copied() {
@@ -527,6 +1503,12 @@ impl Runner<'_> {
                             } else {
                                 None
                             }
+                        } else if *match_from_unseen
+                            && (self.actors.values().any(|a| *a == sent_from)
+                                || self.proxies.values().any(|p| p.addr() == sent_from))
+                        {
+                            recorder.write(records::SenderAlreadySeen(sent_from));
+                            continue;
                         } else {
                             Some((*from_key, sent_from))
                         }
@@ -534,6 +1516,21 @@ impl Runner<'_> {
                         None
                     };
 
+                    if let Some(dummy_key) = match_from_dummy {
+                        let expected_addr = self.dummy_addrs[*dummy_key];
+
+                        recorder.write(records::MatchDummyAddress(
+                            *dummy_key,
+                            *scope_key,
+                            expected_addr,
+                            sent_from,
+                        ));
+
+                        if sent_from != expected_addr {
+                            continue;
+                        }
+                    }
+
                     match (match_to, sent_to_opt) {
                         (Some(dummy_key), Some(sent_to_address)) => {
                             trace!(
@@ -541,8 +1538,7 @@ impl Runner<'_> {
                                 dummy_key,
                                 sent_to_address
                             );
-                            let expected_proxy_key = self.dummies[*dummy_key];
-                            let expected_addr = self.proxies[expected_proxy_key].addr();
+                            let expected_addr = self.dummy_addrs[*dummy_key];
 
                             recorder.write(records::MatchDummyAddress(
                                 *dummy_key,
@@ -573,12 +1569,21 @@ impl Runner<'_> {
                     });
 
                     if !bound {
+                        if let Some(violation) = scope_txn.take_violation() {
+                            return Err(violated(events, violation, recv_key.into()));
+                        }
                         trace!("   marshaller couldn't bind");
                         recorder.write(records::BindOutcome(false));
                         continue;
                     };
+                    if let Some(mismatch) = scope_txn.take_mismatch() {
+                        trace!("   matched, but for the types");
+                        recorder.write(records::BindOutcome(false));
+                        mismatched.get_or_insert((recv_key, mismatch));
+                        continue;
+                    }
 
-                    let valid_from = self.receives_and_delays.remove_recv_by_key(recv_key);
+                    let valid_from = self.receives_and_delays.valid_from(recv_key);
                     recorder.write(records::ValidFrom(valid_from));
 
                     if let Some(too_early) = valid_from
@@ -589,6 +1594,62 @@ impl Runner<'_> {
                         continue;
                     }
 
+                    if let Some(count) = match_count {
+                        let matches = self.recv_counts.entry(recv_key).or_default();
+                        *matches += 1;
+                        self.arrivals
+                            .entry(EventKey::Recv(recv_key))
+                            .or_default()
+                            .push(Instant::now());
+                        recorder.write(records::CountedMatch(*matches));
+                        match *count {
+                            DefRecvCount::AtLeast(n) if *matches >= n => (),
+                            DefRecvCount::AtLeast(_) => {
+                                self.recv_appended
+                                    .entry(recv_key)
+                                    .or_default()
+                                    .extend(scope_txn.take_appended());
+                                self.trail.envelope_matched(envelope_seq_no);
+                                counted = true;
+                                break;
+                            },
+                            DefRecvCount::Exactly { exactly } if *matches > exactly => {
+                                // the recv is not to be reached anymore: no need to wait
+                                recorder.write(records::CountExceeded(exactly));
+                                self.receives_and_delays.remove_recv_by_key(recv_key);
+                                self.ready_events.remove(&EventKey::Recv(recv_key));
+                                self.recv_appended.remove(&recv_key);
+                                self.recv_last_matches.remove(&recv_key);
+                                self.envelopes.remove(&recv_key);
+                                self.trail.envelope_matched(envelope_seq_no);
+                                counted = true;
+                                break;
+                            },
+                            DefRecvCount::Exactly { .. } => {
+                                self.recv_appended
+                                    .entry(recv_key)
+                                    .or_default()
+                                    .extend(scope_txn.take_appended());
+                                let values = scope_txn
+                                    .added()
+                                    .map(|(k, v)| (k.to_owned(), v.clone()))
+                                    .collect();
+                                self.recv_last_matches.insert(
+                                    recv_key,
+                                    LastMatch {
+                                        values,
+                                        actor_address: actor_address_to_store,
+                                        routing_key: routing_key_to_store,
+                                    },
+                                );
+                                self.trail.envelope_matched(envelope_seq_no);
+                                kept_by = Some(recv_key);
+                                break;
+                            },
+                        }
+                    }
+                    self.receives_and_delays.remove_recv_by_key(recv_key);
+
                     if let Some((actor_key, actor_addr)) = actor_address_to_store {
                         recorder.write(records::StoreActorAddress(
                             actor_key, *scope_key, actor_addr,
@@ -600,40 +1661,132 @@ impl Runner<'_> {
                             actor_key
                         );
                     }
-                    scope_txn.commit(&mut recorder);
+                    if let Some(earlier) = self.recv_appended.remove(&recv_key) {
+                        scope_txn.prepend_appended(earlier);
+                    }
+                    scope_txn.commit(recv_key.into(), &mut recorder);
                     recorder.write(records::BindOutcome(true));
 
-                    self.envelopes.insert(recv_key, envelope);
+                    if let Some((routing_key, actor_addr)) = routing_key_to_store {
+                        self.scopes[*scope_key].bind_routing_key(&routing_key, actor_addr);
+                    }
+
+                    if let Some((actor_key, actor_addr)) = actor_address_to_store {
+                        for (known_in, actor_name) in
+                            self.executable.actors[actor_key].known_as.iter()
+                        {
+                            self.scopes[known_in].bind_actor(actor_name.clone(), actor_addr);
+                        }
+                    }
+
                     self.ready_events.remove(&EventKey::Recv(recv_key));
                     actually_fired_events.push(EventKey::Recv(recv_key));
 
                     recorder.write(records::EventFired(recv_key.into()));
 
-                    envelope_unused = false;
+                    self.trail.envelope_matched(envelope_seq_no);
+                    matched_by = Some(recv_key);
                     break;
                 }
 
-                if envelope_unused {
+                if let Some(recv_key) = matched_by {
+                    self.envelopes.insert(recv_key, envelope);
+                    continue;
+                }
+                if let Some(recv_key) = kept_by {
+                    self.envelopes.insert(recv_key, envelope);
+                    counted_envelopes += 1;
+                    continue;
+                }
+                if counted {
+                    counted_envelopes += 1;
+                    continue;
+                }
+                if let Some((recv_key, mismatch)) = mismatched {
+                    self.current_event = Some(recv_key.into());
+                    return Err(violated(events, mismatch.into(), recv_key.into()));
+                }
+                self.current_event = None;
+
+                if let Some((expected, got)) =
+                    self.out_of_order(&envelope, sent_to_opt, &ready_recv_keys)
+                {
+                    self.current_event = Some(expected.into());
+                    let name_of = |k: KeyRecv| events.names[&EventKey::Recv(k)].1.clone();
+                    return Err(RunErrorReason::OutOfOrder {
+                        expected: name_of(expected),
+                        got:      name_of(got),
+                        payload:  serde_json::to_value(envelope.message()).unwrap(),
+                    })
+                }
+
+                if self
+                    .auto_respond(&mut recorder, &envelope, sent_to_opt)
+                    .await?
+                {
+                    self.trail.envelope_matched(envelope_seq_no);
+                    continue;
+                }
+
+                if self.executable.system_messages.skip_unmatched
+                    && envelope.message().protocol() == SYSTEM_PROTOCOL
+                {
+                    debug!("skipping system message {}", envelope_message_name);
+                    recorder.write(records::SystemMessageSkipped {
+                        message_name: envelope_message_name,
+                        ignored_as:   None,
+                    });
+                    ignored_envelopes += 1;
+                } else if self.executable.strict_mailbox {
+                    return Err(RunErrorReason::UnexpectedEnvelope {
+                        message_name: envelope_message_name,
+                        from:         sent_from,
+                        to:           sent_to_opt,
+                        payload:      serde_json::to_value(envelope.message()).unwrap(),
+                    })
+                } else {
                     warn!("unmatched envelope with message {}", envelope_message_name);
+                    self.trail.envelope_unmatched(envelope_seq_no);
+                    self.unmatched.push(UnmatchedEnvelope {
+                        message_name: envelope_message_name,
+                        from:         sent_from,
+                        to:           sent_to_opt,
+                        payload:      serde_json::to_value(envelope.message()).unwrap(),
+                    });
                     unmatched_envelopes += 1;
                 }
             }
 
-            match (actually_fired_events.is_empty(), unmatched_envelopes == 0) {
+            let taken_in = unmatched_envelopes + counted_envelopes + ignored_envelopes;
+            match (actually_fired_events.is_empty(), taken_in == 0) {
                 (true, true) => {
                     let now = Instant::now();
-                    let Some(sleep_until) = self.receives_and_delays.next_sleep_until(now) else {
-                        break 'recv_or_delay
-                    };
+                    let sleep_until = self.receives_and_delays.next_sleep_until(now);
+                    if sleep_until.is_none() && self.requests.is_empty() {
+                        break 'recv_or_delay;
+                    }
 
                     trace!(
                         "nothing to do — sleeping for {:?}...",
-                        sleep_until.checked_duration_since(now),
+                        sleep_until.and_then(|t| t.checked_duration_since(now)),
                     );
-                    tokio::time::sleep_until(sleep_until).await;
+                    let sleep = async move {
+                        match sleep_until {
+                            Some(sleep_until) => tokio::time::sleep_until(sleep_until).await,
+                            None => futures::future::pending().await,
+                        }
+                    };
+                    tokio::select! {
+                        () = sleep => (),
+                        Some((recv_key, response)) = self.requests.next() => {
+                            self.current_event = Some(recv_key.into());
+                            let response = response.map_err(request_failed)?;
+                            self.responses.insert(recv_key, response);
+                        },
+                    }
                 },
                 (true, false) => {
-                    trace!("no fired events, but some unhandled envelopes");
+                    trace!("no fired events, but some envelopes taken in");
                 },
 
                 (false, _) => {
@@ -650,7 +1803,7 @@ impl Runner<'_> {
         &mut self,
         recorder: &mut Recorder<'_>,
         event_key: KeySend,
-    ) -> Result<Vec<EventKey>, RunError> {
+    ) -> Result<Vec<EventKey>, RunErrorReason> {
         let Executable {
             marshalling,
             events: vertices,
@@ -659,8 +1812,15 @@ impl Runner<'_> {
         let EventSend {
             from: send_from,
             to: send_to,
+            to_dummy: send_to_dummy,
+            to_all,
+            to_any,
+            routing_key,
             fqn: message_type,
             payload: message_data,
+            response,
+            faults,
+            batch: _,
             scope_key,
         } = &vertices.send[event_key];
         debug!(
@@ -676,14 +1836,25 @@ impl Runner<'_> {
                     .actors
                     .get(*actor_key)
                     .copied()
-                    .ok_or(RunError::UnboundName(*actor_key))?;
+                    .ok_or(RunErrorReason::UnboundName(*actor_key))?;
                 recorder.write(records::ResolveActorName(*actor_key, *scope_key, addr));
 
                 Ok(addr)
             })
-            .transpose()?;
+            .transpose()?
+            .or_else(|| send_to_dummy.map(|dummy_key| self.dummy_addrs[dummy_key]));
+        let send_to_addr_opt = match routing_key {
+            Some(template) => {
+                let routing_key = bindings::render(template.clone(), &self.scopes[*scope_key])
+                    .map_err(RunErrorReason::BindError)?;
+                let addr = self.scopes[*scope_key].routing_key_addr(&routing_key);
+                recorder.write(records::ResolveRoutingKey(routing_key, addr));
+                addr
+            },
+            None => send_to_addr_opt,
+        };
 
-        let send_from_proxy_key = self.dummies[*send_from];
+        let send_from_proxy_key = self.dummy_proxy_key(*send_from, *scope_key)?;
 
         recorder.write(records::SendMessageType(message_type.clone()));
         recorder.write(records::UsingMsg(message_data.clone()));
@@ -694,44 +1865,445 @@ impl Runner<'_> {
             .resolve(message_type)
             .expect("invalid FQN");
 
+        if let Some(response_key) = *response {
+            recorder.write(records::SendTo(send_to_addr_opt));
+
+            // The request is issued from a proxy of its own: the dummy's one should keep
+            // receiving while the request is pending.
+            let request_to_addrs = if to_all.is_empty() {
+                vec![send_to_addr_opt]
+            } else {
+                self.bound_addrs(recorder, to_all, *scope_key)?
+            };
+            let mut requests = vec![];
+            for request_to in request_to_addrs {
+                let requesting_proxy = self.proxies[send_from_proxy_key].subproxy().await;
+                let request = marshaller
+                    .request(
+                        requesting_proxy,
+                        request_to,
+                        marshalling,
+                        &self.scopes[*scope_key],
+                        message_data.clone(),
+                    )
+                    .map_err(RunErrorReason::Marshalling)?;
+                requests.push(request);
+            }
+            let message_name = marshaller.response_type().unwrap_or_default();
+            let requested_by = self.dummy_addrs[*send_from];
+            let collect_all = !to_all.is_empty();
+            let response = futures::future::join_all(requests).map(move |rs| {
+                let payload = if collect_all {
+                    rs.into_iter()
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(Value::Array)
+                } else {
+                    rs.into_iter().next().expect("a single request")
+                };
+                let response = payload.map(|payload| {
+                    Response {
+                        message_name,
+                        from: send_to_addr_opt,
+                        to: requested_by,
+                        payload,
+                    }
+                });
+                (response_key, response)
+            });
+            self.requests.push(response.boxed_local());
+
+            recorder.write(records::EventFired(event_key.into()));
+            return Ok(vec![EventKey::Send(event_key)]);
+        }
+
         let any_message = marshaller
             .marshal_outbound_message(marshalling, &self.scopes[*scope_key], message_data.clone())
-            .map_err(RunError::Marshalling)?;
+            .map_err(RunErrorReason::Marshalling)?;
         // TODO: maybe print only the third element of the triple?
         recorder.write(records::UsingValue(
             serde_json::to_value(&any_message).unwrap(),
         ));
-        recorder.write(records::SendTo(send_to_addr_opt));
+        let send_to_addrs = if !to_all.is_empty() {
+            self.bound_addrs(recorder, to_all, *scope_key)?
+        } else if !to_any.is_empty() {
+            let bound = self.bound_addrs(recorder, to_any, *scope_key)?;
+            let turn = self.turns.entry(to_any.clone()).or_default();
+            let addr = bound[*turn % bound.len()];
+            *turn += 1;
+            vec![addr]
+        } else {
+            vec![send_to_addr_opt]
+        };
+        for addr in send_to_addrs.iter() {
+            recorder.write(records::SendTo(*addr));
+        }
+
+        let rng = match faults.and_then(|f| f.seed) {
+            Some(seed) => self.send_rngs.entry(event_key).or_insert(SplitMix64(seed)),
+            None => &mut self.faults_rng,
+        };
+        let copies = match faults {
+            Some(faults) if rng.chance(faults.drop) => {
+                recorder.write(records::FaultDropped);
+                0
+            },
+            Some(faults) if rng.chance(faults.duplicate) => {
+                recorder.write(records::FaultDuplicated);
+                2
+            },
+            _ => 1,
+        };
+        let delayed = DelayedSend {
+            message: any_message,
+            addrs: send_to_addrs,
+            copies,
+        };
+        if let Some((min, max)) = faults.and_then(|f| f.latency).filter(|_| copies > 0) {
+            let delay = self
+                .options
+                .scale(min + (max - min).mul_f64(rng.fraction()));
+            recorder.write(records::FaultDelayed(delay));
+
+            // fires once handed over: meanwhile, the run carries on
+            let now = Instant::now();
+            self.receives_and_delays
+                .insert_send(now, event_key, now + delay);
+            self.delayed_sends.insert(event_key, delayed);
+            self.ready_events.insert(EventKey::Send(event_key));
+            return Ok(vec![]);
+        }
+        self.hand_over(event_key, delayed).await?;
+
+        recorder.write(records::EventFired(event_key.into()));
+
+        Ok(vec![EventKey::Send(event_key)])
+    }
 
+    /// Hands the message of the send event over to the dummy's proxy.
+    async fn hand_over(
+        &mut self,
+        event_key: KeySend,
+        delayed: DelayedSend,
+    ) -> Result<(), RunErrorReason> {
+        let EventSend {
+            from: send_from,
+            batch,
+            scope_key,
+            ..
+        } = &self.executable.events.send[event_key];
+        let DelayedSend {
+            message: any_message,
+            addrs: send_to_addrs,
+            copies,
+        } = delayed;
+        let send_from_proxy_key = self.dummy_proxy_key(*send_from, *scope_key)?;
         let proxy = &mut self.proxies[send_from_proxy_key];
 
-        if let Some(dst_addr) = send_to_addr_opt {
-            trace!(
-                "sending directly [from: {}; to: {}]: {:?}",
-                dst_addr,
-                proxy.addr(),
-                any_message
-            );
-            let () = proxy.send_to(dst_addr, any_message).await;
-        } else {
-            trace!(
-                "sending via routing [from: {}]: {:?}",
-                proxy.addr(),
-                any_message
-            );
-            let () = proxy.send(any_message).await;
+        for _ in 0..copies {
+            for send_to_addr_opt in send_to_addrs.iter().copied() {
+                if let Some(dst_addr) = send_to_addr_opt {
+                    trace!(
+                        "sending directly [from: {}; to: {}]: {:?}",
+                        dst_addr,
+                        proxy.addr(),
+                        any_message
+                    );
+                    let () = proxy.send_to(dst_addr, any_message.clone()).await;
+                } else {
+                    trace!(
+                        "sending via routing [from: {}]: {:?}",
+                        proxy.addr(),
+                        any_message
+                    );
+                    let () = proxy.send(any_message.clone()).await;
+                }
+            }
         }
+        if let Some(batch) = batch {
+            self.arrivals
+                .entry(EventKey::Send(*batch))
+                .or_default()
+                .push(Instant::now());
+        }
+
+        Ok(())
+    }
+
+    fn fire_event_stop_dummy(
+        &mut self,
+        recorder: &mut Recorder<'_>,
+        event_key: KeyStopDummy,
+    ) -> Result<Vec<EventKey>, RunErrorReason> {
+        let Executable { events, .. } = self.executable;
+        let EventStopDummy { scope_key, dummy } = &events.stop_dummy[event_key];
 
+        let proxy_key = self.dummy_proxy_key(*dummy, *scope_key)?;
+        self.dummies.remove(*dummy);
+        self.proxies.remove(proxy_key);
+        // the requests the dummy has received fail as their tokens are dropped
+        self.envelopes
+            .retain(|recv_key, _| events.recv[*recv_key].to != Some(*dummy));
+
+        recorder.write(records::DummyStopped(*dummy, *scope_key));
         recorder.write(records::EventFired(event_key.into()));
 
-        Ok(vec![EventKey::Send(event_key)])
+        Ok(vec![EventKey::StopDummy(event_key)])
+    }
+
+    /// Binds what the last match of the recv expecting exactly N has matched
+    /// (nothing if N is zero), as the recv is reached. Returns whether that
+    /// still agrees with the bindings made since.
+    fn bind_last_match(
+        &mut self,
+        recorder: &mut Recorder<'_>,
+        recv_key: KeyRecv,
+    ) -> Result<bool, RunErrorReason> {
+        let Executable { events, actors, .. } = self.executable;
+        let scope_key = events.recv[recv_key].scope_key;
+        let LastMatch {
+            values,
+            actor_address,
+            routing_key,
+        } = self.recv_last_matches.remove(&recv_key).unwrap_or_default();
+
+        if let Some((actor_key, actor_addr)) = actor_address {
+            if self
+                .actors
+                .get(actor_key)
+                .is_some_and(|bound| *bound != actor_addr)
+            {
+                return Ok(false)
+            }
+        }
+        let mut scope_txn = self.scopes[scope_key].txn();
+        if !values.iter().all(|(k, v)| scope_txn.bind_value(k, v)) {
+            if let Some(violation) = scope_txn.take_violation() {
+                return Err(violated(events, violation, recv_key.into()));
+            }
+            return Ok(false)
+        }
+        scope_txn.prepend_appended(self.recv_appended.remove(&recv_key).unwrap_or_default());
+        scope_txn.commit(recv_key.into(), recorder);
+
+        if let Some((routing_key, actor_addr)) = routing_key {
+            self.scopes[scope_key].bind_routing_key(&routing_key, actor_addr);
+        }
+        if let Some((actor_key, actor_addr)) = actor_address {
+            recorder.write(records::StoreActorAddress(actor_key, scope_key, actor_addr));
+            self.actors.insert(actor_key, actor_addr);
+            for (known_in, actor_name) in actors[actor_key].known_as.iter() {
+                self.scopes[known_in].bind_actor(actor_name.clone(), actor_addr);
+            }
+        }
+        Ok(true)
+    }
+
+    /// The addresses of those of the actors bound to a name by now.
+    fn bound_addrs(
+        &self,
+        recorder: &mut Recorder<'_>,
+        actor_keys: &[KeyActor],
+        scope_key: KeyScope,
+    ) -> Result<Vec<Option<Addr>>, RunErrorReason> {
+        let mut addrs = vec![];
+        for actor_key in actor_keys.iter().copied() {
+            if let Some(addr) = self.actors.get(actor_key).copied() {
+                recorder.write(records::ResolveActorName(actor_key, scope_key, addr));
+                addrs.push(Some(addr));
+            }
+        }
+        if addrs.is_empty() {
+            return Err(RunErrorReason::NoneBound(actor_keys.to_vec()))
+        }
+        Ok(addrs)
+    }
+
+    /// The proxy of the dummy, unless it has been stopped.
+    fn dummy_proxy_key(
+        &self,
+        dummy_key: KeyDummy,
+        scope_key: KeyScope,
+    ) -> Result<ProxyKey, RunErrorReason> {
+        self.dummies.get(dummy_key).copied().ok_or_else(|| {
+            RunErrorReason::DummyStopped(
+                self.executable.dummies[dummy_key].known_as[scope_key].clone(),
+            )
+        })
+    }
+
+    /// Returns the scope whose `ignore` list the envelope matches, if any.
+    fn ignored_by(&self, envelope: &Envelope) -> Option<KeyScope> {
+        let Executable {
+            marshalling,
+            ignored,
+            ..
+        } = self.executable;
+
+        ignored
+            .iter()
+            .find(
+                |Ignored {
+                     scope_key,
+                     fqn,
+                     pattern,
+                 }| {
+                    if !marshalling.is_type_of(fqn, envelope) {
+                        return false
+                    }
+                    let Some(pattern) = pattern else { return true };
+
+                    // the values bound by the pattern are thrown away.
+                    let marshaller = marshalling.resolve(fqn).expect("invalid FQN");
+                    let mut bindings = self.scopes[*scope_key].fork();
+                    let mut txn = bindings.txn();
+                    marshaller.match_inbound_message(envelope, pattern, &mut txn)
+                },
+            )
+            .map(|ignored| ignored.scope_key)
+    }
+
+    /// Finds a stream the envelope breaks the order of: the envelope matches an
+    /// item expected later than the one the stream is waiting for.
+    ///
+    /// Returns the awaited item and the later one.
+    fn out_of_order(
+        &self,
+        envelope: &Envelope,
+        sent_to_opt: Option<Addr>,
+        ready_recv_keys: &[KeyRecv],
+    ) -> Option<(KeyRecv, KeyRecv)> {
+        let Executable {
+            marshalling,
+            events,
+            ..
+        } = self.executable;
+
+        let matches = |recv_key: KeyRecv| {
+            let EventRecv {
+                fqn,
+                from,
+                from_dummy,
+                to,
+                payload_matchers,
+                scope_key,
+                ..
+            } = &events.recv[recv_key];
+            if !marshalling.is_type_of(fqn, envelope) {
+                return false
+            }
+
+            let expected_from = from
+                .and_then(|actor_key| self.actors.get(actor_key).copied())
+                .or_else(|| from_dummy.map(|dummy_key| self.dummy_addrs[dummy_key]));
+            let expected_to = to.map(|dummy_key| self.dummy_addrs[dummy_key]);
+            if expected_from.is_some_and(|addr| addr != envelope.sender())
+                || expected_to.is_some_and(|addr| Some(addr) != sent_to_opt)
+            {
+                return false
+            }
+
+            // the values bound by the patterns are thrown away.
+            let marshaller = marshalling.resolve(fqn).expect("invalid FQN");
+            let mut bindings = self.scopes[*scope_key].fork();
+            let mut txn = bindings.txn();
+            payload_matchers
+                .iter()
+                .all(|m| marshaller.match_inbound_message(envelope, m, &mut txn))
+        };
+
+        ready_recv_keys.iter().find_map(|&expected| {
+            events.recv[expected]
+                .stream_rest
+                .iter()
+                .copied()
+                .find(|&later| matches(later))
+                .map(|later| (expected, later))
+        })
+    }
+
+    /// Answers the envelope by the first auto-responder rule matching it.
+    ///
+    /// Returns whether the envelope has been consumed.
+    async fn auto_respond(
+        &mut self,
+        recorder: &mut Recorder<'_>,
+        envelope: &Envelope,
+        sent_to_opt: Option<Addr>,
+    ) -> Result<bool, RunErrorReason> {
+        let Executable {
+            marshalling,
+            auto_responders,
+            ..
+        } = self.executable;
+
+        for AutoResponder {
+            scope_key,
+            dummy,
+            fqn,
+            pattern,
+            reply,
+        } in auto_responders
+        {
+            let Some(proxy_key) = self.dummies.get(*dummy).copied() else {
+                continue;
+            };
+            if sent_to_opt != Some(self.proxies[proxy_key].addr())
+                || !marshalling.is_type_of(fqn, envelope)
+            {
+                continue;
+            }
+
+            let token = match envelope.message_kind() {
+                MessageKind::RequestAny(token) | MessageKind::RequestAll(token) => Some(token),
+                _ => None,
+            };
+            if matches!(reply, AutoReply::Respond { .. }) && token.is_none() {
+                continue;
+            }
+
+            // the values bound by the trigger are visible to the reply only.
+            let marshaller = marshalling.resolve(fqn).expect("invalid FQN");
+            let mut bindings = self.scopes[*scope_key].fork();
+            let mut txn = bindings.txn();
+            if !marshaller.match_inbound_message(envelope, pattern, &mut txn) {
+                continue;
+            }
+            txn.apply();
+
+            recorder.write(records::AutoResponded(*dummy, *scope_key));
+            let proxy = &mut self.proxies[proxy_key];
+            match reply {
+                AutoReply::Send { fqn, payload } => {
+                    let any_message = marshalling
+                        .resolve(fqn)
+                        .expect("invalid FQN")
+                        .marshal_outbound_message(marshalling, &bindings, payload.clone())
+                        .map_err(RunErrorReason::Marshalling)?;
+                    let () = proxy.send_to(envelope.sender(), any_message).await;
+                },
+                AutoReply::Respond { payload } => {
+                    // the original token goes along with the envelope.
+                    let token = token.expect("checked above").duplicate();
+                    marshaller
+                        .response()
+                        .expect("checked at build time")
+                        .respond(proxy, token, marshalling, &bindings, payload.clone())
+                        .await
+                        .map_err(RunErrorReason::Marshalling)?;
+                },
+            }
+
+            return Ok(true)
+        }
+
+        Ok(false)
     }
 
     async fn fire_event_respond(
         &mut self,
         recorder: &mut Recorder<'_>,
         event_key: KeyRespond,
-    ) -> Result<Vec<EventKey>, RunError> {
+    ) -> Result<Vec<EventKey>, RunErrorReason> {
         let Executable {
             marshalling,
             events: vertices,
@@ -753,7 +2325,7 @@ impl Runner<'_> {
         recorder.write(records::ProcessRespond(event_key));
 
         let proxy_key = if let Some(respond_from) = respond_from {
-            self.dummies[*respond_from]
+            self.dummy_proxy_key(*respond_from, *scope_key)?
         } else {
             self.main_proxy_key
         };
@@ -767,14 +2339,60 @@ impl Runner<'_> {
             .response()
             .expect("request_fqn does not point to a Request");
 
-        let Some(request_envelope) = self.envelopes.remove(respond_to) else {
-            return Err(RunError::NoRequest);
+        let Some(request_envelope) = self.envelopes.get(respond_to) else {
+            return Err(RunErrorReason::NoRequest);
         };
 
-        let token = match request_envelope.message_kind() {
-            MessageKind::RequestAny(token) => token.duplicate(),
-            MessageKind::RequestAll(token) => token.duplicate(),
-            _ => return Err(RunError::NoRequest),
+        if !matches!(
+            request_envelope.message_kind(),
+            MessageKind::RequestAny(_) | MessageKind::RequestAll(_)
+        ) {
+            return Err(RunErrorReason::NoRequest);
+        }
+
+        // the payload of the request is visible to the response as `$request`, unless
+        // the name is bound explicitly.
+        let mut bindings = self.scopes[*scope_key].fork();
+        if let Some(request) = extract_message_payload(request_envelope) {
+            let mut txn = bindings.txn();
+            txn.bind_value(bindings::REQUEST_BINDING, &request);
+            txn.apply();
+        }
+
+        let responds_pending = self
+            .responds_pending
+            .get_mut(respond_to)
+            .expect("every request responded to is counted");
+        *responds_pending -= 1;
+
+        // Every respond event but the last one gets its own copy of the token: this
+        // way a `RequestAll` may be responded to several times. The last one takes
+        // the original along with the envelope, so that the requester does not count
+        // it as an ignored request.
+        let token = if *responds_pending == 0 {
+            let request_envelope = self.envelopes.remove(respond_to).expect("checked above");
+            let (_, kind) = request_envelope
+                .unpack::<AnyMessage>()
+                .expect("any message is an AnyMessage");
+            match kind {
+                MessageKind::RequestAny(token) | MessageKind::RequestAll(token) => token,
+                _ => unreachable!("checked above"),
+            }
+        } else {
+            match self.envelopes[respond_to].message_kind() {
+                MessageKind::RequestAny(token) | MessageKind::RequestAll(token) => {
+                    token.duplicate()
+                },
+                _ => unreachable!("checked above"),
+            }
+        };
+
+        let Some(message_data) = message_data else {
+            // The requester gets a failure once all the copies of the token are dropped.
+            recorder.write(records::FailRequest);
+            drop(token);
+            recorder.write(records::EventFired(event_key.into()));
+            return Ok(vec![EventKey::Respond(event_key)]);
         };
 
         let responding_proxy = &mut self.proxies[proxy_key];
@@ -787,11 +2405,11 @@ impl Runner<'_> {
                 responding_proxy,
                 token,
                 marshalling,
-                &self.scopes[*scope_key],
+                &bindings,
                 message_data.clone(),
             )
             .await
-            .map_err(RunError::Marshalling)?;
+            .map_err(RunErrorReason::Marshalling)?;
 
         recorder.write(records::EventFired(event_key.into()));
         Ok(vec![EventKey::Respond(event_key)])
@@ -804,18 +2422,88 @@ impl<'a> Runner<'a> {
         blueprint: Blueprint,
         config: C,
         root_scope_values: HashMap<String, serde_json::Value>,
+        options: RunOptions,
     ) -> Self
     where
         C: for<'de> serde::de::Deserializer<'de>,
     {
-        let main_proxy = elfo::test::proxy(blueprint, config).await;
+        let t_start = std::time::Instant::now();
+        let mut logs = logs::LogCapture::install();
+        #[cfg(feature = "metrics")]
+        let mut metrics = telemetry::MetricsCapture::install();
+
+        let mut scopes: SecondaryMap<KeyScope, bindings::Scope> = executable
+            .scopes
+            .iter()
+            .map(|(key, info)| {
+                let values = info.values.clone().into_iter().collect();
+                let scope = Scope::from_values(values)
+                    .with_invariants(info.invariants.iter().cloned())
+                    .with_types(info.types.clone());
+                (key, scope)
+            })
+            .collect();
+
+        let root_scope_info = &executable.scopes[executable.root_scope_key];
+        let mut start_error = root_scope_values.iter().find_map(|(name, supplied)| {
+            let declared = root_scope_info.types.get(name)?;
+            (!declared.admits(supplied)).then(|| {
+                RunErrorReason::SuppliedTypeMismatch {
+                    name:     name.clone(),
+                    declared: *declared,
+                    supplied: supplied.clone(),
+                }
+            })
+        });
+        let root_scope_values = root_scope_info
+            .values
+            .clone()
+            .into_iter()
+            .chain(root_scope_values)
+            .collect();
+        let root_scope: Scope = Scope::from_values(root_scope_values)
+            .with_invariants(root_scope_info.invariants.iter().cloned())
+            .with_types(root_scope_info.types.clone());
+        scopes.insert(executable.root_scope_key, root_scope);
+
+        let main_proxy = match executable.config.as_ref() {
+            None => elfo::test::proxy(blueprint, config).await,
+            Some(template) => {
+                let config = match Value::deserialize(config) {
+                    Ok(config) => config,
+                    Err(reason) => {
+                        start_error.get_or_insert(RunErrorReason::Config(reason.to_string()));
+                        Value::Null
+                    },
+                };
+                let config =
+                    match bindings::render(template.clone(), &scopes[executable.root_scope_key]) {
+                        Ok(rendered) => merge_config(config, rendered),
+                        Err(reason) => {
+                            start_error.get_or_insert(RunErrorReason::Config(reason.to_string()));
+                            config
+                        },
+                    };
+                elfo::test::proxy(blueprint, config).await
+            },
+        };
+
+        logs.claim(main_proxy.node_launch_id());
+        #[cfg(feature = "metrics")]
+        metrics.claim(main_proxy.node_launch_id());
 
+        let groups = topology_groups(main_proxy.addr());
         let mut proxies: SlotMap<ProxyKey, Proxy> = Default::default();
         let main_proxy_key = proxies.insert(main_proxy);
 
-        let mut receives_and_delays = ReceivesAndDelays::default();
+        let mut receives_and_delays = ReceivesAndDelays::new(options.time_scale);
 
         let ready_events = executable.events.entry_points.clone();
+        let mut trail = audit::Trail::default();
+        ready_events
+            .iter()
+            .copied()
+            .for_each(|k| trail.became_ready(k));
 
         let now = Instant::now();
         for k in ready_events.iter().copied() {
@@ -848,21 +2536,38 @@ impl<'a> Runner<'a> {
                 },
             );
 
-        let mut scopes: SecondaryMap<KeyScope, bindings::Scope> = executable
-            .scopes
-            .iter()
-            .map(|(key, _info)| (key, Default::default()))
-            .collect();
-
-        let root_scope: Scope = Scope::from_values(root_scope_values);
-        scopes.insert(executable.root_scope_key, root_scope);
-
+        let mut stats = RunStats::default();
         let mut dummies = SecondaryMap::default();
+        let mut dummy_addrs = SecondaryMap::default();
         for dummy_key in executable.dummies.keys() {
             let dummy_proxy = proxies[main_proxy_key].subproxy().await;
             let dummy_proxy_key = proxies.insert(dummy_proxy);
             dummies.insert(dummy_key, dummy_proxy_key);
+
+            let dummy_addr = proxies[dummy_proxy_key].addr();
+            dummy_addrs.insert(dummy_key, dummy_addr);
+            for (known_in, dummy_name) in executable.dummies[dummy_key].known_as.iter() {
+                scopes[known_in].bind_dummy(dummy_name.clone(), dummy_addr);
+            }
+        }
+        stats.initial_dummies = dummies.len();
+        for (topic, dummy_key) in executable.system_messages.subscriptions.iter() {
+            let proxy = &mut proxies[dummies[*dummy_key]];
+            match topic {
+                DefSystemTopic::ActorStatuses => {
+                    let () = proxy.send(SubscribeToActorStatuses::default()).await;
+                },
+            }
         }
+        stats.start_time = t_start.elapsed();
+
+        let responds_pending = executable.events.respond.values().fold(
+            HashMap::<KeyRecv, usize>::new(),
+            |mut acc, respond| {
+                *acc.entry(respond.respond_to).or_default() += 1;
+                acc
+            },
+        );
 
         Self {
             executable,
@@ -872,9 +2577,161 @@ impl<'a> Runner<'a> {
             main_proxy_key,
             proxies,
             actors: Default::default(),
+            groups,
             dummies,
+            dummy_addrs,
+            turns: Default::default(),
             scopes,
             envelopes: Default::default(),
+            requests: Default::default(),
+            responses: Default::default(),
+            responds_pending,
+            delayed_sends: Default::default(),
+            cancellation: Default::default(),
+            stats,
+            options,
+            audit: false,
+            trail,
+            scheduling: None,
+            faults_rng: SplitMix64(executable.faults_seed),
+            send_rngs: Default::default(),
+            unmatched: Default::default(),
+            recv_counts: Default::default(),
+            arrivals: Default::default(),
+            recv_appended: Default::default(),
+            recv_last_matches: Default::default(),
+            logs,
+            logs_matched: Default::default(),
+            #[cfg(feature = "metrics")]
+            metrics,
+            #[cfg(feature = "metrics")]
+            metric_baselines: Default::default(),
+            current_event: None,
+            progress: None,
+            start_error,
+        }
+    }
+}
+
+/// Lays the `over` config onto the `base` one, key by key.
+/// Numbers the groups of the topology the `tester` is in: those are numbered
+/// in the order they are added, and the testers' group is one of them.
+fn topology_groups(tester: Addr) -> HashMap<GroupNo, &'static str> {
+    let testers_no = tester.group_no().expect("the proxy is local").into_bits();
+    // `system.testers` is the second one
+    let first_no = testers_no - 1;
+    TOPOLOGY_GROUPS
+        .iter()
+        .zip(first_no..=u8::MAX)
+        .filter_map(|(name, no)| Some((GroupNo::from_bits(no)?, *name)))
+        .collect()
+}
+
+fn merge_config(base: Value, over: Value) -> Value {
+    match (base, over) {
+        (Value::Object(mut base), Value::Object(over)) => {
+            for (key, value) in over {
+                let merged = match base.remove(&key) {
+                    Some(below) => merge_config(below, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        },
+        (_, over) => over,
+    }
+}
+
+/// A tiny PRNG: good enough to shuffle the events, and stable across the
+/// versions of the dependencies, so that a seed keeps reproducing a run.
+#[derive(Debug, Clone, Copy)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a number in `0.0..1.0`.
+    fn fraction(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns `true` with the probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        self.fraction() < p
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
         }
     }
 }
+
+fn violated(
+    events: &Events,
+    violation: bindings::Violation,
+    attempted_by: EventKey,
+) -> RunErrorReason {
+    let name_of = |k: EventKey| events.names.get(&k).map(|(_, n)| n.clone());
+    let attempted_by = name_of(attempted_by).expect("every bind and recv event is named");
+
+    match violation {
+        bindings::Violation::Invariant(bindings::InvariantViolation {
+            name,
+            bound,
+            bound_by,
+            attempted,
+        }) => {
+            RunErrorReason::InvariantViolated {
+                name,
+                bound,
+                bound_by: bound_by.and_then(name_of),
+                attempted,
+                attempted_by,
+            }
+        },
+        bindings::Violation::Type(bindings::TypeMismatch {
+            name,
+            declared,
+            attempted,
+        }) => {
+            RunErrorReason::TypeMismatch {
+                name,
+                declared,
+                attempted,
+                attempted_by,
+            }
+        },
+    }
+}
+
+/// Tells the failures of the requests from the errors of the marshalling of
+/// their responses.
+fn request_failed(reason: marshalling::AnError) -> RunErrorReason {
+    match reason.downcast::<elfo::errors::RequestError>() {
+        Ok(failed) => RunErrorReason::RequestFailed(*failed),
+        Err(reason) => RunErrorReason::Marshalling(reason),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<non-string panic payload>".to_owned()
+    }
+}
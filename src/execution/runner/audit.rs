@@ -0,0 +1,224 @@
+//! A self-check of the [Runner](super::Runner) performed after a run.
+//!
+//! The audit does not judge the scenario: it verifies the runner's own
+//! bookkeeping. Any violation found here is a bug in luci.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+use slotmap::SecondaryMap;
+
+use crate::bindings;
+use crate::execution::{BindScope, EventKey, Events, KeyScope};
+use crate::scenario::SrcMsg;
+
+/// What the runner has done, as far as the audit is concerned.
+#[derive(Debug, Default)]
+pub(super) struct Trail {
+    ever_ready: HashSet<EventKey>,
+    fired:      Vec<EventKey>,
+
+    envelopes_received:  usize,
+    envelopes_matched:   HashSet<usize>,
+    envelopes_unmatched: HashSet<usize>,
+}
+
+impl Trail {
+    pub(super) fn became_ready(&mut self, event_key: EventKey) {
+        self.ever_ready.insert(event_key);
+    }
+
+    pub(super) fn fired(&mut self, event_keys: impl IntoIterator<Item = EventKey>) {
+        self.fired.extend(event_keys);
+    }
+
+    /// Returns the sequence number of the received envelope.
+    pub(super) fn envelope_received(&mut self) -> usize {
+        self.envelopes_received += 1;
+        self.envelopes_received
+    }
+
+    pub(super) fn envelope_matched(&mut self, seq_no: usize) {
+        self.envelopes_matched.insert(seq_no);
+    }
+
+    pub(super) fn envelope_unmatched(&mut self, seq_no: usize) {
+        self.envelopes_unmatched.insert(seq_no);
+    }
+
+    /// Checks the trail against the `events` graph and the resulting `scopes`.
+    ///
+    /// Returns the list of violations found.
+    pub(super) fn check(
+        &self,
+        events: &Events,
+        scopes: &SecondaryMap<KeyScope, bindings::Scope>,
+    ) -> Vec<String> {
+        let name_of = |k: EventKey| {
+            events
+                .names
+                .get(&k)
+                .map(|(_, n)| n.to_string())
+                .unwrap_or_else(|| format!("{:?}", k))
+        };
+
+        let mut prerequisites: HashMap<EventKey, Vec<EventKey>> = HashMap::new();
+        for (prereq, dependants) in events.key_unblocks_values.iter() {
+            for dependant in dependants {
+                prerequisites.entry(*dependant).or_default().push(*prereq);
+            }
+        }
+
+        let mut violations = vec![];
+        let mut fired_at = HashMap::new();
+
+        for (idx, event_key) in self.fired.iter().copied().enumerate() {
+            if !self.ever_ready.contains(&event_key) {
+                violations.push(format!(
+                    "{} has fired without ever being ready",
+                    name_of(event_key)
+                ));
+            }
+            for prereq in prerequisites.get(&event_key).into_iter().flatten() {
+                if !fired_at.contains_key(prereq) {
+                    violations.push(format!(
+                        "{} has fired before its prerequisite {}",
+                        name_of(event_key),
+                        name_of(*prereq)
+                    ));
+                }
+            }
+            if fired_at.insert(event_key, idx).is_some() {
+                violations.push(format!("{} has fired more than once", name_of(event_key)));
+            }
+        }
+
+        let mut both = self
+            .envelopes_matched
+            .intersection(&self.envelopes_unmatched)
+            .collect::<Vec<_>>();
+        both.sort();
+        for seq_no in both {
+            violations.push(format!(
+                "envelope #{} is both matched and reported unmatched",
+                seq_no
+            ));
+        }
+
+        for (scope_key, scope) in scopes.iter() {
+            for (name, bound_by) in scope.provenance() {
+                if !fired_at.contains_key(&bound_by) {
+                    violations.push(format!(
+                        "{} in {:?} is bound by {}, which has never fired",
+                        name,
+                        scope_key,
+                        name_of(bound_by)
+                    ));
+                }
+            }
+        }
+
+        // A value may only be derived from the values bound earlier.
+        for (&event_key, &idx) in fired_at.iter() {
+            let Some((scope_key, template)) = template_of(events, event_key) else {
+                continue;
+            };
+            let Some(scope) = scopes.get(scope_key) else {
+                continue;
+            };
+            let mut names = vec![];
+            variables(template, &mut names);
+            for name in names {
+                let Some(bound_by) = scope.bound_by(name) else {
+                    continue;
+                };
+                if fired_at.get(&bound_by).is_none_or(|at| *at >= idx) {
+                    violations.push(format!(
+                        "{} uses {}, which is bound by {} that has not fired before it",
+                        name_of(event_key),
+                        name,
+                        name_of(bound_by)
+                    ));
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// Returns the template the event renders, along with the scope it is rendered
+/// in.
+fn template_of(events: &Events, event_key: EventKey) -> Option<(KeyScope, &Value)> {
+    let (scope_key, src) = match event_key {
+        EventKey::Bind(k) => {
+            let bind = &events.bind[k];
+            let scope_key = match bind.scope {
+                BindScope::Same(scope_key) => scope_key,
+                BindScope::Two { src, .. } => src,
+            };
+            (scope_key, &bind.src)
+        },
+        EventKey::Send(k) => (events.send[k].scope_key, &events.send[k].payload),
+        EventKey::Respond(k) => {
+            let respond = &events.respond[k];
+            (respond.scope_key, respond.payload.as_ref()?)
+        },
+        EventKey::Recv(_) | EventKey::Delay(_) | EventKey::StopDummy(_) => return None,
+    };
+    match src {
+        SrcMsg::Bind(template) => Some((scope_key, template)),
+        SrcMsg::Literal(_) | SrcMsg::Inject(_) => None,
+    }
+}
+
+fn variables<'a>(template: &'a Value, acc: &mut Vec<&'a str>) {
+    match template {
+        Value::String(wildcard) if wildcard == "$_" => (),
+        Value::String(var_name) if var_name.starts_with('$') => acc.push(var_name),
+        Value::Array(items) => items.iter().for_each(|item| variables(item, acc)),
+        Value::Object(kv) => kv.values().for_each(|v| variables(v, acc)),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::KeyDelay;
+
+    #[test]
+    fn clean_trail_passes() {
+        let mut trail = Trail::default();
+        let key = EventKey::Delay(KeyDelay::default());
+        trail.became_ready(key);
+        trail.fired([key]);
+        let seq_no = trail.envelope_received();
+        trail.envelope_matched(seq_no);
+        let seq_no = trail.envelope_received();
+        trail.envelope_unmatched(seq_no);
+
+        assert!(trail
+            .check(&Events::default(), &Default::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn violations_are_reported() {
+        let mut trail = Trail::default();
+        let key = EventKey::Delay(KeyDelay::default());
+        trail.fired([key, key]);
+        let seq_no = trail.envelope_received();
+        trail.envelope_matched(seq_no);
+        trail.envelope_unmatched(seq_no);
+
+        let violations = trail.check(&Events::default(), &Default::default());
+        assert_eq!(violations.len(), 4, "{:#?}", violations);
+        assert!(violations[0].ends_with("has fired without ever being ready"));
+        assert!(violations[2].ends_with("has fired more than once"));
+        assert_eq!(
+            violations[3],
+            "envelope #1 is both matched and reported unmatched"
+        );
+    }
+}
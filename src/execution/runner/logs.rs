@@ -0,0 +1,253 @@
+//! Capturing the lines logged by the actors under test.
+//!
+//! Each thread a run is started on gets its default dispatcher replaced, once
+//! and for good, with one that notes the events down, and passes everything
+//! on to the dispatcher it has replaced: the logs still go wherever they went
+//! before.
+//!
+//! A line is noted down for the run whose topology the logging actor belongs
+//! to, told by the [launch id](elfo::test::Proxy::node_launch_id) of the
+//! topology: the runs sharing a thread do not see each other's lines. The
+//! dispatcher is the thread's default one, hence only the lines logged on the
+//! threads the runs are started on are captured: the actors are to be run by
+//! the same current-thread runtime as the runner (e.g., `#[tokio::test]`).
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+
+use elfo::addr::NodeLaunchId;
+use parking_lot::{const_mutex, Mutex};
+use serde_json::{Map, Value};
+use tracing::dispatcher::DefaultGuard;
+use tracing::field::{Field, Visit};
+use tracing::span::{self, Attributes, Id};
+use tracing::subscriber::Interest;
+use tracing::{Dispatch, Event, Level, Metadata, Subscriber};
+
+use crate::execution::LogLine;
+
+/// The luci's own lines are not captured: those of the target `luci` and of
+/// its modules, `luci::*`, but not of `lucid`.
+const OWN_TARGET: &str = "luci";
+
+/// The captures of the runs in progress, shared by all the threads.
+static CAPTURES: Mutex<Captures> = const_mutex(Captures {
+    claimed:   Vec::new(),
+    unclaimed: 0,
+    pending:   Vec::new(),
+});
+
+thread_local! {
+    /// Set once per thread, kept until the thread exits.
+    static TEE: RefCell<Option<DefaultGuard>> = const { RefCell::new(None) };
+}
+
+/// The lines captured so far; the capture lasts as long as this is alive.
+pub(super) struct LogCapture {
+    lines:     Arc<Mutex<Vec<LogLine>>>,
+    launch_id: Option<NodeLaunchId>,
+}
+
+struct Captures {
+    claimed:   Vec<(NodeLaunchId, Arc<Mutex<Vec<LogLine>>>)>,
+    /// The number of captures yet to learn the launch id of their topology.
+    unclaimed: usize,
+    /// The lines logged while the topologies are being started, kept until
+    /// the captures of those claim them.
+    pending:   Vec<(NodeLaunchId, LogLine)>,
+}
+
+struct Tee {
+    inner: Dispatch,
+}
+
+impl LogCapture {
+    /// Starts capturing; the lines are attributed to the run once it has
+    /// [claimed](Self::claim) the topology they are logged in.
+    pub(super) fn install() -> Self {
+        TEE.with(|tee| {
+            tee.borrow_mut().get_or_insert_with(|| {
+                let inner = tracing::dispatcher::get_default(Dispatch::clone);
+                tracing::dispatcher::set_default(&Dispatch::new(Tee { inner }))
+            });
+        });
+        CAPTURES.lock().unclaimed += 1;
+        Self {
+            lines:     Default::default(),
+            launch_id: None,
+        }
+    }
+
+    /// Takes the lines logged in the topology launched as `launch_id`,
+    /// including those logged before this call.
+    pub(super) fn claim(&mut self, launch_id: NodeLaunchId) {
+        assert!(self.launch_id.is_none(), "claimed twice");
+        self.launch_id = Some(launch_id);
+
+        let mut captures = CAPTURES.lock();
+        captures.unclaimed -= 1;
+        let (own, others) = std::mem::take(&mut captures.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(id, _)| *id == launch_id);
+        if captures.unclaimed > 0 {
+            captures.pending = others;
+        }
+        self.lines
+            .lock()
+            .extend(own.into_iter().map(|(_, line)| line));
+        captures.claimed.push((launch_id, self.lines.clone()));
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.lines.lock().len()
+    }
+
+    pub(super) fn get(&self, idx: usize) -> Option<LogLine> {
+        self.lines.lock().get(idx).cloned()
+    }
+
+    pub(super) fn take(&self) -> Vec<LogLine> {
+        std::mem::take(&mut *self.lines.lock())
+    }
+}
+
+impl Drop for LogCapture {
+    fn drop(&mut self) {
+        let mut captures = CAPTURES.lock();
+        match self.launch_id {
+            Some(launch_id) => captures.claimed.retain(|(id, _)| *id != launch_id),
+            None => captures.unclaimed -= 1,
+        }
+        if captures.unclaimed == 0 {
+            captures.pending.clear();
+        }
+    }
+}
+
+impl Captures {
+    fn note(&mut self, launch_id: NodeLaunchId, line: LogLine) {
+        if let Some((_, lines)) = self.claimed.iter().find(|(id, _)| *id == launch_id) {
+            lines.lock().push(line);
+        } else if self.unclaimed > 0 {
+            self.pending.push((launch_id, line));
+        }
+    }
+}
+
+fn captures(metadata: &Metadata<'_>) -> bool {
+    let own = metadata
+        .target()
+        .strip_prefix(OWN_TARGET)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"));
+    metadata.is_event() && *metadata.level() <= Level::DEBUG && !own
+}
+
+impl Subscriber for Tee {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        let interest = self.inner.register_callsite(metadata);
+        if captures(metadata) {
+            Interest::always()
+        } else {
+            interest
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        captures(metadata) || self.inner.enabled(metadata)
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.inner.new_span(span)
+    }
+
+    fn record(&self, span: &Id, values: &span::Record<'_>) {
+        self.inner.record(span, values)
+    }
+
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.inner.record_follows_from(span, follows)
+    }
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let launch_id = elfo::scope::try_with(|scope| scope.node_launch_id());
+        if let Some(launch_id) = launch_id.filter(|_| captures(metadata)) {
+            let mut visitor = LineVisitor::default();
+            event.record(&mut visitor);
+            let line = LogLine {
+                level:   *metadata.level(),
+                target:  metadata.target().to_owned(),
+                message: visitor.message,
+                fields:  visitor.fields,
+            };
+            CAPTURES.lock().note(launch_id, line);
+        }
+        if self.inner.enabled(metadata) {
+            self.inner.event(event)
+        }
+    }
+
+    fn enter(&self, span: &Id) {
+        self.inner.enter(span)
+    }
+
+    fn exit(&self, span: &Id) {
+        self.inner.exit(span)
+    }
+
+    fn clone_span(&self, id: &Id) -> Id {
+        self.inner.clone_span(id)
+    }
+
+    fn try_close(&self, id: Id) -> bool {
+        self.inner.try_close(id)
+    }
+
+    fn current_span(&self) -> tracing_core::span::Current {
+        self.inner.current_span()
+    }
+}
+
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields:  Map<String, Value>,
+}
+
+impl LineVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        match value {
+            Value::String(message) if field.name() == "message" => self.message = message,
+            value => {
+                self.fields.insert(field.name().to_owned(), value);
+            },
+        }
+    }
+}
+
+impl Visit for LineVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into())
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into())
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into())
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into())
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into())
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into())
+    }
+}
@@ -0,0 +1,209 @@
+//! Capturing the metrics recorded by the actors under test.
+//!
+//! The actors record the metrics with the [`metrics`](https://docs.rs/metrics/0.17)
+//! facade of the version elfo records its own ones with (0.17). The recorder
+//! of that facade is a global one: the first capture sets it to one keeping
+//! the counters and the gauges in memory, unless another recorder (e.g.,
+//! elfo's telemeter) has been set before — then nothing is captured. The
+//! metrics recorded with the other versions of the facade are not observed.
+//!
+//! As with the logs, a metric is captured for the run whose topology the
+//! recording actor belongs to; those recorded outside of the actors are not.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Once};
+
+use elfo::addr::NodeLaunchId;
+use metrics_017::{GaugeValue, Key, Recorder, Unit};
+use parking_lot::{const_mutex, Mutex};
+
+use crate::execution::{MetricCheck, MetricKind};
+
+/// The recorder is borrowed by the facade for good: all the captures share
+/// this one, which passes the metrics on to the capture of the topology they
+/// are recorded in.
+static TAP: Tap = Tap {
+    stores: const_mutex(Stores {
+        claimed:   Vec::new(),
+        unclaimed: 0,
+        pending:   Vec::new(),
+    }),
+};
+
+/// The metrics captured so far; the capture lasts as long as this is alive.
+pub(super) struct MetricsCapture {
+    store:     Arc<Store>,
+    launch_id: Option<NodeLaunchId>,
+}
+
+struct Tap {
+    stores: Mutex<Stores>,
+}
+
+struct Stores {
+    claimed:   Vec<(NodeLaunchId, Arc<Store>)>,
+    /// The number of captures yet to learn the launch id of their topology.
+    unclaimed: usize,
+    /// The metrics recorded while the topologies are being started, kept
+    /// until the captures of those claim them.
+    pending:   Vec<(NodeLaunchId, Arc<Store>)>,
+}
+
+#[derive(Default)]
+struct Store {
+    counters: Mutex<HashMap<Key, u64>>,
+    gauges:   Mutex<HashMap<Key, f64>>,
+}
+
+impl MetricsCapture {
+    /// Starts capturing; the metrics are attributed to the run once it has
+    /// [claimed](Self::claim) the topology they are recorded in.
+    pub(super) fn install() -> Self {
+        static SET_RECORDER: Once = Once::new();
+        SET_RECORDER.call_once(|| {
+            if metrics_017::set_recorder(&TAP).is_err() {
+                tracing::warn!("another metrics recorder is set, the metrics are not captured");
+            }
+        });
+        TAP.stores.lock().unclaimed += 1;
+        Self {
+            store:     Default::default(),
+            launch_id: None,
+        }
+    }
+
+    /// Takes the metrics recorded in the topology launched as `launch_id`,
+    /// including those recorded before this call.
+    pub(super) fn claim(&mut self, launch_id: NodeLaunchId) {
+        assert!(self.launch_id.is_none(), "claimed twice");
+        self.launch_id = Some(launch_id);
+
+        let mut stores = TAP.stores.lock();
+        stores.unclaimed -= 1;
+        if let Some(idx) = stores.pending.iter().position(|(id, _)| *id == launch_id) {
+            self.store = stores.pending.swap_remove(idx).1;
+        }
+        if stores.unclaimed == 0 {
+            stores.pending.clear();
+        }
+        stores.claimed.push((launch_id, self.store.clone()));
+    }
+
+    /// The values of the series of the metric: the single sum of them for a
+    /// counter, each of them for a gauge.
+    pub(super) fn observe(&self, check: &MetricCheck) -> Vec<f64> {
+        let matches = |key: &Key| {
+            key.name() == check.name
+                && check.labels.iter().all(|(name, value)| {
+                    key.labels()
+                        .any(|label| label.key() == name && label.value() == value)
+                })
+        };
+        match check.kind {
+            MetricKind::Counter => {
+                let sum = self
+                    .store
+                    .counters
+                    .lock()
+                    .iter()
+                    .filter(|(key, _)| matches(key))
+                    .map(|(_, counter)| *counter)
+                    .sum::<u64>();
+                vec![sum as f64]
+            },
+            MetricKind::Gauge => {
+                self.store
+                    .gauges
+                    .lock()
+                    .iter()
+                    .filter(|(key, _)| matches(key))
+                    .map(|(_, gauge)| *gauge)
+                    .collect()
+            },
+        }
+    }
+}
+
+impl Drop for MetricsCapture {
+    fn drop(&mut self) {
+        let mut stores = TAP.stores.lock();
+        match self.launch_id {
+            Some(launch_id) => stores.claimed.retain(|(id, _)| *id != launch_id),
+            None => stores.unclaimed -= 1,
+        }
+        if stores.unclaimed == 0 {
+            stores.pending.clear();
+        }
+    }
+}
+
+impl MetricCheck {
+    /// Whether any of the `observed` values, less the `baseline`, is within
+    /// the bounds.
+    pub(super) fn is_met(&self, observed: &[f64], baseline: f64) -> bool {
+        observed.iter().any(|value| {
+            let value = value - baseline;
+            self.at_least.is_none_or(|at_least| value >= at_least)
+                && self.at_most.is_none_or(|at_most| value <= at_most)
+        })
+    }
+}
+
+impl Tap {
+    /// The store of the topology of the actor the metric is recorded by.
+    fn store(&self) -> Option<Arc<Store>> {
+        let launch_id = elfo::scope::try_with(|scope| scope.node_launch_id())?;
+        let mut stores = self.stores.lock();
+        if let Some((_, store)) = stores.claimed.iter().find(|(id, _)| *id == launch_id) {
+            return Some(store.clone())
+        }
+        if stores.unclaimed == 0 {
+            return None
+        }
+        let store = match stores.pending.iter().find(|(id, _)| *id == launch_id) {
+            Some((_, store)) => store.clone(),
+            None => {
+                let store = Arc::<Store>::default();
+                stores.pending.push((launch_id, store.clone()));
+                store
+            },
+        };
+        Some(store)
+    }
+}
+
+impl Recorder for Tap {
+    fn register_counter(
+        &self,
+        _key: &Key,
+        _unit: Option<Unit>,
+        _description: Option<&'static str>,
+    ) {
+    }
+
+    fn register_gauge(&self, _key: &Key, _unit: Option<Unit>, _description: Option<&'static str>) {}
+
+    fn register_histogram(
+        &self,
+        _key: &Key,
+        _unit: Option<Unit>,
+        _description: Option<&'static str>,
+    ) {
+    }
+
+    fn increment_counter(&self, key: &Key, value: u64) {
+        if let Some(store) = self.store() {
+            *store.counters.lock().entry(key.clone()).or_default() += value;
+        }
+    }
+
+    fn update_gauge(&self, key: &Key, value: GaugeValue) {
+        if let Some(store) = self.store() {
+            let mut gauges = store.gauges.lock();
+            let gauge = gauges.entry(key.clone()).or_default();
+            *gauge = value.update_value(*gauge);
+        }
+    }
+
+    fn record_histogram(&self, _key: &Key, _value: f64) {}
+}
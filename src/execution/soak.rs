@@ -0,0 +1,152 @@
+//! Running the same scenario over and over, to hunt the nondeterministic bugs.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use elfo::Blueprint;
+
+use crate::execution::outcome_table::OutcomeTable;
+use crate::execution::{EventKey, Executable, RunOptions};
+
+/// The aggregated outcome of [Executable::run_repeated].
+#[derive(Debug, Clone, Default)]
+pub struct SoakReport {
+    /// The number of runs performed.
+    pub runs:   usize,
+    /// The number of runs that have met all the requirements.
+    pub passed: usize,
+    /// The number of runs each requirement was not met in.
+    pub unmet:  HashMap<EventKey, usize>,
+    /// The errors the runs have failed with, if any.
+    pub errors: Vec<String>,
+
+    /// Wall-clock time it took to start the blueprint and the dummies, over
+    /// all the runs.
+    pub start_time: Duration,
+}
+
+impl Executable {
+    /// Runs the scenario `runs` times, each time with a fresh runner and a
+    /// fresh `blueprint`.
+    ///
+    /// A run failing with a [RunError](crate::execution::RunError) does not
+    /// stop the others: the error is recorded in the [SoakReport].
+    pub async fn run_repeated<B, C>(
+        &self,
+        runs: usize,
+        blueprint: B,
+        config: C,
+        root_scope_values: impl IntoIterator<Item = (String, serde_json::Value)>,
+    ) -> SoakReport
+    where
+        B: Fn() -> Blueprint,
+        C: for<'de> serde::de::Deserializer<'de> + Clone,
+    {
+        self.run_repeated_with_options(
+            runs,
+            blueprint,
+            config,
+            root_scope_values,
+            RunOptions::default(),
+        )
+        .await
+    }
+
+    /// Same as [Executable::run_repeated], but with the [RunOptions] other than
+    /// the default ones.
+    pub async fn run_repeated_with_options<B, C>(
+        &self,
+        runs: usize,
+        blueprint: B,
+        config: C,
+        root_scope_values: impl IntoIterator<Item = (String, serde_json::Value)>,
+        options: RunOptions,
+    ) -> SoakReport
+    where
+        B: Fn() -> Blueprint,
+        C: for<'de> serde::de::Deserializer<'de> + Clone,
+    {
+        let root_scope_values = root_scope_values.into_iter().collect::<Vec<_>>();
+        let mut soak = SoakReport::default();
+
+        for _ in 0..runs {
+            soak.runs += 1;
+            let report = match self
+                .start_with_options(
+                    blueprint(),
+                    config.clone(),
+                    root_scope_values.clone(),
+                    options,
+                )
+                .await
+                .run()
+                .await
+            {
+                Ok(report) => report,
+                Err(reason) => {
+                    soak.errors.push(reason.to_string());
+                    continue;
+                },
+            };
+
+            soak.start_time += report.stats.start_time;
+            if report.is_ok() {
+                soak.passed += 1;
+            }
+            for event_key in report.unmet_requirements() {
+                *soak.unmet.entry(event_key).or_default() += 1;
+            }
+        }
+
+        soak
+    }
+}
+
+impl SoakReport {
+    pub fn is_ok(&self) -> bool {
+        self.passed == self.runs
+    }
+
+    /// The share of the runs that have passed, from `0.0` to `1.0`.
+    pub fn pass_rate(&self) -> f64 {
+        if self.runs == 0 {
+            return 1.0
+        }
+        self.passed as f64 / self.runs as f64
+    }
+
+    pub fn message<'a>(&'a self, executable: &'a Executable) -> impl fmt::Display + 'a {
+        DisplaySoakReport {
+            soak: self,
+            executable,
+        }
+    }
+}
+
+struct DisplaySoakReport<'a> {
+    soak:       &'a SoakReport,
+    executable: &'a Executable,
+}
+
+impl fmt::Display for DisplaySoakReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { soak, executable } = self;
+
+        let mut table = OutcomeTable::new(f, "SOAK", soak.passed, soak.runs)?;
+        let mut unmet = soak.unmet.iter().collect::<Vec<_>>();
+        unmet.sort_by_key(|(ek, times)| (std::cmp::Reverse(**times), **ek));
+        for (&ek, times) in unmet {
+            let label = match executable.event_name(ek) {
+                Some((_, event_name)) => event_name.to_string(),
+                None => format!("{ek:?}"),
+            };
+            table.failed(format_args!("{label}: unmet {times} time(s)"))?;
+        }
+        for error in soak.errors.iter() {
+            table.error(error)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! When the events of a run became ready, and when they fired or timed out:
+//! see [Report::timeline].
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::execution::cast::scope_path;
+use crate::execution::{EventKey, Executable, Report};
+use crate::names::EventName;
+use crate::recorder::{records as r, RecordKind};
+
+#[derive(Debug, Clone, Default)]
+pub struct Timeline {
+    /// The events that have become ready, in the order they did.
+    pub bars:     Vec<TimelineBar>,
+    /// The time the last record has been written at.
+    pub run_time: Duration,
+}
+
+/// An event, from the moment all its prerequisites have fired.
+#[derive(Debug, Clone)]
+pub struct TimelineBar {
+    pub event_key: EventKey,
+    pub name:      EventName,
+    /// The path of the scope, as in [CastScope](crate::execution::CastScope).
+    pub scope:     String,
+    pub ready_at:  Duration,
+    pub end:       TimelineEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineEnd {
+    Fired(Duration),
+    TimedOut(Duration),
+    /// Neither fired, nor timed out by the end of the run.
+    Pending,
+}
+
+impl Report {
+    /// Lays the events of the run out on the runtime's clock, which is the
+    /// simulated one, if the time is paused.
+    ///
+    /// An event becomes ready once the last of its prerequisites has fired;
+    /// the events that have never become ready are left out.
+    pub fn timeline(&self, executable: &Executable) -> Timeline {
+        let log = &self.record_log;
+        let rt_zero = log.t_zero.1;
+
+        let mut timed_out = HashMap::new();
+        let mut run_time = Duration::ZERO;
+        for record in log.records.values() {
+            let at = record.at.1.duration_since(rt_zero);
+            run_time = run_time.max(at);
+            if let RecordKind::TimedOutRecvKey(r::TimedOutRecvKey(k)) = &record.kind {
+                timed_out.entry(EventKey::Recv(*k)).or_insert(at);
+            }
+        }
+
+        let key_requires_value = executable.key_requires_value();
+        let mut bars = executable
+            .events
+            .names
+            .iter()
+            .filter_map(|(event_key, (scope_key, name))| {
+                let ready_at = match key_requires_value.get(event_key) {
+                    None => Duration::ZERO,
+                    Some(prerequisites) => {
+                        prerequisites
+                            .iter()
+                            .map(|k| self.fired_at.get(k).map(|at| at.runtime))
+                            .try_fold(Duration::ZERO, |acc, at| Some(acc.max(at?)))?
+                    },
+                };
+                let end = match (self.fired_at.get(event_key), timed_out.get(event_key)) {
+                    (Some(at), _) => TimelineEnd::Fired(at.runtime),
+                    (None, Some(at)) => TimelineEnd::TimedOut(*at),
+                    (None, None) => TimelineEnd::Pending,
+                };
+                Some(TimelineBar {
+                    event_key: *event_key,
+                    name: name.clone(),
+                    scope: scope_path(&executable.scopes, *scope_key),
+                    ready_at,
+                    end,
+                })
+            })
+            .collect::<Vec<_>>();
+        bars.sort_by_key(|bar| {
+            (
+                bar.ready_at,
+                executable
+                    .events
+                    .priority
+                    .get(&bar.event_key)
+                    .map(|(_, p)| *p),
+            )
+        });
+
+        Timeline { bars, run_time }
+    }
+}
@@ -0,0 +1,93 @@
+//! The static topology of an [`Executable`]: every event, the scope it is
+//! defined in, and the edges between the events.
+
+use crate::execution::{EventKey, Executable, KeyScope};
+use crate::names::EventName;
+use crate::scenario::RequiredToBe;
+
+#[derive(Debug, Clone)]
+pub struct Topology {
+    /// The events, in the order of their definition.
+    pub events: Vec<TopologyEvent>,
+
+    /// `(prerequisite, dependant)`: the dependant is not ready until the
+    /// prerequisite is reached.
+    pub edges: Vec<(EventKey, EventKey)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TopologyEvent {
+    pub key:         EventKey,
+    pub kind:        EventKind,
+    pub name:        EventName,
+    /// See [Cast::scopes](crate::execution::Cast::scopes).
+    pub scope:       KeyScope,
+    pub required:    Option<RequiredToBe>,
+    /// Whether the event is ready as soon as the run starts.
+    pub entry_point: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum EventKind {
+    #[display("bind")]
+    Bind,
+    #[display("send")]
+    Send,
+    #[display("recv")]
+    Recv,
+    #[display("respond")]
+    Respond,
+    #[display("delay")]
+    Delay,
+    #[display("stop_dummy")]
+    StopDummy,
+}
+
+impl EventKey {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::Bind(_) => EventKind::Bind,
+            Self::Send(_) => EventKind::Send,
+            Self::Recv(_) => EventKind::Recv,
+            Self::Respond(_) => EventKind::Respond,
+            Self::Delay(_) => EventKind::Delay,
+            Self::StopDummy(_) => EventKind::StopDummy,
+        }
+    }
+}
+
+impl Executable {
+    /// Describes the events of this executable and the edges between them.
+    pub fn topology(&self) -> Topology {
+        let mut events = self
+            .events
+            .names
+            .iter()
+            .map(|(&key, (scope, name))| {
+                TopologyEvent {
+                    key,
+                    kind: key.kind(),
+                    name: name.clone(),
+                    scope: *scope,
+                    required: self.events.required.get(&key).copied(),
+                    entry_point: self.events.entry_points.contains(&key),
+                }
+            })
+            .collect::<Vec<_>>();
+        events.sort_by_key(|e| (self.events.priority.get(&e.key).map(|(_, idx)| *idx), e.key));
+
+        let mut edges = self
+            .events
+            .key_unblocks_values
+            .iter()
+            .flat_map(|(&prerequisite, dependants)| {
+                dependants
+                    .iter()
+                    .map(move |&dependant| (prerequisite, dependant))
+            })
+            .collect::<Vec<_>>();
+        edges.sort();
+
+        Topology { events, edges }
+    }
+}
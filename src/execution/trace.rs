@@ -0,0 +1,86 @@
+//! A normalized summary of a run, meant to be compared against a snapshot.
+
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::execution::cast::scope_path;
+use crate::execution::{Executable, Report};
+use crate::recorder::{KeyRecord, RecordKind, RecordLog};
+
+impl Report {
+    /// Returns the events in the order they have fired, one per line, along
+    /// with the digests of the payloads they have sent or matched.
+    ///
+    /// Neither the timings nor the addresses are included: the trace of a
+    /// deterministic run is the same every time, and thus can be stored as an
+    /// `insta` snapshot to catch the changes in behaviour that the
+    /// requirements let through.
+    pub fn golden_trace(&self, executable: &Executable) -> String {
+        let mut trace = String::new();
+        let mut payload = None;
+        for root_key in self.record_log.roots.iter().copied() {
+            golden_trace(
+                &mut trace,
+                &mut payload,
+                &self.record_log,
+                root_key,
+                executable,
+            );
+        }
+        trace
+    }
+}
+
+fn golden_trace(
+    trace: &mut String,
+    payload: &mut Option<Value>,
+    log: &RecordLog,
+    this_key: KeyRecord,
+    executable: &Executable,
+) {
+    let record = &log.records[this_key];
+    match &record.kind {
+        RecordKind::ProcessEventClass(_)
+        | RecordKind::ProcessBindKey(_)
+        | RecordKind::ProcessSend(_)
+        | RecordKind::ProcessRespond(_)
+        | RecordKind::EnvelopeReceived(_)
+        | RecordKind::ResponseReceived(_) => *payload = None,
+        RecordKind::UsingValue(value) => *payload = Some(value.0.clone()),
+        RecordKind::UsingMsg(msg) => *payload = serde_json::to_value(&msg.0).ok(),
+        RecordKind::EventFired(fired) => {
+            let event_key = fired.0;
+            let (scope, event_name) = executable
+                .event_name(event_key)
+                .expect("every fired event has a name");
+            let _ = write!(
+                trace,
+                "{} {}:{}",
+                event_key.kind(),
+                scope_path(&executable.scopes, scope),
+                AsRef::<str>::as_ref(&event_name)
+            );
+            if let Some(payload) = payload.take() {
+                let _ = write!(trace, " #{:016x}", digest(&payload));
+            }
+            trace.push('\n');
+        },
+        _ => (),
+    }
+
+    for child_key in record.children.iter().copied() {
+        golden_trace(trace, payload, log, child_key, executable);
+    }
+}
+
+/// FNV-1a of the JSON text: unlike the std hashers, stable across the
+/// toolchains.
+fn digest(value: &Value) -> u64 {
+    value
+        .to_string()
+        .bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
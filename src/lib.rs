@@ -6,13 +6,27 @@
 //!
 //! We [run executables](crate::execution::Runner) to get
 //! [reports](crate::execution::Report).
+//!
+//! We run [suites](crate::suite::Suite) of scenarios found in a directory,
+//! or generate a test per scenario file with [`scenario_tests!`].
+//!
+//! We draft scenarios out of the traffic [recorded](crate::record::Recording)
+//! while driving a blueprint by hand.
 
 pub mod execution;
 pub mod marshalling;
+#[cfg(feature = "backward-compatibility")]
+pub mod migration;
 pub mod names;
+pub mod record;
 pub mod recorder;
 pub mod scenario;
+pub mod suite;
 pub mod visualization;
 
 mod bindings;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod sources;
+
+pub use luci_macros::scenario_tests;
@@ -1,7 +1,9 @@
+use std::any::Any;
 use std::collections::HashMap;
+use std::fmt;
 
 use elfo::test::Proxy;
-use elfo::{AnyMessage, AnyMessageRef, Envelope, Message, ResponseToken};
+use elfo::{Addr, AnyMessage, Envelope, Message, ResponseToken};
 use futures::future::LocalBoxFuture;
 use futures::FutureExt;
 use ghost::phantom;
@@ -11,6 +13,11 @@ use tracing::debug;
 use crate::bindings;
 use crate::scenario::{DstPattern, SrcMsg};
 
+#[cfg(feature = "json-schema")]
+pub(crate) mod schema;
+#[cfg(feature = "json-schema")]
+pub use schema::WithSchema;
+
 pub type AnError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 #[derive(Debug, Clone, Copy)]
@@ -31,6 +38,52 @@ pub struct Injected {
     pub value: AnyMessage,
 }
 
+/// Like [Injected], but the message is built by `injector` out of the values
+/// bound by the moment of injection.
+#[derive(derive_more::Debug)]
+pub struct InjectedWith<F> {
+    pub key:      String,
+    #[debug(skip)]
+    pub injector: F,
+}
+
+/// A native response to a request of type `Rq`, injected into the message flow
+/// by the `respond` events with `inject: <key>` data.
+///
+/// Useful for the responses that do not round-trip cleanly through JSON.
+pub struct InjectedResponse<Rq: elfo::Request> {
+    pub key:   String,
+    pub value: Rq::Response,
+}
+
+/// A read-only view of the values bound in the scope of an [injection].
+///
+/// [injection]: InjectedWith
+#[derive(Debug, Clone, Copy)]
+pub struct Bindings<'a>(&'a bindings::Scope);
+
+type Injector = Box<dyn Fn(Bindings<'_>) -> Result<AnyMessage, AnError>>;
+
+/// An injected value: either predefined, or built on demand, or a native
+/// response.
+enum Injection {
+    Value(AnyMessage),
+    Injector(Injector),
+    /// Makes a copy of the response for each of the respond events using it.
+    Response(Box<dyn Fn() -> Box<dyn Any>>),
+}
+
+/// A fallback marshaller observing the messages of the types not registered
+/// in the [MarshallingRegistry].
+///
+/// Such messages are matched as an object with the fields `protocol`, `name`
+/// and `payload`, the latter being the message serialized with serde. They
+/// cannot be sent.
+///
+/// Registered as `luci::marshalling::CatchAll`.
+#[derive(Debug, Clone, Copy)]
+pub struct CatchAll;
+
 // This one is used in the tests, that do not require to actually run their
 // scenarios, but instead just check the how build works.
 #[doc(hidden)]
@@ -51,10 +104,27 @@ pub struct Mock {
 #[derive(Default, derive_more::Debug)]
 pub struct MarshallingRegistry {
     #[debug(skip)]
-    values: HashMap<String, AnyMessage>,
+    values: HashMap<String, Injection>,
 
     #[debug(skip)]
     marshallers: HashMap<String, Box<dyn Marshal>>,
+
+    #[cfg(feature = "json-schema")]
+    #[debug(skip)]
+    schemas: HashMap<String, Value>,
+
+    #[cfg(feature = "json-schema")]
+    #[debug(skip)]
+    response_schemas: HashMap<String, Value>,
+}
+
+/// A description of a message registered in a [MarshallingRegistry].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageInfo<'a> {
+    pub fqn:           &'a str,
+    pub is_request:    bool,
+    /// The name of the response type, if known.
+    pub response_type: Option<&'static str>,
 }
 
 /// Registers self as to [MarshallingRegistry] to be used in marshalling.
@@ -88,6 +158,37 @@ pub(crate) trait Marshal {
     /// - dyn [DynRespond] to marshal [Msg]s as elfo responses
     /// - `None` in case [Marshal] implementer only send regular elfo messages
     fn response(&self) -> Option<&dyn DynRespond>;
+
+    /// Returns true if `envelope` contains a message of the type this [Marshal]
+    /// implementer is registered for.
+    fn is_type_of(&self, envelope: &Envelope) -> bool;
+
+    /// Returns the name of the response type, if [Marshal] implementer knows
+    /// it.
+    fn response_type(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns true if [Marshal] implementer should only handle the messages
+    /// no other marshaller is registered for.
+    fn is_fallback(&self) -> bool {
+        false
+    }
+
+    /// Binds values in `msg` with `bindings` and issues it as elfo request
+    /// from `proxy`, either to `to` or via routing.
+    ///
+    /// Returns a future resolving to the serialized response.
+    fn request(
+        &self,
+        _proxy: Proxy,
+        _to: Option<Addr>,
+        _marshalling: &MarshallingRegistry,
+        _bindings: &bindings::Scope,
+        _msg: SrcMsg,
+    ) -> Result<LocalBoxFuture<'static, Result<Value, AnError>>, AnError> {
+        Err("not a request".into())
+    }
 }
 
 /// Marshals [Msg] to [Proxy] as elfo response.
@@ -125,10 +226,109 @@ impl MarshallingRegistry {
         self.marshallers.get(fqn).map(AsRef::as_ref)
     }
 
-    /// Retrieves predefined [AnyMessage] by `key` to inject into the elfo
-    /// message flow.
-    pub(crate) fn value(&self, key: &str) -> Option<AnyMessageRef> {
-        self.values.get(key).map(|am| am.as_ref())
+    /// Returns true if `envelope` contains a message of the type registered
+    /// under `fqn`.
+    ///
+    /// A [fallback](CatchAll) marshaller only accepts the messages of the
+    /// types no other marshaller is registered for.
+    pub(crate) fn is_type_of(&self, fqn: &str, envelope: &Envelope) -> bool {
+        let Some(marshaller) = self.resolve(fqn) else {
+            return false;
+        };
+        if marshaller.is_fallback() {
+            !self
+                .marshallers
+                .values()
+                .any(|m| !m.is_fallback() && m.is_type_of(envelope))
+        } else {
+            marshaller.is_type_of(envelope)
+        }
+    }
+
+    /// Finds the FQN of the type of the message in `envelope`, among the ones
+    /// registered by a non-fallback marshaller.
+    pub(crate) fn type_of(&self, envelope: &Envelope) -> Option<&str> {
+        let mut fqns = self
+            .marshallers
+            .iter()
+            .filter(|(_, m)| !m.is_fallback() && m.is_type_of(envelope))
+            .map(|(fqn, _)| fqn.as_str())
+            .collect::<Vec<_>>();
+        fqns.sort();
+        fqns.into_iter().next()
+    }
+
+    /// Retrieves (or builds with values from `bindings`) the [AnyMessage] to
+    /// inject into the elfo message flow by `key`.
+    pub(crate) fn inject(
+        &self,
+        key: &str,
+        bindings: &bindings::Scope,
+    ) -> Result<AnyMessage, AnError> {
+        match self.values.get(key) {
+            None => Err(format!("no such key: {:?}", key).into()),
+            Some(Injection::Value(value)) => Ok(value.clone()),
+            Some(Injection::Injector(injector)) => injector(Bindings(bindings)),
+            Some(Injection::Response(_)) => {
+                Err(format!("{:?} is an injected response, not a message", key).into())
+            },
+        }
+    }
+
+    /// Retrieves a copy of the native response injected by `key`, if there is
+    /// one.
+    fn injected_response(&self, key: &str) -> Option<Box<dyn Any>> {
+        match self.values.get(key) {
+            Some(Injection::Response(response)) => Some(response()),
+            _ => None,
+        }
+    }
+
+    /// Describes the message registered under `fqn`.
+    pub fn message(&self, fqn: &str) -> Option<MessageInfo<'_>> {
+        self.marshallers
+            .get_key_value(fqn)
+            .map(|(fqn, marshaller)| {
+                MessageInfo {
+                    fqn,
+                    is_request: marshaller.response().is_some(),
+                    response_type: marshaller.response_type(),
+                }
+            })
+    }
+
+    /// Describes all the registered messages, ordered by FQN.
+    pub fn messages(&self) -> impl Iterator<Item = MessageInfo<'_>> {
+        let mut fqns = self.marshallers.keys().collect::<Vec<_>>();
+        fqns.sort();
+        fqns.into_iter().map(|fqn| {
+            self.message(fqn)
+                .expect("the key has just been taken from the map")
+        })
+    }
+
+    /// Lists the keys of the [injected](Injected) values, ordered.
+    pub fn injected_keys(&self) -> impl Iterator<Item = &str> {
+        let mut keys = self.values.keys().map(String::as_str).collect::<Vec<_>>();
+        keys.sort();
+        keys.into_iter()
+    }
+}
+
+impl<'a> Bindings<'a> {
+    /// Returns the value bound to the variable `name` (e.g. `"$VALUE"`).
+    pub fn get(&self, name: &str) -> Option<&'a Value> {
+        self.0.value_of(name)
+    }
+}
+
+impl fmt::Display for MessageInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.is_request, self.response_type) {
+            (false, _) => write!(f, "{}", self.fqn),
+            (true, None) => write!(f, "{} (request)", self.fqn),
+            (true, Some(response_type)) => write!(f, "{} (request -> {})", self.fqn, response_type),
+        }
     }
 }
 
@@ -169,9 +369,44 @@ where
     }
 }
 
+impl<Rq> RegisterMarshaller for InjectedResponse<Rq>
+where
+    Rq: elfo::Request,
+    Rq::Response: Clone,
+{
+    fn register(self, marshalling: &mut MarshallingRegistry) {
+        let value = self.value;
+        let response = move || -> Box<dyn Any> { Box::new(value.clone()) };
+        marshalling
+            .values
+            .insert(self.key, Injection::Response(Box::new(response)));
+    }
+}
+
+impl RegisterMarshaller for CatchAll {
+    fn register(self, marshalling: &mut MarshallingRegistry) {
+        let fqn = std::any::type_name::<Self>();
+        debug!("registering the catch-all marshaller: {}", fqn);
+        marshalling.marshallers.insert(fqn.into(), Box::new(self));
+    }
+}
+
 impl RegisterMarshaller for Injected {
     fn register(self, marshalling: &mut MarshallingRegistry) {
-        marshalling.values.insert(self.key, self.value);
+        marshalling
+            .values
+            .insert(self.key, Injection::Value(self.value));
+    }
+}
+
+impl<F> RegisterMarshaller for InjectedWith<F>
+where
+    F: Fn(Bindings<'_>) -> Result<AnyMessage, AnError> + 'static,
+{
+    fn register(self, marshalling: &mut MarshallingRegistry) {
+        marshalling
+            .values
+            .insert(self.key, Injection::Injector(Box::new(self.injector)));
     }
 }
 
@@ -202,6 +437,10 @@ impl Marshal for Mock {
         panic!("it's a mock!")
     }
 
+    fn is_type_of(&self, _envelope: &Envelope) -> bool {
+        false
+    }
+
     fn response(&self) -> Option<&dyn DynRespond> {
         let dyn_respond: &dyn DynRespond = self;
         Some(dyn_respond).filter(|_| self.is_request)
@@ -221,6 +460,47 @@ impl<'a> Respond<'a> for Mock {
     }
 }
 
+impl Marshal for CatchAll {
+    fn match_inbound_message(
+        &self,
+        envelope: &Envelope,
+        bind_to: &DstPattern,
+        bindings: &mut bindings::Txn,
+    ) -> bool {
+        let message = envelope.message();
+        let payload = extract_message_payload(envelope)
+            .expect("AnyMessage has changed serialization format?");
+        let generic = serde_json::json!({
+            "protocol": message.protocol(),
+            "name": message.name(),
+            "payload": payload,
+        });
+
+        bindings::bind_to_pattern(generic, bind_to, bindings)
+    }
+
+    fn marshal_outbound_message(
+        &self,
+        _marshalling: &MarshallingRegistry,
+        _bindings: &bindings::Scope,
+        _msg: SrcMsg,
+    ) -> Result<AnyMessage, AnError> {
+        Err("messages of unregistered types cannot be sent".into())
+    }
+
+    fn response(&self) -> Option<&'static dyn DynRespond> {
+        None
+    }
+
+    fn is_type_of(&self, _envelope: &Envelope) -> bool {
+        true
+    }
+
+    fn is_fallback(&self) -> bool {
+        true
+    }
+}
+
 impl<M> Marshal for Regular<M>
 where
     M: elfo::Message,
@@ -253,6 +533,10 @@ where
     fn response(&self) -> Option<&'static dyn DynRespond> {
         None
     }
+
+    fn is_type_of(&self, envelope: &Envelope) -> bool {
+        envelope.is::<M>()
+    }
 }
 
 impl<Rq> Marshal for Request<Rq>
@@ -287,6 +571,36 @@ where
     fn response(&self) -> Option<&'static dyn DynRespond> {
         Some(&Response::<Rq>)
     }
+
+    fn is_type_of(&self, envelope: &Envelope) -> bool {
+        envelope.is::<Rq>()
+    }
+
+    fn response_type(&self) -> Option<&'static str> {
+        Some(std::any::type_name::<Rq::Response>())
+    }
+
+    fn request(
+        &self,
+        proxy: Proxy,
+        to: Option<Addr>,
+        marshalling: &MarshallingRegistry,
+        bindings: &bindings::Scope,
+        msg: SrcMsg,
+    ) -> Result<LocalBoxFuture<'static, Result<Value, AnError>>, AnError> {
+        let request = do_marshal_message::<Rq>(marshalling, bindings, msg)?
+            .downcast::<Rq>()
+            .map_err(|_| "couldn't cast")?;
+        Ok(async move {
+            let response = if let Some(to) = to {
+                proxy.request_to_fallible(to, request).await
+            } else {
+                proxy.request_fallible(request).await
+            }?;
+            Ok(serde_json::to_value(Rq::Wrapper::from(response))?)
+        }
+        .boxed_local())
+    }
 }
 
 impl<'a, Rq> Respond<'a> for Response<Rq>
@@ -316,11 +630,15 @@ where
                     }
                 },
                 SrcMsg::Inject(name) => {
-                    let a = marshalling
-                        .values
-                        .get(&name)
-                        .cloned()
-                        .ok_or("no such value")?;
+                    if let Some(response) = marshalling.injected_response(&name) {
+                        let response = response
+                            .downcast::<Rq::Response>()
+                            .map_err(|_| "the injected response is of another type")?;
+                        proxy.respond(token, *response);
+                        return Ok(());
+                    }
+
+                    let a = marshalling.inject(&name, bindings)?;
                     if let Ok(response) = a.downcast::<Rq::Wrapper>() {
                         proxy.respond(token, response.into());
                         Ok(())
@@ -344,7 +662,7 @@ where
     }
 }
 
-fn extract_message_payload(envelope: &Envelope) -> Option<Value> {
+pub(crate) fn extract_message_payload(envelope: &Envelope) -> Option<Value> {
     let mut message_parts = serde_json::to_value(envelope.message()).ok()?;
     let &mut [ref mut _proto, ref mut _name, ref mut payload] =
         &mut message_parts.as_array_mut()?[..]
@@ -368,11 +686,7 @@ fn do_marshal_message<M: Message>(
             Ok(a)
         },
         SrcMsg::Inject(name) => {
-            let a = marshalling
-                .values
-                .get(&name)
-                .cloned()
-                .ok_or("no such value")?;
+            let a = marshalling.inject(&name, bindings)?;
             Ok(a)
         },
         SrcMsg::Literal(value) => {
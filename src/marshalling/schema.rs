@@ -0,0 +1,122 @@
+//! JSON schemas of the messages, captured with [schemars].
+//!
+//! Registering a message wrapped into [`WithSchema`] lets
+//! [`Executable::build`](crate::execution::Executable::build) validate the
+//! literal payloads against the message's schema, and warn about the recv
+//! patterns using the keys the message does not have.
+
+use schemars::JsonSchema;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::marshalling::{MarshallingRegistry, RegisterMarshaller, Regular, Request};
+
+/// Registers the wrapped marshaller along with the schema of its message.
+///
+/// ```ignore
+/// MarshallingRegistry::new()
+///     .with(WithSchema(Regular::<proto::Hey>))
+///     .with(WithSchema(Request::<proto::Whatsup>));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct WithSchema<R>(pub R);
+
+impl<M> RegisterMarshaller for WithSchema<Regular<M>>
+where
+    M: elfo::Message + JsonSchema,
+{
+    fn register(self, marshalling: &mut MarshallingRegistry) {
+        let fqn = std::any::type_name::<M>();
+        debug!("registering schema of: {}", fqn);
+        marshalling
+            .schemas
+            .insert(fqn.into(), schemars::schema_for!(M).into());
+        self.0.register(marshalling);
+    }
+}
+
+impl<Rq> RegisterMarshaller for WithSchema<Request<Rq>>
+where
+    Rq: elfo::Request + JsonSchema,
+    Rq::Response: JsonSchema,
+{
+    fn register(self, marshalling: &mut MarshallingRegistry) {
+        let fqn = std::any::type_name::<Rq>();
+        debug!("registering schemas of: {}", fqn);
+        marshalling
+            .schemas
+            .insert(fqn.into(), schemars::schema_for!(Rq).into());
+        marshalling
+            .response_schemas
+            .insert(fqn.into(), schemars::schema_for!(Rq::Response).into());
+        self.0.register(marshalling);
+    }
+}
+
+impl MarshallingRegistry {
+    /// The schema of the message registered under `fqn`.
+    pub(crate) fn schema(&self, fqn: &str) -> Option<&Value> {
+        self.schemas.get(fqn)
+    }
+
+    /// The schema of the response to the request registered under `fqn`.
+    pub(crate) fn response_schema(&self, fqn: &str) -> Option<&Value> {
+        self.response_schemas.get(fqn)
+    }
+}
+
+/// Validates `payload` against `schema`.
+pub(crate) fn validate(schema: &Value, payload: &Value) -> Result<(), String> {
+    let validator = jsonschema::validator_for(schema).map_err(|e| e.to_string())?;
+    validator.validate(payload).map_err(|e| e.to_string())
+}
+
+/// Lists the keys used in `pattern` that the objects described by `schema` do
+/// not have.
+///
+/// Only the objects with the `properties` listed are checked.
+pub(crate) fn unknown_keys(schema: &Value, pattern: &Value) -> Vec<String> {
+    let mut out = vec![];
+    collect_unknown_keys(schema, schema, pattern, "", &mut out);
+    out
+}
+
+fn collect_unknown_keys(
+    root: &Value,
+    node: &Value,
+    pattern: &Value,
+    path: &str,
+    out: &mut Vec<String>,
+) {
+    let node = resolve_ref(root, node);
+    let (Some(pattern), Some(properties)) = (
+        pattern.as_object(),
+        node.get("properties").and_then(Value::as_object),
+    ) else {
+        return
+    };
+    let allows_extra = node
+        .get("additionalProperties")
+        .is_some_and(|a| a != &Value::Bool(false));
+
+    for (key, sub_pattern) in pattern {
+        let sub_path = if path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        match properties.get(key) {
+            Some(sub_node) => collect_unknown_keys(root, sub_node, sub_pattern, &sub_path, out),
+            None if !allows_extra => out.push(sub_path),
+            None => (),
+        }
+    }
+}
+
+fn resolve_ref<'a>(root: &'a Value, node: &'a Value) -> &'a Value {
+    node.get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| r.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+        .unwrap_or(node)
+}
@@ -0,0 +1,31 @@
+//! Reporting the outcomes of the [suite](crate::suite) runs as the metrics.
+
+use std::path::Path;
+
+use metrics::{counter, histogram};
+
+use crate::execution::{Executable, Report};
+use crate::suite::ScenarioOutcome;
+
+pub(crate) fn scenario_finished(outcome: &ScenarioOutcome) {
+    let scenario = outcome.scenario.display().to_string();
+    let timings = &outcome.timings;
+    let took = timings.load + timings.build + timings.start + timings.run;
+
+    counter!("luci_scenarios_total", "result" => outcome.result.kind()).increment(1);
+    histogram!("luci_scenario_duration_seconds", "scenario" => scenario).record(took.as_secs_f64());
+}
+
+pub(crate) fn events_fired(scenario: &Path, executable: &Executable, report: &Report) {
+    let scenario = scenario.display().to_string();
+    for (event_key, fired_at) in report.fired_at.iter() {
+        let event = executable.event_path(*event_key).unwrap_or_default();
+        histogram!(
+            "luci_event_fired_seconds",
+            "scenario" => scenario.clone(),
+            "event" => event,
+            "kind" => event_key.kind().to_string(),
+        )
+        .record(fired_at.wall.as_secs_f64());
+    }
+}
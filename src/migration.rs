@@ -0,0 +1,167 @@
+//! Rewriting scenarios that use the deprecated field names.
+//!
+//! The `backward-compatibility` feature makes the old names acceptable when
+//! parsing. [`migrate`] renames them in a YAML document, so that the scenarios
+//! parse without that feature:
+//! - `subs` → `subroutines`;
+//! - `after` → `happens_after`;
+//! - `exact` → `literal` (in `bind.src`, `send.data`, `respond.data`);
+//! - `to` → `to_request` (in `respond`).
+
+use std::fmt;
+
+use serde_yaml::{Mapping, Value};
+
+/// A single renamed field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// The location of the map containing the field, e.g. `events[3].respond`.
+    pub path: String,
+    pub from: &'static str,
+    pub to:   &'static str,
+}
+
+/// Renames the deprecated fields in the scenario document.
+///
+/// A field is left as is if the map already contains a field with the new
+/// name.
+pub fn migrate(doc: &mut Value) -> Vec<Change> {
+    let mut changes = vec![];
+
+    let Some(root) = doc.as_mapping_mut() else {
+        return changes
+    };
+    rename(root, "", "subs", "subroutines", &mut changes);
+
+    let Some(events) = root.get_mut("events").and_then(Value::as_sequence_mut) else {
+        return changes
+    };
+    for (idx, event) in events.iter_mut().enumerate() {
+        let Some(event) = event.as_mapping_mut() else {
+            continue
+        };
+        let path = format!("events[{}]", idx);
+
+        rename(event, &path, "after", "happens_after", &mut changes);
+
+        if let Some(bind) = event.get_mut("bind").and_then(Value::as_mapping_mut) {
+            src_msg(bind, &format!("{}.bind", path), "src", &mut changes);
+        }
+        if let Some(send) = event.get_mut("send").and_then(Value::as_mapping_mut) {
+            src_msg(send, &format!("{}.send", path), "data", &mut changes);
+        }
+        if let Some(respond) = event.get_mut("respond").and_then(Value::as_mapping_mut) {
+            let path = format!("{}.respond", path);
+            rename(respond, &path, "to", "to_request", &mut changes);
+            src_msg(respond, &path, "data", &mut changes);
+        }
+    }
+
+    changes
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{} -> {}", self.from, self.to)
+        } else {
+            write!(
+                f,
+                "{}.{} -> {}.{}",
+                self.path, self.from, self.path, self.to
+            )
+        }
+    }
+}
+
+fn src_msg(parent: &mut Mapping, path: &str, field: &str, changes: &mut Vec<Change>) {
+    if let Some(msg) = parent.get_mut(field).and_then(Value::as_mapping_mut) {
+        rename(
+            msg,
+            &format!("{}.{}", path, field),
+            "exact",
+            "literal",
+            changes,
+        );
+    }
+}
+
+fn rename(
+    map: &mut Mapping,
+    path: &str,
+    from: &'static str,
+    to: &'static str,
+    changes: &mut Vec<Change>,
+) {
+    if !map.contains_key(from) || map.contains_key(to) {
+        return
+    }
+
+    // rebuild the map rather than remove/insert to keep the order of the fields
+    *map = std::mem::take(map)
+        .into_iter()
+        .map(|(k, v)| {
+            if k.as_str() == Some(from) {
+                (Value::from(to), v)
+            } else {
+                (k, v)
+            }
+        })
+        .collect();
+
+    changes.push(Change {
+        path: path.to_owned(),
+        from,
+        to,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+
+    const OLD: &str = r#"
+subs:
+  - load: smalltalk.luci.yaml
+    as: smalltalk
+events:
+  - id: bind
+    bind:
+      dst: $X
+      src:
+        exact: 1
+  - id: request
+    after: [bind]
+    recv:
+      type: Q
+      data: $Q
+  - id: respond
+    respond:
+      to: request
+      data:
+        exact: 2
+"#;
+
+    #[test]
+    fn renames_deprecated_fields() {
+        let mut doc: Value = serde_yaml::from_str(OLD).unwrap();
+        let changes = migrate(&mut doc)
+            .into_iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "subs -> subroutines",
+                "events[0].bind.src.exact -> events[0].bind.src.literal",
+                "events[1].after -> events[1].happens_after",
+                "events[2].respond.to -> events[2].respond.to_request",
+                "events[2].respond.data.exact -> events[2].respond.data.literal",
+            ]
+        );
+
+        let _: Scenario = serde_yaml::from_value(doc.clone()).expect("migrated scenario");
+        assert!(migrate(&mut doc).is_empty());
+    }
+}
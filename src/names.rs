@@ -14,6 +14,12 @@ pub struct ActorName(Arc<str>);
 #[display("D:{_0}")]
 pub struct DummyName(Arc<str>);
 
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, derive_more::Display,
+)]
+#[display("G:{_0}")]
+pub struct GroupName(Arc<str>);
+
 #[derive(
     Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, derive_more::Display,
 )]
@@ -32,6 +38,60 @@ pub struct MessageName(Arc<str>);
 #[display("S:{_0}")]
 pub struct SubroutineName(Arc<str>);
 
+impl AsRef<str> for ActorName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for DummyName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for GroupName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for EventName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for MessageName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SubroutineName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl DummyName {
+    /// The dummies declared by this name: a pool `client x 3` declares
+    /// `client[0]`, `client[1]` and `client[2]`, any other name declares
+    /// itself.
+    ///
+    /// Returns `None` if the size of the pool is not a positive number.
+    pub fn members(&self) -> Option<Vec<DummyName>> {
+        let Some((name, size)) = self.0.split_once(" x ") else {
+            return Some(vec![self.clone()])
+        };
+        let size = size.trim().parse::<usize>().ok().filter(|size| *size > 0)?;
+        let members = (0..size)
+            .map(|idx| Self(format!("{}[{}]", name.trim(), idx).into()))
+            .collect();
+        Some(members)
+    }
+}
+
 impl EventName {
     pub fn with_suffix(&self, suffix: &str) -> Self {
         Self(format!("{}{}", self.0, suffix).into())
@@ -0,0 +1,228 @@
+//! Drafting a scenario out of the live traffic.
+//!
+//! A [Recording] starts a blueprint behind a proxy, the way a run would, and
+//! lets a test drive it by hand. Whatever is sent through the recording, and
+//! whatever is received by it, is noted down in the order observed; then
+//! [Recording::scenario] turns the notes into a draft [Scenario] with a
+//! `script`, to be refined by hand: the literal payloads replaced with
+//! bindings, the irrelevant fields dropped from the patterns, etc.
+//!
+//! ```ignore
+//! let mut recording = Recording::start(blueprint(), config, marshalling()).await;
+//! recording.send(proto::Hey).await;
+//! let _ = recording.recv().await;
+//! std::fs::write("hey.luci.yaml", recording.to_yaml())?;
+//! ```
+
+use std::collections::HashSet;
+
+use elfo::test::Proxy;
+use elfo::{Addr, Blueprint, Envelope, Message};
+use serde_json::{json, Value};
+
+use crate::marshalling::{self, MarshallingRegistry};
+use crate::scenario::Scenario;
+
+/// The name of the dummy standing for the recording in the drafted scenario.
+pub const RECORDER_DUMMY: &str = "recorder";
+
+/// A blueprint driven by hand, with the traffic noted down.
+pub struct Recording {
+    proxy:       Proxy,
+    marshalling: MarshallingRegistry,
+    actors:      Vec<Addr>,
+    notes:       Vec<Note>,
+}
+
+#[derive(Debug, Clone)]
+enum Note {
+    Sent {
+        fqn:     String,
+        to:      Option<Addr>,
+        payload: Value,
+    },
+    Received {
+        fqn:     String,
+        from:    Addr,
+        payload: Value,
+    },
+}
+
+impl Recording {
+    /// Starts `blueprint` with `config`.
+    ///
+    /// The received messages are only recorded with their types if those are
+    /// registered in `marshalling`: the others are named after their elfo
+    /// protocol and name.
+    pub async fn start<C>(blueprint: Blueprint, config: C, marshalling: MarshallingRegistry) -> Self
+    where
+        C: for<'de> serde::de::Deserializer<'de>,
+    {
+        let proxy = elfo::test::proxy(blueprint, config).await;
+        Self {
+            proxy,
+            marshalling,
+            actors: Default::default(),
+            notes: Default::default(),
+        }
+    }
+
+    /// The address of the recording's proxy.
+    pub fn addr(&self) -> Addr {
+        self.proxy.addr()
+    }
+
+    /// Sends `message`, routed.
+    pub async fn send<M: Message>(&mut self, message: M) {
+        self.notes.push(Note::Sent {
+            fqn:     std::any::type_name::<M>().to_owned(),
+            to:      None,
+            payload: serde_json::to_value(&message).expect("a message is serializable"),
+        });
+        self.proxy.send(message).await
+    }
+
+    /// Sends `message` directly to `recipient`.
+    pub async fn send_to<M: Message>(&mut self, recipient: Addr, message: M) {
+        self.notes.push(Note::Sent {
+            fqn:     std::any::type_name::<M>().to_owned(),
+            to:      Some(recipient),
+            payload: serde_json::to_value(&message).expect("a message is serializable"),
+        });
+        self.proxy.send_to(recipient, message).await
+    }
+
+    /// Receives an envelope, panicking if there is none.
+    pub async fn recv(&mut self) -> Envelope {
+        let envelope = self.proxy.recv().await;
+        self.note_received(&envelope);
+        envelope
+    }
+
+    /// Receives an envelope, if there is one.
+    pub async fn try_recv(&mut self) -> Option<Envelope> {
+        let envelope = self.proxy.try_recv().await?;
+        self.note_received(&envelope);
+        Some(envelope)
+    }
+
+    /// Drafts a scenario out of the traffic recorded so far.
+    ///
+    /// The events are laid out as a `script`, in the order observed; the last
+    /// one is required to be reached.
+    pub fn scenario(&self) -> Scenario {
+        let mut aliases = Aliases::default();
+        let mut script = vec![];
+
+        for (idx, note) in self.notes.iter().enumerate() {
+            let event = match note {
+                Note::Sent { fqn, to, payload } => {
+                    let alias = aliases.alias(fqn);
+                    let mut send = json!({
+                        "from": RECORDER_DUMMY,
+                        "type": alias,
+                        "data": { "literal": payload },
+                    });
+                    if let Some(actor) = to.and_then(|addr| self.actor_name(addr)) {
+                        send["to"] = actor.into();
+                    }
+                    json!({
+                        "id": format!("{:02}-{}-sends-{}", idx + 1, RECORDER_DUMMY, alias),
+                        "send": send,
+                    })
+                },
+                Note::Received { fqn, from, payload } => {
+                    let alias = aliases.alias(fqn);
+                    let actor = self
+                        .actor_name(*from)
+                        .expect("every sender is listed among the actors");
+                    json!({
+                        "id": format!("{:02}-{}-receives-{}", idx + 1, RECORDER_DUMMY, alias),
+                        "recv": {
+                            "from": actor,
+                            "to": RECORDER_DUMMY,
+                            "type": alias,
+                            "data": payload,
+                        },
+                    })
+                },
+            };
+            script.push(event);
+        }
+        if let Some(last) = script.last_mut() {
+            last["require"] = "reached".into();
+        }
+
+        let actors = (0..self.actors.len()).map(actor_name).collect::<Vec<_>>();
+        let types = aliases
+            .types
+            .into_iter()
+            .map(|(fqn, alias)| json!({ "use": fqn, "as": alias }))
+            .collect::<Vec<_>>();
+
+        serde_json::from_value(json!({
+            "types": types,
+            "actors": actors,
+            "dummies": [RECORDER_DUMMY],
+            "script": script,
+        }))
+        .expect("the draft is a valid scenario")
+    }
+
+    /// Same as [Recording::scenario], rendered as YAML.
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(&self.scenario()).expect("a scenario is serializable")
+    }
+
+    fn note_received(&mut self, envelope: &Envelope) {
+        let message = envelope.message();
+        let fqn = self
+            .marshalling
+            .type_of(envelope)
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("{}::{}", message.protocol(), message.name()));
+        let payload = marshalling::extract_message_payload(envelope)
+            .expect("AnyMessage has changed serialization format?");
+
+        let from = envelope.sender();
+        if !self.actors.contains(&from) {
+            self.actors.push(from);
+        }
+
+        self.notes.push(Note::Received { fqn, from, payload });
+    }
+
+    fn actor_name(&self, addr: Addr) -> Option<String> {
+        self.actors.iter().position(|a| *a == addr).map(actor_name)
+    }
+}
+
+fn actor_name(idx: usize) -> String {
+    format!("actor-{}", idx + 1)
+}
+
+/// The type aliases, in the order of the first use.
+#[derive(Default)]
+struct Aliases {
+    types: Vec<(String, String)>,
+    taken: HashSet<String>,
+}
+
+impl Aliases {
+    /// Aliases `fqn` after its last path segment, unless that one is taken by
+    /// another type: then the whole path is used.
+    fn alias(&mut self, fqn: &str) -> String {
+        if let Some((_, alias)) = self.types.iter().find(|(f, _)| f == fqn) {
+            return alias.clone()
+        }
+        let short = fqn.rsplit("::").next().unwrap_or(fqn);
+        let alias = if self.taken.contains(short) {
+            fqn.replace("::", "_")
+        } else {
+            short.to_owned()
+        };
+        self.taken.insert(alias.clone());
+        self.types.push((fqn.to_owned(), alias.clone()));
+        alias
+    }
+}
@@ -6,6 +6,10 @@
 //! ([`tokio::time::Instant`]).
 //!
 //! [`RecordLog`] — carries the `t_0` timestamp, and the sequence of all logs
+//!
+//! Each record is also emitted as a `DEBUG` event of the `luci::record` target
+//! as soon as it is written: with `RUST_LOG=luci::record=debug`, the progress
+//! of the scenario shows up interleaved with the logs of the actors.
 
 use std::time::Instant as StdInstant;
 
@@ -14,6 +18,9 @@ use tokio::time::Instant as RtInstant;
 
 pub(crate) mod records;
 
+/// The target the records are emitted with as the `tracing` events.
+const RECORD_TARGET: &str = "luci::record";
+
 new_key_type! {
     pub struct KeyRecord;
 }
@@ -30,6 +37,7 @@ pub(crate) struct Recorder<'a> {
     log:    &'a mut RecordLog,
     parent: Option<KeyRecord>,
     last:   Option<KeyRecord>,
+    depth:  usize,
 }
 
 #[derive(derive_more::Debug, Clone)]
@@ -55,6 +63,7 @@ pub(crate) enum RecordKind {
     ReadyBindKeys(records::ReadyBindKeys),
     ReadyRecvKeys(records::ReadyRecvKeys),
     TimedOutRecvKey(records::TimedOutRecvKey),
+    TimeAdvanced(records::TimeAdvanced),
     ProcessBindKey(records::ProcessBindKey),
     BindSrcScope(records::BindSrcScope),
     UsingValue(records::UsingValue),
@@ -67,15 +76,32 @@ pub(crate) enum RecordKind {
     StoreActorAddress(records::StoreActorAddress),
     MatchDummyAddress(records::MatchDummyAddress),
     ResolveActorName(records::ResolveActorName),
+    EnvelopeKindMismatch(records::EnvelopeKindMismatch),
+    SenderAlreadySeen(records::SenderAlreadySeen),
+    MatchGroup(records::MatchGroup),
+    MatchRoutingKey(records::MatchRoutingKey),
+    ResolveRoutingKey(records::ResolveRoutingKey),
     SendMessageType(records::SendMessageType),
     UsingMsg(records::UsingMsg),
     SendTo(records::SendTo),
+    FaultDropped(records::FaultDropped),
+    FaultDelayed(records::FaultDelayed),
+    FaultDuplicated(records::FaultDuplicated),
+    DummyStopped(records::DummyStopped),
     ProcessRespond(records::ProcessRespond),
+    FailRequest(records::FailRequest),
     EnvelopeReceived(records::EnvelopeReceived),
     MatchingRecv(records::MatchingRecv),
+    ResponseReceived(records::ResponseReceived),
     ExpectedDirectedGotRouted(records::ExpectedDirectedGotRouted),
     ValidFrom(records::ValidFrom),
     TooEarly(records::TooEarly),
+    CountedMatch(records::CountedMatch),
+    CountExceeded(records::CountExceeded),
+    Cancelled(records::Cancelled),
+    SystemMessageSkipped(records::SystemMessageSkipped),
+    AutoResponded(records::AutoResponded),
+    EnvelopeIgnored(records::EnvelopeIgnored),
 }
 
 impl RecordLog {
@@ -92,7 +118,7 @@ impl RecordLog {
         self.t_zero
     }
 
-    pub(crate) fn recorder(&mut self) -> Recorder {
+    pub(crate) fn recorder(&mut self) -> Recorder<'_> {
         let at = (StdInstant::now(), RtInstant::now());
         let kind = RecordKind::Root;
         let parent = None;
@@ -111,6 +137,7 @@ impl RecordLog {
             log:    self,
             parent: Some(root_key),
             last:   Some(root_key),
+            depth:  0,
         }
     }
 }
@@ -123,6 +150,13 @@ impl<'a> Recorder<'a> {
         let at = (StdInstant::now(), RtInstant::now());
         let kind = entry.into();
         let parent = self.parent;
+        tracing::debug!(
+            target: RECORD_TARGET,
+            depth = self.depth,
+            rt = ?at.1.duration_since(self.log.t_zero.1),
+            "{:?}",
+            kind
+        );
         let record = Record {
             at,
             parent,
@@ -141,9 +175,15 @@ impl<'a> Recorder<'a> {
             log:    self.log,
             parent: Some(key),
             last:   None,
+            depth:  self.depth + 1,
         }
     }
 
+    /// The record written last by this recorder, if any.
+    pub(crate) fn last_key(&self) -> Option<KeyRecord> {
+        self.last
+    }
+
     #[deprecated(note = "let's see whether we can do without it")]
     #[allow(dead_code)]
     pub(crate) fn on_error<E>(&mut self) -> impl for<'e> FnOnce(&'e E) + use<'_, 'a, E>
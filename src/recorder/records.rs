@@ -9,7 +9,8 @@ use crate::execution::runner::ReadyEventKey;
 use crate::execution::{
     EventKey, KeyActor, KeyBind, KeyDummy, KeyRecv, KeyRespond, KeyScope, KeySend,
 };
-use crate::scenario::{DstPattern, SrcMsg};
+use crate::names::GroupName;
+use crate::scenario::{DefEnvelopeKind, DstPattern, SrcMsg};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Error {
@@ -28,6 +29,10 @@ pub(crate) struct ReadyBindKeys(pub Vec<KeyBind>);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct TimedOutRecvKey(pub KeyRecv);
 
+/// The paused clock has been advanced in one jump.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TimeAdvanced(pub Duration);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ReadyRecvKeys(pub Vec<KeyRecv>);
 
@@ -61,6 +66,26 @@ pub(crate) struct StoreActorAddress(pub KeyActor, pub KeyScope, pub Addr);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ResolveActorName(pub KeyActor, pub KeyScope, pub Addr);
 
+/// The expected kind, and the actual one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EnvelopeKindMismatch(pub DefEnvelopeKind, pub DefEnvelopeKind);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SenderAlreadySeen(pub Addr);
+
+/// The sender, the group it is expected to belong to, and the one it does
+/// (if known).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MatchGroup(pub Addr, pub GroupName, pub Option<&'static str>);
+
+/// The routing key, the address it is bound to (if any), and the sender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MatchRoutingKey(pub Value, pub Option<Addr>, pub Addr);
+
+/// The routing key, and the address it is bound to (if any).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ResolveRoutingKey(pub Value, pub Option<Addr>);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct BindOutcome(pub bool);
 
@@ -76,9 +101,36 @@ pub(crate) struct SendMessageType(pub Arc<str>);
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct SendTo(pub Option<Addr>);
 
+/// The message has been dropped by the injected faults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FaultDropped;
+
+/// The message has been delayed by the injected faults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FaultDelayed(pub Duration);
+
+/// The message has been duplicated by the injected faults.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FaultDuplicated;
+
+/// The envelope has been consumed by the `ignore` list of a scenario.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EnvelopeIgnored(pub KeyScope);
+
+/// The envelope has been answered by an auto-responder rule of the dummy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AutoResponded(pub KeyDummy, pub KeyScope);
+
+/// The dummy has been stopped: its proxy is dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DummyStopped(pub KeyDummy, pub KeyScope);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ProcessRespond(pub KeyRespond);
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FailRequest;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct EnvelopeReceived {
     pub message_name: &'static str,
@@ -89,6 +141,9 @@ pub(crate) struct EnvelopeReceived {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct MatchingRecv(pub KeyRecv);
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ResponseReceived(pub KeyRecv);
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ExpectedDirectedGotRouted(pub KeyDummy);
 
@@ -97,3 +152,22 @@ pub(crate) struct ValidFrom(pub Instant);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct TooEarly(pub Duration);
+
+/// The envelope is the N-th match of a recv expecting several.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CountedMatch(pub usize);
+
+/// The envelope is one match too many for a recv expecting exactly N.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CountExceeded(pub usize);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Cancelled;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SystemMessageSkipped {
+    pub message_name: &'static str,
+    /// The FQN from the ignore-list, if the message was ignored rather than
+    /// left unmatched.
+    pub ignored_as:   Option<Arc<str>>,
+}
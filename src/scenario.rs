@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
@@ -13,23 +15,165 @@ mod subs;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Scenario {
+    /// The name of the scenario within a multi-document file, where it is
+    /// referred to as `file.luci.yaml#name`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub types: Vec<DefTypeAlias>,
 
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "backward-compatibility", serde(alias = "subs"))]
     pub subroutines: Vec<DefDeclareSub>,
 
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub actors:  Vec<ActorName>,
+    /// The dummies: `client x 3` declares a pool of `client[0]`, `client[1]`
+    /// and `client[2]`.
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub dummies: Vec<DummyName>,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_dummies: Option<usize>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[serde(alias = "invariant")]
+    pub invariants: BTreeMap<String, Invariant>,
+
+    /// The types of the variables, e.g. `$ORDER_ID: string`: every value bound
+    /// to a declared variable is checked against its type.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub bindings: BTreeMap<String, BindingType>,
+
+    /// The values bound in the scenario's scope before any event fires, e.g.
+    /// `$TIMEOUT_MS: 500`. The values bound by the caller of the entry point
+    /// take precedence over these.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[serde(alias = "constants")]
+    pub values: BTreeMap<String, Value>,
+
+    /// The config to start the blueprint with, merged over the one given by
+    /// the caller: the keys set here take precedence. The values of the
+    /// scenario's scope are substituted for the `$names` in it. Only the entry
+    /// point's config is in effect.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<Value>,
+
+    /// The table of the cases to run the scenario in: once per row, each row's
+    /// values bound in the scenario's scope on top of `values` and of the
+    /// values bound by the caller. Only the entry point's cases are in effect.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cases: Vec<BTreeMap<String, Value>>,
+
+    /// The named fragments of the patterns, referred to as `{$ref: name}` from
+    /// the patterns of the recvs and binds (and from the other fragments).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub patterns: BTreeMap<String, DstPattern>,
+
+    /// The named templates of the messages, referred to as `{$message: name}`
+    /// from the `data` of the sends, requests and responds. The other keys of
+    /// that object override the template's ones.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub messages: BTreeMap<String, SrcMsg>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_messages: Option<DefSystemMessages>,
+
+    /// The faults injected into the messages sent by the dummies of this
+    /// scenario, unless overridden by a send.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faults: Option<DefFaults>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub auto_responders: Vec<DefAutoResponder>,
+
+    /// The envelopes consumed silently, such as the periodic keep-alives: they
+    /// are matched neither by the recvs, nor by the auto-responders.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<DefIgnore>,
+
+    /// Fail the run on an envelope consumed by neither a recv, nor an ignore
+    /// rule, nor an auto-responder. Only the entry point's flag is in effect.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub strict_mailbox: bool,
+
+    /// The values of the events' fields omitted in this scenario.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<DefDefaults>,
+
+    #[serde(default)]
     pub events: Vec<DefEvent>,
 
+    /// The events happening in the order they are listed: each one happens
+    /// after the one before it (besides its own `happens_after`).
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub script: Vec<DefEvent>,
+
+    /// The events whose requirements become the caller's ones, so that they
+    /// are reported on their own. Exported or not, any event may be referred
+    /// to by the caller as `call/event`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exports: Vec<EventName>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// The values of the events' fields, applied where they are omitted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DefDefaults {
+    /// The `timeout` of the recvs, streams, requests, and status checks.
+    #[serde(with = "humantime_serde")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+
+    /// The `after_duration` of the recvs.
+    #[serde(with = "humantime_serde")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after_duration: Option<Duration>,
+
+    /// The `step` of the delays.
+    #[serde(with = "humantime_serde")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<Duration>,
+
+    /// The dummy the sends are from, and the recvs are to.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DummyName>,
+
+    /// The actor the sends are to, and the recvs are from, unless another
+    /// sender or receiver is specified.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<ActorName>,
+
     #[serde(flatten)]
     pub no_extra: NoExtra,
 }
@@ -64,19 +208,275 @@ pub enum RequiredToBe {
     Unreached,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DefRequirement {
+    Plain(RequiredToBe),
+    Deadline(DefDeadline),
+}
+
+/// The event is required to be reached in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefDeadline {
+    /// The time by the runtime's clock, counting from the start of the run.
+    #[serde(with = "humantime_serde")]
+    pub reached_within: Duration,
+
+    /// Count the time from the event reached earlier instead.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<EventName>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// A property a binding should keep throughout the run.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Invariant {
+    /// Once bound, the variable may not be unified with a different value.
+    Same,
+}
+
+/// The type of the values a variable may be bound to.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    derive_more::Display,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingType {
+    String,
+    /// Any number, the integers included.
+    Number,
+    Integer,
+    Boolean,
+    Array,
+    Object,
+}
+
+impl BindingType {
+    /// Whether `value` is of this type.
+    pub fn admits(&self, value: &Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Number => value.is_number(),
+            Self::Integer => value.is_i64() || value.is_u64(),
+            Self::Boolean => value.is_boolean(),
+            Self::Array => value.is_array(),
+            Self::Object => value.is_object(),
+        }
+    }
+
+    /// Whether every value of the type `other` is of this type.
+    pub fn includes(&self, other: BindingType) -> bool {
+        *self == other || (*self == Self::Number && other == Self::Integer)
+    }
+}
+
+/// How to treat the elfo system messages (those of the `elfo-core` protocol)
+/// reaching the dummies.
+///
+/// Only the entry point's policy is in effect.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefSystemMessages {
+    /// FQNs of the messages to drop without matching them against any recv.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// What to do with the system messages that no recv has matched.
+    #[serde(default)]
+    pub unmatched: UnmatchedSystemMessages,
+
+    /// The system topics each dummy is subscribed to once its proxy is
+    /// created, before any event fires: the messages of those arrive to the
+    /// dummy, so that the recvs `to` the dummy can match them.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub subscribe: BTreeMap<DummyName, Vec<DefSystemTopic>>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// A rule a dummy follows on its own, without the events receiving and
+/// answering each message: useful for the chatty protocols (heartbeats, acks).
+///
+/// The rules apply to the envelopes sent directly to the dummy that no ready
+/// recv has matched; an envelope matching a rule is answered and consumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefAutoResponder {
+    pub dummy: DummyName,
+    pub when:  DefAutoTrigger,
+
+    #[serde(flatten)]
+    pub reply: DefAutoReply,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefAutoTrigger {
+    #[serde(rename = "type")]
+    pub message_type: MessageName,
+    #[serde(rename = "data")]
+    pub message_data: DstPattern,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// The answer to an envelope matching a [DefAutoResponder]: the values bound
+/// by the trigger are available to the template, but do not leak to the
+/// scenario.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefAutoReply {
+    /// Send a message to the sender of the envelope.
+    Send(DefAutoSend),
+    /// Respond to the request in the envelope.
+    Respond(DefAutoRespond),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefAutoSend {
+    #[serde(rename = "type")]
+    pub message_type: MessageName,
+    #[serde(rename = "data")]
+    pub message_data: SrcMsg,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefAutoRespond {
+    pub data: SrcMsg,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefIgnore {
+    #[serde(rename = "type")]
+    pub message_type: MessageName,
+    /// Ignore only the messages matching the pattern: by default, all the
+    /// messages of the type are.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "data")]
+    pub message_data: Option<DstPattern>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// Faults injected into the messages sent by the dummies, so that the
+/// idempotency and the retries of the actors get exercised.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefFaults {
+    /// The probability of a message being dropped.
+    #[serde(default)]
+    pub drop: f64,
+
+    /// The probability of a message being delivered twice.
+    #[serde(default)]
+    pub duplicate: f64,
+
+    /// The artificial delay before a message is handed to the proxy.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<DefLatency>,
+
+    /// The seed of the random choices. The scenario's seed is in effect only
+    /// for the entry point, and is that of the whole run; the seed of an
+    /// event's faults gives the event the random choices of its own.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// A range the artificial delay of a message is uniformly picked from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefLatency {
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Duration::is_zero")]
+    #[serde(default)]
+    pub min: Duration,
+
+    #[serde(with = "humantime_serde")]
+    pub max: Duration,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnmatchedSystemMessages {
+    /// Treat them as any other unmatched envelope.
+    #[default]
+    Report,
+    /// Skip them, leaving a record in the log.
+    Skip,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefEvent {
     pub id: EventName,
 
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub require: Option<RequiredToBe>,
+    pub require: Option<DefRequirement>,
 
+    /// The events this one happens after. An event of a subroutine is referred
+    /// to as `call/inner`, where `call` is the event invoking the subroutine.
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[serde(rename = "happens_after")]
+    #[cfg_attr(feature = "backward-compatibility", serde(alias = "after"))]
     pub prerequisites: Vec<EventName>,
 
+    /// Among the events ready at the same time, the ones of a higher priority
+    /// go first; the others go in the order of definition. The events of a
+    /// subroutine inherit the priority of the call, unless they have their own.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+
+    /// What the event stands for, e.g. "the server acks within the SLA":
+    /// printed along with the event when its requirement is not met.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub describe: Option<String>,
+
     #[serde(flatten)]
     pub kind: DefEventKind,
 
@@ -84,15 +484,79 @@ pub struct DefEvent {
     pub no_extra: NoExtra,
 }
 
+/// The schema of the scenario files, as found in the root of the repository.
+const JSON_SCHEMA: &str = include_str!("../schema.yaml");
+
+impl Scenario {
+    /// The JSON Schema of the scenario files, for the editors to validate and
+    /// autocomplete the scenarios with.
+    pub fn json_schema() -> Value {
+        serde_yaml::from_str(JSON_SCHEMA).expect("schema.yaml should be a valid YAML")
+    }
+
+    /// The events of the scenario: the ones of `events`, followed by the ones
+    /// of `script`, each of the latter happening after the one listed before
+    /// it.
+    pub fn all_events(&self) -> impl Iterator<Item = Cow<'_, DefEvent>> + '_ {
+        let script = self
+            .script
+            .iter()
+            .scan(None::<&EventName>, |previous, event| {
+                let event = match previous.replace(&event.id) {
+                    None => Cow::Borrowed(event),
+                    Some(previous) => {
+                        let mut event = event.clone();
+                        event.prerequisites.insert(0, previous.clone());
+                        Cow::Owned(event)
+                    },
+                };
+                Some(event)
+            });
+        self.events.iter().map(Cow::Borrowed).chain(script)
+    }
+}
+
+impl DefEvent {
+    /// The events this one happens after: the ones listed in `happens_after`,
+    /// followed by the ones joined by a barrier.
+    pub fn all_prerequisites(&self) -> impl Iterator<Item = &EventName> {
+        let joined = match &self.kind {
+            DefEventKind::Barrier(joined) => joined.as_slice(),
+            _ => &[],
+        };
+        self.prerequisites.iter().chain(joined)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DefEventKind {
     Bind(DefEventBind),
     Recv(DefEventRecv),
+    Stream(DefEventStream),
     Send(DefEventSend),
+    /// Sends a number of messages rendered from one template.
+    SendMany(DefEventSendMany),
     Respond(DefEventRespond),
+    Request(DefEventRequest),
+    UpdateConfig(DefEventUpdateConfig),
+    Terminate(DefEventTerminate),
+    /// Stops a dummy, as if the peer has gone away: its proxy is dropped, and
+    /// so are the requests it has received and not responded to yet. The
+    /// messages sent to it afterwards are not delivered.
+    StopDummy(DummyName),
+    Status(DefEventStatus),
+    /// Matches a line logged by the actors under test.
+    Log(DefEventLog),
+    /// Checks a metric recorded by the actors under test.
+    Metric(DefEventMetric),
     Delay(DefEventDelay),
+    /// Moves the paused clock forward at once, rather than step by step.
+    /// Requires the clock to be paused.
+    AdvanceTime(DefEventAdvanceTime),
     Call(DefCallSub),
+    /// Reached once all the listed events are reached.
+    Barrier(Vec<EventName>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,12 +580,47 @@ pub struct DefEventRecv {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub also_match_data: Vec<DstPattern>,
 
+    /// Expect the message from an actor. A `$name` need not be declared: it
+    /// is bound to the first sender that has not been seen so far.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub from: Option<ActorName>,
 
+    /// Expect the message from a dummy rather than from an actor.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_dummy: Option<DummyName>,
+
+    /// Expect the message from any actor of the group, whichever instance it
+    /// is. No address is bound.
+    ///
+    /// The groups are those of elfo's test proxy: the group under test is
+    /// mounted as `subject`, the dummies belong to `system.testers`, and the
+    /// configurer to `system.configurers`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_group: Option<GroupName>,
+
+    /// Expect the message from the actor serving the given routing key: the
+    /// first one to match binds the key to the sender's address. The key may
+    /// refer to the variables bound so far.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_key: Option<Value>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub to: Option<DummyName>,
 
+    /// Expect a certain kind of envelope: by default, any kind matches.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<DefEnvelopeKind>,
+
+    /// Expect several matching envelopes rather than one. Only the last match
+    /// binds the variables.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<DefRecvCount>,
+
     #[serde(with = "humantime_serde")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
@@ -129,9 +628,40 @@ pub struct DefEventRecv {
     pub before_duration: Option<Duration>,
 
     #[serde(with = "humantime_serde")]
-    #[serde(skip_serializing_if = "Duration::is_zero")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub after_duration: Option<Duration>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// A sequence of messages expected from one sender in exactly the given order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventStream {
+    #[serde(rename = "type")]
+    pub message_type: MessageName,
+
+    /// The patterns of the messages, in the order they are expected to arrive.
+    pub items: Vec<DstPattern>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<ActorName>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_dummy: Option<DummyName>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<DummyName>,
+
+    /// How long to wait for each of the items.
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
-    pub after_duration: Duration,
+    pub timeout: Option<Duration>,
 
     #[serde(flatten)]
     pub no_extra: NoExtra,
@@ -139,41 +669,390 @@ pub struct DefEventRecv {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefEventSend {
-    pub from: DummyName,
+    /// Defaults to the scenario's `defaults.from`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DummyName>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub to: Option<ActorName>,
 
+    /// Send the message to a dummy rather than to an actor.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_dummy: Option<DummyName>,
+
+    /// Send a copy of the message to each of the actors bound to a name by
+    /// now.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub to_all: Vec<ActorName>,
+
+    /// Send the message to one of the actors bound to a name by now: the sends
+    /// to the same actors take turns.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub to_any: Vec<ActorName>,
+
+    /// Send the message to the actor serving the given routing key: directly,
+    /// if its address is already known (see [DefEventRecv::from_key]), or via
+    /// routing otherwise. The key may refer to the variables bound so far.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub routing_key: Option<Value>,
+
     #[serde(rename = "type")]
     pub message_type: MessageName,
     #[serde(rename = "data")]
     pub message_data: SrcMsg,
 
+    /// Overrides the scenario's faults for this message.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faults: Option<DefFaults>,
+
     #[serde(flatten)]
     pub no_extra: NoExtra,
 }
 
+/// The messages are rendered with `$index` bound to the number of the message,
+/// starting with zero.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventSendMany {
+    /// Defaults to the scenario's `defaults.from`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DummyName>,
+
+    /// Defaults to the scenario's `defaults.to`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<ActorName>,
+
+    #[serde(rename = "type")]
+    pub message_type: MessageName,
+    #[serde(rename = "data")]
+    pub message_data: SrcMsg,
+
+    pub count: usize,
+
+    /// The delay before each of the messages but the first.
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub interval: Option<Duration>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// How many matching envelopes a recv stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DefRecvCount {
+    /// `count: N`: the recv is reached on the N-th match.
+    AtLeast(usize),
+    /// `count: {exactly: N}`: the recv is reached once its timeout expires,
+    /// provided there have been exactly N matches by then. The variables are
+    /// bound by the last match, which is also the one to respond to; a match
+    /// over N leaves the recv unreached right away.
+    Exactly { exactly: usize },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefEventRespond {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub from:       Option<DummyName>,
+    #[cfg_attr(feature = "backward-compatibility", serde(alias = "to"))]
     pub to_request: EventName,
-    pub data:       SrcMsg,
+    /// The response. The payload of the request is bound to `$request`, so
+    /// that its fields can be referred to as `$request.field`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data:       Option<SrcMsg>,
+
+    /// Fail the request instead of responding to it.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fail: bool,
+
+    /// Hold the response for the given time: the event is reached once the
+    /// response is sent (or the request is failed).
+    #[serde(with = "humantime_serde")]
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Duration::is_zero")]
+    pub after: Duration,
 
     #[serde(flatten)]
     pub no_extra: NoExtra,
 }
 
+/// A request sent by a dummy, paired with the matcher of its response.
+///
+/// The event is reached once the response is received and matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventRequest {
+    /// The dummy issuing the request. The request is sent from an address of
+    /// its own, so that the dummy keeps receiving while it is pending.
+    pub from: DummyName,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<ActorName>,
+
+    /// Request each of the bound actors, and collect their responses: the
+    /// response pattern is matched against the array of them, in this order.
+    ///
+    /// The proxies of the dummies cannot issue a `RequestAll` of their own, so
+    /// the actors are requested one by one.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub to_all: Vec<ActorName>,
+
+    #[serde(rename = "type")]
+    pub message_type: MessageName,
+    #[serde(rename = "data")]
+    pub message_data: SrcMsg,
+
+    /// The pattern to match the response with.
+    pub response: DstPattern,
+
+    /// How long to wait for the response. Without it, the run waits for as
+    /// long as it takes.
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// Sends [`elfo::messages::UpdateConfig`] with the config built from a
+/// template.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventUpdateConfig {
+    pub from: DummyName,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<ActorName>,
+
+    pub config: SrcMsg,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// Sends [`elfo::messages::Terminate`] to an actor, or to the whole group if
+/// routed.
+///
+/// A restart, as the group's restart policy has it, is asserted with the
+/// `status` events and the recvs `from_group`: the restarted actor has an
+/// address of its own. There is no event starting a group: the topology under
+/// test is that of elfo's test proxy, which mounts the single group of the
+/// blueprint the run is started with; its actors are spawned as its router
+/// routes the messages to them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventTerminate {
+    pub from: DummyName,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<ActorName>,
+
+    /// Close the mailbox regardless of the actor's termination policy.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub closing: bool,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// Subscribes a dummy to [`elfo::messages::ActorStatusReport`]s of the actors
+/// in the group, and matches a report of the given kind.
+///
+/// The subscription is made when the event's prerequisites are reached, and
+/// the current statuses are reported right away, so a transition that has
+/// already happened by then is not missed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventStatus {
+    /// The dummy subscribing to the reports.
+    pub to: DummyName,
+
+    /// Match only the reports of the actor with this key, as elfo displays
+    /// it (e.g. `"1"` for the key `1u64` of a router): by default, the reports
+    /// of any actor of the group are.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+
+    pub kind: DefActorStatusKind,
+
+    /// The pattern to match the status details with.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<DstPattern>,
+
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// The kind of envelope a message arrives in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefEnvelopeKind {
+    /// A regular message.
+    Message,
+    /// A request, to be responded to.
+    Request,
+    /// A response to a request.
+    Response,
+}
+
+/// The elfo system topics a dummy may subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefSystemTopic {
+    /// [`elfo::messages::ActorStatusReport`]s of the actors in the group: from
+    /// their spawning on, including the transitions caused by the config
+    /// updates.
+    ActorStatuses,
+}
+
+/// Mirrors [`elfo::ActorStatusKind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DefActorStatusKind {
+    Initializing,
+    Normal,
+    Alarming,
+    Terminating,
+    Terminated,
+    Failed,
+}
+
+/// Matches a line logged by the actors under test, as captured by the runner.
+///
+/// The lines at `DEBUG` and above are captured from the start of the run; each
+/// line is matched by a single `log` event at most, regardless of whether it
+/// has been logged before the event's prerequisites are reached.
+///
+/// Combined with `require: unreached`, requires a line not to be logged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventLog {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<DefLogLevel>,
+
+    /// The target of the line, such as the module path of the callsite.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// The pattern to match the message with.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<DstPattern>,
+
+    /// The pattern to match the structured fields of the line with.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<DstPattern>,
+
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// Mirrors [`tracing::Level`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefLogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+/// Checks a metric recorded by the actors under test via the
+/// [`metrics`](https://docs.rs/metrics) facade, as elfo's telemetry is.
+///
+/// A counter is checked for the increase since the event's prerequisites have
+/// been reached, summed up over the series with the `labels`: by default, for
+/// any increase at all. A gauge is checked for its current value, of any of
+/// the series with the `labels`.
+///
+/// With a `timeout`, the metric is checked until it is within the bounds or
+/// the time runs out; otherwise — until there is nothing else left to do.
+///
+/// The metrics are only captured with the `metrics` feature: without it, the
+/// scenarios checking them fail to build.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventMetric {
+    #[serde(flatten)]
+    pub metric: DefMetric,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub at_least: Option<f64>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub at_most: Option<f64>,
+
+    #[serde(with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+/// The name of the metric checked, along with its kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefMetric {
+    Counter(String),
+    Gauge(String),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DefEventDelay {
     #[serde(with = "humantime_serde")]
     #[serde(rename = "for")]
     pub delay_for: Duration,
 
+    /// The resolution of the delay: defaults to the scenario's `defaults.step`,
+    /// and to 25ms unless that is set either.
     #[serde(with = "humantime_serde")]
     #[serde(rename = "step")]
-    #[serde(default = "defaults::default_delay_step")]
-    pub delay_step: Duration,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delay_step: Option<Duration>,
+
+    #[serde(flatten)]
+    pub no_extra: NoExtra,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefEventAdvanceTime {
+    /// The duration to advance the clock by. The timers expiring within it
+    /// fire all at once, as with [tokio::time::advance].
+    #[serde(with = "humantime_serde")]
+    #[serde(rename = "by")]
+    pub advance_by: Duration,
 
     #[serde(flatten)]
     pub no_extra: NoExtra,
@@ -184,6 +1063,7 @@ pub struct DefEventDelay {
 #[serde(rename_all = "snake_case")]
 pub enum SrcMsg {
     /// Stores [Value] to be marshalled as [elfo::AnyMessage] as-is.
+    #[cfg_attr(feature = "backward-compatibility", serde(alias = "exact"))]
     Literal(Value),
     /// Stores [Value] to be bound with values for variables in it and then
     /// marshalled as [elfo::AnyMessage].
@@ -197,7 +1077,7 @@ pub enum SrcMsg {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DstPattern(pub Value);
 
-mod defaults {
+pub(crate) mod defaults {
     use std::time::Duration;
 
     pub fn default_delay_step() -> Duration {
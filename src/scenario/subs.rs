@@ -38,6 +38,15 @@ pub struct DefCallSub {
     pub actors:  Option<BiHashMap<ActorName, ActorName>>,
     pub dummies: Option<BiHashMap<DummyName, DummyName>>,
 
+    /// The subroutine's bindings are its own: only `in` and `out` cross the
+    /// boundary of the call. With this flag set, all the caller's values are
+    /// also bound in the subroutine's scope when it is entered, and all the
+    /// subroutine's values are bound in the caller's scope when it is left.
+    /// A shared value conflicting with the one bound already fails the run.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub share_bindings: bool,
+
     #[serde(flatten)]
     pub no_extra: NoExtra,
 }
@@ -10,6 +10,10 @@
 //! Scenarios from the preceding paths may "shadow" those having the same name,
 //! but from the paths coming later in the search-path list.
 //!
+//! A file may contain several YAML documents, each of them being a scenario
+//! with a `name`. Such a scenario is referred to as `file.luci.yaml#name`; a
+//! reference without the `#name` part is to the first scenario of the file.
+//!
 //! Example:
 //!
 //! ```rust
@@ -38,12 +42,16 @@
 //! a map from [`SubroutineName`] to the [`KeyScenario`] corresponding to the
 //! subroutine's scenario.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut, Index};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{fmt, io};
 
+use serde::{Deserialize, Serialize};
 use slotmap::SlotMap;
 use tracing::trace;
 
@@ -73,6 +81,12 @@ pub enum LoadError {
 
     #[error("duplicate subroutine definition: {}", _0)]
     DuplicateSubroutine(SubroutineName),
+
+    #[error("document not found: {:?}", _0)]
+    DocumentNotFound(PathBuf),
+
+    #[error("duplicate document name: {:?}", _0)]
+    DuplicateDocument(PathBuf),
 }
 
 #[derive(Debug)]
@@ -80,16 +94,20 @@ pub struct SourceCodeLoader {
     pub search_path: Vec<PathBuf>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct SourceCode {
     by_effective_path:  BTreeMap<Arc<Path>, KeyScenario>,
     pub(crate) sources: SlotMap<KeyScenario, SingleScenarioSource>,
+    loaded_in:          Duration,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SingleScenarioSource {
-    pub source_file: Arc<Path>,
-    pub scenario:    Scenario,
-    pub subroutines: BTreeMap<SubroutineName, KeyScenario>,
+    pub source_file:  Arc<Path>,
+    pub scenario:     Scenario,
+    pub subroutines:  BTreeMap<SubroutineName, KeyScenario>,
+    /// The hash of the contents of the source file.
+    pub content_hash: u64,
 }
 
 impl Index<KeyScenario> for SourceCode {
@@ -100,6 +118,29 @@ impl Index<KeyScenario> for SourceCode {
     }
 }
 
+impl SourceCode {
+    /// Iterates over all the loaded scenarios.
+    pub fn scenarios(&self) -> impl Iterator<Item = (KeyScenario, &SingleScenarioSource)> + '_ {
+        self.sources.iter()
+    }
+
+    /// Wall-clock time it took to load the scenarios.
+    pub fn loaded_in(&self) -> Duration {
+        self.loaded_in
+    }
+
+    /// The hash of the paths and the contents of all the loaded files: it
+    /// stays the same as long as none of the files changes.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (effective_path, source_key) in self.by_effective_path.iter() {
+            effective_path.hash(&mut hasher);
+            self.sources[*source_key].content_hash.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
 impl SourceCodeLoader {
     pub fn new() -> Self {
         Default::default()
@@ -128,26 +169,31 @@ impl SourceCodeLoader {
         &self,
         entry_point_scenario: impl Into<PathBuf>,
     ) -> Result<(KeyScenario, SourceCode), LoadError> {
-        let main = sanitize_path(&entry_point_scenario.into())?;
+        let t_start = Instant::now();
+        let (main, main_document) = split_document(&entry_point_scenario.into());
+        let main = sanitize_path(&main)?;
 
         let mut sources: SourceCode = Default::default();
         let mut context = LoaderContext {
-            search_path: &self.search_path,
-            this_dir:    Path::new("."),
-            this_file:   &main,
-            sources:     &mut sources,
+            search_path:   &self.search_path,
+            this_dir:      Path::new("."),
+            this_file:     &main,
+            this_document: main_document.as_deref(),
+            sources:       &mut sources,
         };
         let root_source_key = context.load()?;
+        sources.loaded_in = t_start.elapsed();
 
         Ok((root_source_key, sources))
     }
 }
 
 struct LoaderContext<'a> {
-    search_path: &'a [PathBuf],
-    this_dir:    &'a Path,
-    this_file:   &'a Path,
-    sources:     &'a mut SourceCode,
+    search_path:   &'a [PathBuf],
+    this_dir:      &'a Path,
+    this_file:     &'a Path,
+    this_document: Option<&'a str>,
+    sources:       &'a mut SourceCode,
 }
 
 impl Default for SourceCodeLoader {
@@ -168,8 +214,10 @@ impl LoaderContext<'_> {
         let effective_path = self.choose_effective_path()?;
         let (source_key, is_new) = self.read_scenario(effective_path.as_ref())?;
 
-        if parent_keys.iter().any(|pk| *pk == source_key) {
-            return Err(LoadError::SourceFileCyclicDependency(effective_path));
+        if parent_keys.contains(&source_key) {
+            return Err(LoadError::SourceFileCyclicDependency(
+                self.sources.sources[source_key].source_file.to_path_buf(),
+            ));
         }
 
         if !is_new {
@@ -181,11 +229,13 @@ impl LoaderContext<'_> {
         let subroutines = source.scenario.subroutines.clone();
         for import in subroutines {
             let parent_keys = &mut *PopOnDrop::new(parent_keys, source_key);
+            let (import_file, import_document) = split_document(&import.file_name);
             let mut context = LoaderContext {
-                search_path: self.search_path,
-                this_dir:    &base_dir,
-                this_file:   &sanitize_path(&import.file_name)?,
-                sources:     self.sources,
+                search_path:   self.search_path,
+                this_dir:      &base_dir,
+                this_file:     &sanitize_path(&import_file)?,
+                this_document: import_document.as_deref(),
+                sources:       self.sources,
             };
             let sub_source_key = context.load_inner(parent_keys)?;
             if self.sources.sources[source_key]
@@ -231,24 +281,87 @@ impl LoaderContext<'_> {
     }
 
     fn read_scenario(&mut self, effective_path: &Path) -> Result<(KeyScenario, bool), LoadError> {
-        if let Some(key) = self.sources.by_effective_path.get(effective_path).copied() {
-            Ok((key, false))
-        } else {
-            let source_code = std::fs::read_to_string(effective_path).map_err(LoadError::Io)?;
-            let scenario: Scenario =
-                serde_yaml::from_str(&source_code).map_err(LoadError::Syntax)?;
-            let source_file: Arc<Path> = effective_path.into();
-            let source = SingleScenarioSource {
-                scenario,
-                source_file: source_file.clone(),
-                subroutines: Default::default(),
-            };
-            let key = self.sources.sources.insert(source);
-            self.sources.by_effective_path.insert(source_file, key);
+        let requested_path = document_path(effective_path, self.this_document);
+        if let Some(key) = self
+            .sources
+            .by_effective_path
+            .get(&*requested_path)
+            .copied()
+        {
+            return Ok((key, false))
+        }
 
-            Ok((key, true))
+        let source_code = std::fs::read_to_string(effective_path).map_err(LoadError::Io)?;
+        let mut documents = serde_yaml::Deserializer::from_str(&source_code)
+            .map(Scenario::deserialize)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(LoadError::Syntax)?;
+
+        let mut names = BTreeSet::new();
+        if let Some(duplicate) = documents
+            .iter()
+            .filter_map(|d| d.name.as_deref())
+            .find(|name| !names.insert(*name))
+        {
+            return Err(LoadError::DuplicateDocument(document_path(
+                effective_path,
+                Some(duplicate),
+            )))
+        }
+
+        let is_multi_document = documents.len() > 1;
+        let idx = match self.this_document {
+            None => (!documents.is_empty()).then_some(0),
+            Some(name) => {
+                documents
+                    .iter()
+                    .position(|d| d.name.as_deref() == Some(name))
+            },
+        }
+        .ok_or(LoadError::DocumentNotFound(requested_path))?;
+        let scenario = documents.swap_remove(idx);
+
+        // the scenarios of a multi-document file are indexed individually.
+        let source_file: Arc<Path> = match scenario.name.as_deref() {
+            Some(name) if is_multi_document => document_path(effective_path, Some(name)),
+            _ => effective_path.to_owned(),
+        }
+        .into();
+        if let Some(key) = self.sources.by_effective_path.get(&source_file).copied() {
+            return Ok((key, false))
         }
+
+        let mut hasher = DefaultHasher::new();
+        source_code.hash(&mut hasher);
+        let source = SingleScenarioSource {
+            scenario,
+            source_file: source_file.clone(),
+            subroutines: Default::default(),
+            content_hash: hasher.finish(),
+        };
+        let key = self.sources.sources.insert(source);
+        self.sources.by_effective_path.insert(source_file, key);
+
+        Ok((key, true))
+    }
+}
+
+/// Splits `file.luci.yaml#name` into the path to the file and the name of the
+/// scenario within it.
+pub(crate) fn split_document(p: &Path) -> (PathBuf, Option<String>) {
+    match p.to_str().and_then(|s| s.rsplit_once('#')) {
+        Some((file, name)) => (file.into(), Some(name.to_owned())),
+        None => (p.to_owned(), None),
+    }
+}
+
+fn document_path(file: &Path, document: Option<&str>) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    if let Some(name) = document {
+        path.push("#");
+        path.push(name);
     }
+    path.into()
 }
 
 fn sanitize_path(p: &Path) -> Result<PathBuf, LoadError> {
@@ -0,0 +1,794 @@
+//! Running a whole directory of scenarios.
+//!
+//! A [`Suite`] discovers the `*.luci.yaml` files under a directory, and runs
+//! each of them against a fresh blueprint and marshalling registry produced by
+//! the user-provided factories. The outcomes are collected into a
+//! [`SuiteReport`], which renders either as a human-readable summary or, with
+//! [`SuiteReport::tap`], in the TAP format.
+//!
+//! By default, scenarios are run one after another on the current runtime, so
+//! the usual `tokio::time::pause()` in the calling test applies to all of them.
+//!
+//! With [`Suite::with_concurrency`] the scenarios are run on a pool of worker
+//! threads instead. Each scenario gets a runtime of its own, with the clock
+//! paused, so that the scenarios share neither the proxies nor the time.
+//!
+//! With [`Suite::with_repeat`] each scenario is run several times, to shake
+//! out the flaky ones. The default number of runs may be set with the
+//! `LUCI_REPEAT` environment variable, e.g. `LUCI_REPEAT=100 cargo test`.
+//!
+//! A scenario with `cases` is run once per case, regardless of the repeat,
+//! and passes only if it passes in every case.
+//!
+//! With [`Suite::with_time_scale`] the durations of the scenarios are scaled,
+//! see [`RunOptions::with_time_scale`]. The default factor may be set with the
+//! `LUCI_TIME_SCALE` environment variable, e.g. `LUCI_TIME_SCALE=10 cargo
+//! test`.
+//!
+//! With [`Suite::with_github_annotations`] the failures are also written as the
+//! GitHub Actions annotations; when the `GITHUB_ACTIONS` environment variable
+//! is set to `true`, they are written to the stdout by default.
+//!
+//! With [`Suite::with_build_cache`] the scenarios whose files have not changed
+//! are not built again.
+//!
+//! With the `metrics` feature, the outcomes are also reported via the
+//! [`metrics`](https://docs.rs/metrics) facade (install an exporter, e.g. the
+//! one of `metrics-exporter-prometheus`, to have them scraped):
+//! - `luci_scenarios_total` — a counter of the scenarios run, labeled by the
+//!   `result` (`passed`, `failed`, `build-error`, etc.);
+//! - `luci_scenario_duration_seconds` — a histogram of the wall-clock time a
+//!   scenario took to load, build and run, labeled by the `scenario`;
+//! - `luci_event_fired_seconds` — a histogram of the wall-clock time from the
+//!   start of a run to the firing of an event, labeled by the `scenario`, by
+//!   the `event` (qualified as `call/inner` within the subroutines) and by its
+//!   `kind`.
+//!
+//! The files that are loaded as subroutines by other scenarios of the same
+//! suite are not run on their own.
+//!
+//! The `luci suite` command only builds the scenarios, as it knows neither the
+//! blueprint nor the messages. To run them from the command line, a crate
+//! makes a binary of its own calling [`main`], e.g. a test target with
+//! `harness = false`:
+//! ```ignore
+//! fn main() -> std::process::ExitCode {
+//!     luci::suite::main(my_crate::marshalling, my_crate::blueprint)
+//! }
+//! ```
+//! and runs it as `cargo test --test scenarios -- tests/scenarios -j 4`.
+
+use std::collections::{HashSet, VecDeque};
+use std::num::NonZeroUsize;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{fmt, io};
+
+use clap::Parser;
+use elfo::Blueprint;
+use futures::channel::mpsc;
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde_json::Value;
+use tracing::{debug, info, warn};
+
+use crate::execution::{
+    file_annotation, BuildCache, Executable, KeyScenario, RunOptions, SourceCode, SourceCodeLoader,
+};
+use crate::marshalling::MarshallingRegistry;
+use crate::sources::LoadError;
+
+const SCENARIO_SUFFIX: &str = ".luci.yaml";
+const ENV_REPEAT: &str = "LUCI_REPEAT";
+const ENV_TIME_SCALE: &str = "LUCI_TIME_SCALE";
+const ENV_GITHUB_ACTIONS: &str = "GITHUB_ACTIONS";
+
+type Loaded = Result<(KeyScenario, SourceCode), LoadError>;
+type Annotations = Arc<Mutex<dyn io::Write + Send>>;
+
+#[derive(Clone)]
+pub struct Suite {
+    root:        PathBuf,
+    search_path: Vec<PathBuf>,
+    marshalling: Arc<dyn Fn() -> MarshallingRegistry + Send + Sync>,
+    blueprint:   Arc<dyn Fn() -> Blueprint + Send + Sync>,
+    config:      Value,
+    values:      Vec<(String, Value)>,
+    concurrency: NonZeroUsize,
+    repeat:      NonZeroUsize,
+    options:     RunOptions,
+    build_cache: Option<Arc<BuildCache>>,
+    annotations: Option<Annotations>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SuiteReport {
+    pub outcomes: Vec<ScenarioOutcome>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioOutcome {
+    /// The path of the scenario, relative to the root of the suite.
+    pub scenario: PathBuf,
+    pub result:   ScenarioResult,
+    pub timings:  Timings,
+}
+
+/// The command line of a binary running a suite, see [`main`].
+#[derive(Parser, Debug, Clone)]
+#[command(about = "Run every scenario found in a directory.")]
+pub struct SuiteArgs {
+    #[clap(help = "Directory to look for *.luci.yaml files in")]
+    pub dir:         PathBuf,
+    #[clap(
+        long = "search-path",
+        short = 'I',
+        help = "Additional directories to look for subroutines in"
+    )]
+    pub search_path: Vec<PathBuf>,
+    #[clap(
+        long = "concurrency",
+        short = 'j',
+        default_value = "1",
+        help = "Number of scenarios to run at the same time"
+    )]
+    pub concurrency: NonZeroUsize,
+    #[clap(
+        long = "repeat",
+        help = "Number of times to run each scenario (default: $LUCI_REPEAT, or 1)"
+    )]
+    pub repeat:      Option<NonZeroUsize>,
+    #[clap(
+        long = "time-scale",
+        value_parser = parse_time_scale,
+        help = "Factor to scale the durations of the scenarios by (default: $LUCI_TIME_SCALE, or 1)"
+    )]
+    pub time_scale:  Option<f64>,
+    #[clap(
+        long = "tap",
+        default_value_t = false,
+        help = "Print the report in the TAP format"
+    )]
+    pub tap:         bool,
+}
+
+/// Wall-clock time spent in each phase of a scenario.
+///
+/// The phases that were not reached are left zero.
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    pub load:  Duration,
+    pub build: Duration,
+    pub start: Duration,
+    pub run:   Duration,
+}
+
+#[derive(Debug, Clone)]
+pub enum ScenarioResult {
+    Passed,
+    /// The run has completed, but the requirements were not met. Contains the
+    /// rendered [`Report`](crate::execution::Report).
+    Failed(String),
+    LoadError(String),
+    BuildError(String),
+    RunError(String),
+}
+
+impl Suite {
+    /// Creates a suite of the scenarios found under `root`.
+    ///
+    /// `marshalling` and `blueprint` are invoked once per scenario.
+    pub fn new<M, B>(root: impl Into<PathBuf>, marshalling: M, blueprint: B) -> Self
+    where
+        M: Fn() -> MarshallingRegistry + Send + Sync + 'static,
+        B: Fn() -> Blueprint + Send + Sync + 'static,
+    {
+        Self {
+            root:        root.into(),
+            search_path: vec![],
+            marshalling: Arc::new(marshalling),
+            blueprint:   Arc::new(blueprint),
+            config:      Value::Null,
+            values:      vec![],
+            concurrency: NonZeroUsize::MIN,
+            repeat:      repeat_from_env(),
+            options:     options_from_env(),
+            build_cache: None,
+            annotations: annotations_from_env(),
+        }
+    }
+
+    /// Creates a suite as the command line `args` describe it.
+    pub fn from_args<M, B>(args: &SuiteArgs, marshalling: M, blueprint: B) -> Self
+    where
+        M: Fn() -> MarshallingRegistry + Send + Sync + 'static,
+        B: Fn() -> Blueprint + Send + Sync + 'static,
+    {
+        let suite = Self::new(&args.dir, marshalling, blueprint)
+            .with_search_path(&args.search_path)
+            .with_concurrency(args.concurrency);
+        let suite = match args.repeat {
+            Some(repeat) => suite.with_repeat(repeat),
+            None => suite,
+        };
+        match args.time_scale {
+            Some(time_scale) => suite.with_time_scale(time_scale),
+            None => suite,
+        }
+    }
+
+    /// Adds directories to look for the subroutines in (besides the root of
+    /// the suite).
+    pub fn with_search_path<I, P>(mut self, extra_search_path: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.search_path
+            .extend(extra_search_path.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the config passed to the blueprint in every run.
+    pub fn with_config(self, config: Value) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Sets the values bound in the root scope of every run.
+    pub fn with_values(self, values: impl IntoIterator<Item = (String, Value)>) -> Self {
+        Self {
+            values: values.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Sets the number of scenarios allowed to run at the same time.
+    ///
+    /// With the concurrency above one, each scenario runs on a worker thread,
+    /// in a runtime of its own with the paused clock.
+    pub fn with_concurrency(self, concurrency: NonZeroUsize) -> Self {
+        Self {
+            concurrency,
+            ..self
+        }
+    }
+
+    /// Sets the number of times each scenario is run.
+    ///
+    /// A scenario passes only if every run of it passes; otherwise, its
+    /// failure describes how often each requirement was not met.
+    pub fn with_repeat(self, repeat: NonZeroUsize) -> Self {
+        Self { repeat, ..self }
+    }
+
+    /// Sets the factor the durations of every scenario are multiplied by.
+    ///
+    /// # Panics
+    /// Panics if `time_scale` is not a finite positive number.
+    pub fn with_time_scale(self, time_scale: f64) -> Self {
+        Self {
+            options: self.options.with_time_scale(time_scale),
+            ..self
+        }
+    }
+
+    /// Sets the cache to reuse the executables built from the unchanged
+    /// sources, e.g. by the other suites of the same test process.
+    pub fn with_build_cache(self, build_cache: Arc<BuildCache>) -> Self {
+        Self {
+            build_cache: Some(build_cache),
+            ..self
+        }
+    }
+
+    /// Sets where to write the GitHub Actions annotations of the failures to.
+    /// When run by GitHub Actions, they are written to the stdout by default.
+    ///
+    /// See [Report::github_annotations](crate::execution::Report::github_annotations).
+    pub fn with_github_annotations(self, annotations: impl io::Write + Send + 'static) -> Self {
+        Self {
+            annotations: Some(Arc::new(Mutex::new(annotations))),
+            ..self
+        }
+    }
+
+    /// Disables the GitHub Actions annotations, even when run by GitHub
+    /// Actions.
+    pub fn without_github_annotations(self) -> Self {
+        Self {
+            annotations: None,
+            ..self
+        }
+    }
+
+    /// Runs all the scenarios of the suite.
+    pub async fn run(&self) -> Result<SuiteReport, io::Error> {
+        let scenarios = discover(&self.root)?;
+
+        let loaded = scenarios
+            .into_iter()
+            .map(|scenario| {
+                let loaded = self.loader().load(&scenario);
+                (scenario, loaded)
+            })
+            .collect::<Vec<_>>();
+
+        let subroutines = loaded
+            .iter()
+            .filter_map(|(_, loaded)| loaded.as_ref().ok())
+            .flat_map(|(key_main, sources)| {
+                sources
+                    .scenarios()
+                    .filter(move |(key, _)| key != key_main)
+                    .filter_map(|(_, source)| source.source_file.canonicalize().ok())
+            })
+            .collect::<HashSet<_>>();
+
+        let to_run = loaded
+            .into_iter()
+            .filter(|(scenario, _)| {
+                let is_subroutine = self
+                    .root
+                    .join(scenario)
+                    .canonicalize()
+                    .is_ok_and(|p| subroutines.contains(&p));
+                if is_subroutine {
+                    debug!("skipping {:?}: it is a subroutine", scenario);
+                }
+                !is_subroutine
+            })
+            .collect::<Vec<_>>();
+
+        let outcomes = if self.concurrency.get() > 1 {
+            self.run_concurrently(to_run).await
+        } else {
+            let mut outcomes = vec![];
+            for (scenario, loaded) in to_run {
+                info!("running {:?}", scenario);
+                outcomes.push(self.run_loaded(scenario, loaded).await);
+            }
+            outcomes
+        };
+        #[cfg(feature = "metrics")]
+        outcomes.iter().for_each(crate::metrics::scenario_finished);
+
+        Ok(SuiteReport { outcomes })
+    }
+
+    /// Runs a single scenario, its path being relative to the root of the
+    /// suite.
+    pub async fn run_scenario(&self, scenario: impl AsRef<Path>) -> ScenarioOutcome {
+        let scenario = scenario.as_ref().to_owned();
+        let loaded = self.loader().load(&scenario);
+        let outcome = self.run_loaded(scenario, loaded).await;
+        #[cfg(feature = "metrics")]
+        crate::metrics::scenario_finished(&outcome);
+        outcome
+    }
+
+    fn loader(&self) -> SourceCodeLoader {
+        SourceCodeLoader::new()
+            .reset_search_path()
+            .with_search_path([&self.root])
+            .with_search_path(&self.search_path)
+    }
+
+    /// Runs the scenarios on `self.concurrency` worker threads.
+    ///
+    /// The runners are not `Send`, so rather than spawning them as tasks, each
+    /// scenario is run to completion on a fresh current-thread runtime.
+    async fn run_concurrently(&self, scenarios: Vec<(PathBuf, Loaded)>) -> Vec<ScenarioOutcome> {
+        let workers = self.concurrency.get().min(scenarios.len());
+        let queue = Arc::new(Mutex::new(
+            scenarios.into_iter().enumerate().collect::<VecDeque<_>>(),
+        ));
+        let (outcomes_tx, outcomes_rx) = mpsc::unbounded();
+
+        for _ in 0..workers {
+            let suite = self.clone();
+            let queue = queue.clone();
+            let outcomes_tx = outcomes_tx.clone();
+
+            std::thread::spawn(move || {
+                while let Some((idx, (scenario, loaded))) = {
+                    // NOTE: bind the popped item so that the lock is released before the run
+                    let next = queue.lock().pop_front();
+                    next
+                } {
+                    info!("running {:?}", scenario);
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_time()
+                        .start_paused(true)
+                        .build()
+                        .expect("tokio runtime");
+                    let outcome = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        runtime.block_on(suite.run_loaded(scenario.clone(), loaded))
+                    }))
+                    .unwrap_or_else(|payload| {
+                        let reason = match payload.downcast_ref::<&str>() {
+                            Some(message) => format!("panicked: {}", message),
+                            None => {
+                                match payload.downcast_ref::<String>() {
+                                    Some(message) => format!("panicked: {}", message),
+                                    None => "panicked".into(),
+                                }
+                            },
+                        };
+                        ScenarioOutcome {
+                            scenario,
+                            result: ScenarioResult::RunError(reason),
+                            timings: Default::default(),
+                        }
+                    });
+
+                    if outcomes_tx.unbounded_send((idx, outcome)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(outcomes_tx);
+
+        let mut outcomes = outcomes_rx.collect::<Vec<_>>().await;
+        outcomes.sort_by_key(|(idx, _)| *idx);
+        outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+    }
+
+    async fn run_loaded(&self, scenario: PathBuf, loaded: Loaded) -> ScenarioOutcome {
+        let mut timings = Timings::default();
+        let result = match loaded {
+            Ok((key_main, sources)) => self.run_built(key_main, &sources, &mut timings).await,
+            Err(reason) => ScenarioResult::LoadError(reason.to_string()),
+        };
+        if let ScenarioResult::LoadError(reason)
+        | ScenarioResult::BuildError(reason)
+        | ScenarioResult::RunError(reason) = &result
+        {
+            self.annotate_file(&self.root.join(&scenario), reason);
+        }
+        ScenarioOutcome {
+            scenario,
+            result,
+            timings,
+        }
+    }
+
+    async fn run_built(
+        &self,
+        key_main: KeyScenario,
+        sources: &SourceCode,
+        timings: &mut Timings,
+    ) -> ScenarioResult {
+        timings.load = sources.loaded_in();
+
+        let built = match self.build_cache.as_deref() {
+            Some(build_cache) => build_cache.build((self.marshalling)(), sources, key_main),
+            None => Executable::build((self.marshalling)(), sources, key_main),
+        };
+        let executable = match built {
+            Ok(executable) => executable,
+            Err(reason) => return ScenarioResult::BuildError(reason.to_string()),
+        };
+        timings.build = executable.built_in();
+
+        let t_start = Instant::now();
+        if !executable.cases().is_empty() {
+            let cases = executable
+                .run_cases_with_options(
+                    executable.cases().to_vec(),
+                    || (self.blueprint)(),
+                    self.config.clone(),
+                    self.values.clone(),
+                    self.options,
+                )
+                .await;
+            timings.start = cases
+                .cases
+                .iter()
+                .filter_map(|case| case.outcome.as_ref().ok())
+                .map(|report| report.stats.start_time)
+                .sum();
+            timings.run = t_start.elapsed().saturating_sub(timings.start);
+            #[cfg(feature = "metrics")]
+            for report in cases
+                .cases
+                .iter()
+                .filter_map(|case| case.outcome.as_ref().ok())
+            {
+                crate::metrics::events_fired(&sources[key_main].source_file, &executable, report);
+            }
+            return if cases.is_ok() {
+                ScenarioResult::Passed
+            } else {
+                let message = cases.message(&executable, sources).to_string();
+                self.annotate_file(&sources[key_main].source_file, &message);
+                ScenarioResult::Failed(message)
+            };
+        }
+        if self.repeat.get() > 1 {
+            let soak = executable
+                .run_repeated_with_options(
+                    self.repeat.get(),
+                    || (self.blueprint)(),
+                    self.config.clone(),
+                    self.values.clone(),
+                    self.options,
+                )
+                .await;
+            timings.start = soak.start_time;
+            timings.run = t_start.elapsed().saturating_sub(timings.start);
+            return if soak.is_ok() {
+                ScenarioResult::Passed
+            } else {
+                let message = soak.message(&executable).to_string();
+                self.annotate_file(&sources[key_main].source_file, &message);
+                ScenarioResult::Failed(message)
+            };
+        }
+
+        let report = match executable
+            .start_with_options(
+                (self.blueprint)(),
+                self.config.clone(),
+                self.values.clone(),
+                self.options,
+            )
+            .await
+            .run()
+            .await
+        {
+            Ok(report) => report,
+            Err(reason) => {
+                timings.run = t_start.elapsed();
+                return ScenarioResult::RunError(reason.to_string())
+            },
+        };
+        timings.start = report.stats.start_time;
+        timings.run = report.stats.run_time;
+        #[cfg(feature = "metrics")]
+        crate::metrics::events_fired(&sources[key_main].source_file, &executable, &report);
+
+        if report.is_ok() {
+            ScenarioResult::Passed
+        } else {
+            self.annotate(report.github_annotations(&executable, sources));
+            ScenarioResult::Failed(report.message(&executable, sources).to_string())
+        }
+    }
+
+    fn annotate_file(&self, source_file: &Path, message: &str) {
+        self.annotate(format_args!("{}\n", file_annotation(source_file, message)));
+    }
+
+    fn annotate(&self, annotations: impl fmt::Display) {
+        let Some(out) = self.annotations.as_ref() else {
+            return
+        };
+        if let Err(reason) = write!(out.lock(), "{}", annotations) {
+            warn!("failed to write the annotations: {}", reason);
+        }
+    }
+}
+
+impl SuiteReport {
+    pub fn is_ok(&self) -> bool {
+        self.outcomes.iter().all(ScenarioOutcome::is_ok)
+    }
+
+    pub fn passed(&self) -> usize {
+        self.outcomes.iter().filter(|o| o.is_ok()).count()
+    }
+}
+
+impl SuiteReport {
+    /// Renders the report in the [TAP](https://testanything.org) format, version
+    /// 13: a test point per scenario, the failures followed by a YAML block
+    /// describing them.
+    pub fn tap(&self) -> impl fmt::Display + '_ {
+        DisplayTap(self)
+    }
+}
+
+impl ScenarioOutcome {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.result, ScenarioResult::Passed)
+    }
+}
+
+impl ScenarioResult {
+    /// A short name of the result, such as `passed` or `build-error`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed(_) => "failed",
+            Self::LoadError(_) => "load-error",
+            Self::BuildError(_) => "build-error",
+            Self::RunError(_) => "run-error",
+        }
+    }
+
+    /// The description of the failure, if the scenario has not passed.
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Passed => None,
+            Self::Failed(message)
+            | Self::LoadError(message)
+            | Self::BuildError(message)
+            | Self::RunError(message) => Some(message),
+        }
+    }
+}
+
+struct DisplayTap<'a>(&'a SuiteReport);
+
+impl fmt::Display for DisplayTap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(report) = self;
+
+        writeln!(f, "TAP version 13")?;
+        writeln!(f, "1..{}", report.outcomes.len())?;
+        for (idx, outcome) in report.outcomes.iter().enumerate() {
+            let status = if outcome.is_ok() { "ok" } else { "not ok" };
+            writeln!(f, "{} {} - {}", status, idx + 1, outcome.scenario.display())?;
+
+            let Some(message) = outcome.result.message() else {
+                continue
+            };
+            writeln!(f, "  ---")?;
+            writeln!(f, "  result: {}", outcome.result.kind())?;
+            writeln!(f, "  message: |")?;
+            for line in message.lines() {
+                writeln!(f, "    {}", line)?;
+            }
+            writeln!(f, "  ...")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SuiteReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "SUITE: {} passed, {} failed",
+            self.passed(),
+            self.outcomes.len() - self.passed()
+        )?;
+        for outcome in &self.outcomes {
+            writeln!(f, "{}", outcome)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ScenarioOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scenario = self.scenario.display();
+        match &self.result {
+            ScenarioResult::Passed => write!(f, " + {} ({})", scenario, self.timings),
+            ScenarioResult::Failed(report) => {
+                write!(f, " - {}: failed ({})", scenario, self.timings)?;
+                for line in report.lines() {
+                    write!(f, "\n   {}", line)?;
+                }
+                Ok(())
+            },
+            ScenarioResult::LoadError(reason) => {
+                write!(f, " - {}: load error: {}", scenario, reason)
+            },
+            ScenarioResult::BuildError(reason) => {
+                write!(f, " - {}: build error: {}", scenario, reason)
+            },
+            ScenarioResult::RunError(reason) => write!(f, " - {}: run error: {}", scenario, reason),
+        }
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "load: {:?}, build: {:?}, start: {:?}, run: {:?}",
+            self.load, self.build, self.start, self.run
+        )
+    }
+}
+
+/// Runs the suite the command line of the process describes (see
+/// [`SuiteArgs`]), against the given `marshalling` and `blueprint`, and prints
+/// its report.
+///
+/// The scenarios are run on a runtime with the paused clock. The exit code is
+/// a failure, unless every scenario has passed.
+pub fn main<M, B>(marshalling: M, blueprint: B) -> ExitCode
+where
+    M: Fn() -> MarshallingRegistry + Send + Sync + 'static,
+    B: Fn() -> Blueprint + Send + Sync + 'static,
+{
+    let args = SuiteArgs::parse();
+    let suite = Suite::from_args(&args, marshalling, blueprint);
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .start_paused(true)
+        .build()
+        .expect("tokio runtime");
+    let report = match runtime.block_on(suite.run()) {
+        Ok(report) => report,
+        Err(reason) => {
+            eprintln!("failed to discover the scenarios: {}", reason);
+            return ExitCode::FAILURE
+        },
+    };
+
+    if args.tap {
+        print!("{}", report.tap());
+    } else {
+        print!("{}", report);
+    }
+    if report.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_time_scale(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(time_scale) if time_scale.is_finite() && time_scale > 0.0 => Ok(time_scale),
+        Ok(_) => Err("should be a finite positive number".into()),
+        Err(reason) => Err(reason.to_string()),
+    }
+}
+
+fn repeat_from_env() -> NonZeroUsize {
+    std::env::var(ENV_REPEAT)
+        .ok()
+        .and_then(|repeat| repeat.parse().ok())
+        .unwrap_or(NonZeroUsize::MIN)
+}
+
+fn annotations_from_env() -> Option<Annotations> {
+    std::env::var(ENV_GITHUB_ACTIONS)
+        .is_ok_and(|v| v == "true")
+        .then(|| Arc::new(Mutex::new(io::stdout())) as Annotations)
+}
+
+fn options_from_env() -> RunOptions {
+    std::env::var(ENV_TIME_SCALE)
+        .ok()
+        .and_then(|time_scale| time_scale.parse::<f64>().ok())
+        .filter(|time_scale| time_scale.is_finite() && *time_scale > 0.0)
+        .map(|time_scale| RunOptions::default().with_time_scale(time_scale))
+        .unwrap_or_default()
+}
+
+/// Finds all the `*.luci.yaml` files under `root`.
+///
+/// Returns the paths relative to `root`, sorted.
+pub fn discover(root: impl AsRef<Path>) -> Result<Vec<PathBuf>, io::Error> {
+    fn walk(root: &Path, relative: &Path, out: &mut Vec<PathBuf>) -> Result<(), io::Error> {
+        for entry in std::fs::read_dir(root.join(relative))? {
+            let entry = entry?;
+            let relative = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                walk(root, &relative, out)?;
+            } else if entry
+                .file_name()
+                .to_str()
+                .is_some_and(|n| n.ends_with(SCENARIO_SUFFIX))
+            {
+                out.push(relative);
+            }
+        }
+        Ok(())
+    }
+
+    let mut out = vec![];
+    walk(root.as_ref(), Path::new(""), &mut out)?;
+    out.sort();
+    Ok(out)
+}
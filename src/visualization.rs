@@ -1,10 +1,48 @@
-use std::collections::HashSet;
-use std::fmt::Display;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Write};
 
 use dot_writer::{Attributes, DotWriter, Scope};
 
+use crate::execution::{
+    Cast, CastMember, KeyScenario, KeyScope, SourceCode, Timeline, TimelineEnd,
+};
+use crate::names::EventName;
 use crate::scenario::{DefEvent, DefEventKind, Scenario};
 
+/// The options of [draw_source_code].
+#[derive(Debug, Clone, Default)]
+pub struct DrawOptions {
+    /// Whether the nodes show the definitions of the events.
+    pub verbose: bool,
+    /// Whether the called subroutines are drawn event by event, in a cluster
+    /// per call, rather than as a single node per call.
+    pub expand:  bool,
+    /// The calls drawn the other way than `expand` says, by their paths, such
+    /// as `outer-call/inner-call`.
+    pub toggled: HashSet<String>,
+}
+
+impl DrawOptions {
+    pub fn with_verbose(self, verbose: bool) -> Self {
+        Self { verbose, ..self }
+    }
+
+    pub fn with_expand(self, expand: bool) -> Self {
+        Self { expand, ..self }
+    }
+
+    /// Draws the call at `path` expanded if the rest are collapsed, and vice
+    /// versa.
+    pub fn with_toggled(mut self, path: impl Into<String>) -> Self {
+        self.toggled.insert(path.into());
+        self
+    }
+
+    fn expands(&self, path: &str) -> bool {
+        self.expand != self.toggled.contains(path)
+    }
+}
+
 pub fn draw_scenario(scenario: &Scenario, verbose: bool) -> String {
     let mut output_bytes = Vec::new();
 
@@ -16,15 +54,14 @@ pub fn draw_scenario(scenario: &Scenario, verbose: bool) -> String {
 
     let mut seen_ids = HashSet::new();
     for event in scenario
-        .events
-        .iter()
+        .all_events()
         .filter(|event| seen_ids.insert(event.id.clone()))
     {
-        draw_node(&mut digraph, event, verbose);
+        draw_node(&mut digraph, &event.id, &event, None, verbose);
     }
 
-    for event in &scenario.events {
-        for subnode_name in &event.prerequisites {
+    for event in scenario.all_events() {
+        for subnode_name in event.all_prerequisites() {
             digraph.edge(quote(subnode_name), quote(&event.id));
         }
     }
@@ -34,23 +71,438 @@ pub fn draw_scenario(scenario: &Scenario, verbose: bool) -> String {
     String::from_utf8(output_bytes).unwrap()
 }
 
-fn draw_node(digraph: &mut Scope, event: &DefEvent, verbose: bool) {
-    let mut node = digraph.node_named(quote(&event.id));
+/// Renders the scenario along with the subroutines it calls, as loaded into
+/// `source_code`.
+///
+/// An expanded call is drawn as a cluster holding the call's node and the
+/// events of the subroutine, named the way the caller refers to them, e.g.
+/// `call-id/event-id`: the call's node leads to the subroutine's first events,
+/// and its last events lead to the events happening after the call.
+///
+/// A collapsed call is drawn as a single node, labelled with the numbers of
+/// the events and the edges it hides: the events happening after an event of
+/// the subroutine are drawn as happening after the call.
+pub fn draw_source_code(
+    source_code: &SourceCode,
+    key_main: KeyScenario,
+    options: &DrawOptions,
+) -> String {
+    let mut output_bytes = Vec::new();
+
+    let mut writer = DotWriter::from(&mut output_bytes);
+    writer.set_pretty_print(true);
+
+    let mut digraph = writer.digraph();
+    digraph.set_rank_direction(dot_writer::RankDirection::LeftRight);
+
+    let mut graph = SubGraph::default();
+    draw_events(
+        &mut digraph,
+        &mut graph,
+        source_code,
+        key_main,
+        None,
+        options,
+    );
+
+    let mut seen_edges = HashSet::new();
+    let entries = graph
+        .entries
+        .iter()
+        .map(|(call, first)| (vec![call.clone()], graph.collapsed_into(first)));
+    let edges = graph.edges.iter().map(|(src, dst)| {
+        // an expanded call is left once all its last events have fired
+        let srcs = match graph.exits.get(src) {
+            Some(exits) => exits.clone(),
+            None => vec![graph.collapsed_into(src)],
+        };
+        (srcs, graph.collapsed_into(dst))
+    });
+    for (srcs, dst) in entries.chain(edges) {
+        for src in srcs {
+            if src != dst && seen_edges.insert((src.clone(), dst.clone())) {
+                digraph.edge(quote(&src), quote(&dst));
+            }
+        }
+    }
+
+    drop(digraph);
+
+    String::from_utf8(output_bytes).unwrap()
+}
+
+/// The state of [draw_source_code] shared by the scopes it descends into.
+#[derive(Default)]
+struct SubGraph {
+    edges:     Vec<(EventName, EventName)>,
+    /// The edges from the expanded calls to the first events of their
+    /// subroutines.
+    entries:   Vec<(EventName, EventName)>,
+    /// The paths of the collapsed calls.
+    collapsed: Vec<EventName>,
+    /// The last events of each expanded call.
+    exits:     HashMap<EventName, Vec<EventName>>,
+}
+
+impl SubGraph {
+    /// The node drawn for the event at `path`: its collapsed call, if any.
+    fn collapsed_into(&self, path: &EventName) -> EventName {
+        self.collapsed
+            .iter()
+            .find(|call| {
+                path.as_ref()
+                    .strip_prefix(call.as_ref())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .unwrap_or(path)
+            .clone()
+    }
+}
+
+/// The name the event is referred to by from the outermost scenario.
+fn path_of(call: Option<&EventName>, event_id: &EventName) -> EventName {
+    match call {
+        None => event_id.clone(),
+        Some(call) => call.with_suffix(&format!("/{}", event_id.as_ref())),
+    }
+}
+
+fn draw_events(
+    scope: &mut Scope,
+    graph: &mut SubGraph,
+    source_code: &SourceCode,
+    key_scenario: KeyScenario,
+    call: Option<&EventName>,
+    options: &DrawOptions,
+) {
+    let source = &source_code[key_scenario];
+    let scenario = &source.scenario;
+
+    let mut seen_ids = HashSet::new();
+    for event in scenario
+        .all_events()
+        .filter(|event| seen_ids.insert(event.id.clone()))
+    {
+        let path = path_of(call, &event.id);
+        let key_sub = match &event.kind {
+            DefEventKind::Call(def) => source.subroutines.get(&def.subroutine_name),
+            _ => None,
+        };
+        match key_sub {
+            Some(key_sub) if options.expands(path.as_ref()) => {
+                let mut cluster = scope.cluster();
+                cluster.set_label(path.as_ref());
+                draw_node(&mut cluster, &path, &event, None, options.verbose);
+                draw_events(
+                    &mut cluster,
+                    graph,
+                    source_code,
+                    *key_sub,
+                    Some(&path),
+                    options,
+                );
+
+                let sub = &source_code[*key_sub].scenario;
+                for first in sub
+                    .all_events()
+                    .filter(|e| e.all_prerequisites().next().is_none())
+                {
+                    let first = path_of(Some(&path), &first.id);
+                    graph.entries.push((path.clone(), first));
+                }
+                let exits = last_events(sub)
+                    .into_iter()
+                    .flat_map(|last| {
+                        let last = path_of(Some(&path), &last);
+                        graph
+                            .exits
+                            .get(&last)
+                            .cloned()
+                            .unwrap_or_else(|| vec![last])
+                    })
+                    .collect::<Vec<_>>();
+                let exits = if exits.is_empty() {
+                    vec![path.clone()]
+                } else {
+                    exits
+                };
+                graph.exits.insert(path, exits);
+            },
+            Some(key_sub) => {
+                let (events, edges) = count_events_and_edges(source_code, *key_sub);
+                let summary = format!("{} events, {} edges", events, edges);
+                draw_node(scope, &path, &event, Some(&summary), options.verbose);
+                graph.collapsed.push(path);
+            },
+            None => draw_node(scope, &path, &event, None, options.verbose),
+        }
+    }
+
+    for event in scenario.all_events() {
+        for prerequisite in event.all_prerequisites() {
+            graph
+                .edges
+                .push((path_of(call, prerequisite), path_of(call, &event.id)));
+        }
+    }
+}
+
+/// The events no other event of the scenario happens after.
+fn last_events(scenario: &Scenario) -> Vec<EventName> {
+    let prerequisites = scenario
+        .all_events()
+        .flat_map(|event| event.all_prerequisites().cloned().collect::<Vec<_>>())
+        .collect::<HashSet<_>>();
+    let mut seen_ids = HashSet::new();
+    scenario
+        .all_events()
+        .map(|event| event.id.clone())
+        .filter(|id| !prerequisites.contains(id) && seen_ids.insert(id.clone()))
+        .collect()
+}
+
+/// The numbers of the events and of the edges of the scenario, including those
+/// of the subroutines it calls.
+fn count_events_and_edges(source_code: &SourceCode, key_scenario: KeyScenario) -> (usize, usize) {
+    let source = &source_code[key_scenario];
+    let mut seen_ids = HashSet::new();
+    source
+        .scenario
+        .all_events()
+        .filter(|event| seen_ids.insert(event.id.clone()))
+        .fold((0, 0), |(events, edges), event| {
+            let (sub_events, sub_edges) = match &event.kind {
+                DefEventKind::Call(call) => {
+                    source
+                        .subroutines
+                        .get(&call.subroutine_name)
+                        .map(|key_sub| count_events_and_edges(source_code, *key_sub))
+                        .unwrap_or_default()
+                },
+                _ => (0, 0),
+            };
+            (
+                events + 1 + sub_events,
+                edges + event.all_prerequisites().count() + sub_edges,
+            )
+        })
+}
+
+fn draw_node(
+    digraph: &mut Scope,
+    node_id: &EventName,
+    event: &DefEvent,
+    summary: Option<&str>,
+    verbose: bool,
+) {
+    let mut node = digraph.node_named(quote(node_id));
 
     let (kind, data) = match &event.kind {
         DefEventKind::Bind(bind) => ("BIND", serde_yaml::to_string(&bind).unwrap()),
         DefEventKind::Recv(recv) => ("RECV", serde_yaml::to_string(&recv).unwrap()),
+        DefEventKind::Stream(stream) => ("STREAM", serde_yaml::to_string(&stream).unwrap()),
         DefEventKind::Send(send) => ("SEND", serde_yaml::to_string(&send).unwrap()),
+        DefEventKind::SendMany(send) => ("SEND MANY", serde_yaml::to_string(&send).unwrap()),
         DefEventKind::Respond(respond) => ("RESPOND", serde_yaml::to_string(&respond).unwrap()),
+        DefEventKind::Request(request) => ("REQUEST", serde_yaml::to_string(&request).unwrap()),
+        DefEventKind::UpdateConfig(update) => {
+            ("UPDATE CONFIG", serde_yaml::to_string(&update).unwrap())
+        },
+        DefEventKind::Terminate(terminate) => {
+            ("TERMINATE", serde_yaml::to_string(&terminate).unwrap())
+        },
+        DefEventKind::StopDummy(dummy) => ("STOP DUMMY", serde_yaml::to_string(&dummy).unwrap()),
+        DefEventKind::Status(status) => ("STATUS", serde_yaml::to_string(&status).unwrap()),
+        DefEventKind::Log(log) => ("LOG", serde_yaml::to_string(&log).unwrap()),
+        DefEventKind::Metric(metric) => ("METRIC", serde_yaml::to_string(&metric).unwrap()),
         DefEventKind::Delay(delay) => ("DELAY", serde_yaml::to_string(&delay).unwrap()),
+        DefEventKind::AdvanceTime(advance) => {
+            ("ADVANCE TIME", serde_yaml::to_string(&advance).unwrap())
+        },
         DefEventKind::Call(call) => ("CALL", serde_yaml::to_string(&call).unwrap()),
+        DefEventKind::Barrier(joined) => ("BARRIER", serde_yaml::to_string(&joined).unwrap()),
     };
 
     let data = if verbose { data } else { "".to_string() };
-    let label = format!(r#"{}\nid={}\n\n{}"#, kind, event.id, data);
+    let label = match summary {
+        None => format!(r#"{}\nid={}\n\n{}"#, kind, node_id, data),
+        Some(summary) => format!(r#"{}\nid={}\n{}\n\n{}"#, kind, node_id, summary, data),
+    };
     node.set_label(&label);
 }
 
 fn quote(str: &impl Display) -> String {
     format!("\"{}\"", str)
 }
+
+/// Renders the [Cast] as a DOT graph: a cluster per scope, a node per name, and
+/// an edge per name mapping of a subroutine call.
+pub fn draw_cast(cast: &Cast) -> String {
+    let scope_idx = scope_indices(cast);
+
+    let mut output_bytes = Vec::new();
+
+    let mut writer = DotWriter::from(&mut output_bytes);
+    writer.set_pretty_print(true);
+
+    let mut digraph = writer.digraph();
+    digraph.set_rank_direction(dot_writer::RankDirection::LeftRight);
+
+    for (idx, scope) in cast.scopes.iter().enumerate() {
+        let mut cluster = digraph.cluster();
+        cluster.set_label(&scope.path);
+
+        for_each_name(cast, scope.key, |node_id, label| {
+            cluster
+                .node_named(quote(&format!("s{idx}_{node_id}")))
+                .set_label(label);
+        });
+    }
+
+    for_each_mapping(cast, &scope_idx, |caller, callee, via| {
+        digraph
+            .edge(quote(&caller), quote(&callee))
+            .attributes()
+            .set_label(via);
+    });
+
+    drop(digraph);
+
+    String::from_utf8(output_bytes).unwrap()
+}
+
+/// Renders the [Cast] as a mermaid flowchart, structured the same way as in
+/// [draw_cast].
+pub fn draw_cast_mermaid(cast: &Cast) -> String {
+    let scope_idx = scope_indices(cast);
+
+    let mut out = String::new();
+    writeln!(out, "flowchart LR").unwrap();
+
+    for (idx, scope) in cast.scopes.iter().enumerate() {
+        writeln!(out, "  subgraph s{idx} [\"{}\"]", mermaid_text(&scope.path)).unwrap();
+        for_each_name(cast, scope.key, |node_id, label| {
+            writeln!(out, "    s{idx}_{node_id}[\"{}\"]", mermaid_text(label)).unwrap();
+        });
+        writeln!(out, "  end").unwrap();
+    }
+
+    for_each_mapping(cast, &scope_idx, |caller, callee, via| {
+        writeln!(out, "  {caller} -- \"{}\" --> {callee}", mermaid_text(via)).unwrap();
+    });
+
+    out
+}
+
+/// Renders the [Timeline] as a mermaid gantt chart, a section per scope, in
+/// milliseconds since the start of the run: the events that fired are `done`,
+/// those that timed out are `crit`, and those still pending by the end of the
+/// run are `active`.
+pub fn draw_timeline_mermaid(timeline: &Timeline) -> String {
+    let mut sections: Vec<(&str, Vec<usize>)> = vec![];
+    for (idx, bar) in timeline.bars.iter().enumerate() {
+        match sections.iter_mut().find(|(scope, _)| *scope == bar.scope) {
+            Some((_, bars)) => bars.push(idx),
+            None => sections.push((&bar.scope, vec![idx])),
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "gantt").unwrap();
+    writeln!(out, "  dateFormat x").unwrap();
+    writeln!(out, "  axisFormat %S.%L").unwrap();
+
+    for (scope, bars) in sections {
+        writeln!(out, "  section {}", gantt_text(scope)).unwrap();
+        for idx in bars {
+            let bar = &timeline.bars[idx];
+            let (tag, end) = match bar.end {
+                TimelineEnd::Fired(at) => ("done", at),
+                TimelineEnd::TimedOut(at) => ("crit", at),
+                TimelineEnd::Pending => ("active", timeline.run_time.max(bar.ready_at)),
+            };
+            writeln!(
+                out,
+                "    {} :{tag}, e{idx}, {}, {}",
+                gantt_text(bar.name.as_ref()),
+                bar.ready_at.as_millis(),
+                end.as_millis(),
+            )
+            .unwrap();
+        }
+    }
+
+    out
+}
+
+/// The quoted text of a flowchart: the quotes and the hashes (which start
+/// mermaid's entity codes) are written as entity codes, and so are the angle
+/// brackets, lest they be taken for HTML.
+fn mermaid_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '#' => escaped.push_str("#35;"),
+            '"' => escaped.push_str("#quot;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The colons and the hashes would be taken for the mermaid syntax.
+fn gantt_text(text: &str) -> String {
+    text.replace([':', '#'], "_")
+}
+
+fn scope_indices(cast: &Cast) -> HashMap<KeyScope, usize> {
+    cast.scopes
+        .iter()
+        .enumerate()
+        .map(|(idx, scope)| (scope.key, idx))
+        .collect()
+}
+
+fn for_each_name(cast: &Cast, scope_key: KeyScope, mut f: impl FnMut(&str, &str)) {
+    fn names_in<N: Display>(
+        members: &[CastMember<N>],
+        prefix: &str,
+        scope_key: KeyScope,
+        f: &mut impl FnMut(&str, &str),
+    ) {
+        for (idx, member) in members.iter().enumerate() {
+            for (_, name) in member.known_as.iter().filter(|(s, _)| *s == scope_key) {
+                f(&format!("{prefix}{idx}"), &name.to_string());
+            }
+        }
+    }
+
+    names_in(&cast.actors, "a", scope_key, &mut f);
+    names_in(&cast.dummies, "d", scope_key, &mut f);
+}
+
+fn for_each_mapping(
+    cast: &Cast,
+    scope_idx: &HashMap<KeyScope, usize>,
+    mut f: impl FnMut(String, String, &str),
+) {
+    fn mappings_of<N>(
+        members: &[CastMember<N>],
+        prefix: &str,
+        scope_idx: &HashMap<KeyScope, usize>,
+        f: &mut impl FnMut(String, String, &str),
+    ) {
+        for (idx, member) in members.iter().enumerate() {
+            for mapping in &member.mappings {
+                let caller = format!("s{}_{prefix}{idx}", scope_idx[&mapping.caller.0]);
+                let callee = format!("s{}_{prefix}{idx}", scope_idx[&mapping.callee.0]);
+                f(caller, callee, mapping.via.as_ref());
+            }
+        }
+    }
+
+    mappings_of(&cast.actors, "a", scope_idx, &mut f);
+    mappings_of(&cast.dummies, "d", scope_idx, &mut f);
+}
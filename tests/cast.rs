@@ -0,0 +1,133 @@
+use luci::execution::{Executable, SourceCodeLoader};
+use luci::marshalling::{MarshallingRegistry, Mock};
+use luci::visualization::{draw_cast, draw_cast_mermaid, draw_source_code, DrawOptions};
+
+#[test]
+fn subroutine_cast() {
+    let marshalling = MarshallingRegistry::new()
+        .with(Mock::request("subroutines::proto::smalltalk::Whatsup"))
+        .with(Mock::regular("subroutines::proto::smalltalk::OhByTheWay"))
+        .with(Mock::regular("subroutines::proto::smalltalk::NoWay"))
+        .with(Mock::request("subroutines::proto::partying::MayI"))
+        .with(Mock::regular("subroutines::proto::partying::SeeYou"))
+        .with(Mock::regular("subroutines::proto::partying::Chug"))
+        .with(Mock::regular("subroutines::proto::partying::Gulp"));
+    let (key_main, sources) = SourceCodeLoader::new()
+        .with_search_path(["tests/subroutines"])
+        .load("main.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling, &sources, key_main).expect("building graph");
+
+    let cast = executable.cast();
+
+    let mut paths = cast
+        .scopes
+        .iter()
+        .map(|s| s.path.as_str())
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(paths, [".", "smalltalk-with-the-host"]);
+
+    let [guest] = &cast.actors[..] else {
+        panic!("expected a single actor: {:?}", cast.actors)
+    };
+    assert_eq!(guest.known_as.len(), 2);
+    let [mapping] = &guest.mappings[..] else {
+        panic!("expected a single mapping: {:?}", guest.mappings)
+    };
+    assert_eq!(mapping.caller.1.to_string(), "A:guest");
+    assert_eq!(mapping.callee.1.to_string(), "A:ALICE");
+    assert_eq!(mapping.via.to_string(), "E:smalltalk-with-the-host");
+
+    let [host] = &cast.dummies[..] else {
+        panic!("expected a single dummy: {:?}", cast.dummies)
+    };
+    assert_eq!(host.mappings.len(), 1);
+
+    assert!(draw_cast(&cast).contains("smalltalk-with-the-host"));
+    assert!(draw_cast_mermaid(&cast).starts_with("flowchart LR"));
+}
+
+#[test]
+fn mermaid_labels_are_escaped() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/cast/quoted-names.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable =
+        Executable::build(MarshallingRegistry::new(), &sources, key_main).expect("building graph");
+
+    let mermaid = draw_cast_mermaid(&executable.cast());
+    assert!(
+        mermaid.contains(r#"["D:peer #quot;a#quot; | #lt;b#gt;"]"#),
+        "{}",
+        mermaid
+    );
+    assert!(mermaid.contains(r#"["D:client[1]"]"#), "{}", mermaid);
+    assert!(
+        mermaid.contains(r#"["call #quot;sub#quot; [#35;1]"]"#),
+        "{}",
+        mermaid
+    );
+    assert!(
+        mermaid.contains(r#"-- "call #quot;sub#quot; [#35;1]" -->"#),
+        "{}",
+        mermaid
+    );
+    // the quotes delimit the labels only
+    for line in mermaid.lines() {
+        assert!(line.matches('"').count() % 2 == 0, "{}", line);
+    }
+}
+
+#[test]
+fn subroutine_graph() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .with_search_path(["tests/subroutines"])
+        .load("qualified-happens-after.luci.yaml")
+        .expect("SourceLoader::load");
+
+    let collapsed = draw_source_code(&sources, key_main, &DrawOptions::default());
+    assert!(collapsed.contains("9 events, 8 edges"), "{}", collapsed);
+    assert!(
+        collapsed.contains(r#""E:smalltalk-with-the-host" -> "E:guest-arrives-to-the-party""#),
+        "{}",
+        collapsed
+    );
+    assert!(!collapsed.contains("ROBERT-greets"), "{}", collapsed);
+
+    let expanded = draw_source_code(
+        &sources,
+        key_main,
+        &DrawOptions::default().with_expand(true),
+    );
+    assert!(
+        expanded.contains(
+            r#""E:smalltalk-with-the-host/ROBERT-greets" -> "E:guest-arrives-to-the-party""#
+        ),
+        "{}",
+        expanded
+    );
+    assert!(
+        expanded.contains(
+            r#""E:smalltalk-with-the-host" -> "E:smalltalk-with-the-host/ALICE-arrives""#
+        ),
+        "{}",
+        expanded
+    );
+    // the subroutine is entered through the call only
+    assert_eq!(
+        expanded
+            .matches(r#"-> "E:smalltalk-with-the-host/ALICE-arrives""#)
+            .count(),
+        1,
+        "{}",
+        expanded
+    );
+
+    let toggled = draw_source_code(
+        &sources,
+        key_main,
+        &DrawOptions::default().with_toggled("smalltalk-with-the-host"),
+    );
+    assert_eq!(toggled, expanded);
+}
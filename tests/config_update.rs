@@ -1,8 +1,8 @@
 use elfo::config::AnyConfig;
 use elfo::messages::UpdateConfig;
 use elfo::AnyMessage;
-use luci::execution::{Executable, SourceCodeLoader};
-use luci::marshalling::{Injected, MarshallingRegistry, Regular};
+use luci::execution::{Executable, RunErrorReason, SourceCodeLoader};
+use luci::marshalling::{AnError, Bindings, Injected, InjectedWith, MarshallingRegistry, Regular};
 use serde_json::json;
 
 mod proto {
@@ -52,32 +52,160 @@ mod configurable {
 
 #[tokio::test]
 async fn config_update() {
-    let scenario_file = "tests/config_update/scenario.luci.yaml";
-    let config_0 = json!({
-        "value": 1,
-    });
     let config_1 = json!({
         "value": 2,
     });
+    let marshalling = marshalling().with(Injected {
+        key:   "update-config".into(),
+        value: update_config(config_1),
+    });
+    run_scenario(marshalling).await;
+}
 
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_max_level(tracing::Level::TRACE)
-        .try_init();
+#[tokio::test]
+async fn config_update_with_injector() {
+    let marshalling = marshalling().with(InjectedWith {
+        key:      "update-config".into(),
+        injector: |bindings: Bindings| -> Result<AnyMessage, AnError> {
+            let value = bindings.get("$VALUE_2").ok_or("$VALUE_2 is unbound")?;
+            Ok(update_config(json!({ "value": value })))
+        },
+    });
+    run_scenario(marshalling).await;
+}
+
+#[tokio::test]
+async fn panicking_injector() {
     tokio::time::pause();
 
+    let marshalling = marshalling().with(InjectedWith {
+        key:      "update-config".into(),
+        injector: |_: Bindings| -> Result<AnyMessage, AnError> { panic!("no config today") },
+    });
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/config_update/scenario.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling, &sources, key_main).expect("building graph");
+    let error = executable
+        .start(
+            configurable::blueprint(),
+            json!({ "value": 1 }),
+            [("$VALUE_1".into(), json!(1)), ("$VALUE_2".into(), json!(2))],
+        )
+        .await
+        .run()
+        .await
+        .expect_err("the injector panics");
+
+    let RunErrorReason::Panicked { reason } = &error.reason else {
+        panic!("unexpected error: {}", error)
+    };
+    assert_eq!(reason, "no config today");
+    let event = error.event.as_ref().expect("the event being fired");
+    assert_eq!(event.name.as_ref(), "update-config");
+    assert!(error.record.is_some());
+}
+
+#[tokio::test]
+async fn config_update_event() {
     let marshalling = MarshallingRegistry::new()
+        .with(Regular::<crate::proto::Ping>)
+        .with(Regular::<crate::proto::Pong>);
+    run_scenario_file(
+        marshalling,
+        "tests/config_update/update-config-event.luci.yaml",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn embedded_config() {
+    run_scenario_file(
+        marshalling(),
+        "tests/config_update/embedded-config.luci.yaml",
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn merged_config() {
+    run_scenario_file(marshalling(), "tests/config_update/merged-config.luci.yaml").await;
+}
+
+#[tokio::test]
+async fn unbound_config() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/config_update/unbound-config.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let error = executable
+        .start(configurable::blueprint(), json!({ "value": 1 }), [])
+        .await
+        .run()
+        .await
+        .expect_err("the config refers to an unbound value");
+    assert!(
+        matches!(error.reason, RunErrorReason::Config(_)),
+        "{}",
+        error.reason
+    );
+}
+
+#[tokio::test]
+async fn config_matrix() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/config_update/matrix.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start_matrix(
+            configurable::blueprint,
+            [("default", json!({})), ("value-2", json!({ "value": 2 }))],
+            [],
+        )
+        .with_base_config(json!({ "value": 1 }))
+        .run()
+        .await;
+
+    assert_eq!(report.passed(), 1);
+    let default = report.get("default").expect("default");
+    assert!(default.is_ok(), "{}", report.message(&executable, &sources));
+    let value_2 = report.get("value-2").expect("value-2");
+    assert!(!value_2.is_ok());
+    assert_eq!(value_2.config, json!({ "value": 2 }));
+}
+
+fn marshalling() -> MarshallingRegistry {
+    MarshallingRegistry::new()
         .with(Regular::<crate::proto::Ping>)
         .with(Regular::<crate::proto::Pong>)
         .with(Regular::<elfo::messages::UpdateConfig>)
-        .with(Injected {
-            key:   "update-config".into(),
-            value: AnyMessage::new({
-                let serde_value = serde_json::from_value(config_1).expect("serde_value");
-                let any_config = AnyConfig::from_value(serde_value);
-                UpdateConfig::new(any_config)
-            }),
-        });
+}
+
+fn update_config(config: serde_json::Value) -> AnyMessage {
+    let serde_value = serde_json::from_value(config).expect("serde_value");
+    let any_config = AnyConfig::from_value(serde_value);
+    AnyMessage::new(UpdateConfig::new(any_config))
+}
+
+async fn run_scenario(marshalling: MarshallingRegistry) {
+    run_scenario_file(marshalling, "tests/config_update/scenario.luci.yaml").await;
+}
+
+async fn run_scenario_file(marshalling: MarshallingRegistry, scenario_file: &str) {
+    let config_0 = json!({
+        "value": 1,
+    });
+
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_max_level(tracing::Level::TRACE)
+        .try_init();
+    tokio::time::pause();
 
     let (key_main, sources) = SourceCodeLoader::new()
         .load(scenario_file)
@@ -1,6 +1,18 @@
-use luci::execution::{Executable, SourceCodeLoader};
-use luci::marshalling::{MarshallingRegistry, Regular, Request};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use elfo::Blueprint;
+use luci::execution::{
+    self, BuildCache, BuildOptions, EventKind, Executable, PrecompiledError, RunError,
+    RunErrorReason, SourceCodeLoader, Style, TimelineEnd,
+};
+use luci::marshalling::{CatchAll, InjectedResponse, MarshallingRegistry, Regular, Request};
+use luci::record::Recording;
+use luci::scenario::{BindingType, DefRequirement, RequiredToBe};
+use luci::suite::Suite;
+use luci::visualization::draw_timeline_mermaid;
 use serde_json::json;
+use tokio_util::sync::CancellationToken;
 
 pub mod proto {
     use elfo::message;
@@ -9,35 +21,111 @@ pub mod proto {
     #[message]
     pub struct Hey;
 
+    /// Makes the echo request everyone it is routed to with a [R], and send
+    /// back the `responses` collected.
+    #[message]
+    pub struct HeyAll;
+
     #[message]
     pub struct V(pub Value);
 
     #[message(ret = Value)]
     pub struct R(pub Value);
+
+    #[message]
+    pub struct Poison;
 }
 
 pub mod echo {
-    use elfo::{msg, ActorGroup, Blueprint, Context};
+    use std::time::Duration;
+
+    use elfo::routers::{MapRouter, Outcome};
+    use elfo::{msg, ActorGroup, Blueprint, Context, RestartParams, RestartPolicy};
+    // the macros refer to `metrics`: that of the version elfo records with.
+    use metrics_017 as metrics;
     use serde_json::json;
 
     use crate::proto;
 
-    pub async fn actor(mut ctx: Context) {
+    pub async fn actor<K>(mut ctx: Context<(), K>) {
         while let Some(envelope) = ctx.recv().await {
             let sender = envelope.sender();
             msg!(match envelope {
                 proto::Hey => {
-                    ctx.request_to(sender, proto::R(json!("hello!")))
+                    let response = ctx
+                        .request_to(sender, proto::R(json!("hello!")))
+                        .resolve()
+                        .await;
+                    if response.is_err() {
+                        let _ = ctx
+                            .send_to(sender, proto::V(json!({ "error": "request failed" })))
+                            .await;
+                    }
+                },
+                proto::HeyAll => {
+                    let responses = ctx
+                        .request(proto::R(json!({ "hello": "all" })))
+                        .all()
                         .resolve()
                         .await
-                        .expect("oh :(");
+                        .into_iter()
+                        .map(|response| response.unwrap_or(json!({ "error": "request failed" })))
+                        .collect::<Vec<_>>();
+                    let _ = ctx
+                        .send_to(sender, proto::V(json!({ "responses": responses })))
+                        .await;
                 },
                 v @ proto::V => {
+                    metrics::increment_counter!("echo_echoed_total", "type" => "V");
                     let _ = ctx.send_to(sender, v).await;
                 },
                 (r @ proto::R, t) => {
                     ctx.respond(t, r.0);
                 },
+                proto::Poison => panic!("poisoned"),
+            })
+        }
+    }
+
+    pub fn blueprint() -> Blueprint {
+        ActorGroup::new().exec(actor)
+    }
+
+    /// Several echo actors: a [proto::V] goes to the one its `to` field names.
+    pub fn routed_blueprint() -> Blueprint {
+        ActorGroup::new()
+            .router(MapRouter::new(|envelope| {
+                msg!(match envelope {
+                    proto::V(v) => v["to"].as_u64().map_or(Outcome::Default, Outcome::Unicast),
+                    _ => Outcome::Default,
+                })
+            }))
+            .exec(actor)
+    }
+
+    /// The echo restarted shortly after it fails.
+    pub fn restarting_blueprint() -> Blueprint {
+        let params = RestartParams::new(Duration::from_millis(10), Duration::from_millis(100));
+        ActorGroup::new()
+            .restart_policy(RestartPolicy::on_failure(params))
+            .exec(actor)
+    }
+}
+
+pub mod logger {
+    //! Logs the payloads of the [proto::V]s it receives, on behalf of a crate
+    //! whose name starts with `luci`.
+    use elfo::{msg, ActorGroup, Blueprint, Context};
+
+    use crate::proto;
+
+    pub async fn actor(mut ctx: Context) {
+        while let Some(envelope) = ctx.recv().await {
+            msg!(match envelope {
+                proto::V(v) => {
+                    let payload = v["payload"].as_str().unwrap_or_default();
+                    tracing::warn!(target: "lucid", payload, "the payload is logged");
+                },
             })
         }
     }
@@ -53,7 +141,7 @@ async fn bind_node() {
 }
 
 #[tokio::test]
-async fn marshalling() {
+async fn marshalling_scenario() {
     run_scenario("tests/echo/marshalling.luci.yaml", []).await;
 }
 
@@ -63,43 +151,1680 @@ async fn request_response() {
 }
 
 #[tokio::test]
-async fn check_init_bind() {
-    run_scenario(
-        "tests/echo/check-init-bind.luci.yaml",
-        [
-            ("$ARG_1".into(), json!("one")),
-            ("$ARG_2".into(), json!("two")),
-        ],
+async fn respond_with_request() {
+    run_scenario("tests/echo/respond-with-request.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn delayed_respond() {
+    run_scenario("tests/echo/delayed-respond.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn envelope_kind() {
+    run_scenario("tests/echo/envelope-kind.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn envelope_kind_mismatch() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/envelope-kind-mismatch.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    let mut record_log = vec![];
+    report
+        .dump_record_log_with_style(&mut record_log, &sources, &executable, Style::Never)
+        .expect("dump_record_log");
+    let record_log = String::from_utf8(record_log).expect("utf-8");
+    assert!(
+        record_log.contains("MISMATCH KIND exp=Message, act=Request"),
+        "{}",
+        record_log
+    );
+}
+
+#[test]
+fn envelope_kind_not_a_request() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/envelope-kind-not-a-request.luci.yaml")
+        .expect("SourceLoader::load");
+    let err = Executable::build(marshalling(), &sources, key_main)
+        .expect_err("Hey is not a request")
+        .to_string();
+    assert!(
+        err.contains("not a request: E:server-requests-a-Hey"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn injected_response() {
+    let marshalling = marshalling().with(InjectedResponse::<crate::proto::R> {
+        key:   "greeting-response".into(),
+        value: json!("hi there!"),
+    });
+    try_run_scenario_with(marshalling, "tests/echo/injected-response.luci.yaml", [])
+        .await
+        .expect("runner.run");
+}
+
+#[tokio::test]
+async fn multiple_responses() {
+    run_scenario("tests/echo/multiple-responses.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn request_all_responses() {
+    let marshalling = marshalling().with(Regular::<crate::proto::HeyAll>);
+    try_run_scenario_with(
+        marshalling,
+        "tests/echo/request-all-responses.luci.yaml",
+        [],
     )
-    .await;
+    .await
+    .expect("runner.run");
 }
 
-async fn run_scenario(
-    scenario_file: &str,
-    args: impl IntoIterator<Item = (String, serde_json::Value)>,
-) {
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .with_max_level(tracing::Level::TRACE)
-        .try_init();
+#[tokio::test]
+async fn to_all_to_any() {
+    run_scenario("tests/echo/to-all-to-any.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn to_all_to_any_bound() {
+    try_run_scenario_on(
+        echo::routed_blueprint(),
+        marshalling(),
+        "tests/echo/to-all-to-any-bound.luci.yaml",
+        [],
+        None,
+    )
+    .await
+    .expect("runner.run");
+}
+
+#[tokio::test]
+async fn to_any_none_bound() {
+    let err = try_run_scenario("tests/echo/to-any-none-bound.luci.yaml", [])
+        .await
+        .expect_err("none of the actors has been bound");
+    let RunErrorReason::NoneBound(actors) = err.reason else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(actors.len(), 2);
+}
+
+#[tokio::test]
+async fn dummy_pool() {
+    run_scenario("tests/echo/dummy-pool.luci.yaml", []).await;
+}
+
+#[test]
+fn dummy_pool_invalid() {
+    for scenario_file in [
+        "tests/echo/dummy-pool-empty.luci.yaml",
+        "tests/echo/dummy-pool-unsized.luci.yaml",
+    ] {
+        let (key_main, sources) = SourceCodeLoader::new()
+            .load(scenario_file)
+            .expect("SourceLoader::load");
+        let err = Executable::build(marshalling(), &sources, key_main)
+            .expect_err("the pool should have been rejected")
+            .to_string();
+        assert!(
+            err.contains("invalid pool of dummies"),
+            "{}: {}",
+            scenario_file,
+            err
+        );
+    }
+}
+
+#[test]
+fn too_many_dummies() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/too-many-dummies.luci.yaml")
+        .expect("SourceLoader::load");
+    let err = Executable::build(marshalling(), &sources, key_main)
+        .expect_err("the dummies should have been over the budget")
+        .to_string();
+    assert!(
+        err.contains("too many dummies: 2 (at most 1 allowed)"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn stop_dummy() {
     tokio::time::pause();
 
-    let marshalling = MarshallingRegistry::new()
-        .with(Regular::<crate::proto::V>)
-        .with(Request::<crate::proto::R>)
-        .with(Regular::<crate::proto::Hey>);
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/stop-dummy.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    let [server_stops] = executable.events_named("server-stops")[..] else {
+        panic!("expected a single event")
+    };
+    assert_eq!(server_stops.kind(), EventKind::StopDummy);
+    // the stopped dummy counts as well
+    assert_eq!(report.stats.initial_dummies, 2);
+}
+
+#[tokio::test]
+async fn stop_dummy_then_send() {
+    let err = try_run_scenario("tests/echo/stop-dummy-then-send.luci.yaml", [])
+        .await
+        .expect_err("the server should have been stopped");
+    let RunErrorReason::DummyStopped(dummy) = err.reason else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(dummy.as_ref(), "server");
+}
+
+#[tokio::test]
+async fn cancelled() {
+    tokio::time::pause();
 
     let (key_main, sources) = SourceCodeLoader::new()
-        .load(scenario_file)
+        .load("tests/echo/cancelled.luci.yaml")
         .expect("SourceLoader::load");
-    let executable = Executable::build(marshalling, &sources, key_main).expect("building graph");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+
+    let cancellation = CancellationToken::new();
+    tokio::spawn({
+        let cancellation = cancellation.clone();
+        async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            cancellation.cancel();
+        }
+    });
     let report = executable
-        .start(echo::blueprint(), json!(null), args)
+        .start(echo::blueprint(), json!(null), [])
         .await
+        .with_cancellation(cancellation)
         .run()
         .await
         .expect("runner.run");
 
+    assert!(report.cancelled);
+    assert!(!report.is_ok());
+
+    // the report holds what has happened before the cancellation.
+    let [announce] = executable.events_named("server-announces-itself")[..] else {
+        panic!("expected a single event")
+    };
+    let [much_later] = executable.events_named("much-later")[..] else {
+        panic!("expected a single event")
+    };
+    assert!(report.reached_events.contains(&announce));
+    assert!(!report.reached_events.contains(&much_later));
+    assert!(report
+        .message(&executable, &sources)
+        .to_string()
+        .contains("CANCELLED"));
+}
+
+#[tokio::test]
+async fn dummy_request() {
+    run_scenario("tests/echo/dummy-request.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn dummy_request_all() {
+    try_run_scenario_on(
+        echo::routed_blueprint(),
+        marshalling(),
+        "tests/echo/request-all.luci.yaml",
+        [],
+        None,
+    )
+    .await
+    .expect("runner.run");
+}
+
+#[tokio::test]
+async fn dummy_request_failed() {
+    let marshalling = marshalling().with(Regular::<crate::proto::Poison>);
+    let err = try_run_scenario_with(marshalling, "tests/echo/request-failed.luci.yaml", [])
+        .await
+        .expect_err("the echo is gone");
+    assert!(
+        matches!(err.reason, RunErrorReason::RequestFailed(_)),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn dummy_request_mismatch() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/dummy-request-mismatch.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    let [unmatched] = &report.unmatched[..] else {
+        panic!("expected a single unmatched response")
+    };
+    assert_eq!(unmatched.payload, json!({"question": "how are you?"}));
+    assert!(unmatched.to.is_some());
+}
+
+#[tokio::test]
+async fn capture_sender() {
+    run_scenario("tests/echo/capture-sender.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn from_group() {
+    run_scenario("tests/echo/from-group.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn routing_key() {
+    run_scenario("tests/echo/routing-key.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn routing_key_scopes() {
+    try_run_scenario_on(
+        echo::routed_blueprint(),
+        marshalling(),
+        "tests/echo/routing-key-scopes.luci.yaml",
+        [],
+        None,
+    )
+    .await
+    .expect("runner.run");
+}
+
+#[tokio::test]
+async fn auto_responders() {
+    run_scenario("tests/echo/auto-responders.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn ignore_keep_alives() {
+    run_scenario("tests/echo/ignore-keep-alives.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn recv_count() {
+    run_scenario("tests/echo/recv-count.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn recv_count_exceeded() {
+    let t_start = tokio::time::Instant::now();
+    run_scenario("tests/echo/recv-count-exceeded.luci.yaml", []).await;
+    assert!(t_start.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn array_bindings() {
+    run_scenario("tests/echo/array-bindings.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn counters() {
+    run_scenario("tests/echo/counters.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn barrier() {
+    run_scenario("tests/echo/barrier.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn priority() {
+    run_scenario("tests/echo/priority.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn faults() {
+    run_scenario("tests/echo/faults.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn faults_seeded() {
+    run_scenario("tests/echo/faults-seeded.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn terminate() {
+    run_scenario("tests/echo/terminate.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn restart() {
+    let marshalling = marshalling().with(Regular::<crate::proto::Poison>);
+    try_run_scenario_on(
+        echo::restarting_blueprint(),
+        marshalling,
+        "tests/echo/restart.luci.yaml",
+        [],
+        None,
+    )
+    .await
+    .expect("runner.run");
+}
+
+#[tokio::test]
+async fn actor_status() {
+    let marshalling = marshalling().with(Regular::<crate::proto::Poison>);
+    try_run_scenario_with(marshalling, "tests/echo/actor-status.luci.yaml", [])
+        .await
+        .expect("runner.run");
+}
+
+#[tokio::test]
+async fn actor_status_key() {
+    try_run_scenario_on(
+        echo::routed_blueprint(),
+        marshalling(),
+        "tests/echo/actor-status-key.luci.yaml",
+        [],
+        None,
+    )
+    .await
+    .expect("runner.run");
+}
+
+#[tokio::test]
+async fn seeded_scheduling() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/concurrent-sends.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    for seed in 0..8 {
+        let report = executable
+            .start(echo::blueprint(), json!(null), [])
+            .await
+            .with_seed(seed)
+            .run()
+            .await
+            .expect("runner.run");
+        assert_eq!(report.seed, Some(seed));
+        assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+    }
+}
+
+#[tokio::test]
+async fn seeded_recvs() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/seeded-recvs.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let [one] = executable.events_named("one")[..] else {
+        panic!("a single one")
+    };
+
+    let mut one_matched = vec![];
+    for seed in std::iter::once(0).chain(0..16) {
+        let report = executable
+            .start(echo::blueprint(), json!(null), [])
+            .await
+            .with_seed(seed)
+            .run()
+            .await
+            .expect("runner.run");
+        one_matched.push(report.reached_events.contains(&one));
+    }
+
+    assert_eq!(
+        one_matched[0], one_matched[1],
+        "the same seed, the same order"
+    );
+    assert!(one_matched.contains(&true), "{:?}", one_matched);
+    assert!(one_matched.contains(&false), "{:?}", one_matched);
+}
+
+#[tokio::test]
+async fn explore_interleavings() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/concurrent-sends.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let exploration = executable
+        .explore(16, echo::blueprint, json!(null), [])
+        .await;
+
+    assert!(
+        exploration.is_ok(),
+        "{}",
+        exploration.message(&executable, &sources)
+    );
+    assert!(exploration.exhausted);
+    assert_eq!(exploration.runs, 2);
+}
+
+#[tokio::test]
+async fn fail_request() {
+    run_scenario("tests/echo/fail-request.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn check_init_bind() {
+    run_scenario(
+        "tests/echo/check-init-bind.luci.yaml",
+        [
+            ("$ARG_1".into(), json!("one")),
+            ("$ARG_2".into(), json!("two")),
+        ],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn values() {
+    run_scenario(
+        "tests/echo/values.luci.yaml",
+        [("$WHO".into(), json!("luci"))],
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn transforms() {
+    run_scenario("tests/echo/transforms.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn value_paths() {
+    run_scenario("tests/echo/value-paths.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn variants() {
+    run_scenario("tests/echo/variants.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn address_literals() {
+    run_scenario("tests/echo/address-literals.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn address_of_actor() {
+    run_scenario("tests/echo/address-of-actor.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn address_of_unbound_actor() {
+    let err = try_run_scenario("tests/echo/address-of-unbound-actor.luci.yaml", [])
+        .await
+        .expect_err("the actor has not been bound");
+    assert!(err.to_string().contains("unknown address: echo"), "{}", err);
+}
+
+#[tokio::test]
+async fn share_bindings() {
+    run_scenario("tests/echo/share-bindings.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn share_bindings_conflict() {
+    let err = try_run_scenario("tests/echo/share-bindings-conflict.luci.yaml", [])
+        .await
+        .expect_err("the shared binding should have conflicted");
+    let RunErrorReason::SharedBindingConflict {
+        name,
+        bound,
+        shared,
+        shared_by,
+    } = err.reason
+    else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(name, "$X");
+    assert_eq!(bound, json!("not-the-callers"));
+    assert_eq!(shared, json!("from-the-caller"));
+    assert_eq!(shared_by.as_ref(), "call-sub[ENTER SUB]");
+}
+
+#[tokio::test]
+async fn patterns() {
+    run_scenario("tests/echo/patterns.luci.yaml", []).await;
+}
+
+#[test]
+fn patterns_invalid() {
+    for (scenario_file, expected) in [
+        (
+            "tests/echo/patterns-unknown.luci.yaml",
+            "unknown pattern: person",
+        ),
+        (
+            "tests/echo/patterns-recursive.luci.yaml",
+            "recursive pattern: greeting",
+        ),
+    ] {
+        let (key_main, sources) = SourceCodeLoader::new()
+            .load(scenario_file)
+            .expect("SourceLoader::load");
+        let err = Executable::build(marshalling(), &sources, key_main)
+            .expect_err("the patterns should have been rejected")
+            .to_string();
+        assert!(err.contains(expected), "{}: {}", scenario_file, err);
+    }
+}
+
+#[tokio::test]
+async fn message_templates() {
+    run_scenario("tests/echo/message-templates.luci.yaml", []).await;
+}
+
+#[test]
+fn message_templates_mixed() {
+    for scenario_file in [
+        "tests/echo/message-literal-template.luci.yaml",
+        "tests/echo/message-bound-template.luci.yaml",
+    ] {
+        let (key_main, sources) = SourceCodeLoader::new()
+            .load(scenario_file)
+            .expect("SourceLoader::load");
+        let err = Executable::build(marshalling(), &sources, key_main)
+            .expect_err("the template and the data should have mismatched")
+            .to_string();
+        assert!(
+            err.contains("message template mixes literal and bind"),
+            "{}: {}",
+            scenario_file,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn default_from_to() {
+    run_scenario("tests/echo/default-from-to.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn script() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/script.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let script = [
+        "alice-sends-ping",
+        "alice-gets-ping",
+        "alice-sends-pong",
+        "alice-gets-pong",
+    ];
+
+    // each event is unblocked by the one listed before it, and by nothing else
+    let steps = executable
+        .plan()
+        .steps
+        .iter()
+        .map(|step| {
+            step.iter()
+                .map(|e| executable.event_name(e.key).unwrap().1.as_ref().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(steps, script.map(|name| vec![name.to_owned()]));
+
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    let mut record_log = vec![];
+    report
+        .dump_record_log_with_style(&mut record_log, &sources, &executable, Style::Never)
+        .expect("dump_record_log");
+    let record_log = String::from_utf8(record_log).expect("utf-8");
+    let completed = script.map(|name| {
+        record_log
+            .find(&format!("completed E:{} ", name))
+            .unwrap_or_else(|| panic!("{} has not completed: {}", name, record_log))
+    });
+    assert!(completed.windows(2).all(|w| w[0] < w[1]), "{:?}", completed);
+}
+
+#[test]
+fn explain() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/request-response.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+
+    let [request] = executable.events_named("client-requests-an-R")[..] else {
+        panic!("expected a single event")
+    };
+    let [respond] = executable.events_named("server-responds-to-R")[..] else {
+        panic!("expected a single event")
+    };
+    let explanation = executable.explain(respond).expect("explain");
+
+    assert_eq!(explanation.required, Some(RequiredToBe::Reached));
+    assert!(explanation.prerequisites.contains(&request));
+    assert!(explanation.unblocks.is_empty());
+    assert!(!explanation.is_entry_point);
+}
+
+#[test]
+fn plan() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/marshalling.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let plan = executable.plan();
+
+    let steps = plan
+        .steps
+        .iter()
+        .map(|step| {
+            step.iter()
+                .filter_map(|e| executable.event_name(e.key))
+                .map(|(_, name)| name.as_ref().to_owned())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        [
+            vec!["bind"],
+            vec!["send"],
+            vec!["recv"],
+            vec!["check-one", "check-two"]
+        ]
+    );
+    assert!(plan.never_ready.is_empty());
+
+    let send = &plan.steps[1][0];
+    assert_eq!(
+        send.value,
+        Some(json!({"one": "vienas", "two": "du", "three": "trys"}))
+    );
+    let check_two = &plan.steps[3][1];
+    assert_eq!(check_two.value, Some(json!(["<$ONE>", "du", "<$THREE>"])));
+    assert!(plan
+        .message(&executable, &sources)
+        .to_string()
+        .starts_with("PLAN: 5 events in 4 steps"));
+}
+
+#[test]
+fn plan_renders_as_a_run_would() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/transforms.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let plan = executable.plan();
+
+    let send = &plan.steps[0][0];
+    assert_eq!(
+        send.value,
+        Some(json!({"count": 3, "name": "alice", "ids": "2"}))
+    );
+}
+
+#[test]
+fn topology() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/request-response.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let topology = executable.topology();
+
+    let event = |name: &str| {
+        topology
+            .events
+            .iter()
+            .find(|e| e.name.as_ref() == name)
+            .expect("no such event")
+    };
+    let announce = event("server-announces-itself");
+    let request = event("client-requests-an-R");
+    let respond = event("server-responds-to-R");
+
+    assert_eq!(announce.kind, EventKind::Send);
+    assert!(announce.entry_point);
+    assert_eq!(request.kind, EventKind::Recv);
+    assert_eq!(respond.kind, EventKind::Respond);
+    assert_eq!(respond.required, Some(RequiredToBe::Reached));
+    assert!(topology.edges.contains(&(announce.key, request.key)));
+    assert!(topology.edges.contains(&(request.key, respond.key)));
+}
+
+#[tokio::test]
+async fn golden_trace() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/request-response.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+
+    let mut traces = vec![];
+    for _ in 0..2 {
+        let report = executable
+            .start(echo::blueprint(), json!(null), [])
+            .await
+            .run()
+            .await
+            .expect("runner.run");
+        assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+        traces.push(report.golden_trace(&executable));
+    }
+
+    assert_eq!(traces[0], traces[1]);
+    let fired = traces[0]
+        .lines()
+        .map(|line| line.split(' ').take(2).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fired,
+        [
+            "send .:server-announces-itself",
+            "recv .:client-requests-an-R",
+            "respond .:server-responds-to-R",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn precompiled() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/request-response.luci.yaml")
+        .expect("SourceLoader::load");
+    let stored = Executable::build(marshalling(), &sources, key_main)
+        .expect("building graph")
+        .to_json(&sources)
+        .expect("Executable::to_json");
+
+    let unregistered = Executable::from_json(MarshallingRegistry::new(), &stored);
+    assert!(matches!(
+        unregistered,
+        Err(PrecompiledError::UnknownMessage(_))
+    ));
+
+    let (executable, sources) =
+        Executable::from_json(marshalling(), &stored).expect("Executable::from_json");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+}
+
+#[test]
+fn build_cache_builtins() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/terminate.luci.yaml")
+        .expect("SourceLoader::load");
+
+    let build_cache = BuildCache::new();
+    for _ in 0..2 {
+        build_cache
+            .build(marshalling(), &sources, key_main)
+            .expect("BuildCache::build");
+    }
+    assert_eq!(build_cache.misses(), 1);
+    assert_eq!(build_cache.hits(), 1);
+}
+
+#[tokio::test]
+async fn precompiled_builtins() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/terminate.luci.yaml")
+        .expect("SourceLoader::load");
+    let stored = Executable::build(marshalling(), &sources, key_main)
+        .expect("building graph")
+        .to_json(&sources)
+        .expect("Executable::to_json");
+
+    // the registry lacks the `Terminate` the built-in event sends
+    let (executable, sources) =
+        Executable::from_json(marshalling(), &stored).expect("Executable::from_json");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+}
+
+#[tokio::test]
+async fn subscribe() {
+    let marshalling = marshalling().with(Regular::<crate::proto::Poison>);
+    try_run_scenario_with(marshalling, "tests/echo/subscribe.luci.yaml", [])
+        .await
+        .expect("runner.run");
+}
+
+#[tokio::test]
+async fn skip_unmatched_system_messages() {
+    tokio::time::pause();
+
+    for (scenario_file, skip_unmatched) in [
+        ("tests/echo/subscribe.luci.yaml", false),
+        ("tests/echo/subscribe-skip-unmatched.luci.yaml", true),
+    ] {
+        let marshalling = marshalling().with(Regular::<crate::proto::Poison>);
+        let (key_main, sources) = SourceCodeLoader::new()
+            .load(scenario_file)
+            .expect("SourceLoader::load");
+        let executable =
+            Executable::build(marshalling, &sources, key_main).expect("building graph");
+        let report = executable
+            .start(echo::blueprint(), json!(null), [])
+            .await
+            .run()
+            .await
+            .expect("runner.run");
+        assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+        // unless opted out, the reports no recv has matched are reported as such
+        let reported = report
+            .unmatched
+            .iter()
+            .any(|u| u.message_name == "ActorStatusReport");
+        assert_eq!(reported, !skip_unmatched, "{}", scenario_file);
+    }
+}
+
+#[tokio::test]
+async fn cases() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/cases.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    assert_eq!(executable.cases().len(), 2);
+
+    let report = executable.run_cases(echo::blueprint, json!(null), []).await;
+    assert!(!report.is_ok());
+    assert_eq!(report.passed(), 1);
+    assert!(
+        report.cases[0].is_ok(),
+        "{}",
+        report.message(&executable, &sources)
+    );
+    assert_eq!(report.cases[1].values["$WORD"], json!("pong"));
+
+    let report = executable
+        .run_cases_with_options(
+            [[("$WORD".to_owned(), json!("ping"))].into()],
+            echo::blueprint,
+            json!(null),
+            [("$EXPECTED".into(), json!("ping"))],
+            Default::default(),
+        )
+        .await;
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+}
+
+#[tokio::test]
+async fn minimize() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/minimize.luci.yaml")
+        .expect("SourceLoader::load");
+    let minimized = execution::minimize(
+        marshalling,
+        &sources,
+        key_main,
+        100,
+        echo::blueprint,
+        json!(null),
+        [],
+    )
+    .await
+    .expect("building graph")
+    .expect("the scenario should have failed");
+
+    assert!(minimized.exhausted);
+    let mut removed = minimized
+        .removed
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<&str>>();
+    removed.sort();
+    assert_eq!(
+        removed,
+        ["bind-greeting", "bob-gets-noise", "bob-sends-noise"]
+    );
+    let kept = minimized
+        .scenario
+        .events
+        .iter()
+        .map(|e| e.id.as_ref())
+        .collect::<Vec<&str>>();
+    assert_eq!(kept, ["alice-sends-ping", "alice-does-not-get-ping"]);
+}
+
+#[tokio::test]
+async fn log() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/log.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(logger::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    let warning = report
+        .logs
+        .iter()
+        .find(|line| line.level == tracing::Level::WARN)
+        .expect("the warning is captured");
+    assert_eq!(warning.target, "lucid");
+    assert_eq!(warning.message, "the payload is logged");
+    assert_eq!(warning.fields["payload"], json!("hello!"));
+}
+
+#[tokio::test]
+async fn log_concurrent_runs() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/log.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let first = executable.start(logger::blueprint(), json!(null), []).await;
+    let second = executable.start(logger::blueprint(), json!(null), []).await;
+    let (first, second) = tokio::join!(first.run(), second.run());
+
+    // the runs share the thread, but each captures the lines of its own actors.
+    for report in [first, second] {
+        let report = report.expect("runner.run");
+        assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+        let warnings = report
+            .logs
+            .iter()
+            .filter(|line| line.level == tracing::Level::WARN)
+            .count();
+        assert_eq!(warnings, 1);
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn metric() {
+    run_scenario("tests/echo/metric.luci.yaml", []).await;
+}
+
+#[cfg(not(feature = "metrics"))]
+#[test]
+fn metric_requires_the_feature() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/metric.luci.yaml")
+        .expect("SourceLoader::load");
+    let err = Executable::build(marshalling(), &sources, key_main)
+        .expect_err("the metrics feature is off")
+        .to_string();
+    assert!(
+        err.starts_with("E:echoed-once checks a metric, which requires the `metrics` feature"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn record() {
+    let mut recording = Recording::start(echo::blueprint(), json!(null), marshalling()).await;
+    recording.send(proto::V(json!({"say": "ping"}))).await;
+    let envelope = recording.recv().await;
+    let echo_addr = envelope.sender();
+    recording
+        .send_to(echo_addr, proto::V(json!({"say": "pong"})))
+        .await;
+    let _ = recording.recv().await;
+
+    let scenario = recording.scenario();
+    assert_eq!(scenario.types.len(), 1);
+    assert_eq!(scenario.actors.len(), 1);
+    let ids = scenario
+        .script
+        .iter()
+        .map(|e| e.id.as_ref())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        ids,
+        [
+            "01-recorder-sends-V",
+            "02-recorder-receives-V",
+            "03-recorder-sends-V",
+            "04-recorder-receives-V",
+        ]
+    );
+    assert!(matches!(
+        scenario.script[3].require,
+        Some(DefRequirement::Plain(RequiredToBe::Reached))
+    ));
+}
+
+#[tokio::test]
+async fn dummy_to_dummy() {
+    run_scenario("tests/echo/dummy-to-dummy.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn ignore_messages() {
+    run_scenario("tests/echo/ignore-messages.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn catch_all() {
+    let marshalling = MarshallingRegistry::new()
+        .with(Regular::<crate::proto::Hey>)
+        .with(CatchAll);
+    try_run_scenario_with(marshalling, "tests/echo/catch-all.luci.yaml", [])
+        .await
+        .expect("runner.run");
+}
+
+#[tokio::test]
+async fn invariant() {
+    let err = try_run_scenario("tests/echo/invariant.luci.yaml", [])
+        .await
+        .expect_err("invariant should have been violated");
+    let event = err
+        .event
+        .as_ref()
+        .expect("the error should point at the event");
+    assert_eq!(event.name.as_ref(), "session-drifts");
+    assert!(event.called_by.is_empty());
+    assert!(err.record.is_some());
+    assert!(err.to_string().ends_with("(at E:session-drifts)"));
+    let RunErrorReason::InvariantViolated {
+        name,
+        bound,
+        attempted,
+        ..
+    } = err.reason
+    else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(name, "$SESSION_ID");
+    assert_eq!(bound, json!("session-1"));
+    assert_eq!(attempted, json!("session-2"));
+}
+
+#[test]
+fn unbound_variable() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/unbound-variable.luci.yaml")
+        .expect("SourceLoader::load");
+    let build =
+        |options| Executable::build_with_options(marshalling(), &sources, key_main, options);
+
+    build(BuildOptions::default()).expect("a warning only by default");
+    let err = build(BuildOptions::default().with_deny_warnings(true))
+        .expect_err("$N should have been unbound")
+        .to_string();
+    assert!(
+        err.starts_with("E:ping-again reads $N, which none of the events it happens after binds"),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn unbound_variable_supplied() {
+    // `$N` is bound by the time `ping-again` reads it, as it is passed to the run
+    try_run_scenario(
+        "tests/echo/unbound-variable.luci.yaml",
+        [("$N".into(), json!(1))],
+    )
+    .await
+    .expect("runner.run");
+}
+
+#[tokio::test]
+async fn binding_types() {
+    let err = try_run_scenario("tests/echo/binding-types.luci.yaml", [])
+        .await
+        .expect_err("the amount should have mismatched");
+    let RunErrorReason::TypeMismatch {
+        name,
+        declared,
+        attempted,
+        attempted_by,
+    } = err.reason
+    else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(name, "$AMOUNT");
+    assert_eq!(declared, BindingType::Number);
+    assert_eq!(attempted, json!("10"));
+    assert_eq!(attempted_by.as_ref(), "alice-gets-order");
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/binding-types-bind.luci.yaml")
+        .expect("SourceLoader::load");
+    let err = Executable::build(marshalling(), &sources, key_main)
+        .expect_err("the amount should have mismatched")
+        .to_string();
+    assert!(
+        err.starts_with(
+            "E:order-placed binds $AMOUNT, declared Number, to a value of another type"
+        ),
+        "{}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn binding_types_trial() {
+    try_run_scenario("tests/echo/binding-types-trial.luci.yaml", [])
+        .await
+        .expect("the envelope should have been left to the other recv");
+}
+
+#[tokio::test]
+async fn binding_types_supplied() {
+    let err = try_run_scenario(
+        "tests/echo/binding-types.luci.yaml",
+        [("$ORDER_ID".into(), json!(1))],
+    )
+    .await
+    .expect_err("the order id should have mismatched");
+    let RunErrorReason::SuppliedTypeMismatch {
+        name,
+        declared,
+        supplied,
+    } = err.reason
+    else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(name, "$ORDER_ID");
+    assert_eq!(declared, BindingType::String);
+    assert_eq!(supplied, json!(1));
+}
+
+#[tokio::test]
+async fn strict_mailbox() {
+    let err = try_run_scenario("tests/echo/strict-mailbox.luci.yaml", [])
+        .await
+        .expect_err("the pong should have been unexpected");
+    assert!(err.event.is_none());
+    let RunErrorReason::UnexpectedEnvelope { message_name, .. } = err.reason else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(message_name, "V");
+}
+
+#[tokio::test]
+async fn stream() {
+    run_scenario("tests/echo/stream.luci.yaml", []).await;
+}
+
+#[tokio::test]
+async fn send_many() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/send-many.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    let [pings] = executable.events_named("pings")[..] else {
+        panic!("expected a single event")
+    };
+    let [throughput] = report.stats.throughput[..] else {
+        panic!("{}", report.message(&executable, &sources))
+    };
+    assert_eq!(throughput.event_key, pings);
+    assert_eq!(throughput.messages, 3);
+    assert!(
+        throughput.min_interval >= Duration::from_millis(10),
+        "{:?}",
+        throughput
+    );
+    assert!(throughput.min_interval <= throughput.mean_interval);
+    assert!(throughput.mean_interval <= throughput.max_interval);
+}
+
+#[tokio::test]
+async fn stream_out_of_order() {
+    let err = try_run_scenario("tests/echo/stream-out-of-order.luci.yaml", [])
+        .await
+        .expect_err("the pongs should have been out of order");
+    let RunErrorReason::OutOfOrder { expected, got, .. } = err.reason else {
+        panic!("unexpected error: {}", err)
+    };
+    assert_eq!(expected.as_ref(), "pongs[0]");
+    assert_eq!(got.as_ref(), "pongs");
+}
+
+#[tokio::test]
+async fn unmatched_envelopes() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/unmatched-envelopes.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+
+    assert!(!report.is_ok());
+    let [unmatched] = &report.unmatched[..] else {
+        panic!("{}", report.message(&executable, &sources))
+    };
+    assert_eq!(unmatched.message_name, "V");
+    assert!(unmatched.to.is_some());
+}
+
+#[tokio::test]
+async fn github_annotations() {
+    tokio::time::pause();
+
+    let annotations = SharedBuffer::default();
+    let outcome = Suite::new("tests/echo", marshalling, echo::blueprint)
+        .with_github_annotations(annotations.clone())
+        .run_scenario("describe.luci.yaml")
+        .await;
+    assert!(!outcome.is_ok());
+
+    let annotations = annotations.text();
+    assert!(annotations.starts_with("::error file="), "{}", annotations);
+    assert!(
+        annotations.contains("describe.luci.yaml,line=17,title=wrong-pong::"),
+        "{}",
+        annotations
+    );
+    assert_eq!(annotations.lines().count(), 1, "{}", annotations);
+}
+
+#[tokio::test]
+async fn describe() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/describe.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+
+    assert!(!report.is_ok());
+    let message = report
+        .message(&executable, &sources)
+        .with_style(Style::Never)
+        .to_string();
+    assert!(
+        message.contains("wrong-pong") && message.contains("— the echo changes the payload"),
+        "{}",
+        message
+    );
+    assert!(!message.contains('\x1b'), "{}", message);
+
+    let coloured = report
+        .message(&executable, &sources)
+        .with_style(Style::Always)
+        .to_string();
+    assert!(coloured.contains('\x1b'), "{}", coloured);
+
+    let mut record_log = vec![];
+    report
+        .dump_record_log_with_style(&mut record_log, &sources, &executable, Style::Never)
+        .expect("dump_record_log");
+    let record_log = String::from_utf8(record_log).expect("utf-8");
+    assert!(record_log.contains("completed E:ping"), "{}", record_log);
+    assert!(!record_log.contains('\x1b'), "{}", record_log);
+
+    let file = std::fs::File::open("Cargo.toml").expect("Cargo.toml");
+    assert_eq!(Style::Auto.for_sink(&file), Style::Never);
+}
+
+#[tokio::test]
+async fn timeline() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/describe.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(echo::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+
+    let timeline = report.timeline(&executable);
+    let [ping] = executable.events_named("ping")[..] else {
+        panic!("a single ping")
+    };
+    let [wrong_pong] = executable.events_named("wrong-pong")[..] else {
+        panic!("a single wrong-pong")
+    };
+    let end_of = |event_key| {
+        timeline
+            .bars
+            .iter()
+            .find(|bar| bar.event_key == event_key)
+            .map(|bar| bar.end)
+    };
+    assert!(matches!(end_of(ping), Some(TimelineEnd::Fired(_))));
+    assert!(matches!(
+        end_of(wrong_pong),
+        Some(TimelineEnd::TimedOut(at)) if at >= Duration::from_secs(1)
+    ));
+
+    let gantt = draw_timeline_mermaid(&timeline);
+    assert!(gantt.starts_with("gantt"), "{}", gantt);
+    assert!(gantt.contains("ping :done"), "{}", gantt);
+    assert!(gantt.contains("wrong-pong :crit"), "{}", gantt);
+}
+
+#[tokio::test]
+async fn run_until() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/marshalling.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let [recv] = executable.events_named("recv")[..] else {
+        panic!("expected a single event")
+    };
+    let [check_one] = executable.events_named("check-one")[..] else {
+        panic!("expected a single event")
+    };
+    let mut runner = executable.start(echo::blueprint(), json!(null), []).await;
+
+    assert!(runner.run_until("recv").await.expect("runner.run_until"));
+    assert!(runner.has_fired(recv));
+    assert!(!runner.has_fired(check_one), "the run has not paused");
+    // has fired already
+    assert!(runner.run_until("send").await.expect("runner.run_until"));
+    assert!(!runner.has_fired(check_one));
+
+    let report = runner.run().await.expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+    assert!(report.reached_events.contains(&recv));
+    assert!(report.reached_events.contains(&check_one));
+}
+
+#[tokio::test]
+async fn run_until_failed() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/binding-types.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let mut runner = executable.start(echo::blueprint(), json!(null), []).await;
+
+    let err = runner
+        .run_until("alice-gets-order")
+        .await
+        .expect_err("the amount should have mismatched");
+    assert!(matches!(err.reason, RunErrorReason::TypeMismatch { .. }));
+
+    let err = runner.run().await.expect_err("the run has failed already");
+    let RunErrorReason::AlreadyFailed(reason) = &err.reason else {
+        panic!("unexpected error: {}", err)
+    };
+    assert!(reason.starts_with("type mismatch"), "{}", reason);
+}
+
+#[cfg(feature = "opentelemetry")]
+#[test]
+fn export_otlp() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    // a collector taking in whatever is posted to it.
+    let listener = TcpListener::bind("127.0.0.1:0").expect("TcpListener::bind");
+    let endpoint = format!(
+        "http://{}/v1/traces",
+        listener.local_addr().expect("local_addr")
+    );
+    let (bodies_tx, bodies_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let bodies_tx = bodies_tx.clone();
+            std::thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().expect("try_clone"));
+                loop {
+                    let mut content_length = 0;
+                    let mut header = String::new();
+                    loop {
+                        header.clear();
+                        if reader.read_line(&mut header).unwrap_or(0) == 0 {
+                            return
+                        }
+                        match header.trim_end().split_once(':') {
+                            Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                                content_length = value.trim().parse().unwrap_or(0);
+                            },
+                            Some(_) => (),
+                            None if header.trim_end().is_empty() => break,
+                            None => (),
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    if reader.read_exact(&mut body).is_err() {
+                        return
+                    }
+                    let _ = bodies_tx.send(body);
+                    let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n");
+                }
+            });
+        }
+    });
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/echo/request-response.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .expect("tokio runtime");
+    let report = runtime
+        .block_on(async {
+            executable
+                .start(echo::blueprint(), json!(null), [])
+                .await
+                .run()
+                .await
+        })
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    // the export blocks: it is done off the runtime.
+    report
+        .export_otlp(&endpoint, &executable, &sources)
+        .expect("Report::export_otlp");
+
+    let bodies = bodies_rx.try_iter().collect::<Vec<_>>();
+    let contains = |needle: &[u8]| {
+        bodies
+            .iter()
+            .any(|body| body.windows(needle.len()).any(|w| w == needle))
+    };
+    assert!(contains(b"luci run"));
+    assert!(contains(b"luci.event"));
+    assert!(contains(b"server-responds-to-R"));
+}
+
+#[test]
+fn registry_introspection() {
+    let marshalling = marshalling();
+
+    let messages = marshalling
+        .messages()
+        .map(|m| m.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "echo::proto::Hey",
+            "echo::proto::R (request -> serde_json::value::Value)",
+            "echo::proto::V",
+        ]
+    );
+    assert!(marshalling.message("echo::proto::R").unwrap().is_request);
+    assert!(marshalling.message("echo::proto::Nope").is_none());
+}
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).expect("utf-8")
+    }
+}
+
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn marshalling() -> MarshallingRegistry {
+    MarshallingRegistry::new()
+        .with(Regular::<crate::proto::V>)
+        .with(Request::<crate::proto::R>)
+        .with(Regular::<crate::proto::Hey>)
+}
+
+async fn run_scenario(
+    scenario_file: &str,
+    args: impl IntoIterator<Item = (String, serde_json::Value)>,
+) {
+    try_run_scenario(scenario_file, args)
+        .await
+        .expect("runner.run");
+}
+
+async fn try_run_scenario(
+    scenario_file: &str,
+    args: impl IntoIterator<Item = (String, serde_json::Value)>,
+) -> Result<(), RunError> {
+    try_run_scenario_with(marshalling(), scenario_file, args).await
+}
+
+async fn try_run_scenario_with(
+    marshalling: MarshallingRegistry,
+    scenario_file: &str,
+    args: impl IntoIterator<Item = (String, serde_json::Value)>,
+) -> Result<(), RunError> {
+    try_run_scenario_seeded(marshalling, scenario_file, args, None).await
+}
+
+async fn try_run_scenario_seeded(
+    marshalling: MarshallingRegistry,
+    scenario_file: &str,
+    args: impl IntoIterator<Item = (String, serde_json::Value)>,
+    seed: Option<u64>,
+) -> Result<(), RunError> {
+    try_run_scenario_on(echo::blueprint(), marshalling, scenario_file, args, seed).await
+}
+
+async fn try_run_scenario_on(
+    blueprint: Blueprint,
+    marshalling: MarshallingRegistry,
+    scenario_file: &str,
+    args: impl IntoIterator<Item = (String, serde_json::Value)>,
+    seed: Option<u64>,
+) -> Result<(), RunError> {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_max_level(tracing::Level::TRACE)
+        .try_init();
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load(scenario_file)
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling, &sources, key_main).expect("building graph");
+    let mut runner = executable
+        .start(blueprint, json!(null), args)
+        .await
+        .with_audit(true);
+    if let Some(seed) = seed {
+        runner = runner.with_seed(seed);
+    }
+    let report = runner.run().await?;
+    assert_eq!(report.seed, seed);
+
     let _ = report.dump_record_log(std::io::stderr().lock(), &sources, &executable);
     assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+    Ok(())
 }
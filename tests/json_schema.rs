@@ -0,0 +1,52 @@
+#![cfg(feature = "json-schema")]
+
+use luci::execution::{Executable, SourceCodeLoader};
+use luci::marshalling::{MarshallingRegistry, Regular, WithSchema};
+use luci::scenario::Scenario;
+use serde::Deserialize;
+use test_case::test_case;
+
+pub mod proto {
+    use elfo::message;
+
+    #[message]
+    #[derive(schemars::JsonSchema)]
+    pub struct Greet {
+        pub name: String,
+    }
+}
+
+#[test_case("valid", None)]
+#[test_case("invalid", Some("invalid payload in E:greet"))]
+fn build(name: &str, expected_error: Option<&str>) {
+    let marshalling = MarshallingRegistry::new().with(WithSchema(Regular::<crate::proto::Greet>));
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load(format!("tests/json_schema/{name}.luci.yaml"))
+        .expect("SourceLoader::load");
+    let result = Executable::build(marshalling, &sources, key_main).map_err(|e| e.to_string());
+
+    match (result, expected_error) {
+        (Ok(_), None) => (),
+        (Err(reason), Some(expected)) => assert!(reason.contains(expected), "{}", reason),
+        (Ok(_), Some(expected)) => panic!("expected an error: {}", expected),
+        (Err(reason), None) => panic!("unexpected error: {}", reason),
+    }
+}
+
+#[test]
+fn scenarios_conform_to_the_schema() {
+    let validator =
+        jsonschema::validator_for(&Scenario::json_schema()).expect("jsonschema::validator_for");
+
+    for file in luci::suite::discover("tests").expect("suite::discover") {
+        let yaml = std::fs::read_to_string(std::path::Path::new("tests").join(&file))
+            .expect("fs::read_to_string");
+        for document in serde_yaml::Deserializer::from_str(&yaml) {
+            let scenario = serde_json::Value::deserialize(document).expect("yaml::from_str");
+            if let Err(reason) = validator.validate(&scenario) {
+                panic!("{}: {}", file.display(), reason);
+            }
+        }
+    }
+}
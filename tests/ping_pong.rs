@@ -1,5 +1,12 @@
-use luci::execution::{Executable, SourceCodeLoader};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use clap::Parser;
+use elfo::{ActorGroup, Blueprint, Context};
+use luci::execution::{BuildCache, Executable, SourceCodeLoader};
 use luci::marshalling::{MarshallingRegistry, Regular};
+use luci::suite::{ScenarioOutcome, ScenarioResult, Suite, SuiteArgs};
 use serde_json::json;
 
 pub mod proto {
@@ -126,6 +133,197 @@ async fn test_one_peer() {
     run_scenario("tests/ping_pong/test-one-peer.luci.yaml").await
 }
 
+mod generated {
+    luci::scenario_tests!(
+        glob = "tests/ping_pong/*.luci.yaml",
+        blueprint = crate::pinger::blueprint,
+        marshalling = crate::marshalling(),
+    );
+}
+
+#[tokio::test]
+async fn run_suite_concurrently() {
+    let report = Suite::new("tests/ping_pong", marshalling, pinger::blueprint)
+        .with_concurrency(NonZeroUsize::new(2).unwrap())
+        .run()
+        .await
+        .expect("Suite::run");
+
+    assert_eq!(report.outcomes.len(), 2, "{}", report);
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[tokio::test]
+async fn run_suite_repeatedly() {
+    tokio::time::pause();
+
+    let report = Suite::new("tests/ping_pong", marshalling, pinger::blueprint)
+        .with_repeat(NonZeroUsize::new(3).unwrap())
+        .run()
+        .await
+        .expect("Suite::run");
+
+    assert_eq!(report.outcomes.len(), 2, "{}", report);
+    assert!(report.is_ok(), "{}", report);
+    assert!(
+        report.outcomes.iter().all(|o| !o.timings.start.is_zero()),
+        "{}",
+        report
+    );
+}
+
+#[tokio::test]
+async fn run_suite_from_args() {
+    tokio::time::pause();
+
+    let parse = |args: &[&str]| SuiteArgs::try_parse_from(["scenarios"].iter().chain(args));
+    assert!(parse(&["tests/ping_pong", "--time-scale", "0"]).is_err());
+    assert!(parse(&["tests/ping_pong", "--time-scale", "inf"]).is_err());
+
+    let args =
+        parse(&["tests/ping_pong", "-j", "2", "--time-scale", "0.5"]).expect("SuiteArgs::parse");
+    assert_eq!(args.repeat, None);
+    assert_eq!(args.time_scale, Some(0.5));
+    let report = Suite::from_args(&args, marshalling, pinger::blueprint)
+        .run()
+        .await
+        .expect("Suite::run");
+
+    assert_eq!(report.outcomes.len(), 2, "{}", report);
+    assert!(report.is_ok(), "{}", report);
+}
+
+#[tokio::test]
+async fn soak_suite_from_args() {
+    tokio::time::pause();
+
+    let args = SuiteArgs::try_parse_from(["scenarios", "tests/ping_pong", "--repeat", "3"])
+        .expect("SuiteArgs::parse");
+    let report = Suite::from_args(&args, marshalling, || {
+        ActorGroup::new().exec(|_ctx: Context| async {})
+    })
+    .run()
+    .await
+    .expect("Suite::run");
+
+    // the silent actor fails every run of the scenarios
+    assert_eq!(report.outcomes.len(), 2, "{}", report);
+    for outcome in &report.outcomes {
+        let message = outcome.result.message().unwrap_or_default();
+        assert!(message.starts_with("SOAK: 0/3 passed"), "{}", report);
+    }
+}
+
+#[tokio::test]
+async fn run_suite_with_panics() {
+    let report = Suite::new("tests/ping_pong", marshalling, || -> Blueprint {
+        panic!("no blueprint")
+    })
+    .with_concurrency(NonZeroUsize::new(2).unwrap())
+    .run()
+    .await
+    .expect("Suite::run");
+
+    for outcome in &report.outcomes {
+        assert!(
+            matches!(&outcome.result, ScenarioResult::RunError(reason) if reason == "panicked: no blueprint"),
+            "{}",
+            report
+        );
+    }
+}
+
+#[tokio::test]
+async fn soak_with_failing_runs() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .load("tests/ping_pong/test-no-peers.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let [initial_bro] = executable.events_named("initial-bro")[..] else {
+        panic!("expected a single event")
+    };
+
+    // every other run, the actor keeps silent and never says bro
+    let started = AtomicUsize::new(0);
+    let blueprint = || {
+        match started.fetch_add(1, Ordering::Relaxed) % 2 {
+            0 => pinger::blueprint(),
+            _ => ActorGroup::new().exec(|_ctx: Context| async {}),
+        }
+    };
+    let soak = executable.run_repeated(3, blueprint, json!(null), []).await;
+
+    assert!(!soak.is_ok());
+    assert_eq!((soak.runs, soak.passed), (3, 2));
+    assert_eq!(soak.unmet.get(&initial_bro), Some(&1));
+    assert!(soak.errors.is_empty(), "{:?}", soak.errors);
+    assert_eq!(
+        soak.message(&executable).to_string(),
+        "SOAK: 2/3 passed (66.7%)\n - E:initial-bro: unmet 1 time(s)\n"
+    );
+}
+
+#[tokio::test]
+async fn run_suite_with_build_cache() {
+    tokio::time::pause();
+
+    let build_cache = Arc::new(BuildCache::new());
+    for round in 0..2 {
+        let report = Suite::new("tests/ping_pong", marshalling, pinger::blueprint)
+            .with_build_cache(build_cache.clone())
+            .run()
+            .await
+            .expect("Suite::run");
+
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!(build_cache.len(), 2);
+        assert_eq!(build_cache.misses(), 2);
+        assert_eq!(build_cache.hits(), 2 * round);
+    }
+}
+
+#[tokio::test]
+async fn run_suite_as_tap() {
+    tokio::time::pause();
+
+    let mut report = Suite::new("tests/ping_pong", marshalling, pinger::blueprint)
+        .run()
+        .await
+        .expect("Suite::run");
+    report.outcomes.push(ScenarioOutcome {
+        scenario: "broken.luci.yaml".into(),
+        result:   ScenarioResult::BuildError("unknown actor: A:nobody".into()),
+        timings:  Default::default(),
+    });
+
+    let expected = [
+        "TAP version 13",
+        "1..3",
+        "ok 1 - test-no-peers.luci.yaml",
+        "ok 2 - test-one-peer.luci.yaml",
+        "not ok 3 - broken.luci.yaml",
+        "  ---",
+        "  result: build-error",
+        "  message: |",
+        "    unknown actor: A:nobody",
+        "  ...",
+    ];
+    assert_eq!(
+        report.tap().to_string().lines().collect::<Vec<_>>(),
+        expected
+    );
+}
+
+fn marshalling() -> MarshallingRegistry {
+    MarshallingRegistry::new()
+        .with(Regular::<crate::proto::Bro>)
+        .with(Regular::<crate::proto::Ping>)
+        .with(Regular::<crate::proto::Pong>)
+        .with(Regular::<crate::proto::Bye>)
+}
+
 async fn run_scenario(scenario_file: &str) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -133,11 +331,7 @@ async fn run_scenario(scenario_file: &str) {
         .try_init();
     tokio::time::pause();
 
-    let marshalling = MarshallingRegistry::new()
-        .with(Regular::<crate::proto::Bro>)
-        .with(Regular::<crate::proto::Ping>)
-        .with(Regular::<crate::proto::Pong>)
-        .with(Regular::<crate::proto::Bye>);
+    let marshalling = marshalling();
     let (key_main, sources) = SourceCodeLoader::new()
         .load(scenario_file)
         .expect("SourceLoader::load");
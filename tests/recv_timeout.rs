@@ -1,4 +1,7 @@
-use luci::execution::{Executable, SourceCodeLoader};
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use luci::execution::{Executable, Report, RunOptions, SourceCodeLoader};
 use luci::marshalling::{MarshallingRegistry, Regular};
 use serde_json::json;
 
@@ -51,13 +54,232 @@ async fn with_intervals() {
     run_scenario("tests/recv_timeout/with-intervals.luci.yaml").await;
 }
 
+#[tokio::test]
+async fn with_defaults() {
+    run_scenario("tests/recv_timeout/with-defaults.luci.yaml").await;
+}
+
+#[tokio::test]
+async fn with_defaults_overridden() {
+    run_scenario("tests/recv_timeout/with-defaults-overridden.luci.yaml").await;
+}
+
+#[tokio::test]
+async fn with_time_scale() {
+    // the 2s-timeout shrinks to 500ms: too short for the actor to say hi back
+    let options = RunOptions::default().with_time_scale(0.25);
+    let (executable, report) =
+        run_scenario_with_options("tests/recv_timeout/with-defaults.luci.yaml", false, options)
+            .await;
+    let unmet = report.unmet_requirements().collect::<Vec<_>>();
+    assert_eq!(unmet.len(), 1);
+    assert_eq!(
+        executable.event_name(unmet[0]).unwrap().1.as_ref(),
+        "actor-says-hi-back"
+    );
+}
+
+#[tokio::test]
+async fn github_annotations() {
+    let options = RunOptions::default().with_time_scale(0.25);
+    let (executable, report) =
+        run_scenario_with_options("tests/recv_timeout/with-defaults.luci.yaml", false, options)
+            .await;
+    // loaded the same way, the sources have the same keys as the executable's
+    let (_, sources) = SourceCodeLoader::new()
+        .load("tests/recv_timeout/with-defaults.luci.yaml")
+        .expect("SourceLoader::load");
+
+    assert_eq!(
+        report.github_annotations(&executable, &sources).to_string(),
+        "::error file=tests/recv_timeout/with-defaults.luci.yaml,line=28,\
+         title=actor-says-hi-back::the event is required to be reached, but has not been\n"
+    );
+}
+
+#[tokio::test]
+async fn why_unreached() {
+    let options = RunOptions::default().with_time_scale(0.25);
+    let (executable, report) =
+        run_scenario_with_options("tests/recv_timeout/with-defaults.luci.yaml", false, options)
+            .await;
+    let [hi_back] = executable.events_named("actor-says-hi-back")[..] else {
+        panic!("expected a single event")
+    };
+    let [hi] = executable.events_named("dummy-says-hi-to-actor")[..] else {
+        panic!("expected a single event")
+    };
+
+    let why = report
+        .why_unreached(&executable, hi_back)
+        .expect("the event is unreached");
+    // nothing to wait for: the recv has timed out on its own
+    assert!(why.reached.is_empty());
+    assert!(why.unreached.is_empty());
+    assert_eq!(why.earliest_blockers(), BTreeSet::from([hi_back]));
+    assert!(report.why_unreached(&executable, hi).is_none());
+}
+
+#[tokio::test]
+async fn why_unreached_transitively() {
+    let (executable, report) = run_scenario("tests/recv_timeout/why-unreached.luci.yaml").await;
+    let event = |name| {
+        let [event_key] = executable.events_named(name)[..] else {
+            panic!("expected a single event: {}", name)
+        };
+        event_key
+    };
+    let hi_back = event("actor-says-hi-back");
+    let bye_early = event("actor-says-bye-early");
+    let waves = event("dummy-waves");
+    let leaves = event("dummy-leaves");
+
+    let why = report
+        .why_unreached(&executable, waves)
+        .expect("the event is unreached");
+    assert_eq!(why.reached, [hi_back]);
+    let [blocker] = &why.unreached[..] else {
+        panic!("expected a single unreached prerequisite: {:?}", why)
+    };
+    assert_eq!(blocker.event_key, bye_early);
+    assert!(blocker.unreached.is_empty());
+
+    let why = report
+        .why_unreached(&executable, leaves)
+        .expect("the event is unreached");
+    assert!(why.reached.is_empty());
+    assert_eq!(
+        why.unreached
+            .iter()
+            .map(|u| u.event_key)
+            .collect::<BTreeSet<_>>(),
+        BTreeSet::from([waves, bye_early])
+    );
+    // a prerequisite both directly and through `dummy-waves`: explained once
+    assert_eq!(why.earliest_blockers(), BTreeSet::from([bye_early]));
+}
+
+#[tokio::test]
+async fn advance_time() {
+    let (executable, report) = run_scenario("tests/recv_timeout/advance-time.luci.yaml").await;
+    let (&bye, _) = report
+        .fired_at
+        .iter()
+        .find(|(k, _)| executable.event_name(**k).unwrap().1.as_ref() == "actor-says-bye")
+        .expect("the event has fired");
+    let bye = report.fired_at(bye).unwrap();
+    assert!(bye.runtime >= Duration::from_secs(61), "{:?}", bye);
+}
+
+#[tokio::test]
+async fn fired_at() {
+    let (executable, report) = run_scenario("tests/recv_timeout/no-timeouts.luci.yaml").await;
+    let fired_at = |name: &str| {
+        let (&event_key, _) = report
+            .fired_at
+            .iter()
+            .find(|(k, _)| executable.event_name(**k).unwrap().1.as_ref() == name)
+            .expect("the event has fired");
+        report.fired_at(event_key).unwrap()
+    };
+
+    let hi = fired_at("actor-says-hi-back");
+    let bye = fired_at("actor-says-bye");
+    assert!(hi.runtime >= Duration::from_secs(1), "{:?}", hi);
+    assert!(
+        bye.runtime >= hi.runtime + Duration::from_secs(60),
+        "{:?}",
+        bye
+    );
+}
+
+#[tokio::test]
+async fn with_latency() {
+    let (executable, report) = run_scenario("tests/recv_timeout/with-latency.luci.yaml").await;
+    let fired_at = |name: &str| {
+        let (&event_key, _) = report
+            .fired_at
+            .iter()
+            .find(|(k, _)| executable.event_name(**k).unwrap().1.as_ref() == name)
+            .expect("the event has fired");
+        report.fired_at(event_key).unwrap()
+    };
+
+    let sent = fired_at("dummy-says-hi-to-actor");
+    let hi = fired_at("actor-says-hi-back");
+    assert!(sent.runtime >= Duration::from_secs(5), "{:?}", sent);
+    assert!(sent.runtime <= Duration::from_secs(10), "{:?}", sent);
+    assert!(
+        hi.runtime >= sent.runtime + Duration::from_secs(1),
+        "{:?}",
+        hi
+    );
+}
+
+#[tokio::test]
+async fn latency_meanwhile() {
+    let (executable, report) = run_scenario("tests/recv_timeout/latency-meanwhile.luci.yaml").await;
+    let fired_at = |name: &str| {
+        let (&event_key, _) = report
+            .fired_at
+            .iter()
+            .find(|(k, _)| executable.event_name(**k).unwrap().1.as_ref() == name)
+            .expect("the event has fired");
+        report.fired_at(event_key).unwrap()
+    };
+
+    // the delay is not held up by the send being delayed
+    let meanwhile = fired_at("meanwhile");
+    let sent = fired_at("dummy-says-hi-to-actor");
+    assert!(
+        meanwhile.runtime < Duration::from_secs(10),
+        "{:?}",
+        meanwhile
+    );
+    assert!(sent.runtime >= Duration::from_secs(10), "{:?}", sent);
+}
+
+#[tokio::test]
+async fn deadlines_met() {
+    run_scenario("tests/recv_timeout/deadlines-met.luci.yaml").await;
+}
+
+#[tokio::test]
+async fn deadlines_missed() {
+    let (executable, report) =
+        run_scenario_expecting("tests/recv_timeout/deadlines-missed.luci.yaml", false).await;
+    let unmet = report.unmet_requirements().collect::<Vec<_>>();
+    assert_eq!(unmet.len(), 1);
+    assert_eq!(
+        executable.event_name(unmet[0]).unwrap().1.as_ref(),
+        "actor-says-bye"
+    );
+
+    let took = report
+        .missed_deadline(unmet[0])
+        .expect("missed the deadline");
+    assert!(took >= Duration::from_secs(60), "{:?}", took);
+}
+
 #[tokio::test]
 #[ignore = "minimal demo of the problem"]
 async fn time_resolution_nuisance() {
     run_scenario("tests/recv_timeout/time-resolution-nuisance.luci.yaml").await;
 }
 
-async fn run_scenario(scenario_file: &str) {
+async fn run_scenario(scenario_file: &str) -> (Executable, Report) {
+    run_scenario_expecting(scenario_file, true).await
+}
+
+async fn run_scenario_expecting(scenario_file: &str, ok: bool) -> (Executable, Report) {
+    run_scenario_with_options(scenario_file, ok, RunOptions::default()).await
+}
+
+async fn run_scenario_with_options(
+    scenario_file: &str,
+    ok: bool,
+    options: RunOptions,
+) -> (Executable, Report) {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .with_max_level(tracing::Level::TRACE)
@@ -73,8 +295,9 @@ async fn run_scenario(scenario_file: &str) {
         .expect("SourceLoader::load");
     let executable = Executable::build(marshalling, &sources, key_main).expect("building graph");
     let report = executable
-        .start(echo::blueprint(), json!(null), [])
+        .start_with_options(echo::blueprint(), json!(null), [], options)
         .await
+        .with_audit(true)
         .run()
         .await
         .expect("runner.run");
@@ -82,5 +305,11 @@ async fn run_scenario(scenario_file: &str) {
     report
         .dump_record_log(std::io::stderr().lock(), &sources, &executable)
         .unwrap();
-    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+    assert_eq!(
+        report.is_ok(),
+        ok,
+        "{}",
+        report.message_with_timings(&executable, &sources)
+    );
+    (executable, report)
 }
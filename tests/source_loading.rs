@@ -10,6 +10,9 @@ use test_case::test_case;
 #[test_case("02.a", "02-direct-cyclic-inclusion.luci.yaml", &["tests/source_loading"])]
 #[test_case("03", "03-indirect-cyclic-inclusion.luci.yaml", &["tests/source_loading"])]
 #[test_case("04", "04-diamond.luci.yaml", &["tests/source_loading", "tests/source_loading/04-diamond"])]
+#[test_case("05", "tests/source_loading/05-multi-document.luci.yaml", &["."])]
+#[test_case("05.a", "tests/source_loading/05-multi-document.luci.yaml#helper", &["."])]
+#[test_case("05.b", "tests/source_loading/05-multi-document.luci.yaml#missing", &["."])]
 fn load_sources(name: &str, main: &str, search_paths: &[&str]) {
     let mut loader = SourceCodeLoader::new();
     loader.search_path = search_paths.iter().copied().map(From::from).collect();
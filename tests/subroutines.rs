@@ -1,5 +1,6 @@
-use luci::execution::{Executable, SourceCodeLoader};
+use luci::execution::{BuildOptions, Executable, Lint, SourceCodeLoader};
 use luci::marshalling::{MarshallingRegistry, Regular, Request};
+use luci::suite::Suite;
 use serde_json::json;
 use test_case::test_case;
 
@@ -94,6 +95,7 @@ mod socialite {
 }
 
 #[test_case("main.luci.yaml", &["tests/subroutines"])]
+#[test_case("qualified-happens-after.luci.yaml", &["tests/subroutines"])]
 #[tokio::test]
 async fn run_scenario(scenario_file: &str, search_path: &[&str]) {
     let _ = tracing_subscriber::fmt()
@@ -102,14 +104,7 @@ async fn run_scenario(scenario_file: &str, search_path: &[&str]) {
         .try_init();
     tokio::time::pause();
 
-    let marshalling = MarshallingRegistry::new()
-        .with(Request::<crate::proto::smalltalk::Whatsup>)
-        .with(Regular::<crate::proto::smalltalk::OhByTheWay>)
-        .with(Regular::<crate::proto::smalltalk::NoWay>)
-        .with(Request::<crate::proto::partying::MayI>)
-        .with(Regular::<crate::proto::partying::SeeYou>)
-        .with(Regular::<crate::proto::partying::Chug>)
-        .with(Regular::<crate::proto::partying::Gulp>);
+    let marshalling = marshalling();
 
     let (key_main, sources) = SourceCodeLoader::new()
         .with_search_path(search_path)
@@ -128,3 +123,156 @@ async fn run_scenario(scenario_file: &str, search_path: &[&str]) {
         .expect("ew...");
     assert!(report.is_ok(), "{}", report.message(&executable, &sources));
 }
+
+#[tokio::test]
+async fn exports() {
+    tokio::time::pause();
+
+    let (key_main, sources) = SourceCodeLoader::new()
+        .with_search_path(["tests/exports"])
+        .load("main.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+    let report = executable
+        .start(socialite::blueprint(), json!(null), [])
+        .await
+        .run()
+        .await
+        .expect("runner.run");
+    assert!(report.is_ok(), "{}", report.message(&executable, &sources));
+
+    // the requirements of the exported events are reported on their own.
+    let required = |name: &str| {
+        report.required_events.keys().any(|&event_key| {
+            executable
+                .event_name(event_key)
+                .is_some_and(|(_, n)| n.as_ref() == name)
+        })
+    };
+    assert!(required("ROBERT-greets"));
+    assert!(!required("ROBERT-remembers-a-thing-4"));
+}
+
+#[tokio::test]
+async fn run_suite() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_max_level(tracing::Level::TRACE)
+        .try_init();
+    tokio::time::pause();
+
+    let report = Suite::new("tests/subroutines", marshalling, socialite::blueprint)
+        .run()
+        .await
+        .expect("Suite::run");
+
+    // `smalltalk.luci.yaml` is only used as a subroutine by the other two.
+    assert_eq!(report.outcomes.len(), 2, "{}", report);
+    assert_eq!(
+        report.outcomes[0].scenario,
+        std::path::Path::new("main.luci.yaml")
+    );
+    assert_eq!(
+        report.outcomes[1].scenario,
+        std::path::Path::new("qualified-happens-after.luci.yaml")
+    );
+    assert!(report.is_ok(), "{}", report);
+    assert!(!report.outcomes[0].timings.run.is_zero());
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn run_suite_metrics() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    tokio::time::pause();
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let _guard = metrics::set_default_local_recorder(&recorder);
+
+    let report = Suite::new("tests/subroutines", marshalling, socialite::blueprint)
+        .run()
+        .await
+        .expect("Suite::run");
+    assert!(report.is_ok(), "{}", report);
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    let label = |key: &metrics::Key, name: &str| {
+        key.labels()
+            .find(|label| label.key() == name)
+            .map(|label| label.value().to_owned())
+    };
+    assert!(snapshot.iter().any(|(key, _, _, value)| {
+        key.key().name() == "luci_scenarios_total"
+            && label(key.key(), "result").as_deref() == Some("passed")
+            && *value == DebugValue::Counter(2)
+    }));
+
+    let fired = snapshot
+        .iter()
+        .filter(|(key, ..)| key.key().name() == "luci_event_fired_seconds")
+        .filter_map(|(key, ..)| label(key.key(), "event"))
+        .collect::<Vec<_>>();
+    assert!(fired.contains(&"guest-leaves-the-party-willingly".to_owned()));
+    assert!(fired.contains(&"smalltalk-with-the-host/ROBERT-greets".to_owned()));
+}
+
+#[test]
+fn explain_qualified() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .with_search_path(["tests/subroutines"])
+        .load("qualified-happens-after.luci.yaml")
+        .expect("SourceLoader::load");
+    let executable = Executable::build(marshalling(), &sources, key_main).expect("building graph");
+
+    let [greets] = executable.events_named("smalltalk-with-the-host/ROBERT-greets")[..] else {
+        panic!("expected a single event")
+    };
+    assert_eq!(executable.events_named("ROBERT-greets"), [greets]);
+    assert!(executable
+        .events_named("no-such-call/ROBERT-greets")
+        .is_empty());
+
+    let [arrives] = executable.events_named("guest-arrives-to-the-party")[..] else {
+        panic!("expected a single event")
+    };
+    let explanation = executable.explain(arrives).expect("explain");
+    assert!(explanation.prerequisites.contains(&greets));
+
+    let explanation = executable.explain(greets).expect("explain");
+    assert!(explanation.unblocks.contains(&arrives));
+}
+
+#[test]
+fn unmapped_dummy() {
+    let (key_main, sources) = SourceCodeLoader::new()
+        .with_search_path(["tests/unmapped", "tests/subroutines"])
+        .load("main.luci.yaml")
+        .expect("SourceLoader::load");
+    let build =
+        |options| Executable::build_with_options(marshalling(), &sources, key_main, options);
+
+    build(BuildOptions::default()).expect("a warning only by default");
+    let err = build(BuildOptions::default().with_deny_warnings(true))
+        .expect_err("the warnings are denied")
+        .to_string();
+    assert!(err.starts_with("dummy is not mapped: D:ROBERT"), "{}", err);
+    build(
+        BuildOptions::default()
+            .with_deny_warnings(true)
+            .with_allowed_lint(Lint::UnmappedDummy),
+    )
+    .expect("the lint is allowed");
+}
+
+fn marshalling() -> MarshallingRegistry {
+    MarshallingRegistry::new()
+        .with(Request::<crate::proto::smalltalk::Whatsup>)
+        .with(Regular::<crate::proto::smalltalk::OhByTheWay>)
+        .with(Regular::<crate::proto::smalltalk::NoWay>)
+        .with(Request::<crate::proto::partying::MayI>)
+        .with(Regular::<crate::proto::partying::SeeYou>)
+        .with(Regular::<crate::proto::partying::Chug>)
+        .with(Regular::<crate::proto::partying::Gulp>)
+}